@@ -4,14 +4,27 @@
 //! All commands are exposed via Tauri's IPC mechanism and return Result<T, String> for
 //! frontend compatibility.
 
-use crate::infrastructure::{Database, KeychainService};
-use crate::models::{AwsCredentials, Deployment, Environment, FrameworkType, GitCredentials, Project};
+use crate::infrastructure::{CaBundle, Database, DatabaseError, KeychainService};
+use crate::models::{ActiveDeployment, AppSettings, Application, ApplicationProjects, AwsCredentialInfo, AwsCredentials, ClaudeApiKeys, Deployment, DeploymentDetail, DeploymentError, DeploymentTrigger, DiagnosticsReport, Environment, FrameworkType, GitCredentialInfo, GitCredentials, EmergencyStopSummary, ImageInfo, OperationMetric, PermissionCheck, Project, ProjectHealth, ProjectPatch, ReconcileReport, ResourceSize, ResourceSuggestion, SloMetrics, TestBuildReport};
 use crate::services::{
-    AwsService, ClaudeService, DeploymentContext, GitService, TerraformService,
+    check_ecr_region, inspect_image, resolve_ecr_repository, resolve_ecr_uri, spawn_keepalive, ApplyResult, AwsClientConfig, AwsService, ClaudeResponse, ClaudeService, DeploymentContext, GitService, BackgroundTaskHandle, OperationMetrics,
+    Redactor, RemoteRefs, TerraformService, TokioCommandRunner, EPHEMERAL_STORAGE_GIB_RANGE,
 };
-use crate::application::orchestrator::DeploymentOrchestrator;
+use crate::application::{deployment_control, diagnostics, health_snapshot, iam_preflight, log_snapshot, preview, reconcile, right_sizing, service_import, test_build};
+use crate::application::deploy_plan::{self, DeploymentPlan, PlanStore};
+use crate::application::teardown::{self, TeardownPlan};
+use crate::application::deployment_control::DeploymentControl;
+use crate::application::health_watch::{spawn_health_watch, HealthListener};
+use crate::application::log_maintenance::{spawn_log_maintenance, LOG_PRUNE_INTERVAL};
+use crate::application::orchestrator::{DeploymentOrchestrator, OrchestratorError};
+use crate::application::progress::EventBus;
+use crate::application::service_monitor::{ServiceMonitor, DEFAULT_POLL_INTERVAL};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex};
-use tauri::State;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+use tokio::sync::{oneshot, Semaphore};
 
 /// Shared application state accessible to all commands
 pub struct AppState {
@@ -19,22 +32,521 @@ pub struct AppState {
     pub keychain: Arc<Mutex<KeychainService>>,
     pub git_service: Arc<GitService>,
     pub terraform_service: Arc<TerraformService>,
+    /// AWS services cached per access key and region so SDK connections are reused
+    pub aws_services: Arc<tokio::sync::Mutex<HashMap<String, Arc<AwsService>>>>,
+    /// Running AWS keepalive task, if enabled
+    pub keepalive: Arc<Mutex<Option<BackgroundTaskHandle>>>,
+    /// Running background health refresh, if enabled
+    pub health_watch: Arc<Mutex<Option<BackgroundTaskHandle>>>,
+    /// Running periodic log pruning, once started
+    pub log_maintenance: Arc<Mutex<Option<BackgroundTaskHandle>>>,
+    /// Service health monitors cached like `aws_services`, shared by deployments
+    pub service_monitors: Arc<tokio::sync::Mutex<HashMap<String, Arc<ServiceMonitor>>>>,
+    /// Custom CA certificates loaded at startup, if configured
+    pub ca_bundle: Option<CaBundle>,
+    /// Secret redaction rules compiled from settings
+    pub redactor: Mutex<Arc<Redactor>>,
+    /// Running deployment tasks and the emergency-stop pause flag
+    pub deployment_control: Arc<DeploymentControl>,
+    /// Deployment progress, fanned out to the frontend and other subscribers
+    pub event_bus: Arc<EventBus>,
+    /// Limits image builds running at once across all deployments
+    pub build_slots: Arc<Semaphore>,
+    /// Cancel senders for streaming Claude answers in flight, by stream ID
+    pub claude_streams: Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>,
+    /// Deployment plans awaiting confirmation
+    pub deployment_plans: Arc<Mutex<PlanStore>>,
+    /// Outcome counts of AWS and Git operations for this session
+    pub operation_metrics: Arc<OperationMetrics>,
+    /// Problems hit outside any command, e.g. at startup, for the frontend to show
+    pub warnings: Arc<Mutex<Vec<String>>>,
 }
 
 impl AppState {
     /// Create a new AppState with initialized services
     pub fn new() -> Result<Self, String> {
-        let database = Database::new()
+        let mut database = Database::new()
             .map_err(|e| format!("Failed to initialize database: {}", e))?;
         
-        let keychain = KeychainService::new();
+        // Load and apply the custom CA bundle before any git/HTTPS work starts.
+        // A bundle that went missing shouldn't keep the app from starting.
+        let settings = database.get_settings()
+            .map_err(|e| format!("Failed to load settings: {}", e))?;
+        let (ca_bundle, ca_bundle_warning) = match &settings.custom_ca_bundle {
+            Some(path) => match CaBundle::load(path).and_then(|bundle| bundle.apply_to_git().map(|_| bundle)) {
+                Ok(bundle) => (Some(bundle), None),
+                Err(e) => (None, Some(format!("Custom CA bundle not used: {}", e))),
+            },
+            None => (None, None),
+        };
         
-        Ok(Self {
+        database.set_log_compression(settings.compress_logs);
+        
+        // update_settings rejects invalid patterns, so none should be stored
+        let redactor = Redactor::new(&settings.redaction_patterns);
+        
+        // Startup maintenance: drop logs past their retention, keeping the records.
+        // The log maintenance task repeats this hourly once started.
+        let mut prune_warning = None;
+        if let Some(cutoff) = settings.log_retention_cutoff(chrono::Utc::now().timestamp()) {
+            if let Err(e) = database.prune_deployment_logs_older_than(cutoff) {
+                prune_warning = Some(format!("Failed to prune old deployment logs: {}", e));
+            }
+        }
+        
+        let keychain = KeychainService::with_backend(settings.keychain_backend)
+            .map_err(|e| format!("Failed to open credential storage: {}", e))?;
+        
+        let state = Self::from_parts(database, keychain, ca_bundle, redactor);
+        for warning in ca_bundle_warning.into_iter().chain(prune_warning) {
+            state.warn(warning);
+        }
+        
+        Ok(state)
+    }
+    
+    /// Assemble state around an already-initialized database and keychain
+    pub fn from_parts(database: Database, keychain: KeychainService, ca_bundle: Option<CaBundle>, redactor: Redactor) -> Self {
+        let settings = database.get_settings().unwrap_or_default();
+        let build_concurrency = settings.build_concurrency();
+        let operation_metrics = Arc::new(OperationMetrics::new());
+        
+        Self {
             database: Arc::new(Mutex::new(database)),
             keychain: Arc::new(Mutex::new(keychain)),
-            git_service: Arc::new(GitService::new()),
+            git_service: Arc::new(
+                GitService::new()
+                    .with_operation_metrics(operation_metrics.clone())
+                    .with_ca_bundle(ca_bundle.clone())
+            ),
             terraform_service: Arc::new(TerraformService::new()),
-        })
+            aws_services: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            keepalive: Arc::new(Mutex::new(None)),
+            health_watch: Arc::new(Mutex::new(None)),
+            log_maintenance: Arc::new(Mutex::new(None)),
+            service_monitors: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            ca_bundle,
+            redactor: Mutex::new(Arc::new(redactor)),
+            deployment_control: Arc::new(DeploymentControl::new()),
+            event_bus: Arc::new(EventBus::default()),
+            build_slots: Arc::new(Semaphore::new(build_concurrency)),
+            claude_streams: Arc::new(Mutex::new(HashMap::new())),
+            deployment_plans: Arc::new(Mutex::new(PlanStore::new())),
+            operation_metrics,
+            warnings: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+    
+    /// Record a problem no command could return, for `get_warnings`
+    pub fn warn(&self, message: String) {
+        push_warning(&self.warnings, message);
+    }
+    
+    /// Start pruning expired deployment logs hourly, replacing any running pruner
+    ///
+    /// Runs regardless of AWS credentials or the health watch; failures are
+    /// recorded as warnings.
+    pub fn start_log_maintenance(&self) {
+        let warnings = self.warnings.clone();
+        let handle = spawn_log_maintenance(
+            self.database.clone(),
+            LOG_PRUNE_INTERVAL,
+            Arc::new(move |message| push_warning(&warnings, message)),
+        );
+        
+        let mut log_maintenance = match self.log_maintenance.lock() {
+            Ok(log_maintenance) => log_maintenance,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Some(previous) = log_maintenance.replace(handle) {
+            previous.cancel();
+        }
+    }
+    
+    /// Current secret redaction rules
+    pub fn redactor(&self) -> Arc<Redactor> {
+        match self.redactor.lock() {
+            Ok(redactor) => redactor.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+    
+    /// AWS credentials stored in the keychain
+    fn stored_aws_credentials(&self) -> Result<AwsCredentials, String> {
+        let keychain = self.keychain.lock()
+            .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+        keychain.get_aws_credentials()
+            .map_err(|e| format!("AWS credentials not configured: {}", e))
+    }
+    
+    /// Git credentials stored in the keychain, if any
+    fn stored_git_credentials(&self) -> Option<GitCredentials> {
+        self.keychain.lock().ok()?.get_git_credentials().ok()
+    }
+    
+    /// Get the cached AWS service for the credentials stored in the keychain
+    pub async fn stored_aws_service(&self) -> Result<Arc<AwsService>, String> {
+        let credentials = self.stored_aws_credentials()?;
+        self.aws_service(&credentials).await
+    }
+    
+    /// Get the cached AWS service for stored credentials, creating it on first use
+    ///
+    /// Services sign with the given keys directly rather than process env vars.
+    pub async fn aws_service(&self, credentials: &AwsCredentials) -> Result<Arc<AwsService>, String> {
+        let key = aws_cache_key(credentials);
+        let mut services = self.aws_services.lock().await;
+        
+        if let Some(service) = services.get(&key) {
+            return Ok(service.clone());
+        }
+        
+        let client_config = {
+            let db = self.database.lock()
+                .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+            AwsClientConfig::from_settings(&db.get_settings()
+                .map_err(|e| format!("Failed to load settings: {}", e))?)
+                .with_ca_bundle(self.ca_bundle.clone())
+        };
+        
+        let service = Arc::new(
+            AwsService::with_credentials(credentials, &client_config)
+                .await
+                .map_err(|e| format!("Failed to initialize AWS service: {}", e))?
+                .with_operation_metrics(self.operation_metrics.clone())
+        );
+        services.insert(key, service.clone());
+        
+        Ok(service)
+    }
+    
+    /// Drop cached AWS services and monitors so the next use builds them afresh
+    ///
+    /// Cached services sign with the credentials they were built with, so this
+    /// must run whenever the stored credentials change.
+    pub async fn clear_aws_clients(&self) {
+        self.aws_services.lock().await.clear();
+        self.service_monitors.lock().await.clear();
+    }
+    
+    /// Get the shared service monitor for stored credentials, creating it on first use
+    pub async fn service_monitor(&self, credentials: &AwsCredentials) -> Result<Arc<ServiceMonitor>, String> {
+        let aws_service = self.aws_service(credentials).await?;
+        let mut monitors = self.service_monitors.lock().await;
+        
+        let monitor = monitors.entry(aws_cache_key(credentials))
+            .or_insert_with(|| Arc::new(ServiceMonitor::new(aws_service, DEFAULT_POLL_INTERVAL)))
+            .clone();
+        
+        Ok(monitor)
+    }
+    
+    /// Start, restart or stop the AWS keepalive to match current settings
+    ///
+    /// Does nothing beyond stopping the old task when keepalive is disabled or
+    /// AWS credentials are not configured.
+    pub async fn refresh_keepalive(&self) -> Result<(), String> {
+        self.stop_keepalive();
+        
+        let settings = {
+            let db = self.database.lock()
+                .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+            db.get_settings()
+                .map_err(|e| format!("Failed to load settings: {}", e))?
+        };
+        
+        if !settings.aws_keepalive_enabled {
+            return Ok(());
+        }
+        
+        let credentials = {
+            let keychain = self.keychain.lock()
+                .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+            match keychain.get_aws_credentials() {
+                Ok(credentials) => credentials,
+                Err(_) => return Ok(()),
+            }
+        };
+        
+        let aws_service = self.aws_service(&credentials).await?;
+        let handle = spawn_keepalive(aws_service, Duration::from_secs(settings.aws_keepalive_interval_secs));
+        
+        let mut keepalive = self.keepalive.lock()
+            .map_err(|e| format!("Failed to acquire keepalive lock: {}", e))?;
+        *keepalive = Some(handle);
+        
+        Ok(())
+    }
+    
+    /// Cancel the AWS keepalive task if one is running
+    pub fn stop_keepalive(&self) {
+        if let Ok(mut keepalive) = self.keepalive.lock() {
+            if let Some(handle) = keepalive.take() {
+                handle.cancel();
+            }
+        }
+    }
+    
+    /// Start, restart or stop the background health refresh to match current settings
+    ///
+    /// Like the keepalive, nothing runs while it is disabled or AWS credentials
+    /// are not configured. `listener` hears about every health change.
+    pub async fn refresh_health_watch(&self, listener: HealthListener) -> Result<(), String> {
+        self.stop_health_watch();
+        
+        let settings = {
+            let db = self.database.lock()
+                .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+            db.get_settings()
+                .map_err(|e| format!("Failed to load settings: {}", e))?
+        };
+        
+        if !settings.health_watch_enabled {
+            return Ok(());
+        }
+        
+        let credentials = {
+            let keychain = self.keychain.lock()
+                .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+            match keychain.get_aws_credentials() {
+                Ok(credentials) => credentials,
+                Err(_) => return Ok(()),
+            }
+        };
+        
+        let aws_service = self.aws_service(&credentials).await?;
+        let handle = spawn_health_watch(
+            self.database.clone(),
+            aws_service,
+            Duration::from_secs(settings.health_watch_interval_secs),
+            listener,
+        );
+        
+        let mut health_watch = self.health_watch.lock()
+            .map_err(|e| format!("Failed to acquire health watch lock: {}", e))?;
+        *health_watch = Some(handle);
+        
+        Ok(())
+    }
+    
+    /// Cancel the background health refresh if one is running
+    pub fn stop_health_watch(&self) {
+        if let Ok(mut health_watch) = self.health_watch.lock() {
+            if let Some(handle) = health_watch.take() {
+                handle.cancel();
+            }
+        }
+    }
+    
+    // ===== Shared command logic (Tauri commands and the REST API) =====
+    
+    /// Validate and save a new project
+    pub async fn create_project(&self, new_project: NewProject) -> Result<Project, CommandError> {
+        // Parse framework and environment from strings
+        let framework_type: FrameworkType = serde_json::from_str(&format!("\"{}\"", new_project.framework))
+            .map_err(|e| CommandError::Invalid(format!("Invalid framework type: {}", e)))?;
+        
+        let env_type: Environment = serde_json::from_str(&format!("\"{}\"", new_project.environment))
+            .map_err(|e| CommandError::Invalid(format!("Invalid environment type: {}", e)))?;
+        
+        let resource_size = new_project.resource_size.unwrap_or_else(|| env_type.default_resource_size());
+        if !resource_size.is_valid() {
+            let (cpu, memory) = resource_size.cpu_memory();
+            return Err(CommandError::Invalid(format!("Invalid Fargate resource size: {} CPU / {} MiB", cpu, memory)));
+        }
+        
+        validate_ecr_repository(self, &new_project.ecr_repository, &env_type).map_err(CommandError::Invalid)?;
+        check_ephemeral_storage(new_project.ephemeral_storage_gib).map_err(CommandError::Invalid)?;
+        check_container_port(new_project.container_port).map_err(CommandError::Invalid)?;
+        
+        // Create project model
+        let mut project = Project::new(
+            new_project.name,
+            new_project.repository_url,
+            new_project.branch,
+            framework_type,
+            env_type,
+            new_project.aws_cluster,
+            new_project.aws_service,
+            new_project.ecr_repository,
+        );
+        project.resource_size = resource_size;
+        project.ephemeral_storage_gib = new_project.ephemeral_storage_gib;
+        project.container_port = new_project.container_port;
+        
+        // Save to database
+        let db = self.database.lock()
+            .map_err(|e| CommandError::Failed(format!("Failed to acquire database lock: {}", e)))?;
+        
+        db.create_project(&project)
+            .map_err(|e| CommandError::Failed(format!("Failed to create project: {}", e)))?;
+        
+        Ok(project)
+    }
+    
+    /// Run a deployment of a project to completion, returning its ID
+    ///
+    /// Without `wait_for_healthy`, the deployment succeeds once ECS has been
+    /// told to deploy, without checking the service becomes healthy.
+    pub async fn start_deployment(&self, project_id: &str, notes: Option<String>, wait_for_healthy: bool) -> Result<String, CommandError> {
+        // Get project details
+        let project = {
+            let db = self.database.lock()
+                .map_err(|e| CommandError::Failed(format!("Failed to acquire database lock: {}", e)))?;
+            db.get_project(project_id)
+                .map_err(|e| match e {
+                    DatabaseError::ProjectNotFound(_) => CommandError::NotFound(format!("Project not found: {}", e)),
+                    e => CommandError::Failed(format!("Failed to get project: {}", e)),
+                })?
+        };
+        
+        // Refuse frozen projects before touching credentials or AWS
+        if !project.deploys_enabled {
+            return Err(CommandError::Conflict(OrchestratorError::DeploysDisabled(project.name).to_string()));
+        }
+        
+        if self.deployment_control.is_paused() {
+            return Err(CommandError::Conflict(deployment_control::ControlError::Paused.to_string()));
+        }
+        
+        let orchestrator = self.deployment_orchestrator().await?;
+        
+        // Run deployment as a cancellable task and return deployment ID
+        let notes = normalize_notes(notes);
+        let deployment_id = self.deployment_control
+            .run(|cancel| async move {
+                orchestrator.with_cancel_token(cancel)
+                    .run_deployment(project, notes, DeploymentTrigger::Manual, wait_for_healthy)
+                    .await
+            })
+            .await
+            .map_err(|e| CommandError::Failed(format!("Deployment failed: {}", e)))?
+            .map_err(|e| CommandError::Failed(format!("Deployment failed: {}", e)))?;
+        
+        Ok(deployment_id)
+    }
+    
+    /// Run a deployment prepared with `prepare_deployment`, returning its ID
+    ///
+    /// Each plan runs at most once and deploys the commit it was prepared
+    /// for. It is refused once expired, or if the project was edited or its
+    /// branch moved since it was prepared.
+    pub async fn confirm_deployment(&self, plan_id: &str) -> Result<String, CommandError> {
+        if self.deployment_control.is_paused() {
+            return Err(CommandError::Conflict(deployment_control::ControlError::Paused.to_string()));
+        }
+        
+        let orchestrator = self.deployment_orchestrator().await?;
+        let plans = self.deployment_plans.clone();
+        let database = self.database.clone();
+        let git_service = self.git_service.clone();
+        let git_credentials = self.stored_git_credentials();
+        let plan_id = plan_id.to_string();
+        
+        self.deployment_control
+            .run(|cancel| async move {
+                deploy_plan::confirm_deployment(
+                    &plans,
+                    &plan_id,
+                    &database,
+                    git_service.as_ref(),
+                    git_credentials.as_ref(),
+                    orchestrator.with_cancel_token(cancel),
+                )
+                .await
+            })
+            .await
+            .map_err(|e| CommandError::Failed(format!("Deployment failed: {}", e)))?
+            .map_err(|e| match e {
+                OrchestratorError::StalePlan(_) => CommandError::Conflict(format!("Failed to confirm deployment: {}", e)),
+                e => CommandError::Failed(format!("Deployment failed: {}", e)),
+            })
+    }
+    
+    /// Orchestrator over the stored AWS and Git credentials and the app's shared services
+    async fn deployment_orchestrator(&self) -> Result<DeploymentOrchestrator, CommandError> {
+        // Get AWS credentials
+        let aws_credentials = self.stored_aws_credentials().map_err(CommandError::Failed)?;
+        
+        // Reuse the cached AWS service for this region
+        let aws_service = self.aws_service(&aws_credentials).await.map_err(CommandError::Failed)?;
+        let service_monitor = self.service_monitor(&aws_credentials).await.map_err(CommandError::Failed)?;
+        
+        Ok(DeploymentOrchestrator::new(
+            self.database.clone(),
+            self.git_service.clone(),
+            aws_service,
+            self.terraform_service.clone(),
+            service_monitor,
+            self.event_bus.clone(),
+        )
+        .with_build_slots(self.build_slots.clone())
+        .with_redactor(self.redactor())
+        .with_git_credentials(self.stored_git_credentials()))
+    }
+    
+    /// Get a deployment's status and details
+    pub fn get_deployment(&self, deployment_id: &str) -> Result<Deployment, CommandError> {
+        let db = self.database.lock()
+            .map_err(|e| CommandError::Failed(format!("Failed to acquire database lock: {}", e)))?;
+        
+        db.get_deployment(deployment_id)
+            .map_err(|e| match e {
+                DatabaseError::DeploymentNotFound(_) => CommandError::NotFound(format!("Failed to get deployment: {}", e)),
+                e => CommandError::Failed(format!("Failed to get deployment: {}", e)),
+            })
+    }
+}
+
+/// Error from the command logic shared with the REST API, classified so the
+/// API can choose a status code (Tauri commands only see the message)
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError {
+    /// The request itself is invalid
+    #[error("{0}")]
+    Invalid(String),
+    
+    #[error("{0}")]
+    NotFound(String),
+    
+    /// The request can't run in the app's current state (e.g. a frozen project)
+    #[error("{0}")]
+    Conflict(String),
+    
+    /// Something failed on our side or in AWS
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// Fields for a new project, with framework and environment as their string names
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct NewProject {
+    pub name: String,
+    pub repository_url: String,
+    pub branch: String,
+    pub framework: String,
+    pub environment: String,
+    pub aws_cluster: String,
+    pub aws_service: String,
+    pub ecr_repository: String,
+    #[serde(default)]
+    pub resource_size: Option<ResourceSize>,
+    #[serde(default)]
+    pub ephemeral_storage_gib: Option<i32>,
+    #[serde(default)]
+    pub container_port: Option<i32>,
+}
+
+/// Cache key for per-account, per-region AWS clients
+fn aws_cache_key(credentials: &AwsCredentials) -> String {
+    format!("{}:{}", credentials.access_key_id, credentials.region)
+}
+
+/// Append to the warnings shown by `get_warnings`, even if the lock was poisoned
+fn push_warning(warnings: &Mutex<Vec<String>>, message: String) {
+    match warnings.lock() {
+        Ok(mut warnings) => warnings.push(message),
+        Err(poisoned) => poisoned.into_inner().push(message),
     }
 }
 
@@ -52,34 +564,23 @@ pub async fn create_project(
     aws_cluster: String,
     aws_service: String,
     ecr_repository: String,
+    resource_size: Option<ResourceSize>,
+    ephemeral_storage_gib: Option<i32>,
+    container_port: Option<i32>,
 ) -> Result<Project, String> {
-    // Parse framework and environment from strings
-    let framework_type: FrameworkType = serde_json::from_str(&format!("\"{}\"", framework))
-        .map_err(|e| format!("Invalid framework type: {}", e))?;
-    
-    let env_type: Environment = serde_json::from_str(&format!("\"{}\"", environment))
-        .map_err(|e| format!("Invalid environment type: {}", e))?;
-    
-    // Create project model
-    let project = Project::new(
+    state.create_project(NewProject {
         name,
         repository_url,
         branch,
-        framework_type,
-        env_type,
+        framework,
+        environment,
         aws_cluster,
         aws_service,
         ecr_repository,
-    );
-    
-    // Save to database
-    let db = state.database.lock()
-        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
-    
-    db.create_project(&project)
-        .map_err(|e| format!("Failed to create project: {}", e))?;
-    
-    Ok(project)
+        resource_size,
+        ephemeral_storage_gib,
+        container_port,
+    }).await.map_err(|e| e.to_string())
 }
 
 /// Get all deployment projects
@@ -108,108 +609,659 @@ pub async fn update_project(
     state: State<'_, AppState>,
     project: Project,
 ) -> Result<(), String> {
+    if !project.resource_size.is_valid() {
+        let (cpu, memory) = project.resource_size.cpu_memory();
+        return Err(format!("Invalid Fargate resource size: {} CPU / {} MiB", cpu, memory));
+    }
+    
+    validate_ecr_repository(&state, &project.ecr_repository, &project.environment)?;
+    check_ephemeral_storage(project.ephemeral_storage_gib)?;
+    check_container_port(project.container_port)?;
+    check_extra_image_tags(&project.extra_image_tags)?;
+    check_shared_alb_arn(project.network.shared_alb_arn.as_deref())?;
+    
     let db = state.database.lock()
         .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
     
+    // Freezing and grouping have their own commands; a project sent without
+    // those fields must not unfreeze it or drop it from its application
+    let stored = db.get_project(&project.id)
+        .map_err(|e| format!("Failed to update project: {}", e))?;
+    let mut project = project;
+    project.deploys_enabled = stored.deploys_enabled;
+    project.application_id = stored.application_id;
+    // The client's copy carries the timestamp it loaded
+    project.touch();
+    
     db.update_project(&project)
         .map_err(|e| format!("Failed to update project: {}", e))
 }
 
-/// Delete a project and all associated deployments
+/// Update only the project fields set in `patch`, leaving the rest as stored
+#[tauri::command]
+pub async fn update_project_partial(
+    state: State<'_, AppState>,
+    project_id: String,
+    patch: ProjectPatch,
+) -> Result<Project, String> {
+    if let Some(resource_size) = &patch.resource_size {
+        if !resource_size.is_valid() {
+            let (cpu, memory) = resource_size.cpu_memory();
+            return Err(format!("Invalid Fargate resource size: {} CPU / {} MiB", cpu, memory));
+        }
+    }
+    if let Some(ephemeral_storage_gib) = patch.ephemeral_storage_gib {
+        check_ephemeral_storage(ephemeral_storage_gib)?;
+    }
+    if let Some(container_port) = patch.container_port {
+        check_container_port(container_port)?;
+    }
+    if let Some(extra_image_tags) = &patch.extra_image_tags {
+        check_extra_image_tags(extra_image_tags)?;
+    }
+    if let Some(network) = &patch.network {
+        check_shared_alb_arn(network.shared_alb_arn.as_deref())?;
+    }
+    
+    let region = credentials_region(&state)?;
+    
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    if patch.ecr_repository.is_some() || patch.environment.is_some() {
+        let current = db.get_project(&project_id)
+            .map_err(|e| format!("Failed to get project: {}", e))?;
+        check_ecr_repository(
+            patch.ecr_repository.as_deref().unwrap_or(&current.ecr_repository),
+            patch.environment.as_ref().unwrap_or(&current.environment),
+            region.as_deref(),
+        )?;
+    }
+    
+    db.update_project_partial(&project_id, patch)
+        .map_err(|e| format!("Failed to update project: {}", e))
+}
+
+/// Build the full ECR URI for a repository name in the stored credentials' account and region
+#[tauri::command]
+pub async fn build_project_ecr_uri(
+    state: State<'_, AppState>,
+    repository_name: String,
+) -> Result<String, String> {
+    let aws_service = state.stored_aws_service().await?;
+    
+    resolve_ecr_uri(aws_service.as_ref(), &repository_name)
+        .await
+        .map_err(|e| format!("Failed to build ECR URI: {}", e))
+}
+
+/// Reject an ECR repository that doesn't resolve to a valid name for the
+/// environment, or whose region differs from the stored AWS credentials
+///
+/// The region check is skipped when no AWS credentials are configured yet.
+fn validate_ecr_repository(state: &AppState, ecr_repository: &str, environment: &Environment) -> Result<(), String> {
+    check_ecr_repository(ecr_repository, environment, credentials_region(state)?.as_deref())
+}
+
+/// Region of the stored AWS credentials, if any
+fn credentials_region(state: &AppState) -> Result<Option<String>, String> {
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    Ok(keychain.get_aws_credentials().ok().map(|credentials| credentials.region))
+}
+
+/// Check an ECR repository resolves for the environment and, given a region, lives in it
+fn check_ecr_repository(ecr_repository: &str, environment: &Environment, region: Option<&str>) -> Result<(), String> {
+    let ecr_repository = resolve_ecr_repository(ecr_repository, environment)
+        .map_err(|e| format!("Invalid ECR repository: {}", e))?;
+    
+    match region {
+        Some(region) => check_ecr_region(&ecr_repository, region)
+            .map_err(|e| format!("Invalid ECR repository: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// Reject an ephemeral storage override outside Fargate's accepted range
+fn check_ephemeral_storage(size_in_gib: Option<i32>) -> Result<(), String> {
+    match size_in_gib {
+        Some(size) if !EPHEMERAL_STORAGE_GIB_RANGE.contains(&size) => Err(format!(
+            "Invalid ephemeral storage: must be between {} and {} GiB, got {}",
+            EPHEMERAL_STORAGE_GIB_RANGE.start(), EPHEMERAL_STORAGE_GIB_RANGE.end(), size
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reject a container port override outside the valid TCP/UDP port range
+fn check_container_port(port: Option<i32>) -> Result<(), String> {
+    match port {
+        Some(port) if !(1..=65535).contains(&port) => Err(format!(
+            "Invalid container port: must be between 1 and 65535, got {}", port
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Reject extra image tags ECR wouldn't accept
+///
+/// A tag is 1-128 letters, digits, `_`, `.` and `-`, and can't start with `.` or `-`.
+fn check_extra_image_tags(tags: &[String]) -> Result<(), String> {
+    for tag in tags {
+        let valid = (1..=128).contains(&tag.len())
+            && !tag.starts_with(['.', '-'])
+            && tag.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+        if !valid {
+            return Err(format!("Invalid image tag: {:?}", tag));
+        }
+    }
+    Ok(())
+}
+
+/// Reject a shared load balancer that isn't given as an ALB ARN
+fn check_shared_alb_arn(arn: Option<&str>) -> Result<(), String> {
+    TerraformService::validate_shared_alb_arn(arn)
+        .map_err(|e| format!("Invalid shared load balancer: {}", e))
+}
+
+/// Enable or disable new deployments for a project
+#[tauri::command]
+pub async fn set_project_deploys_enabled(
+    state: State<'_, AppState>,
+    project_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.set_project_deploys_enabled(&project_id, enabled)
+        .map_err(|e| format!("Failed to update project: {}", e))
+}
+
+/// Delete a project and all associated deployments
+#[tauri::command]
+pub async fn delete_project(state: State<'_, AppState>, project_id: String) -> Result<(), String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.delete_project(&project_id)
+        .map_err(|e| format!("Failed to delete project: {}", e))
+}
+
+// ===== Application Commands =====
+
+/// Create an application to group a project per environment
+///
+/// Projects join it through `update_project_partial` with `application_id`.
+#[tauri::command]
+pub async fn create_application(state: State<'_, AppState>, name: String) -> Result<Application, String> {
+    if name.trim().is_empty() {
+        return Err("Application name must not be empty".to_string());
+    }
+    
+    let application = Application::new(name);
+    
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.create_application(&application)
+        .map_err(|e| format!("Failed to create application: {}", e))?;
+    
+    Ok(application)
+}
+
+/// Get all applications
+#[tauri::command]
+pub async fn get_applications(state: State<'_, AppState>) -> Result<Vec<Application>, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_all_applications()
+        .map_err(|e| format!("Failed to get applications: {}", e))
+}
+
+/// Get an application's projects grouped by environment
+#[tauri::command]
+pub async fn get_application_projects(
+    state: State<'_, AppState>,
+    application_id: String,
+) -> Result<ApplicationProjects, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_application_projects(&application_id)
+        .map_err(|e| format!("Failed to get application projects: {}", e))
+}
+
+// ===== Deployment Commands =====
+
+/// Start a new deployment for a project
+#[tauri::command]
+pub async fn start_deployment(
+    state: State<'_, AppState>,
+    project_id: String,
+    notes: Option<String>,
+    wait_for_healthy: Option<bool>,
+) -> Result<String, String> {
+    state.start_deployment(&project_id, notes, wait_for_healthy.unwrap_or(true))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Summarize what deploying a project would change, without changing anything
+///
+/// The plan is kept until `confirm_deployment` runs it or it expires
+/// (`deploy_plan::PLAN_TTL`).
+#[tauri::command]
+pub async fn prepare_deployment(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<DeploymentPlan, String> {
+    let aws_service = state.stored_aws_service().await?;
+    
+    let git_credentials = state.stored_git_credentials();
+    let plan = deploy_plan::prepare_deployment(&state.database, state.git_service.as_ref(), git_credentials.as_ref(), aws_service.as_ref(), &project_id)
+        .await
+        .map_err(|e| format!("Failed to prepare deployment: {}", e))?;
+    
+    state.deployment_plans.lock()
+        .map_err(|e| format!("Failed to acquire plan lock: {}", e))?
+        .insert(plan.clone(), Instant::now());
+    
+    Ok(plan)
+}
+
+/// Run a deployment prepared with `prepare_deployment`
+///
+/// Each plan runs at most once and deploys the commit it was prepared for.
+/// It is refused once expired, or if the project was edited or its branch
+/// moved since it was prepared.
+#[tauri::command]
+pub async fn confirm_deployment(
+    state: State<'_, AppState>,
+    plan_id: String,
+) -> Result<String, String> {
+    state.confirm_deployment(&plan_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Halt every in-flight deployment and refuse new ones until resumed
+///
+/// Safe to call when nothing is running.
+#[tauri::command]
+pub async fn emergency_stop(
+    state: State<'_, AppState>,
+) -> Result<EmergencyStopSummary, String> {
+    deployment_control::emergency_stop(&state.deployment_control, &state.database)
+        .map_err(|e| format!("Failed to stop deployments: {}", e))
+}
+
+/// Allow deployments again after an emergency stop
+#[tauri::command]
+pub async fn resume_deployments(
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.deployment_control.resume();
+    Ok(())
+}
+
+/// Get deployment status and details
+#[tauri::command]
+pub async fn get_deployment_status(
+    state: State<'_, AppState>,
+    deployment_id: String,
+) -> Result<Deployment, String> {
+    state.get_deployment(&deployment_id)
+        .map_err(|e| e.to_string())
+}
+
+/// Get all deployments for a project
+#[tauri::command]
+pub async fn get_project_deployments(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<Deployment>, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_deployments_for_project(&project_id)
+        .map_err(|e| format!("Failed to get deployments: {}", e))
+}
+
+/// Delete a finished deployment and its logs from history
+///
+/// Refused while the deployment is pending or in progress.
+#[tauri::command]
+pub async fn delete_deployment(
+    state: State<'_, AppState>,
+    deployment_id: String,
+) -> Result<(), String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.delete_deployment(&deployment_id)
+        .map_err(|e| format!("Failed to delete deployment: {}", e))
+}
+
+/// Get all pending or in-progress deployments across every project
+#[tauri::command]
+pub async fn get_active_deployments(
+    state: State<'_, AppState>,
+) -> Result<Vec<ActiveDeployment>, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_active_deployments()
+        .map_err(|e| format!("Failed to get active deployments: {}", e))
+}
+
+/// Get a deployment with its project, progress history and phase timings in one read
+#[tauri::command]
+pub async fn get_deployment_detail(
+    state: State<'_, AppState>,
+    deployment_id: String,
+) -> Result<DeploymentDetail, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_deployment_detail(&deployment_id)
+        .map_err(|e| format!("Failed to get deployment detail: {}", e))
+}
+
+/// Get the files stored with a deployment (e.g. its Terraform), keyed by file name
+#[tauri::command]
+pub async fn get_deployment_artifacts(
+    state: State<'_, AppState>,
+    deployment_id: String,
+) -> Result<BTreeMap<String, String>, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_deployment_artifacts(&deployment_id)
+        .map_err(|e| format!("Failed to get deployment artifacts: {}", e))
+}
+
+/// Get deployment logs
+#[tauri::command]
+pub async fn get_deployment_logs(
+    state: State<'_, AppState>,
+    deployment_id: String,
+) -> Result<String, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    let deployment = db.get_deployment(&deployment_id)
+        .map_err(|e| format!("Failed to get deployment: {}", e))?;
+    
+    Ok(deployment.logs.unwrap_or_else(|| "No logs available".to_string()))
+}
+
+/// Get the last `lines` lines of a deployment's logs, for compact previews
+#[tauri::command]
+pub async fn get_deployment_log_tail(
+    state: State<'_, AppState>,
+    deployment_id: String,
+    lines: usize,
+) -> Result<Vec<String>, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_deployment_log_tail(&deployment_id, lines)
+        .map_err(|e| format!("Failed to get deployment logs: {}", e))
+}
+
+/// Get the most recent error recorded across a project's deployments
+#[tauri::command]
+pub async fn get_last_error(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Option<DeploymentError>, String> {
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_last_error(&project_id)
+        .map_err(|e| format!("Failed to get last error: {}", e))
+}
+
+/// Deployment success rate and mean time to recovery over the last `window_days`
+///
+/// Covers all projects when `project_id` is not given.
+#[tauri::command]
+pub async fn get_slo_metrics(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+    window_days: u32,
+) -> Result<SloMetrics, String> {
+    if window_days == 0 {
+        return Err("The window must be at least 1 day".to_string());
+    }
+    
+    let db = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    
+    db.get_slo_metrics(project_id.as_deref(), window_days, chrono::Utc::now().timestamp())
+        .map_err(|e| format!("Failed to compute SLO metrics: {}", e))
+}
+
+/// How often each AWS and Git operation succeeded, failed or was retried since startup
+#[tauri::command]
+pub async fn get_operation_metrics(
+    state: State<'_, AppState>,
+) -> Result<Vec<OperationMetric>, String> {
+    Ok(state.operation_metrics.snapshot())
+}
+
+/// Problems recorded outside any command since startup, oldest first
+#[tauri::command]
+pub async fn get_warnings(
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    state.warnings.lock()
+        .map(|warnings| warnings.clone())
+        .map_err(|e| format!("Failed to acquire warnings lock: {}", e))
+}
+
+/// Copy up to `lines` recent CloudWatch log lines onto a deployment's stored logs
+#[tauri::command]
+pub async fn snapshot_service_logs(
+    state: State<'_, AppState>,
+    deployment_id: String,
+    lines: i32,
+) -> Result<Deployment, String> {
+    let aws_service = state.stored_aws_service().await?;
+    
+    log_snapshot::snapshot_service_logs(&state.database, aws_service.as_ref(), &deployment_id, lines)
+        .await
+        .map_err(|e| format!("Failed to snapshot logs: {}", e))
+}
+
+/// Compare a project's records with its live ECS service
+///
+/// Reports discrepancies only; nothing is changed locally or in AWS.
+#[tauri::command]
+pub async fn reconcile_project(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<ReconcileReport, String> {
+    let aws_service = state.stored_aws_service().await?;
+    
+    reconcile::reconcile_project(&state.database, aws_service.as_ref(), &project_id)
+        .await
+        .map_err(|e| format!("Failed to reconcile project: {}", e))
+}
+
+/// Read the live ECS health of every project's service
+///
+/// A project whose service can't be read carries an error rather than
+/// failing the whole refresh.
+#[tauri::command]
+pub async fn refresh_all_service_health(
+    state: State<'_, AppState>,
+) -> Result<Vec<ProjectHealth>, String> {
+    let aws_service = state.stored_aws_service().await?;
+    
+    health_snapshot::refresh_all_service_health(&state.database, aws_service.as_ref())
+        .await
+        .map_err(|e| format!("Failed to refresh service health: {}", e))
+}
+
+/// Recommend a task size for a project from its service's recent CPU and memory utilization
+#[tauri::command]
+pub async fn suggest_resources(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<ResourceSuggestion, String> {
+    let aws_service = state.stored_aws_service().await?;
+    
+    right_sizing::suggest_resources(&state.database, aws_service.as_ref(), &project_id)
+        .await
+        .map_err(|e| format!("Failed to suggest resources: {}", e))
+}
+
+/// Check which IAM actions deploying a project needs are allowed for the stored AWS credentials
 #[tauri::command]
-pub async fn delete_project(state: State<'_, AppState>, project_id: String) -> Result<(), String> {
-    let db = state.database.lock()
-        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+pub async fn check_iam_permissions(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<PermissionCheck>, String> {
+    let aws_service = state.stored_aws_service().await?;
     
-    db.delete_project(&project_id)
-        .map_err(|e| format!("Failed to delete project: {}", e))
+    iam_preflight::check_iam_permissions(&state.database, aws_service.as_ref(), &project_id)
+        .await
+        .map_err(|e| format!("Failed to check IAM permissions: {}", e))
 }
 
-// ===== Deployment Commands =====
+/// Create a project from an ECS service that is already running
+///
+/// The project takes its image repository, port, task size and task count
+/// from the service's current task definition; its repository URL must be
+/// set before deploying.
+#[tauri::command]
+pub async fn import_ecs_service(
+    state: State<'_, AppState>,
+    cluster: String,
+    service: String,
+    environment: String,
+    branch: String,
+) -> Result<Project, String> {
+    let environment: Environment = serde_json::from_str(&format!("\"{}\"", environment))
+        .map_err(|e| format!("Invalid environment type: {}", e))?;
+    let aws_service = state.stored_aws_service().await?;
+    
+    service_import::import_ecs_service(&state.database, aws_service.as_ref(), &cluster, &service, environment, &branch)
+        .await
+        .map_err(|e| format!("Failed to import ECS service: {}", e))
+}
 
-/// Start a new deployment for a project
+/// Delete the ECS service behind a Preview project's branch, returning its name
 #[tauri::command]
-pub async fn start_deployment(
+pub async fn teardown_preview(
     state: State<'_, AppState>,
-    window: tauri::Window,
     project_id: String,
 ) -> Result<String, String> {
-    // Get project details
-    let project = {
-        let db = state.database.lock()
-            .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
-        db.get_project(&project_id)
-            .map_err(|e| format!("Project not found: {}", e))?
-    };
-    
-    // Get AWS credentials
-    let aws_credentials = {
-        let keychain = state.keychain.lock()
-            .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
-        keychain.get_aws_credentials()
-            .map_err(|e| format!("AWS credentials not configured: {}", e))?
-    };
+    let aws_service = state.stored_aws_service().await?;
     
-    // Create AWS service
-    let aws_service = AwsService::new(Some(aws_credentials.region.clone()))
+    preview::teardown_preview(&state.database, aws_service.as_ref(), &project_id)
         .await
-        .map_err(|e| format!("Failed to initialize AWS service: {}", e))?;
-    
-    // Create orchestrator
-    let orchestrator = DeploymentOrchestrator::new(
-        state.database.clone(),
-        state.git_service.clone(),
-        Arc::new(aws_service),
-        state.terraform_service.clone(),
-        window,
-    );
-    
-    // Run deployment in background and return deployment ID
-    let deployment_id = orchestrator.run_deployment(project).await
-        .map_err(|e| format!("Deployment failed: {}", e))?;
+        .map_err(|e| format!("Failed to tear down preview: {}", e))
+}
+
+/// List the AWS resources tearing a project down would delete, deleting nothing
+#[tauri::command]
+pub async fn teardown_plan(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<TeardownPlan, String> {
+    let aws_service = state.stored_aws_service().await?;
     
-    Ok(deployment_id)
+    teardown::teardown_plan(&state.database, aws_service.as_ref(), &project_id)
+        .await
+        .map_err(|e| format!("Failed to plan teardown: {}", e))
 }
 
-/// Get deployment status and details
+/// Delete the ECS service, task definitions, log group and pushed images a
+/// reviewed `teardown_plan` lists
+///
+/// Refused if the plan is out of date. Production projects need their name
+/// repeated in `confirmed_name`. Returns the teardown plan that was carried out.
 #[tauri::command]
-pub async fn get_deployment_status(
+pub async fn teardown_service(
     state: State<'_, AppState>,
-    deployment_id: String,
-) -> Result<Deployment, String> {
-    let db = state.database.lock()
-        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    plan: TeardownPlan,
+    confirmed_name: Option<String>,
+) -> Result<TeardownPlan, String> {
+    let aws_service = state.stored_aws_service().await?;
     
-    db.get_deployment(&deployment_id)
-        .map_err(|e| format!("Failed to get deployment: {}", e))
+    teardown::teardown_service(&state.database, aws_service.as_ref(), &plan, confirmed_name.as_deref())
+        .await
+        .map_err(|e| format!("Failed to tear down service: {}", e))
 }
 
-/// Get all deployments for a project
+/// Clone a project and build its image locally without deploying
+///
+/// Build output is emitted line by line as `test-build-log` events. A failed
+/// build is returned as a report with its logs, not an error. With
+/// `remove_image`, a successful build's image is deleted afterwards.
 #[tauri::command]
-pub async fn get_project_deployments(
+pub async fn test_build(
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
     project_id: String,
-) -> Result<Vec<Deployment>, String> {
-    let db = state.database.lock()
-        .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+    remove_image: bool,
+) -> Result<TestBuildReport, String> {
+    let redactor = state.redactor();
+    let on_line = |line: &str| {
+        // Build output is informational; a lost line shouldn't fail the build
+        let _ = app.emit_all("test-build-log", test_build::TestBuildLogEvent {
+            project_id: project_id.clone(),
+            line: redactor.redact(line),
+        });
+    };
     
-    db.get_deployments_for_project(&project_id)
-        .map_err(|e| format!("Failed to get deployments: {}", e))
+    let mut report = test_build::test_build(
+        &state.database,
+        state.git_service.as_ref(),
+        &TokioCommandRunner,
+        &project_id,
+        remove_image,
+        &on_line,
+    )
+    .await
+    .map_err(|e| format!("Failed to run test build: {}", e))?;
+    
+    report.logs = state.redactor().redact(&report.logs);
+    Ok(report)
 }
 
-/// Get deployment logs
+/// Get the size and layer breakdown of a locally built image
 #[tauri::command]
-pub async fn get_deployment_logs(
+pub async fn get_image_info(image_tag: String) -> Result<ImageInfo, String> {
+    inspect_image(&TokioCommandRunner, &image_tag)
+        .await
+        .map_err(|e| format!("Failed to inspect image: {}", e))
+}
+
+/// Set or clear the operator note on a deployment
+#[tauri::command]
+pub async fn set_deployment_notes(
     state: State<'_, AppState>,
     deployment_id: String,
-) -> Result<String, String> {
+    notes: Option<String>,
+) -> Result<(), String> {
     let db = state.database.lock()
         .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
     
-    let deployment = db.get_deployment(&deployment_id)
-        .map_err(|e| format!("Failed to get deployment: {}", e))?;
-    
-    Ok(deployment.logs.unwrap_or_else(|| "No logs available".to_string()))
+    db.set_deployment_notes(&deployment_id, normalize_notes(notes).as_deref())
+        .map_err(|e| format!("Failed to set deployment notes: {}", e))
+}
+
+/// Trim a note, treating blank input as no note
+fn normalize_notes(notes: Option<String>) -> Option<String> {
+    notes
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
 }
 
 // ===== Credential Commands =====
@@ -221,18 +1273,25 @@ pub async fn store_aws_credentials(
     access_key_id: String,
     secret_access_key: String,
     region: String,
+    session_token: Option<String>,
 ) -> Result<(), String> {
     let credentials = AwsCredentials {
         access_key_id,
         secret_access_key,
         region,
+        session_token: session_token.filter(|t| !t.trim().is_empty()),
     };
     
-    let keychain = state.keychain.lock()
-        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    {
+        let keychain = state.keychain.lock()
+            .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+        
+        keychain.store_aws_credentials(&credentials)
+            .map_err(|e| format!("Failed to store AWS credentials: {}", e))?;
+    }
     
-    keychain.store_aws_credentials(&credentials)
-        .map_err(|e| format!("Failed to store AWS credentials: {}", e))
+    state.clear_aws_clients().await;
+    Ok(())
 }
 
 /// Store Git credentials securely
@@ -266,21 +1325,83 @@ pub async fn get_credentials_status(
     
     let aws_configured = keychain.get_aws_credentials().is_ok();
     let git_configured = keychain.get_git_credentials().is_ok();
+    let claude_configured = keychain.get_claude_api_keys().is_ok();
     
     Ok(CredentialsStatus {
         aws_configured,
         git_configured,
+        claude_configured,
     })
 }
 
-/// Delete AWS credentials
+/// Validate the app configuration for the startup health panel
 #[tauri::command]
-pub async fn delete_aws_credentials(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn diagnose(
+    state: State<'_, AppState>,
+) -> Result<DiagnosticsReport, String> {
+    let data_dir = dirs::data_dir().map(|dir| dir.join("deployotron"));
+    
+    Ok(diagnostics::diagnose(&state.database, &state.keychain, &TokioCommandRunner, data_dir.as_deref()).await)
+}
+
+/// Describe the stored AWS credentials (account, region, key suffix) without secrets
+#[tauri::command]
+pub async fn get_aws_credential_info(
+    state: State<'_, AppState>,
+) -> Result<AwsCredentialInfo, String> {
+    let aws_credentials = state.stored_aws_credentials()?;
+    
+    // Still describe the credentials if STS is unreachable or rejects them
+    let account_id = match state.aws_service(&aws_credentials).await {
+        Ok(aws_service) => aws_service.get_account_id().await.ok(),
+        Err(_) => None,
+    };
+    
+    Ok(aws_credentials.info(account_id))
+}
+
+/// Describe the stored Git credentials (provider, username) without the token
+#[tauri::command]
+pub async fn get_git_credential_info(
+    state: State<'_, AppState>,
+) -> Result<GitCredentialInfo, String> {
     let keychain = state.keychain.lock()
         .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
     
-    keychain.delete_aws_credentials()
-        .map_err(|e| format!("Failed to delete AWS credentials: {}", e))
+    keychain.get_git_credentials()
+        .map(|credentials| credentials.info())
+        .map_err(|e| format!("Git credentials not configured: {}", e))
+}
+
+/// List a repository's branches and tags, for picking what to deploy
+///
+/// Uses the stored git credentials when configured, so private repositories work.
+#[tauri::command]
+pub async fn list_remote_refs(
+    state: State<'_, AppState>,
+    repo_url: String,
+) -> Result<RemoteRefs, String> {
+    let credentials = state.stored_git_credentials();
+    
+    state.git_service
+        .list_remote_refs(&repo_url, credentials.as_ref())
+        .await
+        .map_err(|e| format!("Failed to list remote refs: {}", e))
+}
+
+/// Delete AWS credentials
+#[tauri::command]
+pub async fn delete_aws_credentials(state: State<'_, AppState>) -> Result<(), String> {
+    {
+        let keychain = state.keychain.lock()
+            .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+        
+        keychain.delete_aws_credentials()
+            .map_err(|e| format!("Failed to delete AWS credentials: {}", e))?;
+    }
+    
+    state.clear_aws_clients().await;
+    Ok(())
 }
 
 /// Delete Git credentials
@@ -293,21 +1414,338 @@ pub async fn delete_git_credentials(state: State<'_, AppState>) -> Result<(), St
         .map_err(|e| format!("Failed to delete Git credentials: {}", e))
 }
 
+/// Store the primary and optional secondary Claude API keys
+#[tauri::command]
+pub async fn store_claude_api_keys(
+    state: State<'_, AppState>,
+    primary: String,
+    secondary: Option<String>,
+) -> Result<(), String> {
+    if primary.trim().is_empty() {
+        return Err("Primary Claude API key cannot be empty".to_string());
+    }
+    let keys = ClaudeApiKeys {
+        primary,
+        secondary: secondary.filter(|k| !k.trim().is_empty()),
+    };
+    
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    keychain.store_claude_api_keys(&keys)
+        .map_err(|e| format!("Failed to store Claude API keys: {}", e))
+}
+
+/// Rotate to a new Claude API key, keeping the current primary as the secondary
+#[tauri::command]
+pub async fn rotate_claude_api_key(
+    state: State<'_, AppState>,
+    new_key: String,
+) -> Result<(), String> {
+    if new_key.trim().is_empty() {
+        return Err("New Claude API key cannot be empty".to_string());
+    }
+    
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    let keys = keychain.get_claude_api_keys()
+        .map_err(|e| format!("Claude API keys not configured: {}", e))?;
+    keychain.store_claude_api_keys(&keys.rotate(new_key))
+        .map_err(|e| format!("Failed to store Claude API keys: {}", e))
+}
+
+/// Delete the Claude API keys
+#[tauri::command]
+pub async fn delete_claude_api_keys(state: State<'_, AppState>) -> Result<(), String> {
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    keychain.delete_claude_api_keys()
+        .map_err(|e| format!("Failed to delete Claude API keys: {}", e))
+}
+
+/// Store the bearer token REST API requests must present (applied at startup)
+#[tauri::command]
+pub async fn store_rest_api_token(state: State<'_, AppState>, token: String) -> Result<(), String> {
+    if token.trim().is_empty() {
+        return Err("REST API token cannot be empty".to_string());
+    }
+    
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    keychain.store_rest_api_token(&token)
+        .map_err(|e| format!("Failed to store REST API token: {}", e))
+}
+
+/// Delete the REST API token
+#[tauri::command]
+pub async fn delete_rest_api_token(state: State<'_, AppState>) -> Result<(), String> {
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    keychain.delete_rest_api_token()
+        .map_err(|e| format!("Failed to delete REST API token: {}", e))
+}
+
+// ===== Terraform Commands =====
+
+/// Apply generated Terraform in `output_dir`, streaming progress as
+/// "terraform-progress" events
+#[tauri::command]
+pub async fn apply_terraform(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    output_dir: String,
+) -> Result<ApplyResult, String> {
+    state.terraform_service
+        .apply_terraform(std::path::Path::new(&output_dir), &|message| {
+            let _ = window.emit("terraform-progress", message);
+        })
+        .await
+        .map_err(|e| format!("Failed to apply Terraform: {}", e))
+}
+
+// ===== Settings Commands =====
+
+/// Get application settings
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
+    let mut settings = {
+        let db = state.database.lock()
+            .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
+        
+        db.get_settings()
+            .map_err(|e| format!("Failed to get settings: {}", e))?
+    };
+    settings.rest_api_token_set = rest_api_token_set(&state)?;
+    
+    Ok(settings)
+}
+
+/// Whether a REST API token is stored in the keychain
+fn rest_api_token_set(state: &AppState) -> Result<bool, String> {
+    let keychain = state.keychain.lock()
+        .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+    
+    Ok(keychain.get_rest_api_token().is_ok())
+}
+
+/// Update application settings and apply them to background tasks
+///
+/// A changed custom CA bundle takes effect on the next launch.
+#[tauri::command]
+pub async fn update_settings(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+    mut settings: AppSettings,
+) -> Result<(), String> {
+    if settings.aws_keepalive_interval_secs == 0 {
+        return Err("Keepalive interval must be at least 1 second".to_string());
+    }
+    
+    if settings.health_watch_interval_secs == 0 {
+        return Err("Health refresh interval must be at least 1 second".to_string());
+    }
+    
+    if settings.max_concurrent_builds == Some(0) {
+        return Err("At least one concurrent build must be allowed".to_string());
+    }
+    
+    if settings.max_concurrent_pushes == Some(0) {
+        return Err("At least one concurrent push must be allowed".to_string());
+    }
+    
+    if settings.aws_operation_timeout_secs == Some(0) || settings.aws_attempt_timeout_secs == Some(0) {
+        return Err("AWS timeouts must be at least 1 second".to_string());
+    }
+    
+    if settings.aws_max_attempts == Some(0) {
+        return Err("AWS operations need at least one attempt".to_string());
+    }
+    
+    settings.rest_api_token_set = rest_api_token_set(&state)?;
+    if settings.rest_api_enabled && !settings.rest_api_token_set {
+        return Err("The REST API requires an auth token".to_string());
+    }
+    
+    let redactor = Redactor::new(&settings.redaction_patterns);
+    if let Some((pattern, error)) = redactor.invalid_patterns().first() {
+        return Err(format!("Invalid redaction pattern {:?}: {}", pattern, error));
+    }
+    
+    // The bundle is applied at startup; validate now so a bad path can't block the next launch
+    if let Some(path) = &settings.custom_ca_bundle {
+        CaBundle::load(path)
+            .map_err(|e| format!("Invalid custom CA bundle: {}", e))?;
+    }
+    
+    // Move stored credentials before the new backend is recorded, so a failed move changes nothing
+    let previous_backend = {
+        let mut keychain = state.keychain.lock()
+            .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+        let previous_backend = keychain.backend();
+        keychain.switch_backend(settings.keychain_backend)
+            .map_err(|e| format!("Failed to move credentials to the new storage: {}", e))?;
+        previous_backend
+    };
+    
+    let saved = state.database.lock()
+        .map_err(|e| format!("Failed to acquire database lock: {}", e))
+        .and_then(|mut db| {
+            // The usage total is maintained by the backend; don't let a stale copy overwrite it
+            let previous = db.get_settings()
+                .map_err(|e| format!("Failed to load settings: {}", e))?;
+            settings.claude_usage = previous.claude_usage.clone();
+            db.save_settings(&settings)
+                .map_err(|e| format!("Failed to save settings: {}", e))?;
+            db.set_log_compression(settings.compress_logs);
+            Ok(previous)
+        });
+    
+    // The stored settings still name the old backend; move the credentials back to match
+    let previous = match saved {
+        Ok(previous) => previous,
+        Err(e) => {
+            let mut keychain = state.keychain.lock()
+                .map_err(|le| format!("{}; failed to acquire keychain lock: {}", e, le))?;
+            keychain.switch_backend(previous_backend)
+                .map_err(|re| format!("{}; failed to move credentials back: {}", e, re))?;
+            return Err(e);
+        }
+    };
+    
+    // Cached clients keep the timeouts they were built with; rebuild them on next use
+    if AwsClientConfig::from_settings(&previous) != AwsClientConfig::from_settings(&settings) {
+        state.clear_aws_clients().await;
+    }
+    
+    if let Ok(mut current) = state.redactor.lock() {
+        *current = Arc::new(redactor);
+    }
+    
+    state.refresh_health_watch(health_event_emitter(app)).await?;
+    state.refresh_keepalive().await
+}
+
+/// Health watch listener emitting each change to every window as a `service-health` event
+pub fn health_event_emitter(app: tauri::AppHandle) -> HealthListener {
+    Arc::new(move |health: &ProjectHealth| {
+        let _ = app.emit_all("service-health", health);
+    })
+}
+
 // ===== AI Chat Commands =====
 
+/// Build a Claude client from `api_key`, or from the stored keys when none is given
+///
+/// Only stored keys carry a secondary to fail over to.
+fn claude_service(state: &AppState, api_key: Option<String>) -> Result<ClaudeService, String> {
+    let (primary, secondary) = match api_key.filter(|k| !k.is_empty()) {
+        Some(key) => (key, None),
+        None => {
+            let keychain = state.keychain.lock()
+                .map_err(|e| format!("Failed to acquire keychain lock: {}", e))?;
+            let keys = keychain.get_claude_api_keys()
+                .map_err(|e| format!("Claude API key not configured: {}", e))?;
+            (keys.primary, keys.secondary)
+        }
+    };
+    
+    Ok(ClaudeService::with_ca_bundle(primary, state.ca_bundle.as_ref())
+        .map_err(|e| format!("Failed to initialize Claude service: {}", e))?
+        .with_secondary_key(secondary)
+        .with_redactor(state.redactor()))
+}
+
 /// Ask Claude a question about deployments
 #[tauri::command]
 pub async fn ask_claude(
     state: State<'_, AppState>,
     question: String,
     project_id: Option<String>,
-    api_key: String,
+    api_key: Option<String>,
 ) -> Result<ClaudeResponseDto, String> {
     // Create Claude service
-    let claude = ClaudeService::new(api_key)
-        .map_err(|e| format!("Failed to initialize Claude service: {}", e))?;
+    let claude = claude_service(&state, api_key)?;
+    let context = question_context(&state, project_id)?;
+    
+    // Ask Claude
+    let response = claude.ask_question(&question, context.as_ref())
+        .await
+        .map_err(|e| format!("Claude request failed: {}", e))?;
+    
+    record_claude_usage(&state, &response);
+    
+    Ok(ClaudeResponseDto::from(response))
+}
+
+/// Ask Claude a question, streaming the answer as "claude-stream-chunk" events
+///
+/// `stream_id` identifies the answer to `cancel_claude_stream` and must not
+/// belong to a stream that is still running; a cancelled answer resolves
+/// with the text received so far and `partial` set.
+#[tauri::command]
+pub async fn ask_claude_streaming(
+    state: State<'_, AppState>,
+    window: tauri::Window,
+    stream_id: String,
+    question: String,
+    project_id: Option<String>,
+    api_key: Option<String>,
+) -> Result<ClaudeResponseDto, String> {
+    let claude = claude_service(&state, api_key)?;
+    let context = question_context(&state, project_id)?;
     
-    // Build context if project ID provided
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    match state.claude_streams.lock()
+        .map_err(|e| format!("Failed to acquire stream lock: {}", e))?
+        .entry(stream_id.clone())
+    {
+        // Replacing the sender would orphan the running stream's cancel handle
+        Entry::Occupied(_) => return Err(format!("Claude stream {} is already running", stream_id)),
+        Entry::Vacant(entry) => {
+            entry.insert(cancel_tx);
+        }
+    }
+    
+    let result = claude
+        .ask_question_streaming(&question, context.as_ref(), cancel_rx, &|text| {
+            let _ = window.emit("claude-stream-chunk", ClaudeStreamChunk {
+                stream_id: stream_id.clone(),
+                text: text.to_string(),
+            });
+        })
+        .await;
+    
+    if let Ok(mut streams) = state.claude_streams.lock() {
+        streams.remove(&stream_id);
+    }
+    
+    let response = result.map_err(|e| format!("Claude request failed: {}", e))?;
+    record_claude_usage(&state, &response);
+    
+    Ok(ClaudeResponseDto::from(response))
+}
+
+/// Stop a streaming Claude answer started with `ask_claude_streaming`
+///
+/// Returns false if the stream already finished or never existed.
+#[tauri::command]
+pub async fn cancel_claude_stream(state: State<'_, AppState>, stream_id: String) -> Result<bool, String> {
+    let sender = state.claude_streams.lock()
+        .map_err(|e| format!("Failed to acquire stream lock: {}", e))?
+        .remove(&stream_id);
+    
+    Ok(match sender {
+        Some(sender) => sender.send(()).is_ok(),
+        None => false,
+    })
+}
+
+/// Deployment context for a question about `project_id`, from its latest deployment
+fn question_context(state: &AppState, project_id: Option<String>) -> Result<Option<DeploymentContext>, String> {
     let context = if let Some(pid) = project_id {
         let db = state.database.lock()
             .map_err(|e| format!("Failed to acquire database lock: {}", e))?;
@@ -325,23 +1763,16 @@ pub async fn ask_claude(
             environment: format!("{:?}", project.environment),
             cluster_name: project.aws_cluster.clone(),
             service_name: project.aws_service.clone(),
-            commit_sha: d.commit_sha.clone(),
+            commit_sha: d.commit_sha.clone().unwrap_or_else(|| "unresolved".to_string()),
             error_message: d.error_message.clone(),
             logs: d.logs.as_ref().map(|logs| logs.lines().map(|s| s.to_string()).collect()),
+            notes: d.notes.clone(),
         })
     } else {
         None
     };
     
-    // Ask Claude
-    let response = claude.ask_question(&question, context.as_ref())
-        .await
-        .map_err(|e| format!("Claude request failed: {}", e))?;
-    
-    Ok(ClaudeResponseDto {
-        answer: response.answer,
-        suggestions: response.suggestions,
-    })
+    Ok(context)
 }
 
 /// Analyze deployment logs with Claude AI
@@ -349,7 +1780,7 @@ pub async fn ask_claude(
 pub async fn analyze_deployment_logs(
     state: State<'_, AppState>,
     deployment_id: String,
-    api_key: String,
+    api_key: Option<String>,
 ) -> Result<ClaudeResponseDto, String> {
     // Get deployment and project details
     let (deployment, project) = {
@@ -366,8 +1797,7 @@ pub async fn analyze_deployment_logs(
     };
     
     // Create Claude service
-    let claude = ClaudeService::new(api_key)
-        .map_err(|e| format!("Failed to initialize Claude service: {}", e))?;
+    let claude = claude_service(&state, api_key)?;
     
     // Build deployment context
     let logs: Vec<String> = deployment.logs
@@ -381,9 +1811,10 @@ pub async fn analyze_deployment_logs(
         environment: format!("{:?}", project.environment),
         cluster_name: project.aws_cluster.clone(),
         service_name: project.aws_service.clone(),
-        commit_sha: deployment.commit_sha.clone(),
+        commit_sha: deployment.commit_sha.clone().unwrap_or_else(|| "unresolved".to_string()),
         error_message: deployment.error_message.clone(),
         logs: Some(logs.clone()),
+        notes: deployment.notes.clone(),
     };
     
     // Analyze logs
@@ -391,10 +1822,30 @@ pub async fn analyze_deployment_logs(
         .await
         .map_err(|e| format!("Log analysis failed: {}", e))?;
     
-    Ok(ClaudeResponseDto {
-        answer: response.answer,
-        suggestions: response.suggestions,
-    })
+    record_claude_usage(&state, &response);
+    
+    Ok(ClaudeResponseDto::from(response))
+}
+
+/// Add a response's token usage to the running total in settings
+///
+/// Best effort: a failure to persist the total shouldn't discard the answer.
+fn record_claude_usage(state: &AppState, response: &ClaudeResponse) {
+    let result = state.database.lock()
+        .map_err(|e| e.to_string())
+        .and_then(|db| {
+            let mut settings = db.get_settings().map_err(|e| e.to_string())?;
+            settings.claude_usage.record(
+                response.usage.input_tokens,
+                response.usage.output_tokens,
+                response.estimated_cost_usd,
+            );
+            db.save_settings(&settings).map_err(|e| e.to_string())
+        });
+    
+    if let Err(error) = result {
+        state.warn(format!("Failed to record Claude usage: {}", error));
+    }
 }
 
 // ===== Response DTOs =====
@@ -404,6 +1855,7 @@ pub async fn analyze_deployment_logs(
 pub struct CredentialsStatus {
     pub aws_configured: bool,
     pub git_configured: bool,
+    pub claude_configured: bool,
 }
 
 /// Claude response DTO for frontend
@@ -411,4 +1863,30 @@ pub struct CredentialsStatus {
 pub struct ClaudeResponseDto {
     pub answer: String,
     pub suggestions: Vec<String>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Estimated cost in USD, if the model has a known price
+    pub estimated_cost_usd: Option<f64>,
+    /// The answer was cancelled part way through
+    pub partial: bool,
+}
+
+/// Piece of a streaming Claude answer, emitted as "claude-stream-chunk"
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ClaudeStreamChunk {
+    pub stream_id: String,
+    pub text: String,
+}
+
+impl From<ClaudeResponse> for ClaudeResponseDto {
+    fn from(response: ClaudeResponse) -> Self {
+        Self {
+            answer: response.answer,
+            suggestions: response.suggestions,
+            input_tokens: response.usage.input_tokens,
+            output_tokens: response.usage.output_tokens,
+            estimated_cost_usd: response.estimated_cost_usd,
+            partial: response.partial,
+        }
+    }
 }
@@ -0,0 +1,389 @@
+//! Deployment plans confirmed before anything changes in AWS
+//!
+//! Preparing a plan gathers what a deployment would change — commits since
+//! the last successful deployment, environment variable and task size changes
+//! against the live service, the image and the target service — using only
+//! reads. Confirming the plan deploys the planned commit, unless the branch
+//! or the project has moved on since the plan was prepared or the plan has
+//! expired.
+
+use crate::application::env_diff::{diff_env_vars, EnvDiff};
+use crate::application::orchestrator::{DeploymentOrchestrator, OrchestratorError};
+use crate::application::preview::PreviewTarget;
+use crate::infrastructure::Database;
+use crate::models::{Deployment, DeploymentStatus, DeploymentTrigger, Environment, GitCredentials, Project};
+use crate::services::{resolve_deployment_spec, resolve_ecr_repository, AwsOperations, CommitInfo, GitOperations};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Most commits listed in a plan
+const MAX_PLAN_COMMITS: usize = 50;
+
+/// How long a prepared plan can be confirmed
+pub const PLAN_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Task size and count, as running or as a deployment would set them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskResources {
+    /// CPU units, if set at task level
+    pub cpu: Option<u32>,
+    /// Memory in MiB, if set at task level
+    pub memory: Option<u32>,
+    pub desired_count: i32,
+}
+
+/// What deploying a project would change, awaiting confirmation
+#[derive(Debug, Clone, Serialize)]
+pub struct DeploymentPlan {
+    /// Passed to `confirm_deployment` to run the deployment
+    pub id: String,
+    pub project_id: String,
+    pub cluster: String,
+    pub service: String,
+    /// Branch head the plan was prepared for
+    pub commit_sha: String,
+    /// Image the deployment will push, tagged with the short commit SHA
+    pub image_uri: String,
+    /// Commit of the last successful deployment, if any
+    pub last_deployed_sha: Option<String>,
+    /// Commits since the last successful deployment, newest first
+    pub commits: Vec<CommitInfo>,
+    /// Environment variable changes; all variables count as added for a new service
+    pub env_diff: EnvDiff,
+    /// Running task size and count; `None` when the service doesn't exist yet
+    pub current_resources: Option<TaskResources>,
+    pub planned_resources: TaskResources,
+    /// Task size changes, e.g. `memory 512 → 1024 MiB`
+    pub resource_changes: Vec<String>,
+    /// Fingerprint of the project settings when prepared; later edits make the plan stale
+    pub project_fingerprint: String,
+}
+
+/// Prepared plans awaiting confirmation, each usable once until it expires
+#[derive(Default)]
+pub struct PlanStore {
+    plans: HashMap<String, (DeploymentPlan, Instant)>,
+}
+
+impl PlanStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Keep `plan` until it is taken or expires, dropping plans that already expired
+    pub fn insert(&mut self, plan: DeploymentPlan, now: Instant) {
+        self.plans.retain(|_, (_, prepared_at)| now.duration_since(*prepared_at) < PLAN_TTL);
+        self.plans.insert(plan.id.clone(), (plan, now));
+    }
+    
+    /// Remove a plan for confirmation
+    ///
+    /// Fails with `StalePlan` for an unknown, already confirmed or expired plan.
+    pub fn take(&mut self, plan_id: &str, now: Instant) -> Result<DeploymentPlan, OrchestratorError> {
+        match self.plans.remove(plan_id) {
+            Some((plan, prepared_at)) if now.duration_since(prepared_at) < PLAN_TTL => Ok(plan),
+            Some(_) => Err(OrchestratorError::StalePlan(format!("plan {} expired", plan_id))),
+            None => Err(OrchestratorError::StalePlan(format!("plan {} not found", plan_id))),
+        }
+    }
+}
+
+/// Work out what deploying a project would change, without changing anything
+pub async fn prepare_deployment(
+    database: &Mutex<Database>,
+    git: &dyn GitOperations,
+    git_credentials: Option<&GitCredentials>,
+    aws: &dyn AwsOperations,
+    project_id: &str,
+) -> Result<DeploymentPlan, OrchestratorError> {
+    let (mut project, last_deployed_sha, preview_domain) = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        let project = db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        let last_deployed_sha = db.get_deployments_for_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .find(|deployment| deployment.status == DeploymentStatus::Success)
+            .and_then(|deployment| deployment.commit_sha);
+        let preview_domain = db.get_settings()
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+            .preview_domain;
+        (project, last_deployed_sha, preview_domain)
+    };
+    
+    if !project.deploys_enabled {
+        return Err(OrchestratorError::DeploysDisabled(project.name));
+    }
+    
+    // Taken before the Preview service is filled in, as stored
+    let project_fingerprint = project_fingerprint(&project);
+    if project.environment == Environment::Preview {
+        project.aws_service = PreviewTarget::for_project(&project, preview_domain.as_deref()).service_name;
+    }
+    let ecr_repository = resolve_ecr_repository(&project.ecr_repository, &project.environment)
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    
+    let commit_sha = git.resolve_remote_commit(&project.repository_url, &project.branch, git_credentials)
+        .await
+        .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+    let commits = git.list_remote_commits(&project.repository_url, &project.branch, last_deployed_sha.as_deref(), MAX_PLAN_COMMITS, git_credentials)
+        .await
+        .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+    
+    // A service that can't be described is treated as not deployed yet
+    let live = aws.describe_service_definition(&project.aws_cluster, &project.aws_service).await.ok();
+    
+    // A draft deployment stands in for the metadata variables the real one sets
+    let image_uri = format!("{}:{}", ecr_repository, commit_sha.get(..8).unwrap_or(&commit_sha));
+    let draft = Deployment::new(project.id.clone(), Some(commit_sha.clone()), None, image_uri.clone(), None);
+    let desired_environment = DeploymentOrchestrator::container_environment(&project, &draft);
+    let no_environment = BTreeMap::new();
+    let env_diff = diff_env_vars(
+        &desired_environment,
+        live.as_ref().map(|live| &live.environment).unwrap_or(&no_environment),
+    );
+    
+    let spec = resolve_deployment_spec(&project);
+    let planned_resources = TaskResources {
+        cpu: Some(spec.cpu),
+        memory: Some(spec.memory),
+        desired_count: spec.desired_count,
+    };
+    let current_resources = live.map(|live| TaskResources {
+        cpu: live.cpu,
+        memory: live.memory,
+        desired_count: live.desired_count,
+    });
+    let resource_changes = resource_changes(current_resources.as_ref(), &planned_resources);
+    
+    Ok(DeploymentPlan {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: project.id.clone(),
+        cluster: project.aws_cluster.clone(),
+        service: project.aws_service.clone(),
+        commit_sha,
+        image_uri,
+        last_deployed_sha,
+        commits,
+        env_diff,
+        current_resources,
+        planned_resources,
+        resource_changes,
+        project_fingerprint,
+    })
+}
+
+/// SHA-256 of the project's settings, leaving out its timestamps
+///
+/// Unlike `updated_at`, which has one-second resolution and is whatever the
+/// last writer sent, this changes with every edit that could change what a
+/// deployment does.
+fn project_fingerprint(project: &Project) -> String {
+    let mut settings = serde_json::to_value(project).unwrap_or_default();
+    if let Some(fields) = settings.as_object_mut() {
+        fields.remove("created_at");
+        fields.remove("updated_at");
+    }
+    
+    let digest = ring::digest::digest(&ring::digest::SHA256, settings.to_string().as_bytes());
+    digest.as_ref().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Check a plan still describes what a deployment would do, returning its project
+///
+/// Fails with `StalePlan` once the project was edited or its branch moved.
+pub async fn check_plan_current(
+    plan: &DeploymentPlan,
+    database: &Mutex<Database>,
+    git: &dyn GitOperations,
+    git_credentials: Option<&GitCredentials>,
+) -> Result<Project, OrchestratorError> {
+    let project = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_project(&plan.project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+    };
+    
+    if project_fingerprint(&project) != plan.project_fingerprint {
+        return Err(OrchestratorError::StalePlan(format!("{} was edited", project.name)));
+    }
+    
+    let head = git.resolve_remote_commit(&project.repository_url, &project.branch, git_credentials)
+        .await
+        .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+    if head != plan.commit_sha {
+        return Err(OrchestratorError::StalePlan(format!("{} moved to {}", project.branch, head)));
+    }
+    
+    Ok(project)
+}
+
+/// Run the deployment a stored plan describes, returning its ID
+///
+/// The plan is removed whether or not it is still current, so each plan
+/// runs at most once. The deployment builds exactly the planned commit.
+pub async fn confirm_deployment(
+    plans: &Mutex<PlanStore>,
+    plan_id: &str,
+    database: &Mutex<Database>,
+    git: &dyn GitOperations,
+    git_credentials: Option<&GitCredentials>,
+    orchestrator: DeploymentOrchestrator,
+) -> Result<String, OrchestratorError> {
+    let plan = plans.lock()
+        .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?
+        .take(plan_id, Instant::now())?;
+    
+    let project = check_plan_current(&plan, database, git, git_credentials).await?;
+    
+    orchestrator.with_commit(Some(plan.commit_sha))
+        .run_deployment(project, None, DeploymentTrigger::Manual, true)
+        .await
+}
+
+/// Describe how the planned task size and count differ from the running ones
+fn resource_changes(current: Option<&TaskResources>, planned: &TaskResources) -> Vec<String> {
+    let current = match current {
+        Some(current) => current,
+        None => return Vec::new(),
+    };
+    
+    let mut changes = Vec::new();
+    if let (Some(from), Some(to)) = (current.cpu, planned.cpu) {
+        if from != to {
+            changes.push(format!("cpu {} → {} units", from, to));
+        }
+    }
+    if let (Some(from), Some(to)) = (current.memory, planned.memory) {
+        if from != to {
+            changes.push(format!("memory {} → {} MiB", from, to));
+        }
+    }
+    if current.desired_count != planned.desired_count {
+        changes.push(format!("tasks {} → {}", current.desired_count, planned.desired_count));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProjectPatch, ResourceSize};
+    use crate::shadow::test_utils::TestOrchestrator;
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    fn commit(sha: &str, message: &str) -> CommitInfo {
+        CommitInfo {
+            sha: sha.to_string(),
+            message: message.to_string(),
+            author: "Dev".to_string(),
+            timestamp: 0,
+            parent_sha: None,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_plan_reports_changes_and_deploys_only_once_confirmed() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { orchestrator, database, aws, git, project, .. } = TestOrchestrator::new(state.clone());
+        
+        // A first deployment puts the service live
+        let first_id = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let deployed_sha = database.lock().unwrap().get_deployment(&first_id).unwrap().commit_sha.unwrap();
+        state.set_commit_history(vec![
+            commit("c3", "Add search"),
+            commit("c2", "Fix header"),
+            commit(&deployed_sha, "Deployed"),
+            commit("c1", "Older"),
+        ]);
+        
+        let mut environment_variables = project.environment_variables.clone();
+        environment_variables.insert("FEATURE_SEARCH".to_string(), "on".to_string());
+        let project = database.lock().unwrap().update_project_partial(&project.id, ProjectPatch {
+            environment_variables: Some(environment_variables),
+            resource_size: Some(ResourceSize::Large),
+            desired_count: Some(Some(2)),
+            ..Default::default()
+        }).unwrap();
+        
+        let operations_before = state.called_operations().len();
+        let clone_before = state.get_cloned_repo(&project.repository_url);
+        let plan = prepare_deployment(&database, git.as_ref(), None, aws.as_ref(), &project.id).await.unwrap();
+        
+        let shas: Vec<&str> = plan.commits.iter().map(|commit| commit.sha.as_str()).collect();
+        assert_eq!(shas, vec!["c3", "c2"]);
+        assert_eq!(plan.last_deployed_sha.as_deref(), Some(deployed_sha.as_str()));
+        assert_eq!(plan.env_diff.added, vec!["FEATURE_SEARCH".to_string()]);
+        assert!(plan.env_diff.removed.is_empty());
+        assert_eq!(plan.current_resources.as_ref().unwrap().desired_count, 1);
+        assert_eq!(plan.planned_resources.desired_count, 2);
+        assert!(plan.resource_changes.contains(&"tasks 1 → 2".to_string()));
+        assert!(plan.resource_changes.iter().any(|change| change.starts_with("memory ")));
+        assert_eq!((plan.cluster.as_str(), plan.service.as_str()), ("cluster", "web-service"));
+        assert!(plan.image_uri.ends_with(&format!(":{}", plan.commit_sha.get(..8).unwrap_or(&plan.commit_sha))));
+        
+        // Preparing only read from AWS, cloned nothing and recorded no deployment
+        assert_eq!(state.called_operations()[operations_before..], ["describe_service_definition".to_string()]);
+        assert_eq!(state.get_cloned_repo(&project.repository_url), clone_before);
+        assert_eq!(database.lock().unwrap().get_deployments_for_project(&project.id).unwrap().len(), 1);
+        
+        // Confirming deploys the planned settings at the planned commit
+        let plans = Mutex::new(PlanStore::new());
+        plans.lock().unwrap().insert(plan.clone(), Instant::now());
+        let id = confirm_deployment(&plans, &plan.id, &database, git.as_ref(), None, orchestrator).await.unwrap();
+        let deployment = database.lock().unwrap().get_deployment(&id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Success);
+        assert_eq!(deployment.commit_sha.as_deref(), Some(plan.commit_sha.as_str()));
+        assert_eq!(state.checked_out_commits(), vec![plan.commit_sha.clone()]);
+        let task = state.get_task_definition_config("web-task").unwrap();
+        assert_eq!(task.environment.get("FEATURE_SEARCH").map(String::as_str), Some("on"));
+        assert_eq!(task.desired_count, 2);
+        
+        // Saving the project unchanged, even with a newer timestamp, keeps the plan current
+        let mut resaved = database.lock().unwrap().get_project(&project.id).unwrap();
+        resaved.updated_at += 60;
+        database.lock().unwrap().update_project(&resaved).unwrap();
+        assert!(check_plan_current(&plan, &database, git.as_ref(), None).await.is_ok());
+        
+        // Editing it within the same second as the plan still makes the plan stale
+        let mut edited = resaved.clone();
+        edited.updated_at = project.updated_at;
+        edited.desired_count = Some(3);
+        database.lock().unwrap().update_project(&edited).unwrap();
+        assert!(matches!(
+            check_plan_current(&plan, &database, git.as_ref(), None).await,
+            Err(OrchestratorError::StalePlan(_))
+        ));
+    }
+    
+    #[tokio::test]
+    async fn test_plans_are_single_use_and_expire() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { database, aws, git, project, .. } = TestOrchestrator::new(state);
+        let plan = prepare_deployment(&database, git.as_ref(), None, aws.as_ref(), &project.id).await.unwrap();
+        
+        let mut plans = PlanStore::new();
+        let prepared_at = Instant::now();
+        plans.insert(plan.clone(), prepared_at);
+        assert_eq!(plans.take(&plan.id, prepared_at).unwrap().id, plan.id);
+        assert!(matches!(plans.take(&plan.id, prepared_at), Err(OrchestratorError::StalePlan(_))));
+        
+        plans.insert(plan.clone(), prepared_at);
+        assert!(matches!(
+            plans.take(&plan.id, prepared_at + PLAN_TTL),
+            Err(OrchestratorError::StalePlan(_))
+        ));
+        
+        // Expired plans are dropped as new ones are stored
+        plans.insert(plan.clone(), prepared_at);
+        let mut later = plan.clone();
+        later.id = "later".to_string();
+        plans.insert(later, prepared_at + PLAN_TTL);
+        assert!(plans.take(&plan.id, prepared_at).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! Control over in-flight deployments
+//!
+//! Hands each deployment a cancel token so an emergency stop can halt
+//! everything at once and keep new deployments from starting until resumed.
+//! Cancellation is cooperative: a deployment checks its token between steps
+//! and cleans up after itself, rather than being dropped mid-step.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{ActiveDeployment, DeploymentStatus, EmergencyStopSummary};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use thiserror::Error;
+
+/// Error message recorded on deployments halted by an emergency stop
+pub const EMERGENCY_STOP_MESSAGE: &str = "Stopped by emergency stop";
+
+/// Reasons a controlled deployment did not run to completion
+#[derive(Error, Debug, PartialEq)]
+pub enum ControlError {
+    #[error("Deployments are paused by an emergency stop")]
+    Paused,
+    
+    #[error("Deployment was stopped")]
+    Stopped,
+}
+
+/// Request for a running deployment to stop at its next step boundary
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Ask the deployment holding this token to stop
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+    
+    /// Whether the deployment has been asked to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Tracks running deployments and whether new ones may start
+#[derive(Default)]
+pub struct DeploymentControl {
+    inner: Mutex<ControlInner>,
+}
+
+#[derive(Default)]
+struct ControlInner {
+    running: HashMap<u64, CancelToken>,
+    next_id: u64,
+    paused: bool,
+}
+
+impl DeploymentControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, ControlInner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    
+    /// Whether new deployments are currently refused
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused
+    }
+    
+    /// Allow new deployments again after an emergency stop
+    pub fn resume(&self) {
+        self.lock().paused = false;
+    }
+    
+    /// Number of deployments currently running
+    pub fn running_count(&self) -> usize {
+        self.lock().running.len()
+    }
+    
+    /// Run a tracked deployment, handing it the token `stop_all` cancels
+    ///
+    /// Refused while paused. The deployment always runs to its own end; one
+    /// that was cancelled is reported as `Stopped` whatever it returned.
+    pub async fn run<F, Fut>(&self, deployment: F) -> Result<Fut::Output, ControlError>
+    where
+        F: FnOnce(CancelToken) -> Fut,
+        Fut: Future,
+    {
+        let (id, token) = {
+            let mut inner = self.lock();
+            if inner.paused {
+                return Err(ControlError::Paused);
+            }
+            
+            let id = inner.next_id;
+            inner.next_id += 1;
+            let token = CancelToken::new();
+            inner.running.insert(id, token.clone());
+            (id, token)
+        };
+        
+        let output = deployment(token.clone()).await;
+        self.lock().running.remove(&id);
+        
+        if token.is_cancelled() {
+            Err(ControlError::Stopped)
+        } else {
+            Ok(output)
+        }
+    }
+    
+    /// Pause new deployments and cancel every running one
+    ///
+    /// # Returns
+    /// Number of deployments cancelled
+    pub fn stop_all(&self) -> usize {
+        let mut inner = self.lock();
+        inner.paused = true;
+        
+        for token in inner.running.values() {
+            token.cancel();
+        }
+        inner.running.len()
+    }
+}
+
+/// Halt every deployment and mark unfinished records as failed
+///
+/// Safe to call when nothing is running: the summary is simply empty.
+pub fn emergency_stop(
+    control: &DeploymentControl,
+    database: &Mutex<Database>,
+) -> Result<EmergencyStopSummary, OrchestratorError> {
+    let tasks_aborted = control.stop_all();
+    
+    let db = database.lock()
+        .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+    let active = db.get_active_deployments()
+        .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+    
+    let mut stopped_deployments = Vec::new();
+    for ActiveDeployment { mut deployment, .. } in active {
+        deployment.complete(DeploymentStatus::Failed, Some(EMERGENCY_STOP_MESSAGE.to_string()));
+        db.update_deployment(&deployment)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        stopped_deployments.push(deployment.id);
+    }
+    
+    Ok(EmergencyStopSummary {
+        stopped_deployments,
+        tasks_aborted,
+        deployments_paused: control.is_paused(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Deployment, Environment};
+    use crate::shadow::test_utils::test_project;
+    use std::sync::Arc;
+    use std::time::Duration;
+    
+    #[tokio::test]
+    async fn test_emergency_stop_cancels_running_deployments() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut ids = Vec::new();
+        for sha in ["aaa111", "bbb222"] {
+            let mut deployment = Deployment::new(project.id.clone(), Some(sha.to_string()), None, "v1".to_string(), None);
+            deployment.status = DeploymentStatus::InProgress;
+            db.create_deployment(&deployment).unwrap();
+            ids.push(deployment.id);
+        }
+        let database = Mutex::new(db);
+        
+        // Two deployments that run until cancelled, then clean up
+        let control = Arc::new(DeploymentControl::new());
+        let cleaned_up = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            let (control, cleaned_up) = (control.clone(), cleaned_up.clone());
+            runs.push(tokio::spawn(async move {
+                control.run(|cancel| async move {
+                    while !cancel.is_cancelled() {
+                        tokio::time::sleep(Duration::from_millis(1)).await;
+                    }
+                    cleaned_up.fetch_add(1, Ordering::SeqCst);
+                }).await
+            }));
+        }
+        while control.running_count() < 2 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        
+        let summary = emergency_stop(&control, &database).unwrap();
+        
+        assert_eq!(summary.tasks_aborted, 2);
+        assert!(summary.deployments_paused);
+        assert_eq!(summary.stopped_deployments.len(), 2);
+        for run in runs {
+            assert_eq!(run.await.unwrap(), Err(ControlError::Stopped));
+        }
+        assert_eq!(cleaned_up.load(Ordering::SeqCst), 2);
+        assert_eq!(control.running_count(), 0);
+        for id in &ids {
+            let stored = database.lock().unwrap().get_deployment(id).unwrap();
+            assert_eq!(stored.status, DeploymentStatus::Failed);
+            assert_eq!(stored.error_message.as_deref(), Some(EMERGENCY_STOP_MESSAGE));
+        }
+        
+        // New deployments are refused until resumed
+        assert_eq!(control.run(|_| async {}).await, Err(ControlError::Paused));
+        control.resume();
+        assert_eq!(control.run(|_| async {}).await, Ok(()));
+    }
+    
+    #[tokio::test]
+    async fn test_emergency_stop_with_nothing_running() {
+        let database = Mutex::new(Database::new_in_memory().unwrap());
+        let control = DeploymentControl::new();
+        
+        let summary = emergency_stop(&control, &database).unwrap();
+        
+        assert_eq!(summary.tasks_aborted, 0);
+        assert!(summary.stopped_deployments.is_empty());
+        assert!(summary.deployments_paused);
+    }
+}
@@ -0,0 +1,163 @@
+//! Startup diagnostics
+//!
+//! Runs the checks that catch misconfiguration before the first deployment
+//! does: database schema, keychain encryption, data directory, Docker and
+//! stored credentials. Each check reports on its own so one failure doesn't
+//! hide the rest.
+
+use crate::infrastructure::database::SCHEMA_VERSION;
+use crate::infrastructure::{Database, KeychainService};
+use crate::models::{CheckStatus, DiagnosticCheck, DiagnosticsReport};
+use crate::services::CommandRunner;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+fn check(name: &str, status: CheckStatus, detail: impl Into<String>) -> DiagnosticCheck {
+    DiagnosticCheck {
+        name: name.to_string(),
+        status,
+        detail: detail.into(),
+    }
+}
+
+/// Validate the app configuration
+///
+/// `data_dir` is the directory holding the database and credential files.
+pub async fn diagnose(
+    database: &Mutex<Database>,
+    keychain: &Mutex<KeychainService>,
+    runner: &dyn CommandRunner,
+    data_dir: Option<&Path>,
+) -> DiagnosticsReport {
+    let mut checks = vec![check_database(database)];
+    checks.extend(check_keychain(keychain));
+    checks.push(check_data_dir(data_dir));
+    checks.push(check_docker(runner).await);
+    
+    DiagnosticsReport::new(checks)
+}
+
+/// Database reachable and migrated to the expected schema version
+fn check_database(database: &Mutex<Database>) -> DiagnosticCheck {
+    let version = database.lock()
+        .map_err(|e| format!("Lock failed: {}", e))
+        .and_then(|db| db.schema_version().map_err(|e| e.to_string()));
+    
+    match version {
+        Ok(version) if version == SCHEMA_VERSION => {
+            check("database", CheckStatus::Ok, format!("Schema version {}", version))
+        }
+        Ok(version) => check(
+            "database",
+            CheckStatus::Error,
+            format!("Schema version {} (expected {})", version, SCHEMA_VERSION),
+        ),
+        Err(e) => check("database", CheckStatus::Error, format!("Database unreachable: {}", e)),
+    }
+}
+
+/// Keychain encryption round-trip, then one check per stored credential
+fn check_keychain(keychain: &Mutex<KeychainService>) -> Vec<DiagnosticCheck> {
+    let keychain = match keychain.lock() {
+        Ok(keychain) => keychain,
+        Err(e) => return vec![check("keychain", CheckStatus::Error, format!("Lock failed: {}", e))],
+    };
+    
+    let mut checks = vec![match keychain.self_test() {
+        Ok(()) => check("keychain", CheckStatus::Ok, "Encryption round-trip succeeded"),
+        Err(e) => check("keychain", CheckStatus::Error, e.to_string()),
+    }];
+    
+    let credentials = [
+        ("aws_credentials", "AWS credentials", keychain.get_aws_credentials().map(|_| ())),
+        ("git_credentials", "Git credentials", keychain.get_git_credentials().map(|_| ())),
+        ("claude_api_keys", "Claude API key", keychain.get_claude_api_keys().map(|_| ())),
+    ];
+    for (name, label, result) in credentials {
+        checks.push(match result {
+            Ok(()) => check(name, CheckStatus::Ok, format!("{} configured", label)),
+            Err(e) => check(name, CheckStatus::Warning, format!("{} not configured: {}", label, e)),
+        });
+    }
+    
+    checks
+}
+
+/// Data directory exists (or can be created) and accepts writes
+fn check_data_dir(data_dir: Option<&Path>) -> DiagnosticCheck {
+    let dir = match data_dir {
+        Some(dir) => dir,
+        None => return check("data_dir", CheckStatus::Error, "Could not determine data directory"),
+    };
+    
+    let probe = dir.join(".write-test");
+    let result = fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&probe, b"ok"))
+        .and_then(|_| fs::remove_file(&probe));
+    
+    match result {
+        Ok(()) => check("data_dir", CheckStatus::Ok, format!("{} is writable", dir.display())),
+        Err(e) => check("data_dir", CheckStatus::Error, format!("{} is not writable: {}", dir.display(), e)),
+    }
+}
+
+/// Docker CLI installed and its daemon reachable
+async fn check_docker(runner: &dyn CommandRunner) -> DiagnosticCheck {
+    match runner.run("docker", &["version", "--format", "{{.Server.Version}}"], None).await {
+        Ok(output) if output.success => check(
+            "docker",
+            CheckStatus::Ok,
+            format!("Docker {}", output.stdout.trim()).trim_end().to_string(),
+        ),
+        Ok(output) => check(
+            "docker",
+            CheckStatus::Error,
+            format!("Docker daemon not reachable: {}", output.stderr.trim()),
+        ),
+        Err(e) => check("docker", CheckStatus::Error, format!("Docker not available: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::OsKeyring;
+    use crate::models::KeychainBackend;
+    use crate::services::CommandOutput;
+    use crate::shadow::MockCommandRunner;
+    use std::io::ErrorKind;
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_report_flags_missing_credentials_with_healthy_database() {
+        let dir = std::env::temp_dir().join(format!("diagnostics_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let database = Mutex::new(Database::new_in_memory().unwrap());
+        let keychain = Mutex::new(KeychainService::open(KeychainBackend::EncryptedFile, Arc::new(OsKeyring), dir.clone()).unwrap());
+        let runner = MockCommandRunner::new();
+        runner.push_response(CommandOutput::success("24.0.7\n"));
+        
+        let report = diagnose(&database, &keychain, &runner, Some(&dir)).await;
+        
+        assert_eq!(report.check("database").unwrap().status, CheckStatus::Ok);
+        assert_eq!(report.check("keychain").unwrap().status, CheckStatus::Ok);
+        assert_eq!(report.check("data_dir").unwrap().status, CheckStatus::Ok);
+        assert_eq!(report.check("docker").unwrap().detail, "Docker 24.0.7");
+        assert_eq!(report.check("aws_credentials").unwrap().status, CheckStatus::Warning);
+        assert_eq!(report.check("git_credentials").unwrap().status, CheckStatus::Warning);
+        assert!(report.healthy);
+        
+        fs::remove_dir_all(&dir).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_missing_docker_is_an_error() {
+        let runner = MockCommandRunner::new();
+        runner.push_spawn_failure(ErrorKind::NotFound);
+        
+        let docker = check_docker(&runner).await;
+        assert_eq!(docker.status, CheckStatus::Error);
+        assert!(!DiagnosticsReport::new(vec![docker]).healthy);
+    }
+}
@@ -0,0 +1,95 @@
+//! Environment variable changes against the live task
+//!
+//! Before a deployment registers its task definition, the container
+//! environment it is about to set is compared with what the service runs now,
+//! so an accidentally dropped variable shows up before it takes effect.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Deployment metadata whose value differs on every deployment by design
+const PER_DEPLOYMENT_VARIABLES: &[&str] = &["DEPLOY_COMMIT_SHA", "DEPLOY_TIMESTAMP", "DEPLOY_ID"];
+
+/// Names of environment variables a deployment adds, removes or changes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EnvDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl EnvDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+    
+    /// One-line summary such as `+NEW_VAR, -OLD_VAR, ~CHANGED_VAR`
+    ///
+    /// Only names are listed, so secret values never appear in logs or events.
+    pub fn summary(&self) -> String {
+        let added = self.added.iter().map(|name| format!("+{}", name));
+        let removed = self.removed.iter().map(|name| format!("-{}", name));
+        let changed = self.changed.iter().map(|name| format!("~{}", name));
+        added.chain(removed).chain(changed).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Compare the environment a deployment will set with the one running now
+///
+/// Names come out sorted. Per-deployment metadata (`DEPLOY_ID` and friends)
+/// counts as added or removed but never as changed.
+pub fn diff_env_vars(desired: &BTreeMap<String, String>, live: &BTreeMap<String, String>) -> EnvDiff {
+    let mut diff = EnvDiff::default();
+    
+    for (name, value) in desired {
+        match live.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(live_value) if live_value != value && !PER_DEPLOYMENT_VARIABLES.contains(&name.as_str()) => {
+                diff.changed.push(name.clone());
+            }
+            Some(_) => {}
+        }
+    }
+    diff.removed = live.keys()
+        .filter(|name| !desired.contains_key(*name))
+        .cloned()
+        .collect();
+    
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn env(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+    
+    #[test]
+    fn test_changes_categorized() {
+        let live = env(&[
+            ("DATABASE_URL", "postgres://old"),
+            ("LOG_LEVEL", "info"),
+            ("LEGACY_FLAG", "1"),
+            ("DEPLOY_ID", "previous"),
+        ]);
+        let desired = env(&[
+            ("DATABASE_URL", "postgres://new"),
+            ("LOG_LEVEL", "info"),
+            ("API_KEY", "sk-live-123"),
+            ("DEPLOY_ID", "current"),
+        ]);
+        
+        let diff = diff_env_vars(&desired, &live);
+        
+        assert_eq!(diff, EnvDiff {
+            added: vec!["API_KEY".to_string()],
+            removed: vec!["LEGACY_FLAG".to_string()],
+            changed: vec!["DATABASE_URL".to_string()],
+        });
+        assert_eq!(diff.summary(), "+API_KEY, -LEGACY_FLAG, ~DATABASE_URL");
+        assert!(!diff.summary().contains("sk-live-123"));
+        assert!(diff_env_vars(&live, &live).is_empty());
+    }
+}
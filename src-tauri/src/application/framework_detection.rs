@@ -0,0 +1,105 @@
+//! Framework detection cached by commit
+//!
+//! Detection reads manifest files from the checkout. The result is stored
+//! against the repository's HEAD SHA, so redeploying an unchanged commit
+//! reuses it instead of inspecting the files again.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::FrameworkType;
+use crate::services::GitOperations;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Detect the framework of a project's checkout, reusing the cached result for the same HEAD
+///
+/// A different HEAD SHA replaces the cached entry.
+pub async fn detect_framework(
+    database: &Mutex<Database>,
+    git: &dyn GitOperations,
+    project_id: &str,
+    repo_path: &Path,
+) -> Result<FrameworkType, OrchestratorError> {
+    let commit_sha = git.get_latest_commit_sha(repo_path)
+        .await
+        .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+    
+    detect_framework_at(database, git, project_id, repo_path, &commit_sha).await
+}
+
+/// Detect the framework of a checkout whose commit is already known
+///
+/// Archive checkouts have no `.git` directory to read HEAD from.
+pub async fn detect_framework_at(
+    database: &Mutex<Database>,
+    git: &dyn GitOperations,
+    project_id: &str,
+    repo_path: &Path,
+    commit_sha: &str,
+) -> Result<FrameworkType, OrchestratorError> {
+    {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        if let Some(framework) = db.get_cached_framework(project_id, commit_sha)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+        {
+            return Ok(framework);
+        }
+    }
+    
+    let framework = git.detect_framework(repo_path)
+        .await
+        .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+    
+    let db = database.lock()
+        .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+    db.set_cached_framework(project_id, commit_sha, &framework)
+        .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+    
+    Ok(framework)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Environment;
+    use crate::shadow::test_utils::{mock_git, test_project};
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    fn create_checkout(package_json: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("framework_detection_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).unwrap();
+        std::fs::write(path.join("package.json"), package_json).unwrap();
+        path
+    }
+    
+    #[tokio::test]
+    async fn test_same_sha_hits_cache() {
+        let state = Arc::new(ShadowState::new());
+        let git = mock_git(state.clone());
+        let database = Mutex::new(Database::new_in_memory().unwrap());
+        let mut project = test_project("web", Environment::Production);
+        project.framework = FrameworkType::Other;
+        database.lock().unwrap().create_project(&project).unwrap();
+        
+        let checkout = create_checkout(r#"{"dependencies": {"next": "14.0.0"}}"#);
+        
+        let first = detect_framework(&database, &git, &project.id, &checkout).await.unwrap();
+        let second = detect_framework(&database, &git, &project.id, &checkout).await.unwrap();
+        
+        assert_eq!(first, FrameworkType::NextJs);
+        assert_eq!(second, FrameworkType::NextJs);
+        assert_eq!(state.framework_detections(), 1);
+        
+        // The mock derives HEAD from the checkout path, so a new checkout is a new SHA
+        let changed = create_checkout(r#"{"dependencies": {"react": "18.0.0"}}"#);
+        let third = detect_framework(&database, &git, &project.id, &changed).await.unwrap();
+        
+        assert_eq!(third, FrameworkType::React);
+        assert_eq!(state.framework_detections(), 2);
+        
+        std::fs::remove_dir_all(&checkout).ok();
+        std::fs::remove_dir_all(&changed).ok();
+    }
+}
@@ -0,0 +1,156 @@
+//! Live health snapshot across all projects
+//!
+//! Services are described in batches per cluster, so a dashboard refresh costs
+//! one `DescribeServices` call per cluster (per ten services) rather than one
+//! per project.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{Project, ProjectHealth};
+use crate::services::{AwsOperations, MAX_DESCRIBE_SERVICES};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Read the current health of every project's ECS service
+///
+/// Results follow the project listing order. A project whose service is
+/// missing, or whose batch could not be described, gets an `error` instead
+/// of failing the whole refresh.
+pub async fn refresh_all_service_health(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+) -> Result<Vec<ProjectHealth>, OrchestratorError> {
+    let projects = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_all_projects()
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+    };
+    
+    Ok(service_health(&projects, aws).await)
+}
+
+/// Read the current health of the given projects' ECS services, batched per cluster
+///
+/// Results follow the order of `projects`, with failures reported per project.
+pub async fn service_health(projects: &[Project], aws: &dyn AwsOperations) -> Vec<ProjectHealth> {
+    let mut services_by_cluster: HashMap<&str, Vec<String>> = HashMap::new();
+    for project in projects {
+        let services = services_by_cluster.entry(project.aws_cluster.as_str()).or_default();
+        if !services.contains(&project.aws_service) {
+            services.push(project.aws_service.clone());
+        }
+    }
+    
+    // (cluster, service) -> health or the error describing its batch
+    let mut results = HashMap::new();
+    for (cluster, services) in &services_by_cluster {
+        for batch in services.chunks(MAX_DESCRIBE_SERVICES) {
+            match aws.describe_services(cluster, batch).await {
+                Ok(mut described) => {
+                    for service in batch {
+                        let result = described.remove(service).ok_or_else(|| format!(
+                            "ECS service '{}' not found in cluster '{}'",
+                            service, cluster
+                        ));
+                        results.insert((*cluster, service.as_str()), result);
+                    }
+                }
+                Err(e) => {
+                    for service in batch {
+                        results.insert((*cluster, service.as_str()), Err(e.to_string()));
+                    }
+                }
+            }
+        }
+    }
+    
+    projects.iter()
+        .map(|project| match &results[&(project.aws_cluster.as_str(), project.aws_service.as_str())] {
+            Ok(health) => ProjectHealth {
+                project_id: project.id.clone(),
+                running: health.running_count,
+                desired: health.desired_count,
+                is_healthy: health.is_healthy,
+                error: None,
+            },
+            Err(error) => ProjectHealth {
+                project_id: project.id.clone(),
+                running: 0,
+                desired: 0,
+                is_healthy: false,
+                error: Some(error.clone()),
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Environment, Project};
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::{ServiceStatus, ShadowState};
+    use std::sync::Arc;
+    
+    fn create_project(db: &Database, name: &str, cluster: &str) -> Project {
+        let mut project = test_project(name, Environment::Production);
+        project.aws_cluster = cluster.to_string();
+        db.create_project(&project).unwrap();
+        project
+    }
+    
+    #[tokio::test]
+    async fn test_snapshot_includes_every_project() {
+        let db = Database::new_in_memory().unwrap();
+        let api = create_project(&db, "api", "prod");
+        let web = create_project(&db, "web", "prod");
+        let worker = create_project(&db, "worker", "batch");
+        let gone = create_project(&db, "gone", "prod");
+        let database = Mutex::new(db);
+        
+        let state = Arc::new(ShadowState::new());
+        state.set_service_status("prod", "api-service", ServiceStatus {
+            running_count: 2,
+            desired_count: 2,
+            pending_count: 0,
+        });
+        state.set_service_status("prod", "web-service", ServiceStatus {
+            running_count: 1,
+            desired_count: 3,
+            pending_count: 0,
+        });
+        state.set_service_status("batch", "worker-service", ServiceStatus {
+            running_count: 1,
+            desired_count: 1,
+            pending_count: 0,
+        });
+        state.set_service_status("prod", "gone-service", ServiceStatus {
+            running_count: 1,
+            desired_count: 1,
+            pending_count: 0,
+        });
+        state.delete_service("prod", "gone-service");
+        let aws = mock_aws(state.clone());
+        
+        let snapshot = refresh_all_service_health(&database, &aws).await.unwrap();
+        assert_eq!(snapshot.len(), 4);
+        
+        let health = |id: &str| snapshot.iter().find(|h| h.project_id == id).unwrap();
+        assert_eq!(health(&api.id), &ProjectHealth {
+            project_id: api.id.clone(),
+            running: 2,
+            desired: 2,
+            is_healthy: true,
+            error: None,
+        });
+        assert_eq!((health(&web.id).running, health(&web.id).desired), (1, 3));
+        assert!(!health(&web.id).is_healthy);
+        assert!(health(&worker.id).is_healthy);
+        assert!(health(&gone.id).error.as_deref().unwrap().contains("gone-service"));
+        assert!(!health(&gone.id).is_healthy);
+        
+        // One batched call per cluster
+        assert_eq!(state.describe_services_calls(), 2);
+    }
+}
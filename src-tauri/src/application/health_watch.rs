@@ -0,0 +1,180 @@
+//! Background health refresh for deployed services
+//!
+//! While enabled, projects whose last deployment succeeded are polled on an
+//! interval (one batched `DescribeServices` call per cluster) and a listener
+//! is told whenever a service's health changes, such as a task dying. A
+//! project whose health can't be read several polls in a row stops being
+//! polled until the watch is restarted.
+
+use crate::application::health_snapshot::service_health;
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{DeploymentStatus, ProjectHealth};
+use crate::services::{AwsOperations, BackgroundTaskHandle};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Consecutive failed reads after which a project is no longer polled
+pub const MAX_CONSECUTIVE_ERRORS: u32 = 3;
+
+/// Called with a project's new health whenever it changes
+pub type HealthListener = Arc<dyn Fn(&ProjectHealth) + Send + Sync>;
+
+/// Health seen on previous polls, used to report only changes
+#[derive(Default)]
+pub struct HealthWatch {
+    last_seen: HashMap<String, ProjectHealth>,
+    consecutive_errors: HashMap<String, u32>,
+    given_up: HashSet<String>,
+}
+
+impl HealthWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Poll every watched project once, returning those whose health changed
+    ///
+    /// A project's first successful read only sets its baseline. Failed reads
+    /// are never reported as changes.
+    pub async fn poll(
+        &mut self,
+        database: &Mutex<Database>,
+        aws: &dyn AwsOperations,
+    ) -> Result<Vec<ProjectHealth>, OrchestratorError> {
+        let projects = {
+            let db = database.lock()
+                .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+            let mut deployed = Vec::new();
+            for project in db.get_all_projects().map_err(|e| OrchestratorError::DatabaseError(e.to_string()))? {
+                if self.given_up.contains(&project.id) {
+                    continue;
+                }
+                let latest = db.get_latest_deployment_status(&project.id)
+                    .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+                if latest == Some(DeploymentStatus::Success) {
+                    deployed.push(project);
+                }
+            }
+            deployed
+        };
+        
+        let mut changed = Vec::new();
+        for health in service_health(&projects, aws).await {
+            if health.error.is_some() {
+                let errors = self.consecutive_errors.entry(health.project_id.clone()).or_insert(0);
+                *errors += 1;
+                if *errors >= MAX_CONSECUTIVE_ERRORS {
+                    self.given_up.insert(health.project_id.clone());
+                }
+                continue;
+            }
+            
+            self.consecutive_errors.remove(&health.project_id);
+            if let Some(previous) = self.last_seen.insert(health.project_id.clone(), health.clone()) {
+                if previous != health {
+                    changed.push(health);
+                }
+            }
+        }
+        
+        Ok(changed)
+    }
+}
+
+/// Spawn a background task that polls deployed services every `interval`
+///
+/// The first poll only records baselines; `listener` hears about changes
+/// from the second poll on. A poll that fails outright is skipped.
+pub fn spawn_health_watch(
+    database: Arc<Mutex<Database>>,
+    aws: Arc<dyn AwsOperations>,
+    interval: Duration,
+    listener: HealthListener,
+) -> BackgroundTaskHandle {
+    BackgroundTaskHandle::spawn(|mut shutdown_rx| async move {
+        let mut watch = HealthWatch::new();
+        let mut ticker = tokio::time::interval(interval);
+        
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Ok(changed) = watch.poll(&database, aws.as_ref()).await {
+                        for health in &changed {
+                            listener(health);
+                        }
+                    }
+                }
+                _ = &mut shutdown_rx => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Deployment, Environment, Project};
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::{ServiceStatus, ShadowState};
+    
+    fn create_deployed_project(db: &Database, name: &str) -> Project {
+        let project = test_project(name, Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        deployment.complete(DeploymentStatus::Success, None);
+        db.create_deployment(&deployment).unwrap();
+        project
+    }
+    
+    fn running(running_count: i32, desired_count: i32) -> ServiceStatus {
+        ServiceStatus { running_count, desired_count, pending_count: 0 }
+    }
+    
+    #[tokio::test]
+    async fn test_health_change_reported_once() {
+        let db = Database::new_in_memory().unwrap();
+        let web = create_deployed_project(&db, "web");
+        let database = Mutex::new(db);
+        let state = Arc::new(ShadowState::new());
+        state.set_service_status("cluster", "web-service", running(2, 2));
+        let aws = mock_aws(state.clone());
+        let mut watch = HealthWatch::new();
+        
+        // The first poll only records the baseline
+        assert!(watch.poll(&database, &aws).await.unwrap().is_empty());
+        
+        // A task dies and stays dead across several polls
+        state.set_service_status("cluster", "web-service", running(1, 2));
+        let mut events = Vec::new();
+        for _ in 0..3 {
+            events.extend(watch.poll(&database, &aws).await.unwrap());
+        }
+        
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].project_id, web.id);
+        assert_eq!((events[0].running, events[0].desired), (1, 2));
+        assert!(!events[0].is_healthy);
+    }
+    
+    #[tokio::test]
+    async fn test_repeated_errors_stop_polling_project() {
+        let db = Database::new_in_memory().unwrap();
+        create_deployed_project(&db, "gone");
+        let database = Mutex::new(db);
+        let state = Arc::new(ShadowState::new());
+        state.delete_service("cluster", "gone-service");
+        let aws = mock_aws(state.clone());
+        
+        let mut watch = HealthWatch::new();
+        for _ in 0..MAX_CONSECUTIVE_ERRORS {
+            assert!(watch.poll(&database, &aws).await.unwrap().is_empty());
+        }
+        
+        // Given up on: no further describe calls
+        let calls = state.describe_services_calls();
+        watch.poll(&database, &aws).await.unwrap();
+        assert_eq!(state.describe_services_calls(), calls);
+    }
+}
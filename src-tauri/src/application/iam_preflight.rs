@@ -0,0 +1,152 @@
+//! IAM permission pre-flight for deployments
+//!
+//! Simulates the configured identity's policies for every IAM action behind
+//! the AWS calls a deployment makes, so a missing permission is reported up
+//! front instead of as an `AccessDenied` part way through a deployment.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::PermissionCheck;
+use crate::services::AwsOperations;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// IAM actions behind each AWS operation, keyed by `AwsOperations` method name
+///
+/// Docker builds and image inspection run locally and need none; pushing
+/// goes through the ECR registry API with the login token's identity.
+const OPERATION_ACTIONS: &[(&str, &[&str])] = &[
+    ("ensure_ecr_repository", &["ecr:DescribeRepositories", "ecr:CreateRepository", "ecr:ListTagsForResource", "ecr:TagResource"]),
+    ("docker_login_ecr", &["ecr:GetAuthorizationToken"]),
+    ("build_docker_image", &[]),
+    ("build_image", &[]),
+    ("push_docker_image", &[
+        "ecr:BatchCheckLayerAvailability",
+        "ecr:InitiateLayerUpload",
+        "ecr:UploadLayerPart",
+        "ecr:CompleteLayerUpload",
+        "ecr:PutImage",
+    ]),
+    ("inspect_image", &[]),
+    ("register_task_definition", &["ecs:RegisterTaskDefinition", "ecs:TagResource"]),
+    ("deploy_service", &["ecs:DescribeServices", "ecs:UpdateService", "ecs:CreateService"]),
+    ("get_service_health", &["ecs:DescribeServices"]),
+    ("describe_services", &["ecs:DescribeServices"]),
+    ("list_stopped_tasks", &["ecs:ListTasks", "ecs:DescribeTasks"]),
+    ("describe_service_definition", &["ecs:DescribeServices", "ecs:DescribeTaskDefinition"]),
+    ("force_new_deployment", &["ecs:UpdateService"]),
+    ("delete_service", &["ecs:DeleteService"]),
+    ("list_task_definitions", &["ecs:ListTaskDefinitions"]),
+    ("deregister_task_definition", &["ecs:DeregisterTaskDefinition"]),
+    ("log_group_exists", &["logs:DescribeLogGroups"]),
+    ("delete_log_group", &["logs:DeleteLogGroup"]),
+    ("list_image_digests", &["ecr:ListImages"]),
+    ("delete_images", &["ecr:BatchDeleteImage"]),
+    ("fetch_logs", &["logs:GetLogEvents"]),
+    ("list_log_streams", &["logs:DescribeLogStreams"]),
+    ("get_service_utilization", &["cloudwatch:GetMetricStatistics"]),
+    ("ping", &[]),
+    ("get_account_id", &[]),
+];
+
+/// AWS operations a deployment may call, including the image pull retry and
+/// the CloudWatch reads behind its log snapshots
+const DEPLOYMENT_OPERATIONS: &[&str] = &[
+    "ensure_ecr_repository",
+    "build_docker_image",
+    "build_image",
+    "docker_login_ecr",
+    "push_docker_image",
+    "inspect_image",
+    "describe_service_definition",
+    "register_task_definition",
+    "deploy_service",
+    "describe_services",
+    "list_stopped_tasks",
+    "force_new_deployment",
+    "list_log_streams",
+    "fetch_logs",
+];
+
+/// IAM actions behind one AWS operation, or `None` for an unknown operation
+pub fn operation_actions(operation: &str) -> Option<&'static [&'static str]> {
+    OPERATION_ACTIONS.iter()
+        .find(|(name, _)| *name == operation)
+        .map(|(_, actions)| *actions)
+}
+
+/// IAM actions needed by `operations`, sorted and deduplicated
+///
+/// Unknown operations contribute nothing.
+pub fn actions_for<S: AsRef<str>>(operations: &[S]) -> Vec<String> {
+    let actions: BTreeSet<&str> = operations.iter()
+        .filter_map(|operation| operation_actions(operation.as_ref()))
+        .flat_map(|actions| actions.iter().copied())
+        .collect();
+    
+    actions.into_iter().map(|action| action.to_string()).collect()
+}
+
+/// IAM actions a deployment needs
+pub fn deployment_actions() -> Vec<String> {
+    actions_for(DEPLOYMENT_OPERATIONS)
+}
+
+/// Check the configured identity may perform every action deploying a project needs
+pub async fn check_iam_permissions(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    project_id: &str,
+) -> Result<Vec<PermissionCheck>, OrchestratorError> {
+    {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+    }
+    
+    aws.simulate_permissions(&deployment_actions())
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Environment;
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_denied_action_reported() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Development);
+        db.create_project(&project).unwrap();
+        let database = Mutex::new(db);
+        
+        let state = Arc::new(ShadowState::new());
+        let aws = mock_aws(state.clone());
+        
+        let checks = check_iam_permissions(&database, &aws, &project.id).await.unwrap();
+        let checked: Vec<String> = checks.iter().map(|check| check.action.clone()).collect();
+        assert_eq!(checked, deployment_actions());
+        assert!(checked.iter().any(|action| action == "logs:GetLogEvents"));
+        assert!(checked.iter().any(|action| action == "logs:DescribeLogStreams"));
+        assert!(checked.iter().any(|action| action == "ecr:ListTagsForResource"));
+        assert!(checked.iter().any(|action| action == "ecs:TagResource"));
+        assert!(checks.iter().all(|check| check.allowed));
+        
+        state.deny_action("ecs:UpdateService");
+        let checks = check_iam_permissions(&database, &aws, &project.id).await.unwrap();
+        let denied: Vec<&PermissionCheck> = checks.iter().filter(|check| !check.allowed).collect();
+        assert_eq!(denied.len(), 1);
+        assert_eq!(denied[0].action, "ecs:UpdateService");
+        assert_eq!(denied[0].decision, "implicitDeny");
+        
+        assert!(matches!(
+            check_iam_permissions(&database, &aws, "missing").await,
+            Err(OrchestratorError::DatabaseError(_))
+        ));
+    }
+}
@@ -0,0 +1,133 @@
+//! Periodic pruning of old deployment logs
+//!
+//! Logs past the configured retention are dropped at startup and then on an
+//! interval for as long as the app runs, independently of any AWS access.
+//! The deployment records themselves are kept.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::services::BackgroundTaskHandle;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often the maintenance task prunes logs after startup
+pub const LOG_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Called with a message whenever a prune fails
+pub type MaintenanceErrorListener = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Drop the logs of deployments that finished before the retention cutoff
+///
+/// Settings are read on every call so retention changes apply without a
+/// restart. Returns how many deployments lost logs.
+pub fn prune_expired_logs(database: &Mutex<Database>, now: i64) -> Result<usize, OrchestratorError> {
+    let db = database.lock()
+        .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+    let settings = db.get_settings()
+        .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+    match settings.log_retention_cutoff(now) {
+        Some(cutoff) => db.prune_deployment_logs_older_than(cutoff)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string())),
+        None => Ok(0),
+    }
+}
+
+/// Spawn a background task that prunes expired logs every `interval`
+///
+/// The first prune runs one `interval` after spawning, since startup already
+/// pruned. A failed prune is passed to `on_error` and retried on the next tick.
+pub fn spawn_log_maintenance(
+    database: Arc<Mutex<Database>>,
+    interval: Duration,
+    on_error: MaintenanceErrorListener,
+) -> BackgroundTaskHandle {
+    BackgroundTaskHandle::spawn(|mut shutdown_rx| async move {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if let Err(e) = prune_expired_logs(&database, chrono::Utc::now().timestamp()) {
+                        on_error(format!("Failed to prune old deployment logs: {}", e));
+                    }
+                }
+                _ = &mut shutdown_rx => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Deployment, DeploymentStatus, Environment};
+    use crate::shadow::test_utils::test_project;
+    
+    #[test]
+    fn test_prune_expired_logs_follows_retention_setting() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut old = Deployment::new(project.id.clone(), None, None, "v1".to_string(), None);
+        old.logs = Some("Deployed".to_string());
+        old.complete(DeploymentStatus::Success, None);
+        old.completed_at = Some(1_000);
+        db.create_deployment(&old).unwrap();
+        let database = Mutex::new(db);
+        let now = 1_000 + 2 * 86_400;
+        
+        // No retention configured: nothing is pruned
+        assert_eq!(prune_expired_logs(&database, now).unwrap(), 0);
+        
+        {
+            let db = database.lock().unwrap();
+            let mut settings = db.get_settings().unwrap();
+            settings.log_retention_days = Some(1);
+            db.save_settings(&settings).unwrap();
+        }
+        assert_eq!(prune_expired_logs(&database, now).unwrap(), 1);
+        
+        let db = database.lock().unwrap();
+        assert!(db.get_deployment_log_lines(&old.id, 0).unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_maintenance_prunes_on_interval_and_reports_failures() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut old = Deployment::new(project.id.clone(), None, None, "v1".to_string(), None);
+        old.logs = Some("Deployed".to_string());
+        old.complete(DeploymentStatus::Success, None);
+        old.completed_at = Some(1_000);
+        db.create_deployment(&old).unwrap();
+        let mut settings = db.get_settings().unwrap();
+        settings.log_retention_days = Some(1);
+        db.save_settings(&settings).unwrap();
+        let database = Arc::new(Mutex::new(db));
+        
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let recorded = errors.clone();
+        let handle = spawn_log_maintenance(
+            database.clone(),
+            Duration::from_millis(5),
+            Arc::new(move |message| recorded.lock().unwrap().push(message)),
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(database.lock().unwrap().get_deployment_log_lines(&old.id, 0).unwrap().is_empty());
+        assert!(errors.lock().unwrap().is_empty());
+        
+        // A poisoned database lock makes every prune fail
+        let poisoner = database.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = poisoner.lock().unwrap();
+            panic!("poison the database lock");
+        }).join();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.stop().await;
+        
+        let errors = errors.lock().unwrap();
+        assert!(!errors.is_empty());
+        assert!(errors[0].starts_with("Failed to prune old deployment logs"));
+    }
+}
@@ -0,0 +1,142 @@
+//! CloudWatch log snapshots
+//!
+//! Copies a service's current CloudWatch logs onto a deployment record so they
+//! survive CloudWatch retention.
+
+use crate::application::orchestrator::{DeploymentOrchestrator, OrchestratorError};
+use crate::infrastructure::Database;
+use crate::models::Deployment;
+use crate::services::AwsOperations;
+use std::sync::Mutex;
+
+/// Maximum number of log streams read for a single snapshot
+const SNAPSHOT_MAX_STREAMS: i32 = 5;
+
+/// Append up to `lines` recent CloudWatch log lines to a deployment's logs
+///
+/// Streams are read newest first until the line budget is spent. The snapshot
+/// is preceded by a timestamped header naming the log group. Nothing is
+/// appended when no log events were found.
+///
+/// Only the logs are written, so a snapshot of a running deployment doesn't
+/// race the orchestrator's own updates to the record.
+///
+/// # Returns
+/// The updated deployment record
+pub async fn snapshot_service_logs(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    deployment_id: &str,
+    lines: i32,
+) -> Result<Deployment, OrchestratorError> {
+    let (deployment, project) = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        let deployment = db.get_deployment(deployment_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        let project = db.get_project(&deployment.project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        (deployment, project)
+    };
+    
+    let log_group = DeploymentOrchestrator::build_ecs_config(&project, "").log_group_name();
+    let streams = aws.list_log_streams(&log_group, SNAPSHOT_MAX_STREAMS)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    
+    let mut snapshot = format!(
+        "\n===== CloudWatch snapshot {} ({}) =====\n",
+        chrono::Utc::now().to_rfc3339(),
+        log_group
+    );
+    
+    let budget = lines.max(0);
+    let mut remaining = budget;
+    for stream in streams {
+        if remaining == 0 {
+            break;
+        }
+        
+        let messages = aws.fetch_logs(&log_group, &stream, remaining)
+            .await
+            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+        if messages.is_empty() {
+            continue;
+        }
+        
+        remaining -= messages.len() as i32;
+        snapshot.push_str(&format!("--- {} ---\n", stream));
+        for message in messages {
+            snapshot.push_str(&message);
+            snapshot.push('\n');
+        }
+    }
+    
+    if remaining == budget {
+        return Ok(deployment);
+    }
+    
+    let db = database.lock()
+        .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+    db.append_deployment_logs(deployment_id, &snapshot)
+        .and_then(|_| db.get_deployment(deployment_id))
+        .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Environment;
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_snapshot_appends_fetched_logs() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        deployment.append_logs("Deployment completed\n");
+        db.create_deployment(&deployment).unwrap();
+        let database = Mutex::new(db);
+        
+        let state = Arc::new(ShadowState::new());
+        state.add_log("/ecs/web-task", "ecs/web-container/old", "old request".to_string());
+        state.add_log("/ecs/web-task", "ecs/web-container/new", "GET /health 200".to_string());
+        state.add_log("/ecs/web-task", "ecs/web-container/new", "GET /api 500".to_string());
+        state.add_log("/ecs/other-task", "ecs/other", "unrelated".to_string());
+        let aws = mock_aws(state);
+        
+        let updated = snapshot_service_logs(&database, &aws, &deployment.id, 2).await.unwrap();
+        let logs = updated.logs.unwrap();
+        
+        assert!(logs.starts_with("Deployment completed\n"));
+        assert!(logs.contains("===== CloudWatch snapshot"));
+        assert!(logs.contains("--- ecs/web-container/new ---\nGET /health 200\nGET /api 500\n"));
+        // Line budget was spent on the newest stream
+        assert!(!logs.contains("old request"));
+        assert!(!logs.contains("unrelated"));
+        
+        let stored = database.lock().unwrap().get_deployment(&deployment.id).unwrap();
+        assert_eq!(stored.logs.unwrap(), logs);
+    }
+    
+    #[tokio::test]
+    async fn test_snapshot_without_events_leaves_logs_alone() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        deployment.append_logs("Deployment completed\n");
+        db.create_deployment(&deployment).unwrap();
+        let database = Mutex::new(db);
+        let aws = mock_aws(Arc::new(ShadowState::new()));
+        
+        let updated = snapshot_service_logs(&database, &aws, &deployment.id, 50).await.unwrap();
+        
+        assert_eq!(updated.logs.as_deref(), Some("Deployment completed\n"));
+        let stored = database.lock().unwrap().get_deployment(&deployment.id).unwrap();
+        assert_eq!(stored.logs.as_deref(), Some("Deployment completed\n"));
+    }
+}
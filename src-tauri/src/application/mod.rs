@@ -3,9 +3,54 @@
 //! This module provides the application layer components:
 //! - commands: Tauri command handlers for frontend communication
 //! - orchestrator: Deployment workflow orchestration
+//! - log_snapshot: Copying CloudWatch logs onto deployment records
+//! - service_monitor: Batched ECS health polling shared across deployments
+//! - reconcile: Reporting drift between local records and live ECS services
+//! - rollout: Waiting for ECS rollouts, retrying transient image pull failures
+//! - deployment_control: Cancellable deployments and the emergency stop
+//! - framework_detection: Framework detection cached by commit SHA
+//! - progress: Deployment progress reporting and the event bus fanning it out
+//! - health_snapshot: Live service health for every project, batched per cluster
+//! - health_watch: Optional background health polling that reports changes
+//! - log_maintenance: Periodic pruning of deployment logs past their retention
+//! - test_build: Building a project's image without deploying it
+//! - rest_api: Optional localhost REST API for headless automation
+//! - service_import: Creating a project from an existing ECS service
+//! - env_diff: Environment variable changes against the live task
+//! - preview: Per-branch service names and URLs for Preview deployments
+//! - diagnostics: Startup checks of the database, keychain, data directory and Docker
+//! - right_sizing: Task size recommendations from CloudWatch utilization
+//! - iam_preflight: Simulating the IAM permissions a deployment needs
+//! - deploy_plan: Summarizing a deployment's changes for confirmation before it runs
+//! - teardown: Listing and deleting the AWS resources a project deployed
 
 pub mod commands;
 pub mod orchestrator;
+pub mod log_snapshot;
+pub mod service_monitor;
+pub mod reconcile;
+pub mod rollout;
+pub mod deployment_control;
+pub mod framework_detection;
+pub mod progress;
+pub mod health_snapshot;
+pub mod health_watch;
+pub mod log_maintenance;
+pub mod test_build;
+pub mod rest_api;
+pub mod service_import;
+pub mod env_diff;
+pub mod preview;
+pub mod diagnostics;
+pub mod right_sizing;
+pub mod iam_preflight;
+pub mod deploy_plan;
+pub mod teardown;
 
 pub use commands::{AppState, CredentialsStatus, ClaudeResponseDto};
 pub use orchestrator::{DeploymentOrchestrator, OrchestratorError};
+pub use progress::{
+    forward_build_logs, forward_events, BuildLogEvent, EventBus, NoopProgressReporter, ProgressEvent, ProgressReporter, RecordingProgressReporter,
+    TauriProgressReporter,
+};
+pub use service_monitor::ServiceMonitor;
@@ -1,14 +1,25 @@
 //! Deployment orchestrator
 //!
 //! Coordinates the full deployment workflow from git clone to ECS service running.
-//! Emits progress events to the frontend via Tauri events.
+//! Reports progress through a `ProgressReporter`, which emits Tauri events in the app.
 
+use crate::application::env_diff::diff_env_vars;
+use crate::application::framework_detection;
+use crate::application::preview::PreviewTarget;
+use crate::application::progress::{BuildLogEvent, ProgressEvent, ProgressReporter};
+use crate::application::deployment_control::{CancelToken, EMERGENCY_STOP_MESSAGE};
+use crate::application::rollout::{wait_for_rollout, PollBackoff, RolloutEvent, RolloutPolicy};
+use crate::application::ServiceMonitor;
 use crate::infrastructure::Database;
-use crate::models::{Deployment, DeploymentStatus, Project};
-use crate::services::{AwsOperations, AwsService, EcsDeploymentConfig, GitOperations, TerraformService, TerraformConfig};
+use crate::models::{Deployment, DeploymentStatus, DeploymentTrigger, Environment, GitCredentials, ProgressRecord, Project};
+use crate::services::{
+    build_with_repository, check_build_prerequisites, check_ecr_region, push_image_tags, push_images, resolve_deployment_spec, resolve_ecr_repository, AwsOperations, BuildSpec, CommitInfo, EcrUri, EcsDeploymentConfig,
+    GitOperations, Redactor, SidecarContainer, TagPush, TerraformService, TerraformConfig,
+};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
-use tauri::Window;
+use tokio::sync::{Semaphore, SemaphorePermit};
 use thiserror::Error;
 
 /// Deployment orchestrator errors
@@ -28,16 +39,31 @@ pub enum OrchestratorError {
     
     #[error("Event emission failed: {0}")]
     EventError(String),
+    
+    #[error("Deploys are disabled for project: {0}")]
+    DeploysDisabled(String),
+    
+    #[error("Missing build prerequisites: {0}")]
+    MissingPrerequisites(String),
+    
+    #[error("Project is not a Preview environment: {0}")]
+    NotPreview(String),
+    
+    #[error("Plan is out of date: {0}")]
+    StalePlan(String),
+    
+    #[error("Tearing down a Production project requires confirming its name: {0}")]
+    ConfirmationRequired(String),
+    
+    #[error("Teardown incomplete: {0}")]
+    TeardownIncomplete(String),
+    
+    #[error("Deployment was stopped")]
+    Cancelled,
 }
 
-/// Deployment progress event payload
-#[derive(Debug, Clone, serde::Serialize)]
-struct ProgressEvent {
-    pub deployment_id: String,
-    pub step: String,
-    pub progress: u8,
-    pub message: String,
-}
+/// Maximum number of a deployment's image builds (app and sidecars) run at the same time
+const MAX_CONCURRENT_IMAGE_OPS: usize = 2;
 
 /// Deployment orchestrator that coordinates the full workflow
 pub struct DeploymentOrchestrator {
@@ -45,7 +71,19 @@ pub struct DeploymentOrchestrator {
     git_service: Arc<dyn GitOperations>,
     aws_service: Arc<dyn AwsOperations>,
     terraform_service: Arc<TerraformService>,
-    window: Window,
+    service_monitor: Arc<ServiceMonitor>,
+    reporter: Arc<dyn ProgressReporter>,
+    poll_backoff: PollBackoff,
+    /// Shared limit on concurrent image builds; unlimited when unset
+    build_slots: Option<Arc<Semaphore>>,
+    /// Masks secrets in build output before it is reported
+    redactor: Arc<Redactor>,
+    /// Checked between steps; once cancelled the deployment cleans up and stops
+    cancel: CancelToken,
+    /// Sent when resolving the branch and downloading archives of private repositories
+    git_credentials: Option<GitCredentials>,
+    /// Commit to deploy instead of the branch head
+    commit_sha: Option<String>,
 }
 
 impl DeploymentOrchestrator {
@@ -55,17 +93,65 @@ impl DeploymentOrchestrator {
         git_service: Arc<dyn GitOperations>,
         aws_service: Arc<dyn AwsOperations>,
         terraform_service: Arc<TerraformService>,
-        window: Window,
+        service_monitor: Arc<ServiceMonitor>,
+        reporter: Arc<dyn ProgressReporter>,
     ) -> Self {
         Self {
             database,
             git_service,
             aws_service,
             terraform_service,
-            window,
+            service_monitor,
+            reporter,
+            poll_backoff: PollBackoff::default(),
+            build_slots: None,
+            redactor: Arc::new(Redactor::default()),
+            cancel: CancelToken::new(),
+            git_credentials: None,
+            commit_sha: None,
         }
     }
     
+    /// Replace the interval schedule used while waiting for the service to become healthy
+    pub fn with_poll_backoff(mut self, poll_backoff: PollBackoff) -> Self {
+        self.poll_backoff = poll_backoff;
+        self
+    }
+    
+    /// Hold a permit from `build_slots` while building images
+    ///
+    /// Sharing one semaphore across orchestrators caps concurrent builds app-wide.
+    pub fn with_build_slots(mut self, build_slots: Arc<Semaphore>) -> Self {
+        self.build_slots = Some(build_slots);
+        self
+    }
+    
+    /// Replace the rules used to mask secrets in reported build output
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = redactor;
+        self
+    }
+    
+    /// Stop at the next step boundary once `cancel` is cancelled
+    pub fn with_cancel_token(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    /// Authenticate remote lookups and archive downloads with `credentials`
+    pub fn with_git_credentials(mut self, credentials: Option<GitCredentials>) -> Self {
+        self.git_credentials = credentials;
+        self
+    }
+    
+    /// Deploy `commit_sha` rather than whatever the branch head is when the source is fetched
+    ///
+    /// The deployment fails if a clone of the branch doesn't contain the commit.
+    pub fn with_commit(mut self, commit_sha: Option<String>) -> Self {
+        self.commit_sha = commit_sha;
+        self
+    }
+    
     /// Run the complete deployment workflow
     ///
     /// This orchestrates the 10-step deployment process:
@@ -73,31 +159,65 @@ impl DeploymentOrchestrator {
     /// 2. Clone git repository
     /// 3. Detect framework type
     /// 4. Get commit information
-    /// 5. Build Docker image
+    /// 5. Build Docker images (app and sidecars) and ensure the ECR repository
     /// 6. Login to ECR
-    /// 7. Push image to ECR
+    /// 7. Push images to ECR
     /// 8. Register ECS task definition
     /// 9. Deploy to ECS service
-    /// 10. Monitor until running
-    pub async fn run_deployment(&self, project: Project) -> Result<String, OrchestratorError> {
+    /// 10. Monitor until running (skipped when `wait_for_healthy` is false)
+    pub async fn run_deployment(
+        &self,
+        project: Project,
+        notes: Option<String>,
+        trigger: DeploymentTrigger,
+        wait_for_healthy: bool,
+    ) -> Result<String, OrchestratorError> {
+        if !project.deploys_enabled {
+            return Err(OrchestratorError::DeploysDisabled(project.name));
+        }
+        
+        // Images for each environment go to that environment's repository
+        let mut project = project;
+        project.ecr_repository = resolve_ecr_repository(&project.ecr_repository, &project.environment)
+            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+        
+        // Pre-flight: a cross-region repository would only fail once tasks try to pull
+        check_ecr_region(&project.ecr_repository, self.aws_service.region())
+            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+        
+        // Each Preview branch deploys to a service of its own
+        let preview = if project.environment == Environment::Preview {
+            let target = self.preview_target(&project)?;
+            project.aws_service = target.service_name.clone();
+            Some(target)
+        } else {
+            None
+        };
+        
         // Step 1: Initialize deployment record (0-10%)
-        let mut deployment = self.initialize_deployment(&project).await?;
+        let mut deployment = self.initialize_deployment(&project, notes, trigger, wait_for_healthy).await?;
         
         self.emit_progress(&deployment.id, "Initializing deployment", 10).await?;
         
-        // Step 2: Clone repository (10-20%)
-        let repo_path = match self.clone_repository(&project, &deployment.id).await {
-            Ok(path) => path,
+        self.stop_if_cancelled(None, &mut deployment).await?;
+        
+        // Step 2: Clone repository, or download an archive of it (10-20%)
+        let (repo_path, archive_commit) = match self.fetch_source(&project, &mut deployment).await {
+            Ok(source) => source,
             Err(e) => {
                 self.fail_deployment(&mut deployment, &format!("Git clone failed: {}", e)).await?;
                 return Err(e);
             }
         };
         
-        self.emit_progress(&deployment.id, "Repository cloned", 20).await?;
+        let fetched = if archive_commit.is_some() { "Repository archive downloaded" } else { "Repository cloned" };
+        self.emit_progress(&deployment.id, fetched, 20).await?;
+        
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
         
         // Step 3: Detect framework (20-25%)
-        let framework = match self.detect_framework(&repo_path, &deployment.id).await {
+        let archive_sha = archive_commit.as_ref().map(|commit| commit.sha.clone());
+        let framework = match self.detect_framework(&project, &repo_path, archive_sha.as_deref(), &deployment.id).await {
             Ok(fw) => fw,
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
@@ -108,8 +228,10 @@ impl DeploymentOrchestrator {
         
         self.emit_progress(&deployment.id, &format!("Framework detected: {:?}", framework), 25).await?;
         
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
         // Step 4: Get commit information (25-30%)
-        let commit_info = match self.get_commit_info(&repo_path, &deployment.id).await {
+        let commit_info = match self.get_commit_info(&repo_path, archive_commit, &deployment.id).await {
             Ok(info) => info,
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
@@ -119,25 +241,50 @@ impl DeploymentOrchestrator {
         };
         
         // Update deployment with commit info
-        deployment.commit_sha = commit_info.sha.clone();
-        deployment.commit_message = Some(commit_info.message.clone());
+        deployment.commit_sha = Some(commit_info.sha.clone());
+        deployment.commit_message = Some(commit_info.message.clone()).filter(|message| !message.is_empty());
         self.update_deployment(&deployment).await?;
         
         self.emit_progress(&deployment.id, &format!("Commit: {}", &commit_info.sha[..8]), 30).await?;
         
-        // Step 5: Build Docker image (30-50%)
-        let image_tag = format!("{}:{}", project.name, &commit_info.sha[..8]);
-        match self.build_docker_image(&repo_path, &image_tag, &project, &deployment.id).await {
-            Ok(_) => {},
+        // Pre-build: warn about (or refuse) repos the generated Dockerfile can't build
+        if let Err(e) = self.check_prerequisites(&project, &repo_path, &mut deployment).await {
+            self.cleanup_repository(&repo_path).await;
+            self.fail_deployment(&mut deployment, &e.to_string()).await?;
+            return Err(e);
+        }
+        
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
+        // Step 5: Build Docker images and ensure the ECR repository (30-50%)
+        let short_sha = &commit_info.sha[..8];
+        let image_tag = format!("{}:{}", project.name, short_sha);
+        let sidecar_specs = Self::sidecar_build_specs(&project, &repo_path, short_sha);
+        // The build slot is released as soon as the images are built
+        let build_result = match self.acquire_build_slot().await {
+            Ok(_slot) => self.build_docker_image(&repo_path, &image_tag, &project, sidecar_specs.clone(), &deployment.id).await,
+            Err(e) => Err(e),
+        };
+        let repository_uri = match build_result {
+            Ok(uri) => uri,
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
-                self.fail_deployment(&mut deployment, &format!("Docker build failed: {}", e)).await?;
+                self.fail_deployment(&mut deployment, &format!("Build stage failed: {}", e)).await?;
                 return Err(e);
             }
         };
         
+        // Best effort: a missing breakdown shouldn't fail the deployment
+        match self.aws_service.inspect_image(&image_tag).await {
+            Ok(image_info) => deployment.image_info = Some(image_info),
+            Err(e) => deployment.append_logs(&format!("Warning: could not inspect image: {}\n", e)),
+        }
+        self.update_deployment(&deployment).await?;
+        
         self.emit_progress(&deployment.id, "Docker image built", 50).await?;
         
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
         // Step 6: Login to ECR (50-55%)
         match self.login_to_ecr(&deployment.id).await {
             Ok(_) => {},
@@ -150,10 +297,19 @@ impl DeploymentOrchestrator {
         
         self.emit_progress(&deployment.id, "Authenticated with ECR", 55).await?;
         
-        // Step 7: Push image to ECR (55-70%)
-        let ecr_image_uri = format!("{}:{}", project.ecr_repository, &commit_info.sha[..8]);
-        match self.push_to_ecr(&image_tag, &ecr_image_uri, &deployment.id).await {
-            Ok(_) => {},
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
+        // Step 7: Push images to ECR (55-70%)
+        let ecr_image_uri = format!("{}:{}", repository_uri, short_sha);
+        let mut app_uris = vec![ecr_image_uri.clone()];
+        app_uris.extend(project.extra_image_tags.iter().map(|tag| format!("{}:{}", repository_uri, tag)));
+        let mut sidecar_images = Vec::new();
+        for (sidecar, spec) in project.sidecars.iter().zip(&sidecar_specs) {
+            let sidecar_uri = format!("{}:{}-{}", repository_uri, sidecar.name, short_sha);
+            sidecar_images.push((spec.image_tag.clone(), sidecar_uri));
+        }
+        let mut pushed = match self.push_to_ecr(&image_tag, app_uris, sidecar_images, &mut deployment).await {
+            Ok(pushed) => pushed,
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
                 self.fail_deployment(&mut deployment, &format!("ECR push failed: {}", e)).await?;
@@ -161,10 +317,34 @@ impl DeploymentOrchestrator {
             }
         };
         
-        self.emit_progress(&deployment.id, "Image pushed to ECR", 70).await?;
+        let image_digest = pushed.remove(0).digest;
+        
+        // Record the pushed digest for integrity/audit
+        deployment.image_digest = Some(image_digest.clone());
+        self.update_deployment(&deployment).await?;
+        
+        self.emit_progress(&deployment.id, &format!("Image pushed to ECR ({})", image_digest), 70).await?;
+        
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
         
         // Step 8: Register ECS task definition (70-80%)
-        let task_arn = match self.register_task_definition(&project, &ecr_image_uri, &deployment.id).await {
+        let pinned_uri = |uri: String, digest: &str| if project.pin_image_digest {
+            format!("{}@{}", repository_uri, digest)
+        } else {
+            uri
+        };
+        let task_image_uri = pinned_uri(ecr_image_uri.clone(), &image_digest);
+        let sidecars = project.sidecars.iter()
+            .zip(pushed)
+            .map(|(sidecar, image)| SidecarContainer {
+                name: sidecar.name.clone(),
+                image_uri: pinned_uri(image.ecr_uri, &image.digest),
+            })
+            .collect();
+        self.report_env_changes(&project, &mut deployment).await?;
+        let deployed_image = if project.pin_image_digest { image_digest.as_str() } else { short_sha };
+        self.persist_terraform(&project, deployed_image, &mut deployment).await?;
+        let task_arn = match self.register_task_definition(&project, &task_image_uri, sidecars, &deployment).await {
             Ok(arn) => arn,
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
@@ -175,8 +355,11 @@ impl DeploymentOrchestrator {
         
         self.emit_progress(&deployment.id, "ECS task definition registered", 80).await?;
         
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
         // Step 9: Deploy to ECS service (80-90%)
-        match self.deploy_to_ecs(&project, &task_arn, &deployment.id).await {
+        let template_service = preview.as_ref().map(|target| target.base_service.as_str());
+        match self.deploy_to_ecs(&project, &task_arn, template_service).await {
             Ok(_) => {},
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
@@ -187,8 +370,27 @@ impl DeploymentOrchestrator {
         
         self.emit_progress(&deployment.id, "Deployment initiated on ECS", 90).await?;
         
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
+        // Fire-and-forget: succeed without waiting, recording that health wasn't checked
+        if !wait_for_healthy {
+            self.cleanup_repository(&repo_path).await;
+            
+            deployment.status = DeploymentStatus::Success;
+            deployment.completed_at = Some(chrono::Utc::now().timestamp());
+            deployment.append_logs("Deploy initiated, health not verified\n");
+            self.update_deployment(&deployment).await?;
+            
+            self.emit_progress(&deployment.id, "Deploy initiated, health not verified", 100).await?;
+            if let Some(target) = &preview {
+                self.report_preview(target, &mut deployment).await?;
+            }
+            
+            return Ok(deployment.id);
+        }
+        
         // Step 10: Monitor until running (90-100%)
-        match self.monitor_deployment(&project, &deployment.id).await {
+        match self.monitor_deployment(&project, &deployment.id, deployment.started_at).await {
             Ok(_) => {},
             Err(e) => {
                 self.cleanup_repository(&repo_path).await;
@@ -197,6 +399,8 @@ impl DeploymentOrchestrator {
             }
         };
         
+        self.stop_if_cancelled(Some(&repo_path), &mut deployment).await?;
+        
         // Cleanup repository
         self.cleanup_repository(&repo_path).await;
         
@@ -206,6 +410,9 @@ impl DeploymentOrchestrator {
         self.update_deployment(&deployment).await?;
         
         self.emit_progress(&deployment.id, "Deployment successful", 100).await?;
+        if let Some(target) = &preview {
+            self.report_preview(target, &mut deployment).await?;
+        }
         
         Ok(deployment.id)
     }
@@ -213,13 +420,22 @@ impl DeploymentOrchestrator {
     // ===== Step Implementations =====
     
     /// Initialize deployment record in database
-    async fn initialize_deployment(&self, project: &Project) -> Result<Deployment, OrchestratorError> {
-        let deployment = Deployment::new(
+    async fn initialize_deployment(
+        &self,
+        project: &Project,
+        notes: Option<String>,
+        trigger: DeploymentTrigger,
+        wait_for_healthy: bool,
+    ) -> Result<Deployment, OrchestratorError> {
+        let mut deployment = Deployment::new(
             project.id.clone(),
-            "pending".to_string(), // Will be updated with actual commit SHA
+            None, // Resolved after the repository is cloned
             None,
             format!("{}:latest", project.name),
+            Some(trigger),
         );
+        deployment.notes = notes;
+        deployment.wait_for_healthy = wait_for_healthy;
         
         let db = self.database.lock()
             .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
@@ -230,6 +446,59 @@ impl DeploymentOrchestrator {
         Ok(deployment)
     }
     
+    /// Get the source to build
+    ///
+    /// With `build_from_archive` set, downloads a snapshot of the branch head
+    /// (or the pinned commit) and returns its commit, since an archive has no
+    /// history to read it from. Hosts without an archive API fall back to a
+    /// clone, which is checked out at the pinned commit if there is one.
+    async fn fetch_source(&self, project: &Project, deployment: &mut Deployment) -> Result<(PathBuf, Option<CommitInfo>), OrchestratorError> {
+        let from_archive = {
+            let db = self.database.lock()
+                .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+            db.get_settings()
+                .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+                .build_from_archive
+        };
+        
+        if from_archive {
+            match self.fetch_archive(project).await {
+                Ok(source) => return Ok(source),
+                Err(e) => {
+                    deployment.append_logs(&format!("Warning: archive download failed, cloning instead: {}\n", e));
+                    self.update_deployment(deployment).await?;
+                }
+            }
+        }
+        
+        let path = self.clone_repository(project, &deployment.id).await?;
+        Ok((path, None))
+    }
+    
+    /// Download an archive of the pinned commit, or of the branch head resolved with `ls-remote`
+    async fn fetch_archive(&self, project: &Project) -> Result<(PathBuf, Option<CommitInfo>), OrchestratorError> {
+        let sha = match &self.commit_sha {
+            Some(sha) => sha.clone(),
+            None => self.git_service
+                .resolve_remote_commit(&project.repository_url, &project.branch, self.git_credentials.as_ref())
+                .await
+                .map_err(|e| OrchestratorError::GitError(e.to_string()))?,
+        };
+        let path = self.git_service
+            .fetch_archive(&project.repository_url, &sha, self.git_credentials.as_ref())
+            .await
+            .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+        
+        let commit = CommitInfo {
+            sha,
+            message: String::new(),
+            author: String::new(),
+            timestamp: chrono::Utc::now().timestamp(),
+            parent_sha: None,
+        };
+        Ok((path, Some(commit)))
+    }
+    
     /// Clone git repository
     async fn clone_repository(&self, project: &Project, deployment_id: &str) -> Result<PathBuf, OrchestratorError> {
         let path = self.git_service
@@ -237,43 +506,119 @@ impl DeploymentOrchestrator {
             .await
             .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
         
+        if let Some(sha) = &self.commit_sha {
+            if let Err(e) = self.git_service.checkout_commit(&path, sha).await {
+                self.cleanup_repository(&path).await;
+                return Err(OrchestratorError::GitError(e.to_string()));
+            }
+        }
+        
         Ok(path)
     }
     
     /// Detect framework from repository
-    async fn detect_framework(&self, repo_path: &PathBuf, deployment_id: &str) -> Result<crate::models::FrameworkType, OrchestratorError> {
-        let framework = self.git_service
-            .detect_framework(repo_path)
-            .await
-            .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
-        
-        Ok(framework)
+    ///
+    /// Reuses the result cached for this commit when the project is redeployed unchanged.
+    async fn detect_framework(&self, project: &Project, repo_path: &PathBuf, commit_sha: Option<&str>, deployment_id: &str) -> Result<crate::models::FrameworkType, OrchestratorError> {
+        match commit_sha {
+            Some(commit_sha) => framework_detection::detect_framework_at(&self.database, self.git_service.as_ref(), &project.id, repo_path, commit_sha).await,
+            None => framework_detection::detect_framework(&self.database, self.git_service.as_ref(), &project.id, repo_path).await,
+        }
     }
     
-    /// Get commit information
-    async fn get_commit_info(&self, repo_path: &PathBuf, deployment_id: &str) -> Result<crate::services::CommitInfo, OrchestratorError> {
+    /// Get commit information, unless it is already known from the remote
+    async fn get_commit_info(&self, repo_path: &PathBuf, known: Option<CommitInfo>, deployment_id: &str) -> Result<crate::services::CommitInfo, OrchestratorError> {
+        if let Some(commit_info) = known {
+            return Ok(commit_info);
+        }
+        
         let commit_info = self.git_service
-            .get_commit_info(repo_path, None)
+            .get_commit_info(repo_path, self.commit_sha.as_deref())
             .await
             .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
         
         Ok(commit_info)
     }
     
-    /// Build Docker image
-    async fn build_docker_image(&self, repo_path: &PathBuf, image_tag: &str, project: &Project, deployment_id: &str) -> Result<(), OrchestratorError> {
-        self.aws_service
-            .build_docker_image(
-                repo_path.to_str().ok_or_else(|| OrchestratorError::AwsError("Invalid path".to_string()))?,
-                image_tag,
-                &project.framework,
-            )
-            .await
-            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    /// Wait for a build slot, if builds are limited
+    async fn acquire_build_slot(&self) -> Result<Option<SemaphorePermit<'_>>, OrchestratorError> {
+        match &self.build_slots {
+            Some(build_slots) => build_slots.acquire()
+                .await
+                .map(Some)
+                .map_err(|e| OrchestratorError::AwsError(format!("Build slots unavailable: {}", e))),
+            None => Ok(None),
+        }
+    }
+    
+    /// Log anything the generated Dockerfile needs but the repo lacks
+    ///
+    /// Fails only when settings ask for missing prerequisites to block the build.
+    async fn check_prerequisites(&self, project: &Project, repo_path: &PathBuf, deployment: &mut Deployment) -> Result<(), OrchestratorError> {
+        let missing = check_build_prerequisites(repo_path, &project.framework);
+        if missing.is_empty() {
+            return Ok(());
+        }
+        
+        for warning in &missing {
+            deployment.append_logs(&format!("Warning: {}\n", warning));
+        }
+        self.update_deployment(deployment).await?;
+        
+        let block = {
+            let db = self.database.lock()
+                .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+            db.get_settings()
+                .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+                .block_on_missing_prerequisites
+        };
+        
+        if block {
+            return Err(OrchestratorError::MissingPrerequisites(missing.join("; ")));
+        }
         
         Ok(())
     }
     
+    /// Build the app image and any sidecar images while ensuring the ECR repository exists
+    ///
+    /// The app image's build output is reported line by line, redacted.
+    /// Returns the repository URI ECR reports, which images are pushed to.
+    async fn build_docker_image(
+        &self,
+        repo_path: &PathBuf,
+        image_tag: &str,
+        project: &Project,
+        sidecar_specs: Vec<BuildSpec>,
+        deployment_id: &str,
+    ) -> Result<String, OrchestratorError> {
+        let source_dir = repo_path.to_str()
+            .ok_or_else(|| OrchestratorError::AwsError("Invalid path".to_string()))?;
+        
+        let (reporter, redactor) = (&self.reporter, &self.redactor);
+        let on_build_line = |line: &str| {
+            // Build output is informational; a lost line shouldn't fail the build
+            let _ = reporter.report_build_log(BuildLogEvent {
+                deployment_id: deployment_id.to_string(),
+                line: redactor.redact(line),
+            });
+        };
+        
+        build_with_repository(
+            self.aws_service.clone(),
+            &Self::ecr_repository_name(&project.ecr_repository),
+            &project.default_tags,
+            source_dir,
+            image_tag,
+            &project.framework,
+            sidecar_specs,
+            MAX_CONCURRENT_IMAGE_OPS,
+            &on_build_line,
+        )
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))
+    }
+    
     /// Login to ECR
     async fn login_to_ecr(&self, deployment_id: &str) -> Result<(), OrchestratorError> {
         self.aws_service
@@ -284,31 +629,119 @@ impl DeploymentOrchestrator {
         Ok(())
     }
     
-    /// Push Docker image to ECR
-    async fn push_to_ecr(&self, local_tag: &str, ecr_uri: &str, deployment_id: &str) -> Result<(), OrchestratorError> {
-        self.aws_service
-            .push_docker_image(local_tag, ecr_uri)
+    /// Push the app image under each of `app_uris`, then the `(local_tag, ecr_uri)` sidecar images
+    ///
+    /// The first of `app_uris` is the tag the deployment runs and must be
+    /// pushed; a failure on any other tag is only logged on the deployment.
+    /// Returns the app image followed by the sidecars in input order, with
+    /// digests. Pushes run `push_concurrency` at a time, as set in settings.
+    async fn push_to_ecr(
+        &self,
+        app_image: &str,
+        app_uris: Vec<String>,
+        sidecar_images: Vec<(String, String)>,
+        deployment: &mut Deployment,
+    ) -> Result<Vec<crate::services::PushedImage>, OrchestratorError> {
+        let max_concurrent = {
+            let db = self.database.lock()
+                .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+            db.get_settings()
+                .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+                .push_concurrency()
+        };
+        
+        let mut tags = push_image_tags(self.aws_service.clone(), app_image, app_uris, max_concurrent)
             .await
-            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+            .into_iter();
+        let app = tags.next()
+            .ok_or_else(|| OrchestratorError::AwsError("No image tag to push".to_string()))?;
+        let digest = app.result.map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+        
+        let failed: Vec<String> = tags
+            .filter_map(|TagPush { ecr_uri, result }| result.err().map(|e| format!("{} ({})", ecr_uri, e)))
+            .collect();
+        if !failed.is_empty() {
+            deployment.append_logs(&format!("Warning: extra image tags not pushed: {}\n", failed.join("; ")));
+            self.update_deployment(deployment).await?;
+        }
+        
+        let mut pushed = vec![crate::services::PushedImage {
+            local_tag: app_image.to_string(),
+            ecr_uri: app.ecr_uri,
+            digest,
+        }];
+        pushed.extend(
+            push_images(self.aws_service.clone(), sidecar_images, max_concurrent)
+                .await
+                .map_err(|e| OrchestratorError::AwsError(e.to_string()))?,
+        );
+        
+        Ok(pushed)
+    }
+    
+    /// Log and report how the container environment differs from the live task
+    ///
+    /// Best effort: nothing is reported when the service doesn't exist yet or
+    /// can't be described.
+    async fn report_env_changes(&self, project: &Project, deployment: &mut Deployment) -> Result<(), OrchestratorError> {
+        let live = match self.aws_service.describe_service_definition(&project.aws_cluster, &project.aws_service).await {
+            Ok(live) => live,
+            Err(_) => return Ok(()),
+        };
+        
+        let diff = diff_env_vars(&Self::container_environment(project, deployment), &live.environment);
+        if diff.is_empty() {
+            return Ok(());
+        }
+        
+        let message = format!("Environment changes: {}", diff.summary());
+        deployment.append_logs(&format!("{}\n", message));
+        self.update_deployment(deployment).await?;
+        self.emit_progress(&deployment.id, &message, 72).await
+    }
+    
+    /// Store the Terraform describing this deployment's infrastructure, when enabled in settings
+    ///
+    /// Best effort: a failure to generate or save it is logged and the deployment continues.
+    async fn persist_terraform(&self, project: &Project, image_tag: &str, deployment: &mut Deployment) -> Result<(), OrchestratorError> {
+        if let Err(e) = self.save_terraform(project, image_tag, &deployment.id) {
+            deployment.append_logs(&format!("Warning: could not store Terraform: {}\n", e));
+            self.update_deployment(deployment).await?;
+        }
         
         Ok(())
     }
     
-    /// Register ECS task definition
-    async fn register_task_definition(&self, project: &Project, image_uri: &str, deployment_id: &str) -> Result<String, OrchestratorError> {
-        let port = AwsService::get_framework_port(&project.framework);
+    /// Render the Terraform for `project` running `image_tag` and save it with the deployment
+    fn save_terraform(&self, project: &Project, image_tag: &str, deployment_id: &str) -> Result<(), OrchestratorError> {
+        let db = self.database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        let enabled = db.get_settings()
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+            .persist_terraform;
+        if !enabled {
+            return Ok(());
+        }
         
-        let config = EcsDeploymentConfig {
-            cluster_name: project.aws_cluster.clone(),
-            service_name: project.aws_service.clone(),
-            task_family: format!("{}-task", project.name),
-            container_name: format!("{}-container", project.name),
-            image_uri: image_uri.to_string(),
-            cpu: "512".to_string(),
-            memory: "1024".to_string(),
-            port,
-            desired_count: 1,
-        };
+        let mut config = TerraformConfig::for_project(project, self.aws_service.region());
+        config.image_tag = image_tag.to_string();
+        let files = self.terraform_service.render_terraform(&config)
+            .map_err(|e| OrchestratorError::TerraformError(e.to_string()))?;
+        db.save_deployment_artifacts(deployment_id, &files)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))
+    }
+    
+    /// Register ECS task definition
+    async fn register_task_definition(
+        &self,
+        project: &Project,
+        image_uri: &str,
+        sidecars: Vec<SidecarContainer>,
+        deployment: &Deployment,
+    ) -> Result<String, OrchestratorError> {
+        let mut config = Self::build_ecs_config(project, image_uri);
+        config.sidecars = sidecars;
+        config.environment = Self::container_environment(project, deployment);
         
         let task_arn = self.aws_service
             .register_task_definition(&config)
@@ -319,20 +752,11 @@ impl DeploymentOrchestrator {
     }
     
     /// Deploy to ECS service
-    async fn deploy_to_ecs(&self, project: &Project, task_arn: &str, deployment_id: &str) -> Result<(), OrchestratorError> {
-        let port = AwsService::get_framework_port(&project.framework);
-        
-        let config = EcsDeploymentConfig {
-            cluster_name: project.aws_cluster.clone(),
-            service_name: project.aws_service.clone(),
-            task_family: format!("{}-task", project.name),
-            container_name: format!("{}-container", project.name),
-            image_uri: String::new(), // Not used in update
-            cpu: "512".to_string(),
-            memory: "1024".to_string(),
-            port,
-            desired_count: 1,
-        };
+    ///
+    /// A missing service is created like `template_service`, if given.
+    async fn deploy_to_ecs(&self, project: &Project, task_arn: &str, template_service: Option<&str>) -> Result<(), OrchestratorError> {
+        let mut config = Self::build_ecs_config(project, ""); // Image not used in update
+        config.template_service = template_service.map(str::to_string);
         
         self.aws_service
             .deploy_service(&config, task_arn)
@@ -343,42 +767,85 @@ impl DeploymentOrchestrator {
     }
     
     /// Monitor deployment until service is healthy
-    async fn monitor_deployment(&self, project: &Project, deployment_id: &str) -> Result<(), OrchestratorError> {
-        // Poll service health for up to 5 minutes
-        let max_attempts = 30; // 30 attempts * 10 seconds = 5 minutes
-        let mut attempts = 0;
+    ///
+    /// Tasks that stopped with an image pull error since `since` trigger a
+    /// forced redeploy, up to the configured number of retries.
+    async fn monitor_deployment(&self, project: &Project, deployment_id: &str, since: i64) -> Result<(), OrchestratorError> {
+        let pull_retries = {
+            let db = self.database.lock()
+                .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+            db.get_settings()
+                .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+                .image_pull_retries
+        };
         
-        loop {
-            attempts += 1;
-            
-            if attempts > max_attempts {
-                return Err(OrchestratorError::AwsError(
-                    "Deployment timeout: service did not become healthy".to_string()
-                ));
-            }
-            
-            // Check service health
-            let health = self.aws_service
-                .get_service_health(&project.aws_cluster, &project.aws_service)
-                .await
-                .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
-            
-            if health.is_healthy {
-                // Service is healthy!
-                return Ok(());
-            }
-            
-            // Update progress based on running vs desired count
-            let progress = 90 + (10 * health.running_count / health.desired_count.max(1)) as u8;
-            self.emit_progress(
-                deployment_id,
-                &format!("Waiting for service to stabilize ({}/{})", health.running_count, health.desired_count),
-                progress.min(99), // Cap at 99% until fully healthy
-            ).await?;
-            
-            // Wait 10 seconds before next check
-            tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-        }
+        // Poll service health for up to 5 minutes per attempt; the shared monitor
+        // batches polls with other deployments on the same cluster
+        let policy = RolloutPolicy {
+            max_polls: 24, // With the default backoff: 12 * 5s + 8 * 15s + 4 * 30s = 5 minutes
+            pull_retries,
+            poll_backoff: self.poll_backoff.clone(),
+        };
+        
+        wait_for_rollout(
+            &self.service_monitor,
+            self.aws_service.as_ref(),
+            &project.aws_cluster,
+            &project.aws_service,
+            policy,
+            since,
+            |event| match event {
+                RolloutEvent::Waiting(health) => {
+                    // Update progress based on running vs desired count
+                    let progress = 90 + (10 * health.running_count / health.desired_count.max(1)) as u8;
+                    self.send_progress(
+                        deployment_id,
+                        &format!("Waiting for service to stabilize ({}/{})", health.running_count, health.desired_count),
+                        progress.min(99), // Cap at 99% until fully healthy
+                    )
+                }
+                RolloutEvent::PullRetry { attempt, reason } => self.send_progress(
+                    deployment_id,
+                    &format!("Image pull failed, redeploying (retry {}/{}): {}", attempt, pull_retries, reason),
+                    90,
+                ),
+            },
+        ).await
+    }
+    
+    /// Names for a Preview project's branch, with its hostname under the configured preview domain
+    fn preview_target(&self, project: &Project) -> Result<PreviewTarget, OrchestratorError> {
+        let preview_domain = {
+            let db = self.database.lock()
+                .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+            db.get_settings()
+                .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+                .preview_domain
+        };
+        
+        Ok(PreviewTarget::for_project(project, preview_domain.as_deref()))
+    }
+    
+    /// Record a Preview deployment's derived names
+    ///
+    /// Nothing creates the target group or DNS record for the hostname yet,
+    /// so the message names the service rather than promising a URL.
+    async fn report_preview(&self, target: &PreviewTarget, deployment: &mut Deployment) -> Result<(), OrchestratorError> {
+        deployment.append_logs(&format!(
+            "Preview service: {}, target group: {}\n",
+            target.service_name, target.target_group_name
+        ));
+        let message = match &target.hostname {
+            Some(hostname) => format!(
+                "Preview service {} deployed; {} is not routed to it yet",
+                target.service_name, hostname
+            ),
+            None => format!("Preview service {} deployed", target.service_name),
+        };
+        deployment.append_logs(&format!("{}\n", message));
+        self.update_deployment(deployment).await?;
+        
+        self.emit_progress(&deployment.id, &message, 100).await
     }
     
     /// Cleanup cloned repository
@@ -389,20 +856,85 @@ impl DeploymentOrchestrator {
     
     // ===== Helper Methods =====
     
-    /// Emit progress event to frontend
+    /// Build the ECS deployment configuration for a project
+    pub(crate) fn build_ecs_config(project: &Project, image_uri: &str) -> EcsDeploymentConfig {
+        let spec = resolve_deployment_spec(project);
+        
+        EcsDeploymentConfig {
+            cluster_name: project.aws_cluster.clone(),
+            service_name: project.aws_service.clone(),
+            task_family: format!("{}-task", project.name),
+            container_name: format!("{}-container", project.name),
+            image_uri: image_uri.to_string(),
+            cpu: spec.cpu.to_string(),
+            memory: spec.memory.to_string(),
+            port: spec.port,
+            port_protocol: spec.port_protocol,
+            desired_count: spec.desired_count,
+            stop_timeout: project.stop_timeout
+                .or_else(|| project.environment.default_stop_timeout()),
+            sidecars: Vec::new(),
+            ephemeral_storage_gib: spec.ephemeral_storage_gib,
+            launch_type: spec.launch_type,
+            placement_strategies: spec.placement_strategies,
+            tags: project.default_tags.clone(),
+            ..Default::default()
+        }
+    }
+    
+    /// Container environment: deployment metadata (if enabled) overlaid by the project's variables
+    pub(crate) fn container_environment(project: &Project, deployment: &Deployment) -> BTreeMap<String, String> {
+        let mut environment = BTreeMap::new();
+        
+        if project.inject_deploy_metadata {
+            if let Some(sha) = &deployment.commit_sha {
+                environment.insert("DEPLOY_COMMIT_SHA".to_string(), sha.clone());
+            }
+            environment.insert("DEPLOY_BRANCH".to_string(), project.branch.clone());
+            environment.insert("DEPLOY_TIMESTAMP".to_string(), deployment.started_at.to_string());
+            environment.insert("DEPLOY_ID".to_string(), deployment.id.clone());
+        }
+        
+        environment.extend(project.environment_variables.clone());
+        environment
+    }
+    
+    /// Repository name from an ECR repository URI; bare names are returned as is
+    fn ecr_repository_name(ecr_repository: &str) -> String {
+        EcrUri::parse(ecr_repository)
+            .map(|uri| uri.repository)
+            .unwrap_or_else(|| ecr_repository.to_string())
+    }
+    
+    /// Build specs for the project's sidecar images, tagged `{project}-{sidecar}:{sha}`
+    fn sidecar_build_specs(project: &Project, repo_path: &PathBuf, short_sha: &str) -> Vec<BuildSpec> {
+        project.sidecars.iter()
+            .map(|sidecar| BuildSpec {
+                dockerfile_path: Some(repo_path.join(&sidecar.dockerfile_path).to_string_lossy().into_owned()),
+                image_tag: format!("{}-{}:{}", project.name, sidecar.name, short_sha),
+                context: repo_path.join(&sidecar.context).to_string_lossy().into_owned(),
+            })
+            .collect()
+    }
+    
+    /// Report deployment progress
     async fn emit_progress(&self, deployment_id: &str, message: &str, progress: u8) -> Result<(), OrchestratorError> {
-        let event = ProgressEvent {
-            deployment_id: deployment_id.to_string(),
-            step: message.to_string(),
+        self.send_progress(deployment_id, message, progress)
+    }
+    
+    /// Report deployment progress from synchronous callbacks, recording it in the deployment's history
+    fn send_progress(&self, deployment_id: &str, message: &str, progress: u8) -> Result<(), OrchestratorError> {
+        let record = ProgressRecord {
             progress,
             message: message.to_string(),
+            recorded_at_ms: chrono::Utc::now().timestamp_millis(),
         };
+        // Best effort: losing a history entry shouldn't fail the deployment
+        if let Ok(db) = self.database.lock() {
+            let _ = db.record_progress(deployment_id, &record);
+        }
         
-        self.window
-            .emit("deployment-progress", event)
-            .map_err(|e| OrchestratorError::EventError(e.to_string()))?;
-        
-        Ok(())
+        self.reporter.report(ProgressEvent::new(deployment_id, message, progress))
     }
     
     /// Update deployment record in database
@@ -416,6 +948,21 @@ impl DeploymentOrchestrator {
         Ok(())
     }
     
+    /// End the deployment between steps if it has been cancelled
+    ///
+    /// Cleans up the checkout, if any, and records the stop like any other failure.
+    async fn stop_if_cancelled(&self, repo_path: Option<&PathBuf>, deployment: &mut Deployment) -> Result<(), OrchestratorError> {
+        if !self.cancel.is_cancelled() {
+            return Ok(());
+        }
+        
+        if let Some(repo_path) = repo_path {
+            self.cleanup_repository(repo_path).await;
+        }
+        self.fail_deployment(deployment, EMERGENCY_STOP_MESSAGE).await?;
+        Err(OrchestratorError::Cancelled)
+    }
+    
     /// Mark deployment as failed and update database
     async fn fail_deployment(&self, deployment: &mut Deployment, error: &str) -> Result<(), OrchestratorError> {
         deployment.status = DeploymentStatus::Failed;
@@ -430,3 +977,539 @@ impl DeploymentOrchestrator {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::progress::RecordingProgressReporter;
+    use crate::models::{AppSettings, FrameworkType, PortProtocol, ResourceSize};
+    use crate::services::AwsService;
+    use crate::shadow::test_utils::{test_config_with_failures, TestOrchestrator};
+    use crate::shadow::{MockGitService, ShadowState};
+    use std::time::Duration;
+    
+    fn setup(state: Arc<ShadowState>) -> (DeploymentOrchestrator, Arc<RecordingProgressReporter>, Project) {
+        let env = TestOrchestrator::new(state);
+        (env.orchestrator, env.reporter, env.project)
+    }
+    
+    #[test]
+    fn test_project_port_overrides_framework_default() {
+        let (_, _, mut project) = setup(Arc::new(ShadowState::new()));
+        project.framework = FrameworkType::Go;
+        
+        let config = DeploymentOrchestrator::build_ecs_config(&project, "web:latest");
+        assert_eq!(config.port, AwsService::get_framework_port(&FrameworkType::Go));
+        
+        project.container_port = Some(3000);
+        let config = DeploymentOrchestrator::build_ecs_config(&project, "web:latest");
+        assert_eq!(config.port, 3000);
+    }
+    
+    #[test]
+    fn test_ecs_config_and_terraform_resolve_identically() {
+        let (_, _, mut project) = setup(Arc::new(ShadowState::new()));
+        project.environment = Environment::Production;
+        project.ephemeral_storage_gib = Some(40);
+        project.port_protocol = PortProtocol::Udp;
+        
+        for (framework, container_port, resource_size) in [
+            (FrameworkType::Python, None, ResourceSize::Small),
+            (FrameworkType::Go, Some(9000), ResourceSize::Large),
+            (FrameworkType::NextJs, None, ResourceSize::Custom(1024, 3072)),
+        ] {
+            project.framework = framework;
+            project.container_port = container_port;
+            project.resource_size = resource_size;
+            
+            let ecs = DeploymentOrchestrator::build_ecs_config(&project, "web:latest");
+            let terraform = TerraformConfig::for_project(&project, "us-east-1");
+            
+            assert_eq!(terraform.container_port, ecs.port);
+            assert_eq!((terraform.port_protocol, ecs.port_protocol), (PortProtocol::Udp, PortProtocol::Udp));
+            assert_eq!((&terraform.cpu, &terraform.memory), (&ecs.cpu, &ecs.memory));
+            assert_eq!(terraform.desired_count, ecs.desired_count);
+            assert_eq!(terraform.ephemeral_storage_gib, ecs.ephemeral_storage_gib);
+            assert_eq!(terraform.placement_strategies, ecs.placement_strategies);
+        }
+        assert_eq!(TerraformConfig::for_project(&project, "us-east-1").ecr_repository_name, "web");
+    }
+    
+    #[tokio::test]
+    async fn test_mock_deployment_reports_progress_in_order() {
+        let (orchestrator, reporter, project) = setup(Arc::new(ShadowState::new()));
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let events = reporter.events();
+        assert!(events.iter().all(|event| event.deployment_id == deployment_id));
+        assert_eq!(
+            events.iter().map(|event| event.progress).collect::<Vec<_>>(),
+            vec![10, 20, 25, 30, 50, 55, 70, 80, 90, 100],
+        );
+        assert_eq!(events[0].message, "Initializing deployment");
+        assert_eq!(events[1].message, "Repository cloned");
+        assert!(events[2].message.starts_with("Framework detected: "));
+        assert!(events[3].message.starts_with("Commit: "));
+        assert_eq!(events[4].message, "Docker image built");
+        assert_eq!(events[5].message, "Authenticated with ECR");
+        assert!(events[6].message.starts_with("Image pushed to ECR (sha256:"));
+        assert_eq!(events[7].message, "ECS task definition registered");
+        assert_eq!(events[8].message, "Deployment initiated on ECS");
+        assert_eq!(events[9].message, "Deployment successful");
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Success);
+        
+        // The same updates are kept as the deployment's history
+        let detail = orchestrator.database.lock().unwrap().get_deployment_detail(&deployment_id).unwrap();
+        assert_eq!(
+            detail.progress.iter().map(|record| record.message.as_str()).collect::<Vec<_>>(),
+            events.iter().map(|event| event.message.as_str()).collect::<Vec<_>>(),
+        );
+        assert_eq!(detail.phase_timings.len(), events.len() - 1);
+    }
+    
+    #[tokio::test]
+    async fn test_disabled_project_refuses_to_deploy() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, reporter, mut project) = setup(state.clone());
+        project.deploys_enabled = false;
+        let project_id = project.id.clone();
+        
+        let result = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await;
+        
+        assert!(matches!(result, Err(OrchestratorError::DeploysDisabled(name)) if name == "web"));
+        // Refused before anything was recorded, cloned or called
+        assert!(orchestrator.database.lock().unwrap().get_deployments_for_project(&project_id).unwrap().is_empty());
+        assert!(reporter.events().is_empty());
+        assert!(state.called_operations().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_pushed_digest_recorded_on_deployment() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, mut project) = setup(state.clone());
+        project.pin_image_digest = true;
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        let digest = deployment.image_digest.unwrap();
+        assert!(digest.starts_with("sha256:"));
+        // The digest is the one ECR reported for the push, and the task runs it
+        assert_eq!(state.get_ecr_images("web"), vec![digest.clone()]);
+        let task = state.get_task_definition_config("web-task").unwrap();
+        assert!(task.image_uri.ends_with(&format!("/web@{}", digest)));
+    }
+    
+    #[tokio::test]
+    async fn test_extra_image_tags_pushed_and_failures_only_logged() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, mut project) = setup(state.clone());
+        project.extra_image_tags = vec!["production".to_string(), "v1.4.0".to_string()];
+        let repository = resolve_ecr_repository(&project.ecr_repository, &project.environment).unwrap();
+        state.fail_push_to(&format!("{}:v1.4.0", repository));
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Success);
+        let sha_tag = format!("{}:{}", repository, &deployment.commit_sha.unwrap()[..8]);
+        assert!(state.has_docker_image(&sha_tag));
+        assert!(state.has_docker_image(&format!("{}:production", repository)));
+        assert!(!state.has_docker_image(&format!("{}:v1.4.0", repository)));
+        assert_eq!(state.get_task_definition_config("web-task").unwrap().image_uri, sha_tag);
+        let logs = deployment.logs.unwrap();
+        assert!(logs.contains(&format!("Warning: extra image tags not pushed: {}:v1.4.0", repository)), "{}", logs);
+    }
+    
+    #[tokio::test]
+    async fn test_build_output_reported_in_order_and_redacted() {
+        let (orchestrator, reporter, project) = setup(Arc::new(ShadowState::new()));
+        let orchestrator = orchestrator.with_redactor(Arc::new(Redactor::new(&["node:\\d+-alpine".to_string()])));
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let logs = reporter.build_logs();
+        assert!(logs.iter().all(|event| event.deployment_id == deployment_id));
+        let lines: Vec<&str> = logs.iter().map(|event| event.line.as_str()).collect();
+        assert_eq!(lines[..5], [
+            "#1 [internal] load build definition from Dockerfile",
+            "#2 [internal] load metadata for docker.io/library/[REDACTED]",
+            "#3 [1/3] WORKDIR /app",
+            "#4 [2/3] COPY . .",
+            "#5 [3/3] RUN npm ci --omit=dev",
+        ]);
+        assert_eq!(lines.len(), 6);
+        assert!(lines[5].starts_with("#6 naming to web:"));
+    }
+    
+    #[tokio::test]
+    async fn test_preview_deployment_derives_names_from_branch() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, reporter, mut project) = setup(state.clone());
+        project.environment = Environment::Preview;
+        project.branch = "PR-123".to_string();
+        orchestrator.database.lock().unwrap().update_project(&project).unwrap();
+        let settings = AppSettings {
+            preview_domain: Some("preview.example.com".to_string()),
+            ..AppSettings::default()
+        };
+        orchestrator.database.lock().unwrap().save_settings(&settings).unwrap();
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        assert!(state.get_service_status("cluster", "web-service-pr-123-15a88775").is_some());
+        assert!(state.get_service_status("cluster", "web-service").is_none());
+        
+        let last = reporter.events().pop().unwrap();
+        assert_eq!(
+            last.message,
+            "Preview service web-service-pr-123-15a88775 deployed; pr-123-15a88775.preview.example.com is not routed to it yet"
+        );
+        assert!(!last.message.contains("https://"));
+        assert_eq!(last.progress, 100);
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        assert!(deployment.logs.unwrap().contains("Preview service: web-service-pr-123-15a88775, target group: web-pr-123-15a88775"));
+    }
+    
+    #[tokio::test]
+    async fn test_terraform_persisted_with_deployment_when_enabled() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, project) = setup(state.clone());
+        
+        // Off by default
+        let deployment_id = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        assert!(orchestrator.database.lock().unwrap().get_deployment_artifacts(&deployment_id).unwrap().is_empty());
+        
+        let settings = AppSettings {
+            persist_terraform: true,
+            ..AppSettings::default()
+        };
+        orchestrator.database.lock().unwrap().save_settings(&settings).unwrap();
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let artifacts = orchestrator.database.lock().unwrap().get_deployment_artifacts(&deployment_id).unwrap();
+        assert_eq!(
+            artifacts.keys().map(String::as_str).collect::<Vec<_>>(),
+            vec!["main.tf", "outputs.tf", "terraform.tfvars", "variables.tf"],
+        );
+        assert!(artifacts["terraform.tfvars"].contains("project_name = \"web\""));
+        
+        // The stored Terraform runs the image this deployment pushed, not `latest`
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        let short_sha = &deployment.commit_sha.unwrap()[..8];
+        assert!(artifacts["terraform.tfvars"].contains(&format!("image_tag          = \"{}\"", short_sha)));
+    }
+    
+    #[tokio::test]
+    async fn test_terraform_failure_does_not_fail_deployment() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, mut project) = setup(state.clone());
+        
+        // A relative health check path can't be rendered
+        project.network.health_check_path = Some("health".to_string());
+        orchestrator.database.lock().unwrap().update_project(&project).unwrap();
+        let settings = AppSettings {
+            persist_terraform: true,
+            ..AppSettings::default()
+        };
+        orchestrator.database.lock().unwrap().save_settings(&settings).unwrap();
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let db = orchestrator.database.lock().unwrap();
+        assert_eq!(db.get_deployment(&deployment_id).unwrap().status, DeploymentStatus::Success);
+        assert!(db.get_deployment_artifacts(&deployment_id).unwrap().is_empty());
+        assert!(db.get_deployment(&deployment_id).unwrap().logs.unwrap().contains("Warning: could not store Terraform"));
+    }
+    
+    #[tokio::test]
+    async fn test_deployment_calls_match_iam_preflight_actions() {
+        use crate::application::iam_preflight;
+        
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, mut project) = setup(state.clone());
+        
+        // Two tasks take two polls to start, so the rollout also checks stopped tasks
+        project.desired_count = Some(2);
+        orchestrator.database.lock().unwrap().update_project(&project).unwrap();
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Success);
+        
+        let called = state.called_operations();
+        for operation in &called {
+            assert!(iam_preflight::operation_actions(operation).is_some(), "no IAM actions listed for {}", operation);
+        }
+        assert_eq!(iam_preflight::actions_for(&called), iam_preflight::deployment_actions());
+    }
+    
+    #[tokio::test]
+    async fn test_build_from_archive_skips_clone() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, reporter, project) = setup(state.clone());
+        let settings = AppSettings {
+            build_from_archive: true,
+            ..AppSettings::default()
+        };
+        orchestrator.database.lock().unwrap().save_settings(&settings).unwrap();
+        
+        let deployment_id = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        assert!(state.get_fetched_archive(&project.repository_url).is_some());
+        assert!(state.get_cloned_repo(&project.repository_url).is_none());
+        assert!(reporter.events().iter().any(|event| event.message == "Repository archive downloaded"));
+        
+        // The commit comes from ls-remote rather than the (history-less) checkout
+        let remote_sha = orchestrator.git_service.resolve_remote_commit(&project.repository_url, &project.branch, None).await.unwrap();
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Success);
+        assert_eq!(deployment.commit_sha.as_deref(), Some(remote_sha.as_str()));
+        assert_eq!(deployment.commit_message, None);
+    }
+    
+    #[tokio::test]
+    async fn test_ecr_repository_resolved_for_environment() {
+        for (environment, repository) in [
+            (Environment::Production, "web-production"),
+            (Environment::Development, "web-development"),
+        ] {
+            let state = Arc::new(ShadowState::new());
+            let (orchestrator, _, mut project) = setup(state.clone());
+            project.environment = environment;
+            project.ecr_repository = "123456789012.dkr.ecr.us-east-1.amazonaws.com/web-{environment}".to_string();
+            
+            orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+            
+            assert!(state.get_ecr_repository(repository).is_some(), "{} repository not used", repository);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_task_definition_carries_deploy_metadata() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, mut project) = setup(state.clone());
+        project.environment_variables.insert("DEPLOY_BRANCH".to_string(), "release".to_string());
+        project.environment_variables.insert("LOG_LEVEL".to_string(), "debug".to_string());
+        let task_family = format!("{}-task", project.name);
+        
+        let deployment_id = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        let environment = state.get_task_definition_config(&task_family).unwrap().environment;
+        assert_eq!(environment.get("DEPLOY_COMMIT_SHA"), deployment.commit_sha.as_ref());
+        assert_eq!(environment["DEPLOY_ID"], deployment_id);
+        assert_eq!(environment["DEPLOY_TIMESTAMP"], deployment.started_at.to_string());
+        // Project variables win over injected metadata
+        assert_eq!(environment["DEPLOY_BRANCH"], "release");
+        assert_eq!(environment["LOG_LEVEL"], "debug");
+        
+        project.inject_deploy_metadata = false;
+        orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        let environment = state.get_task_definition_config(&task_family).unwrap().environment;
+        assert!(!environment.contains_key("DEPLOY_COMMIT_SHA"));
+        assert_eq!(environment["LOG_LEVEL"], "debug");
+    }
+    
+    #[tokio::test]
+    async fn test_env_changes_reported_against_live_task() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, reporter, mut project) = setup(state);
+        project.environment_variables.insert("DATABASE_URL".to_string(), "postgres://old".to_string());
+        project.environment_variables.insert("LEGACY_FLAG".to_string(), "1".to_string());
+        
+        // Nothing live to compare against on the first deployment
+        orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let is_env_event = |event: &&ProgressEvent| event.message.starts_with("Environment changes");
+        assert!(!reporter.events().iter().any(|event| is_env_event(&event)));
+        
+        project.environment_variables.insert("DATABASE_URL".to_string(), "postgres://new".to_string());
+        project.environment_variables.remove("LEGACY_FLAG");
+        project.environment_variables.insert("API_KEY".to_string(), "sk-live-123".to_string());
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let events = reporter.events();
+        let env_events: Vec<&ProgressEvent> = events.iter().filter(is_env_event).collect();
+        assert_eq!(env_events.len(), 1);
+        assert_eq!(env_events[0].message, "Environment changes: +API_KEY, -LEGACY_FLAG, ~DATABASE_URL");
+        let logs = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap().logs.unwrap_or_default();
+        assert!(logs.contains("~DATABASE_URL"));
+        assert!(!logs.contains("sk-live-123"));
+    }
+    
+    #[tokio::test]
+    async fn test_skipping_health_wait_succeeds_without_polling() {
+        let state = Arc::new(ShadowState::new());
+        state.fail_operation("get_service_health");
+        state.fail_operation("describe_services");
+        let (orchestrator, reporter, project) = setup(state.clone());
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, false).await.unwrap();
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Success);
+        assert!(!deployment.wait_for_healthy);
+        assert!(deployment.logs.unwrap().contains("health not verified"));
+        assert_eq!(state.describe_services_calls(), 0);
+        assert_eq!(reporter.events().last().unwrap().message, "Deploy initiated, health not verified");
+    }
+    
+    #[tokio::test]
+    async fn test_image_info_recorded_on_deployment() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, project) = setup(state);
+        
+        let deployment_id = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&deployment_id).unwrap();
+        let image_info = deployment.image_info.unwrap();
+        assert!(image_info.size_bytes > 0);
+        assert_eq!(image_info.size_bytes, image_info.layers.iter().map(|layer| layer.size_bytes).sum::<u64>());
+    }
+    
+    #[tokio::test]
+    async fn test_build_slots_limit_concurrent_builds() {
+        for (slots, expected_overlap) in [(1, 1), (2, 2)] {
+            let state = Arc::new(ShadowState::new());
+            state.set_build_duration(Duration::from_millis(50));
+            let (orchestrator, _, project) = setup(state.clone());
+            let orchestrator = orchestrator.with_build_slots(Arc::new(Semaphore::new(slots)));
+            
+            let (first, second) = tokio::join!(
+                orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true),
+                orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true),
+            );
+            first.unwrap();
+            second.unwrap();
+            
+            assert_eq!(state.max_concurrent_builds(), expected_overlap, "with {} build slot(s)", slots);
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_default_tags_applied_to_created_resources() {
+        let state = Arc::new(ShadowState::new());
+        let (orchestrator, _, mut project) = setup(state.clone());
+        let tags = vec![
+            ("team".to_string(), "platform".to_string()),
+            ("cost-center".to_string(), "1234".to_string()),
+        ];
+        project.default_tags = tags.clone();
+        let task_family = format!("{}-task", project.name);
+        
+        orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await.unwrap();
+        
+        assert_eq!(state.get_ecr_repository_tags("web"), tags);
+        assert_eq!(state.get_task_definition_config(&task_family).unwrap().tags, tags);
+    }
+    
+    #[tokio::test]
+    async fn test_ecr_repository_failure_aborts_with_cleanup() {
+        let state = Arc::new(ShadowState::new());
+        state.fail_operation("ensure_ecr_repository");
+        let (orchestrator, reporter, project) = setup(state.clone());
+        
+        let result = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await;
+        assert!(matches!(result, Err(OrchestratorError::AwsError(_))));
+        
+        let last = reporter.events().last().unwrap().clone();
+        assert!(last.message.starts_with("Deployment failed: Build stage failed"), "{}", last.message);
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&last.deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Failed);
+        
+        // Nothing was pushed or registered, and the checkout was removed
+        let operations = state.called_operations();
+        assert!(!operations.iter().any(|operation| operation == "push_docker_image" || operation == "register_task_definition"));
+        let repo_path = state.get_cloned_repo(&project.repository_url)
+            .or_else(|| state.get_fetched_archive(&project.repository_url))
+            .unwrap();
+        assert!(!std::path::Path::new(&repo_path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_clone_failure_leaves_commit_sha_unset() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { database, aws, reporter, project, .. } = TestOrchestrator::new(state.clone());
+        let git: Arc<dyn GitOperations> = Arc::new(MockGitService::new(test_config_with_failures(1.0), state));
+        let orchestrator = DeploymentOrchestrator::new(
+            database.clone(),
+            git,
+            aws.clone(),
+            Arc::new(TerraformService::new()),
+            Arc::new(ServiceMonitor::new(aws, Duration::from_millis(5))),
+            reporter,
+        );
+        
+        let result = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await;
+        assert!(matches!(result, Err(OrchestratorError::GitError(_))));
+        
+        let deployments = database.lock().unwrap().get_deployments_for_project(&project.id).unwrap();
+        assert_eq!(deployments.len(), 1);
+        assert_eq!(deployments[0].status, DeploymentStatus::Failed);
+        assert_eq!(deployments[0].commit_sha, None);
+        assert!(deployments[0].error_message.as_deref().unwrap().starts_with("Git clone failed"));
+    }
+    
+    #[test]
+    fn test_ecr_repository_name_from_uri() {
+        assert_eq!(DeploymentOrchestrator::ecr_repository_name("123456789012.dkr.ecr.us-east-1.amazonaws.com/team/web:latest"), "team/web");
+        assert_eq!(DeploymentOrchestrator::ecr_repository_name("web"), "web");
+    }
+    
+    #[tokio::test]
+    async fn test_cancelled_deployment_stops_after_current_step() {
+        let state = Arc::new(ShadowState::new());
+        state.set_build_duration(Duration::from_millis(50));
+        let (orchestrator, reporter, project) = setup(state.clone());
+        let cancel = CancelToken::new();
+        let orchestrator = orchestrator.with_cancel_token(cancel.clone());
+        
+        // Cancel while the image is building; the build itself is left to finish
+        let stopper = {
+            let state = state.clone();
+            tokio::spawn(async move {
+                while state.max_concurrent_builds() == 0 {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                }
+                cancel.cancel();
+            })
+        };
+        let result = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await;
+        stopper.await.unwrap();
+        assert!(matches!(result, Err(OrchestratorError::Cancelled)));
+        
+        let last = reporter.events().last().unwrap().clone();
+        assert_eq!(last.message, format!("Deployment failed: {}", EMERGENCY_STOP_MESSAGE));
+        let deployment = orchestrator.database.lock().unwrap().get_deployment(&last.deployment_id).unwrap();
+        assert_eq!(deployment.status, DeploymentStatus::Failed);
+        assert!(state.has_docker_image(&format!("web:{}", &deployment.commit_sha.unwrap()[..8])));
+        
+        // Nothing after the build ran, and the checkout was removed
+        assert!(!state.called_operations().iter().any(|operation| operation == "docker_login_ecr"));
+        let repo_path = state.get_cloned_repo(&project.repository_url)
+            .or_else(|| state.get_fetched_archive(&project.repository_url))
+            .unwrap();
+        assert!(!std::path::Path::new(&repo_path).exists());
+    }
+    
+    #[tokio::test]
+    async fn test_failed_step_reports_failure_last() {
+        let state = Arc::new(ShadowState::new());
+        state.fail_operation("docker_login_ecr");
+        let (orchestrator, reporter, project) = setup(state);
+        
+        let result = orchestrator.run_deployment(project, None, DeploymentTrigger::Manual, true).await;
+        assert!(matches!(result, Err(OrchestratorError::AwsError(_))));
+        
+        let events = reporter.events();
+        assert_eq!(events[events.len() - 2].message, "Docker image built");
+        let last = events.last().unwrap();
+        assert!(last.message.starts_with("Deployment failed: ECR login failed"));
+        assert_eq!(last.progress, 0);
+    }
+}
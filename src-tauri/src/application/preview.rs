@@ -0,0 +1,174 @@
+//! Per-branch deployments for the Preview environment
+//!
+//! Every branch of a Preview project gets its own ECS service, created in the
+//! project's service's network on first deploy, plus a target group name and
+//! hostname, all derived from the branch so the same branch always
+//! lands on the same names (`pr-123-1a2b3c4d.preview.example.com`). A short
+//! hash of the branch keeps branches that slug alike, such as `feature/a`
+//! and `feature-a`, apart.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{Environment, Project};
+use crate::services::terraform_service::name_hash;
+use crate::services::AwsOperations;
+use std::sync::Mutex;
+
+/// Longest branch slug kept in derived names
+const MAX_SLUG_LEN: usize = 40;
+
+/// ALB target group names are limited to 32 characters
+const MAX_TARGET_GROUP_LEN: usize = 32;
+
+/// Names of the resources a branch's preview deployment uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewTarget {
+    pub service_name: String,
+    /// The project's own service, whose network the branch's service is created in
+    pub base_service: String,
+    pub target_group_name: String,
+    /// `None` when no preview domain is configured
+    pub hostname: Option<String>,
+}
+
+impl PreviewTarget {
+    /// Derive the preview names for a project's branch
+    ///
+    /// `domain` is the base domain previews are served under, such as
+    /// `preview.example.com`.
+    pub fn for_project(project: &Project, domain: Option<&str>) -> Self {
+        let slug = branch_slug(&project.branch);
+        let hash = name_hash(&project.branch);
+        
+        // Truncate the readable part so the hash always survives the limit
+        let prefix: String = format!("{}-{}", branch_slug(&project.name), slug)
+            .chars()
+            .take(MAX_TARGET_GROUP_LEN - hash.len() - 1)
+            .collect();
+        let target_group_name = format!("{}-{}", prefix.trim_end_matches('-'), hash);
+        
+        Self {
+            service_name: format!("{}-{}-{}", project.aws_service, slug, hash),
+            base_service: project.aws_service.clone(),
+            target_group_name,
+            hostname: domain
+                .map(|d| d.trim().trim_matches('.'))
+                .filter(|d| !d.is_empty())
+                .map(|d| format!("{}-{}.{}", slug, hash, d)),
+        }
+    }
+}
+
+/// A DNS-safe label for a branch: lowercase alphanumerics separated by single dashes
+///
+/// `feature/Login_Page` becomes `feature-login-page`.
+pub fn branch_slug(branch: &str) -> String {
+    let mut slug = String::new();
+    for c in branch.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    
+    let slug: String = slug.chars().take(MAX_SLUG_LEN).collect();
+    match slug.trim_end_matches('-') {
+        "" => "preview".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Delete the ECS service backing a Preview project's branch
+///
+/// Returns the name of the deleted service.
+pub async fn teardown_preview(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    project_id: &str,
+) -> Result<String, OrchestratorError> {
+    let project = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+    };
+    
+    if project.environment != Environment::Preview {
+        return Err(OrchestratorError::NotPreview(project.name));
+    }
+    
+    let target = PreviewTarget::for_project(&project, None);
+    aws.delete_service(&project.aws_cluster, &target.service_name)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    
+    Ok(target.service_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::{ServiceStatus, ShadowState};
+    use std::sync::Arc;
+    
+    fn create_preview_project(branch: &str) -> Project {
+        let mut project = test_project("web", Environment::Preview);
+        project.branch = branch.to_string();
+        project
+    }
+    
+    #[test]
+    fn test_branch_slug() {
+        assert_eq!(branch_slug("pr-123"), "pr-123");
+        assert_eq!(branch_slug("feature/Login_Page"), "feature-login-page");
+        assert_eq!(branch_slug("--fix//typo--"), "fix-typo");
+        assert_eq!(branch_slug("///"), "preview");
+        assert_eq!(branch_slug(&"a".repeat(100)).len(), MAX_SLUG_LEN);
+    }
+    
+    #[test]
+    fn test_target_group_name_fits_alb_limit() {
+        let target = PreviewTarget::for_project(&create_preview_project("feature/a-very-long-branch-name"), None);
+        assert_eq!(target.target_group_name, "web-feature-a-very-long-eb688bc8");
+        assert!(target.target_group_name.len() <= MAX_TARGET_GROUP_LEN);
+        assert_eq!(target.hostname, None);
+    }
+    
+    #[test]
+    fn test_target_group_name_uses_slugged_project_name() {
+        let mut project = create_preview_project("pr-1");
+        project.name = "My App_v2".to_string();
+        let target = PreviewTarget::for_project(&project, None);
+        
+        assert!(target.target_group_name.starts_with("my-app-v2-pr-1-"), "{}", target.target_group_name);
+        assert!(target.target_group_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+        assert_eq!(target.base_service, "web-service");
+    }
+    
+    #[test]
+    fn test_branches_with_the_same_slug_get_distinct_names() {
+        let slashed = PreviewTarget::for_project(&create_preview_project("feature/a"), Some("preview.example.com"));
+        let dashed = PreviewTarget::for_project(&create_preview_project("feature-a"), Some("preview.example.com"));
+        
+        assert_ne!(slashed.service_name, dashed.service_name);
+        assert_ne!(slashed.target_group_name, dashed.target_group_name);
+        assert_ne!(slashed.hostname, dashed.hostname);
+    }
+    
+    #[tokio::test]
+    async fn test_teardown_deletes_branch_service() {
+        let db = Database::new_in_memory().unwrap();
+        let project = create_preview_project("pr-7");
+        db.create_project(&project).unwrap();
+        let database = Mutex::new(db);
+        let state = Arc::new(ShadowState::new());
+        state.set_service_status("cluster", "web-service-pr-7-c1cec5c4", ServiceStatus { running_count: 1, desired_count: 1, pending_count: 0 });
+        let aws = mock_aws(state.clone());
+        
+        let deleted = teardown_preview(&database, &aws, &project.id).await.unwrap();
+        assert_eq!(deleted, "web-service-pr-7-c1cec5c4");
+        assert!(state.get_service_status("cluster", "web-service-pr-7-c1cec5c4").is_none());
+    }
+}
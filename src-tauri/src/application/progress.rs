@@ -0,0 +1,257 @@
+//! Deployment progress reporting
+//!
+//! The orchestrator reports progress through a `ProgressReporter` rather than
+//! a Tauri window, so deployments can run (and be tested) without the UI.
+//! In the app it reports to an `EventBus`, which fans events out to every
+//! subscriber (the frontend, and any other consumer that subscribes). Lines of
+//! image build output travel the same way as `BuildLogEvent`s.
+
+use crate::application::orchestrator::OrchestratorError;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast;
+
+/// Events a subscriber may fall behind by before it starts missing them
+pub const EVENT_BUS_CAPACITY: usize = 256;
+
+/// Deployment progress event payload
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ProgressEvent {
+    pub deployment_id: String,
+    pub step: String,
+    pub progress: u8,
+    pub message: String,
+}
+
+impl ProgressEvent {
+    /// Create an event whose step and message are both `message`
+    pub fn new(deployment_id: &str, message: &str, progress: u8) -> Self {
+        Self {
+            deployment_id: deployment_id.to_string(),
+            step: message.to_string(),
+            progress,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// One line of image build output from a deployment, already redacted
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BuildLogEvent {
+    pub deployment_id: String,
+    pub line: String,
+}
+
+/// Receives deployment progress events
+pub trait ProgressReporter: Send + Sync {
+    fn report(&self, event: ProgressEvent) -> Result<(), OrchestratorError>;
+    
+    /// Receive a line of build output; ignored unless the reporter shows builds
+    fn report_build_log(&self, _event: BuildLogEvent) -> Result<(), OrchestratorError> {
+        Ok(())
+    }
+}
+
+/// Emits progress to every frontend window as `deployment-progress` events,
+/// and build output as `build-log` events
+pub struct TauriProgressReporter {
+    app: AppHandle,
+}
+
+impl TauriProgressReporter {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+}
+
+impl ProgressReporter for TauriProgressReporter {
+    fn report(&self, event: ProgressEvent) -> Result<(), OrchestratorError> {
+        self.app
+            .emit_all("deployment-progress", event)
+            .map_err(|e| OrchestratorError::EventError(e.to_string()))
+    }
+    
+    fn report_build_log(&self, event: BuildLogEvent) -> Result<(), OrchestratorError> {
+        self.app
+            .emit_all("build-log", event)
+            .map_err(|e| OrchestratorError::EventError(e.to_string()))
+    }
+}
+
+/// Broadcasts progress events to any number of subscribers
+///
+/// Publishing never blocks; a subscriber that falls more than
+/// `EVENT_BUS_CAPACITY` events behind skips the oldest ones.
+pub struct EventBus {
+    sender: broadcast::Sender<ProgressEvent>,
+    build_logs: broadcast::Sender<BuildLogEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        let (build_logs, _) = broadcast::channel(capacity);
+        Self { sender, build_logs }
+    }
+    
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.sender.subscribe()
+    }
+    
+    /// Receive every line of build output published from now on
+    pub fn subscribe_build_logs(&self) -> broadcast::Receiver<BuildLogEvent> {
+        self.build_logs.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_BUS_CAPACITY)
+    }
+}
+
+impl ProgressReporter for EventBus {
+    fn report(&self, event: ProgressEvent) -> Result<(), OrchestratorError> {
+        // Having no subscribers isn't a failure to report
+        let _ = self.sender.send(event);
+        Ok(())
+    }
+    
+    fn report_build_log(&self, event: BuildLogEvent) -> Result<(), OrchestratorError> {
+        let _ = self.build_logs.send(event);
+        Ok(())
+    }
+}
+
+/// Pass every event from `receiver` to `reporter` until the bus is dropped
+///
+/// Events missed by lagging behind are skipped, and a failing reporter
+/// doesn't stop later events from being forwarded.
+pub async fn forward_events(mut receiver: broadcast::Receiver<ProgressEvent>, reporter: Arc<dyn ProgressReporter>) {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => {
+                let _ = reporter.report(event);
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Pass every line of build output from `receiver` to `reporter` until the bus is dropped
+///
+/// Behaves like `forward_events`.
+pub async fn forward_build_logs(mut receiver: broadcast::Receiver<BuildLogEvent>, reporter: Arc<dyn ProgressReporter>) {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => {
+                let _ = reporter.report_build_log(event);
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Discards progress events
+pub struct NoopProgressReporter;
+
+impl ProgressReporter for NoopProgressReporter {
+    fn report(&self, _event: ProgressEvent) -> Result<(), OrchestratorError> {
+        Ok(())
+    }
+}
+
+/// Keeps every progress event and build log line in order, for inspection in tests
+#[derive(Default)]
+pub struct RecordingProgressReporter {
+    events: Mutex<Vec<ProgressEvent>>,
+    build_logs: Mutex<Vec<BuildLogEvent>>,
+}
+
+impl RecordingProgressReporter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Events reported so far, oldest first
+    pub fn events(&self) -> Vec<ProgressEvent> {
+        self.events.lock().unwrap().clone()
+    }
+    
+    /// Build log lines reported so far, oldest first
+    pub fn build_logs(&self) -> Vec<BuildLogEvent> {
+        self.build_logs.lock().unwrap().clone()
+    }
+}
+
+impl ProgressReporter for RecordingProgressReporter {
+    fn report(&self, event: ProgressEvent) -> Result<(), OrchestratorError> {
+        self.events.lock()
+            .map_err(|e| OrchestratorError::EventError(format!("Lock failed: {}", e)))?
+            .push(event);
+        Ok(())
+    }
+    
+    fn report_build_log(&self, event: BuildLogEvent) -> Result<(), OrchestratorError> {
+        self.build_logs.lock()
+            .map_err(|e| OrchestratorError::EventError(format!("Lock failed: {}", e)))?
+            .push(event);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_every_subscriber_observes_published_events() {
+        let bus = EventBus::default();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        
+        bus.report(ProgressEvent::new("deploy-1", "Repository cloned", 20)).unwrap();
+        
+        let expected = ProgressEvent::new("deploy-1", "Repository cloned", 20);
+        assert_eq!(first.recv().await.unwrap(), expected);
+        assert_eq!(second.recv().await.unwrap(), expected);
+        
+        // Publishing with nobody listening isn't an error
+        assert!(EventBus::default().report(expected).is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_forward_events_relays_until_bus_dropped() {
+        let bus = EventBus::default();
+        let recorder = Arc::new(RecordingProgressReporter::new());
+        let relay = tokio::spawn(forward_events(bus.subscribe(), recorder.clone()));
+        
+        bus.report(ProgressEvent::new("deploy-1", "Initializing deployment", 10)).unwrap();
+        bus.report(ProgressEvent::new("deploy-1", "Repository cloned", 20)).unwrap();
+        drop(bus);
+        relay.await.unwrap();
+        
+        let progress: Vec<u8> = recorder.events().iter().map(|event| event.progress).collect();
+        assert_eq!(progress, vec![10, 20]);
+    }
+    
+    #[tokio::test]
+    async fn test_forward_build_logs_relays_lines_in_order() {
+        let bus = EventBus::default();
+        let recorder = Arc::new(RecordingProgressReporter::new());
+        let relay = tokio::spawn(forward_build_logs(bus.subscribe_build_logs(), recorder.clone()));
+        
+        for line in ["#1 [1/2] FROM node:18-alpine", "#2 [2/2] RUN npm ci", "#3 naming to web:abc12345 done"] {
+            bus.report_build_log(BuildLogEvent { deployment_id: "deploy-1".to_string(), line: line.to_string() }).unwrap();
+        }
+        drop(bus);
+        relay.await.unwrap();
+        
+        let lines: Vec<String> = recorder.build_logs().into_iter().map(|event| event.line).collect();
+        assert_eq!(lines, ["#1 [1/2] FROM node:18-alpine", "#2 [2/2] RUN npm ci", "#3 naming to web:abc12345 done"]);
+        assert!(recorder.events().is_empty());
+    }
+}
@@ -0,0 +1,117 @@
+//! Reconciliation of local records with live AWS state
+//!
+//! Compares what the database believes about a project with its actual ECS
+//! service and reports the differences. Nothing is modified.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{DeploymentStatus, ReconcileReport};
+use crate::services::AwsOperations;
+use std::sync::Mutex;
+
+/// Check a project's ECS service against its deployment history
+///
+/// Flags a service that no longer exists and a service that is not running
+/// the desired number of tasks despite a successful last deployment.
+pub async fn reconcile_project(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    project_id: &str,
+) -> Result<ReconcileReport, OrchestratorError> {
+    let (project, last_deployment) = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        let project = db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        let last_deployment = db.get_deployments_for_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .next();
+        (project, last_deployment)
+    };
+    
+    let mut services = aws.describe_services(&project.aws_cluster, &[project.aws_service.clone()])
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    let health = services.remove(&project.aws_service);
+    
+    let last_succeeded = last_deployment
+        .as_ref()
+        .filter(|d| d.status == DeploymentStatus::Success);
+    
+    let mut discrepancies = Vec::new();
+    match &health {
+        None => {
+            discrepancies.push(format!(
+                "ECS service '{}' not found in cluster '{}'",
+                project.aws_service, project.aws_cluster
+            ));
+            if let Some(deployment) = last_succeeded {
+                discrepancies.push(format!(
+                    "Last deployment {} is marked successful but its service no longer exists",
+                    deployment.id
+                ));
+            }
+        }
+        Some(health) if last_succeeded.is_some() && health.running_count < health.desired_count => {
+            discrepancies.push(format!(
+                "Service is running {}/{} tasks after a successful deployment",
+                health.running_count, health.desired_count
+            ));
+        }
+        Some(_) => {}
+    }
+    
+    Ok(ReconcileReport {
+        project_id: project.id,
+        service_exists: health.is_some(),
+        running_count: health.as_ref().map(|h| h.running_count),
+        desired_count: health.as_ref().map(|h| h.desired_count),
+        discrepancies,
+        checked_at: chrono::Utc::now().timestamp(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Deployment, Environment};
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::{ShadowState, ServiceStatus};
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_reconcile_flags_removed_service() {
+        let db = Database::new_in_memory().unwrap();
+        let project = test_project("web", Environment::Production);
+        db.create_project(&project).unwrap();
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        deployment.status = DeploymentStatus::Success;
+        db.create_deployment(&deployment).unwrap();
+        let database = Mutex::new(db);
+        
+        let state = Arc::new(ShadowState::new());
+        state.set_service_status("cluster", "web-service", ServiceStatus {
+            running_count: 1,
+            desired_count: 1,
+            pending_count: 0,
+        });
+        let aws = mock_aws(state.clone());
+        
+        let report = reconcile_project(&database, &aws, &project.id).await.unwrap();
+        assert!(report.service_exists);
+        assert!(report.discrepancies.is_empty());
+        
+        state.delete_service("cluster", "web-service");
+        
+        let report = reconcile_project(&database, &aws, &project.id).await.unwrap();
+        assert!(!report.service_exists);
+        assert_eq!(report.running_count, None);
+        assert_eq!(report.discrepancies.len(), 2);
+        assert!(report.discrepancies[1].contains(&deployment.id));
+        
+        // Reporting only: the deployment record is untouched
+        let stored = database.lock().unwrap().get_deployment(&deployment.id).unwrap();
+        assert_eq!(stored.status, DeploymentStatus::Success);
+    }
+}
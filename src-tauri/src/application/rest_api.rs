@@ -0,0 +1,328 @@
+//! Local REST API for headless automation
+//!
+//! When enabled in settings, a few core commands are served over HTTP on
+//! localhost so CI pipelines can drive Deployotron without the GUI. Handlers
+//! call the same `AppState` logic as the Tauri commands, and every request
+//! must present the configured token as `Authorization: Bearer <token>`.
+//!
+//! - `POST /projects` creates a project (body as for `create_project`)
+//! - `POST /projects/{id}/deploy` runs a deployment and returns its ID
+//! - `GET /deployments/{id}` returns a deployment's status and details
+
+use crate::application::commands::{AppState, CommandError, NewProject};
+use axum::extract::{Path, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+
+/// Gives REST handlers access to the application state
+///
+/// In the app the state is owned by Tauri and reached through the app handle;
+/// tests hold it directly.
+pub trait AppStateProvider: Clone + Send + Sync + 'static {
+    fn app_state(&self) -> &AppState;
+}
+
+impl AppStateProvider for AppHandle {
+    fn app_state(&self) -> &AppState {
+        self.state::<AppState>().inner()
+    }
+}
+
+impl AppStateProvider for Arc<AppState> {
+    fn app_state(&self) -> &AppState {
+        self
+    }
+}
+
+#[derive(Clone)]
+struct ApiState<P> {
+    provider: P,
+    token: Arc<str>,
+}
+
+/// Error response body: `{"error": "..."}`
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<CommandError> for ApiError {
+    fn from(error: CommandError) -> Self {
+        let status = match &error {
+            CommandError::Invalid(_) => StatusCode::BAD_REQUEST,
+            CommandError::NotFound(_) => StatusCode::NOT_FOUND,
+            CommandError::Conflict(_) => StatusCode::CONFLICT,
+            CommandError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, error.to_string())
+    }
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct DeployRequest {
+    #[serde(default)]
+    notes: Option<String>,
+    #[serde(default)]
+    wait_for_healthy: Option<bool>,
+}
+
+/// Build the API router, rejecting requests without `token`
+pub fn router<P: AppStateProvider>(provider: P, token: &str) -> Router {
+    let state = ApiState { provider, token: Arc::from(token) };
+    
+    Router::new()
+        .route("/projects", post(create_project::<P>))
+        .route("/projects/:id/deploy", post(start_deployment::<P>))
+        .route("/deployments/:id", get(get_deployment::<P>))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token::<P>))
+        .with_state(state)
+}
+
+/// Serve the API on `listener` until the task is dropped
+pub async fn serve<P: AppStateProvider>(listener: TcpListener, provider: P, token: &str) -> std::io::Result<()> {
+    axum::serve(listener, router(provider, token)).await
+}
+
+/// Reject requests whose bearer token doesn't match, comparing in constant time
+async fn require_token<P: AppStateProvider>(
+    State(api): State<ApiState<P>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let presented = request.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    
+    let authorized = match presented {
+        Some(presented) => ring::constant_time::verify_slices_are_equal(presented.as_bytes(), api.token.as_bytes()).is_ok(),
+        None => false,
+    };
+    
+    if !authorized {
+        return ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token".to_string()).into_response();
+    }
+    
+    next.run(request).await
+}
+
+async fn create_project<P: AppStateProvider>(
+    State(api): State<ApiState<P>>,
+    Json(new_project): Json<NewProject>,
+) -> Result<(StatusCode, Json<crate::models::Project>), ApiError> {
+    api.provider.app_state()
+        .create_project(new_project)
+        .await
+        .map(|project| (StatusCode::CREATED, Json(project)))
+        .map_err(ApiError::from)
+}
+
+/// Runs the whole deployment before responding, as the Tauri command does
+async fn start_deployment<P: AppStateProvider>(
+    State(api): State<ApiState<P>>,
+    Path(project_id): Path<String>,
+    request: Option<Json<DeployRequest>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let Json(request) = request.unwrap_or_default();
+    
+    api.provider.app_state()
+        .start_deployment(&project_id, request.notes, request.wait_for_healthy.unwrap_or(true))
+        .await
+        .map(|deployment_id| Json(serde_json::json!({ "deployment_id": deployment_id })))
+        .map_err(ApiError::from)
+}
+
+async fn get_deployment<P: AppStateProvider>(
+    State(api): State<ApiState<P>>,
+    Path(deployment_id): Path<String>,
+) -> Result<Json<crate::models::Deployment>, ApiError> {
+    api.provider.app_state()
+        .get_deployment(&deployment_id)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::{Database, KeychainService};
+    use crate::models::{Deployment, Environment, FrameworkType, Project};
+    use crate::services::Redactor;
+    
+    const TOKEN: &str = "ci-secret";
+    
+    /// Serve the API on an ephemeral port, returning its base URL
+    async fn spawn_api(state: Arc<AppState>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, state, TOKEN));
+        format!("http://{}", address)
+    }
+    
+    fn create_state() -> Arc<AppState> {
+        Arc::new(AppState::from_parts(Database::new_in_memory().unwrap(), KeychainService::new(), None, Redactor::new(&[])))
+    }
+    
+    #[tokio::test]
+    async fn test_requests_without_valid_token_rejected() {
+        let base = spawn_api(create_state()).await;
+        let client = reqwest::Client::new();
+        
+        let missing = client.get(format!("{}/deployments/any", base)).send().await.unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::UNAUTHORIZED);
+        
+        let wrong = client.post(format!("{}/projects/any/deploy", base))
+            .bearer_auth("not-the-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), reqwest::StatusCode::UNAUTHORIZED);
+    }
+    
+    #[tokio::test]
+    async fn test_create_project_and_get_deployment() {
+        let state = create_state();
+        let base = spawn_api(state.clone()).await;
+        let client = reqwest::Client::new();
+        
+        let response = client.post(format!("{}/projects", base))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({
+                "name": "web",
+                "repository_url": "https://github.com/test/web",
+                "branch": "main",
+                "framework": "node",
+                "environment": "development",
+                "aws_cluster": "cluster",
+                "aws_service": "web-service",
+                "ecr_repository": "web",
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+        let project: Project = response.json().await.unwrap();
+        assert_eq!(project.name, "web");
+        assert_eq!(project.framework, FrameworkType::Node);
+        assert_eq!(project.environment, Environment::Development);
+        
+        let deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        state.database.lock().unwrap().create_deployment(&deployment).unwrap();
+        
+        let response = client.get(format!("{}/deployments/{}", base, deployment.id))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let fetched: Deployment = response.json().await.unwrap();
+        assert_eq!(fetched.id, deployment.id);
+        assert_eq!(fetched.commit_sha.as_deref(), Some("abc123"));
+        
+        let missing = client.get(format!("{}/deployments/unknown", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+    
+    #[tokio::test]
+    async fn test_deploy_status_reflects_the_failure() {
+        let state = create_state();
+        let base = spawn_api(state.clone()).await;
+        let client = reqwest::Client::new();
+        
+        let missing = client.post(format!("{}/projects/unknown/deploy", base))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), reqwest::StatusCode::NOT_FOUND);
+        
+        let mut project = Project::new(
+            "web".to_string(),
+            "https://github.com/test/web".to_string(),
+            "main".to_string(),
+            FrameworkType::Node,
+            Environment::Development,
+            "cluster".to_string(),
+            "web-service".to_string(),
+            "web".to_string(),
+        );
+        project.deploys_enabled = false;
+        state.database.lock().unwrap().create_project(&project).unwrap();
+        
+        let frozen = client.post(format!("{}/projects/{}/deploy", base, project.id))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(frozen.status(), reqwest::StatusCode::CONFLICT);
+    }
+    
+    #[tokio::test]
+    async fn test_create_project_rejects_ephemeral_storage_out_of_range() {
+        let state = create_state();
+        let base = spawn_api(state.clone()).await;
+        
+        let response = reqwest::Client::new().post(format!("{}/projects", base))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({
+                "name": "web",
+                "repository_url": "https://github.com/test/web",
+                "branch": "main",
+                "framework": "node",
+                "environment": "development",
+                "aws_cluster": "cluster",
+                "aws_service": "web-service",
+                "ecr_repository": "web",
+                "ephemeral_storage_gib": 500,
+            }))
+            .send()
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("between 21 and 200 GiB, got 500"), "{}", body);
+        assert!(state.database.lock().unwrap().get_all_projects().unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_create_project_rejects_container_port_out_of_range() {
+        let state = create_state();
+        let base = spawn_api(state.clone()).await;
+        
+        let response = reqwest::Client::new().post(format!("{}/projects", base))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({
+                "name": "web",
+                "repository_url": "https://github.com/test/web",
+                "branch": "main",
+                "framework": "node",
+                "environment": "development",
+                "aws_cluster": "cluster",
+                "aws_service": "web-service",
+                "ecr_repository": "web",
+                "container_port": 70000,
+            }))
+            .send()
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("between 1 and 65535, got 70000"), "{}", body);
+        assert!(state.database.lock().unwrap().get_all_projects().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+//! Task size recommendations from observed utilization
+//!
+//! Peak hourly CPU and memory utilization over the last week decide whether
+//! each dimension should halve, double or stay, and the result is rounded up
+//! to the nearest valid Fargate combination.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{ResourceSize, ResourceSuggestion};
+use crate::services::{AwsOperations, ServiceUtilization};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How far back utilization is read
+const UTILIZATION_WINDOW: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Peak utilization below which a dimension is halved
+const DOWNSIZE_BELOW_PERCENT: f64 = 40.0;
+
+/// Peak utilization above which a dimension is doubled
+const UPSIZE_ABOVE_PERCENT: f64 = 80.0;
+
+/// Task CPU units Fargate accepts, smallest first
+const FARGATE_CPU_UNITS: [u32; 7] = [256, 512, 1024, 2048, 4096, 8192, 16384];
+
+/// Suggest a task size for a project from its service's recent utilization
+pub async fn suggest_resources(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    project_id: &str,
+) -> Result<ResourceSuggestion, OrchestratorError> {
+    let project = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+    };
+    
+    let utilization = aws.get_service_utilization(&project.aws_cluster, &project.aws_service, UTILIZATION_WINDOW)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    
+    Ok(suggest(project_id, &project.resource_size, &utilization))
+}
+
+/// Recommend a size for `current` given its utilization
+pub fn suggest(project_id: &str, current: &ResourceSize, utilization: &ServiceUtilization) -> ResourceSuggestion {
+    let (cpu, memory) = current.cpu_memory();
+    let peak_cpu_percent = peak(&utilization.cpu_percent);
+    let peak_memory_percent = peak(&utilization.memory_percent);
+    
+    let target_cpu = rescale(cpu, peak_cpu_percent).max(FARGATE_CPU_UNITS[0]);
+    let target_memory = rescale(memory, peak_memory_percent).max(512);
+    let (suggested_cpu, suggested_memory) = fit_fargate(target_cpu, target_memory);
+    
+    let mut reasons = Vec::new();
+    if let Some(peak) = peak_cpu_percent {
+        if suggested_cpu != cpu {
+            reasons.push(format!("CPU peaked at {:.0}%, suggest {}→{} CPU units", peak, cpu, suggested_cpu));
+        }
+    }
+    if let Some(peak) = peak_memory_percent {
+        if suggested_memory != memory {
+            reasons.push(format!("memory peaked at {:.0}%, suggest {}→{} MiB", peak, memory, suggested_memory));
+        }
+    }
+    if peak_cpu_percent.is_none() && peak_memory_percent.is_none() {
+        reasons.push("No utilization data reported; keeping the current size".to_string());
+    }
+    
+    ResourceSuggestion {
+        project_id: project_id.to_string(),
+        current: current.clone(),
+        suggested: ResourceSize::from_cpu_memory(suggested_cpu, suggested_memory),
+        peak_cpu_percent,
+        peak_memory_percent,
+        reasons,
+    }
+}
+
+fn peak(series: &[f64]) -> Option<f64> {
+    series.iter().copied().reduce(f64::max)
+}
+
+/// Halve or double `value` according to its peak utilization
+fn rescale(value: u32, peak: Option<f64>) -> u32 {
+    match peak {
+        Some(peak) if peak < DOWNSIZE_BELOW_PERCENT => value / 2,
+        Some(peak) if peak > UPSIZE_ABOVE_PERCENT => value * 2,
+        _ => value,
+    }
+}
+
+/// Smallest valid Fargate combination with at least `cpu` units and `memory` MiB
+fn fit_fargate(cpu: u32, memory: u32) -> (u32, u32) {
+    FARGATE_CPU_UNITS.iter()
+        .filter(|&&units| units >= cpu)
+        .find_map(|&units| {
+            (1..=240)
+                .map(|step| step * 512)
+                .find(|&mib| mib >= memory && ResourceSize::is_valid_fargate_combination(units, mib))
+                .map(|mib| (units, mib))
+        })
+        .unwrap_or((16384, 122880))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Environment;
+    use crate::shadow::test_utils::{mock_aws, test_project};
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    async fn suggestion_for(cpu_percent: Vec<f64>, memory_percent: Vec<f64>) -> ResourceSuggestion {
+        let db = Database::new_in_memory().unwrap();
+        let mut project = test_project("web", Environment::Development);
+        project.resource_size = ResourceSize::Large;
+        db.create_project(&project).unwrap();
+        
+        let state = Arc::new(ShadowState::new());
+        state.set_service_utilization("cluster", "web-service", ServiceUtilization { cpu_percent, memory_percent });
+        let aws = mock_aws(state);
+        
+        suggest_resources(&Mutex::new(db), &aws, &project.id).await.unwrap()
+    }
+    
+    #[tokio::test]
+    async fn test_low_utilization_suggests_downsize() {
+        let suggestion = suggestion_for(vec![10.0, 25.0, 15.0], vec![20.0, 30.0, 28.0]).await;
+        
+        assert_eq!(suggestion.current, ResourceSize::Large);
+        assert_eq!(suggestion.suggested, ResourceSize::Medium);
+        assert_eq!(suggestion.peak_memory_percent, Some(30.0));
+        assert!(suggestion.reasons.contains(&"memory peaked at 30%, suggest 2048→1024 MiB".to_string()));
+    }
+    
+    #[tokio::test]
+    async fn test_high_utilization_suggests_upsize() {
+        let suggestion = suggestion_for(vec![60.0, 92.0], vec![70.0, 85.0]).await;
+        
+        assert_eq!(suggestion.suggested, ResourceSize::XLarge);
+        assert_eq!(suggestion.reasons.len(), 2);
+    }
+    
+    #[test]
+    fn test_steady_or_missing_utilization_keeps_size() {
+        let steady = ServiceUtilization { cpu_percent: vec![55.0], memory_percent: vec![65.0] };
+        assert_eq!(suggest("p", &ResourceSize::Medium, &steady).suggested, ResourceSize::Medium);
+        
+        let missing = suggest("p", &ResourceSize::Medium, &ServiceUtilization::default());
+        assert_eq!(missing.suggested, ResourceSize::Medium);
+        assert_eq!(missing.peak_cpu_percent, None);
+    }
+    
+    #[test]
+    fn test_fit_rounds_up_to_valid_combination() {
+        // 256 CPU units can't have 4 GiB, so the CPU grows to fit the memory
+        assert_eq!(fit_fargate(256, 4096), (512, 4096));
+        assert_eq!(fit_fargate(256, 256), (256, 512));
+        assert_eq!(fit_fargate(1024, 1024), (1024, 2048));
+    }
+}
@@ -0,0 +1,229 @@
+//! Waiting for an ECS rollout to become healthy
+//!
+//! Polls service health through the shared monitor and retries rollouts whose
+//! tasks stop with `CannotPullContainerError`, which is usually a transient
+//! registry or networking blip.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::application::ServiceMonitor;
+use crate::services::{AwsOperations, ServiceHealth};
+use std::time::{Duration, Instant};
+
+/// Limits for waiting on a rollout
+#[derive(Debug, Clone)]
+pub struct RolloutPolicy {
+    /// Health polls allowed per rollout attempt before timing out
+    pub max_polls: u32,
+    /// Forced redeploys allowed after image pull failures
+    pub pull_retries: u32,
+    /// Interval between health polls
+    pub poll_backoff: PollBackoff,
+}
+
+/// Interval between health polls as a rollout attempt goes on
+///
+/// Polling quickly right after a deploy gives fast feedback; slowing down
+/// later keeps long rollouts from spending API calls.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PollBackoff {
+    /// Poll at the same interval throughout
+    Constant(Duration),
+    /// Each `(until, interval)` step applies while less than `until` has
+    /// elapsed; `then` applies after the last step
+    Stepped {
+        steps: Vec<(Duration, Duration)>,
+        then: Duration,
+    },
+}
+
+impl PollBackoff {
+    /// Interval before the next poll, `elapsed` into the rollout attempt
+    pub fn interval_at(&self, elapsed: Duration) -> Duration {
+        match self {
+            PollBackoff::Constant(interval) => *interval,
+            PollBackoff::Stepped { steps, then } => steps.iter()
+                .find(|(until, _)| elapsed < *until)
+                .map(|(_, interval)| *interval)
+                .unwrap_or(*then),
+        }
+    }
+}
+
+impl Default for PollBackoff {
+    /// 5s for the first minute, 15s until three minutes, then 30s
+    fn default() -> Self {
+        PollBackoff::Stepped {
+            steps: vec![
+                (Duration::from_secs(60), Duration::from_secs(5)),
+                (Duration::from_secs(180), Duration::from_secs(15)),
+            ],
+            then: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Progress reported while waiting
+#[derive(Debug, Clone)]
+pub enum RolloutEvent {
+    /// The service is not healthy yet
+    Waiting(ServiceHealth),
+    /// Tasks failed to pull their image and the service was redeployed
+    PullRetry { attempt: u32, reason: String },
+}
+
+/// Wait until the service is healthy, redeploying after image pull failures
+///
+/// Only tasks stopped at or after `since` (Unix seconds) are considered. Each
+/// forced redeploy gets a fresh `max_polls` budget; once `pull_retries` are
+/// used up, a further pull failure fails the rollout.
+pub async fn wait_for_rollout<F>(
+    monitor: &ServiceMonitor,
+    aws: &dyn AwsOperations,
+    cluster_name: &str,
+    service_name: &str,
+    policy: RolloutPolicy,
+    since: i64,
+    mut on_event: F,
+) -> Result<(), OrchestratorError>
+where
+    F: FnMut(RolloutEvent) -> Result<(), OrchestratorError>,
+{
+    let mut since = since;
+    let mut retries_used = 0;
+    let mut polls = 0;
+    let mut attempt_started = Instant::now();
+    
+    loop {
+        polls += 1;
+        
+        if polls > policy.max_polls {
+            return Err(OrchestratorError::AwsError(
+                "Deployment timeout: service did not become healthy".to_string()
+            ));
+        }
+        
+        let interval = policy.poll_backoff.interval_at(attempt_started.elapsed());
+        let health = monitor
+            .next_health_after(cluster_name, service_name, interval)
+            .await
+            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+        
+        if health.is_healthy {
+            return Ok(());
+        }
+        
+        let stopped = aws.list_stopped_tasks(cluster_name, service_name)
+            .await
+            .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+        let pull_failure = stopped.into_iter()
+            .filter(|task| task.stopped_at.is_none_or(|at| at >= since))
+            .find(|task| task.is_image_pull_failure());
+        
+        if let Some(task) = pull_failure {
+            if retries_used >= policy.pull_retries {
+                return Err(OrchestratorError::AwsError(format!(
+                    "Image pull failed after {} retries: {}",
+                    retries_used, task.reason
+                )));
+            }
+            
+            aws.force_new_deployment(cluster_name, service_name)
+                .await
+                .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+            
+            retries_used += 1;
+            since = chrono::Utc::now().timestamp();
+            polls = 0;
+            attempt_started = Instant::now();
+            on_event(RolloutEvent::PullRetry { attempt: retries_used, reason: task.reason })?;
+            continue;
+        }
+        
+        on_event(RolloutEvent::Waiting(health))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shadow::test_utils::mock_aws;
+    use crate::shadow::{MockAwsService, ServiceStatus, ShadowState};
+    use std::sync::Arc;
+    use std::time::Duration;
+    
+    const PULL_ERROR: &str = "CannotPullContainerError: pull image manifest has been retried 5 time(s)";
+    
+    fn setup() -> (ServiceMonitor, Arc<MockAwsService>, Arc<ShadowState>) {
+        let state = Arc::new(ShadowState::new());
+        let aws = Arc::new(mock_aws(state.clone()));
+        let monitor = ServiceMonitor::new(aws.clone(), Duration::from_millis(5));
+        (monitor, aws, state)
+    }
+    
+    fn fast_polls() -> PollBackoff {
+        PollBackoff::Constant(Duration::from_millis(5))
+    }
+    
+    /// Put the service in the state ECS shows after tasks fail to pull
+    fn fail_pull(state: &ShadowState) {
+        state.set_service_status("cluster", "web", ServiceStatus {
+            running_count: 0,
+            desired_count: 1,
+            pending_count: 0,
+        });
+        state.add_stopped_task("cluster", "web", PULL_ERROR);
+    }
+    
+    #[tokio::test]
+    async fn test_recovers_after_transient_pull_error() {
+        let (monitor, aws, state) = setup();
+        fail_pull(&state);
+        
+        let policy = RolloutPolicy { max_polls: 5, pull_retries: 2, poll_backoff: fast_polls() };
+        let mut events = Vec::new();
+        let result = wait_for_rollout(&monitor, aws.as_ref(), "cluster", "web", policy, 0, |event| {
+            events.push(event);
+            Ok(())
+        }).await;
+        
+        assert!(result.is_ok());
+        assert_eq!(state.forced_deployments(), 1);
+        assert!(matches!(&events[0], RolloutEvent::PullRetry { attempt: 1, reason } if reason == PULL_ERROR));
+    }
+    
+    #[tokio::test]
+    async fn test_gives_up_when_pull_retries_exhausted() {
+        let (monitor, aws, state) = setup();
+        fail_pull(&state);
+        
+        let policy = RolloutPolicy { max_polls: 5, pull_retries: 0, poll_backoff: fast_polls() };
+        let result = wait_for_rollout(&monitor, aws.as_ref(), "cluster", "web", policy, 0, |_| Ok(())).await;
+        
+        assert!(matches!(result, Err(OrchestratorError::AwsError(msg)) if msg.contains("CannotPullContainerError")));
+        assert_eq!(state.forced_deployments(), 0);
+    }
+    
+    #[test]
+    fn test_backoff_interval_sequence() {
+        let backoff = PollBackoff::default();
+        
+        // Intervals seen by a rollout polling back to back
+        let mut elapsed = Duration::ZERO;
+        let mut intervals = Vec::new();
+        for _ in 0..24 {
+            let interval = backoff.interval_at(elapsed);
+            intervals.push(interval.as_secs());
+            elapsed += interval;
+        }
+        
+        let mut expected = vec![5; 12];
+        expected.extend([15; 8]);
+        expected.extend([30; 4]);
+        assert_eq!(intervals, expected);
+        assert_eq!(elapsed, Duration::from_secs(300));
+        
+        let constant = PollBackoff::Constant(Duration::from_secs(10));
+        assert_eq!(constant.interval_at(Duration::ZERO), Duration::from_secs(10));
+        assert_eq!(constant.interval_at(Duration::from_secs(3600)), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,117 @@
+//! Importing an existing ECS service as a project
+//!
+//! Teams with services already running can manage them here without
+//! recreating anything: the project is filled in from what the service's
+//! current task definition actually runs.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{Environment, FrameworkType, Project, ResourceSize};
+use crate::services::AwsOperations;
+use std::sync::Mutex;
+
+/// Create a project for an existing ECS service
+///
+/// Image repository, port, task size and task count come from the service's
+/// task definition. The framework can't be told from a built image, so it is
+/// `Other`, and the repository URL is left empty for the user to fill in
+/// before the first deployment. The environment and branch are the caller's,
+/// since a running service records neither.
+pub async fn import_ecs_service(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    cluster_name: &str,
+    service_name: &str,
+    environment: Environment,
+    branch: &str,
+) -> Result<Project, OrchestratorError> {
+    let definition = aws.describe_service_definition(cluster_name, service_name)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    
+    let mut project = Project::new(
+        service_name.to_string(),
+        String::new(),
+        branch.to_string(),
+        FrameworkType::Other,
+        environment.clone(),
+        cluster_name.to_string(),
+        service_name.to_string(),
+        image_repository(&definition.image_uri).to_string(),
+    );
+    project.container_port = definition.container_port;
+    project.desired_count = Some(definition.desired_count);
+    project.resource_size = match (definition.cpu, definition.memory) {
+        (Some(cpu), Some(memory)) => ResourceSize::from_cpu_memory(cpu, memory),
+        _ => environment.default_resource_size(),
+    };
+    
+    let db = database.lock()
+        .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+    db.create_project(&project)
+        .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+    
+    Ok(project)
+}
+
+/// An image reference without its tag or digest
+fn image_repository(image_uri: &str) -> &str {
+    let without_digest = image_uri.split('@').next().unwrap_or(image_uri);
+    match without_digest.rsplit_once(':') {
+        // A colon before the last `/` is a registry port, not a tag
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => without_digest,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::EcsDeploymentConfig;
+    use crate::shadow::test_utils::mock_aws;
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_imported_project_matches_task_definition() {
+        let aws = mock_aws(Arc::new(ShadowState::new()));
+        let running = EcsDeploymentConfig {
+            cluster_name: "prod".to_string(),
+            service_name: "billing".to_string(),
+            task_family: "billing-task".to_string(),
+            container_name: "billing".to_string(),
+            image_uri: "123456789012.dkr.ecr.us-east-1.amazonaws.com/billing:v42".to_string(),
+            cpu: "1024".to_string(),
+            memory: "3072".to_string(),
+            port: 9000,
+            desired_count: 3,
+            ..Default::default()
+        };
+        let task_arn = aws.register_task_definition(&running).await.unwrap();
+        aws.deploy_service(&running, &task_arn).await.unwrap();
+        let database = Mutex::new(Database::new_in_memory().unwrap());
+        
+        let project = import_ecs_service(&database, &aws, "prod", "billing", Environment::Staging, "release").await.unwrap();
+        
+        assert_eq!(project.name, "billing");
+        assert_eq!((project.aws_cluster.as_str(), project.aws_service.as_str()), ("prod", "billing"));
+        assert_eq!(project.ecr_repository, "123456789012.dkr.ecr.us-east-1.amazonaws.com/billing");
+        assert_eq!(project.container_port, Some(9000));
+        assert_eq!(project.resource_size, ResourceSize::Custom(1024, 3072));
+        assert_eq!(project.desired_count, Some(3));
+        assert_eq!(project.framework, FrameworkType::Other);
+        assert_eq!((&project.environment, project.branch.as_str()), (&Environment::Staging, "release"));
+        
+        let stored = database.lock().unwrap().get_project(&project.id).unwrap();
+        assert_eq!((stored.container_port, stored.desired_count), (Some(9000), Some(3)));
+        
+        assert!(import_ecs_service(&database, &aws, "prod", "missing", Environment::Staging, "release").await.is_err());
+    }
+    
+    #[test]
+    fn test_image_repository_strips_tag_and_digest() {
+        assert_eq!(image_repository("registry:5000/team/web:v1"), "registry:5000/team/web");
+        assert_eq!(image_repository("registry:5000/team/web"), "registry:5000/team/web");
+        assert_eq!(image_repository("web@sha256:abc123"), "web");
+    }
+}
@@ -0,0 +1,178 @@
+//! Shared ECS service health monitor
+//!
+//! Deployments waiting on services in the same cluster are answered from a
+//! single batched `describe_services` call per poll instead of each polling
+//! ECS on their own.
+
+use crate::services::aws_service::missing_service_error;
+use crate::services::{AwsOperations, AwsServiceError, ServiceHealth, MAX_DESCRIBE_SERVICES};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// Default interval between polls of a cluster
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+type HealthReply = oneshot::Sender<Result<ServiceHealth, AwsServiceError>>;
+
+/// Waiters queued for the next poll: cluster -> (service name, reply)
+type PendingPolls = Arc<Mutex<HashMap<String, Vec<(String, HealthReply)>>>>;
+
+/// Batches service health checks per cluster
+///
+/// One monitor is shared by every deployment using the same AWS client.
+pub struct ServiceMonitor {
+    aws: Arc<dyn AwsOperations>,
+    poll_interval: Duration,
+    pending: PendingPolls,
+}
+
+impl ServiceMonitor {
+    /// Create a monitor polling through `aws` every `poll_interval`
+    pub fn new(aws: Arc<dyn AwsOperations>, poll_interval: Duration) -> Self {
+        Self {
+            aws,
+            poll_interval,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+    
+    /// Wait for the next poll of `cluster_name` and return the service's health
+    ///
+    /// The first waiter on an idle cluster schedules a poll one interval out;
+    /// every service queued on that cluster before it fires shares the same
+    /// `describe_services` call (split into batches of 10).
+    pub async fn next_health(&self, cluster_name: &str, service_name: &str) -> Result<ServiceHealth, AwsServiceError> {
+        self.next_health_after(cluster_name, service_name, self.poll_interval).await
+    }
+    
+    /// Like `next_health`, but an idle cluster is polled after `delay` instead of the default interval
+    ///
+    /// A waiter joining a poll already scheduled on the cluster is answered
+    /// by that poll, whatever its delay.
+    pub async fn next_health_after(
+        &self,
+        cluster_name: &str,
+        service_name: &str,
+        delay: Duration,
+    ) -> Result<ServiceHealth, AwsServiceError> {
+        let (reply, response) = oneshot::channel();
+        
+        let schedule_poll = {
+            let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+            let waiters = pending.entry(cluster_name.to_string()).or_default();
+            waiters.push((service_name.to_string(), reply));
+            waiters.len() == 1
+        };
+        
+        if schedule_poll {
+            tokio::spawn(poll_cluster(
+                self.aws.clone(),
+                self.pending.clone(),
+                cluster_name.to_string(),
+                delay,
+            ));
+        }
+        
+        response.await.map_err(|_| {
+            AwsServiceError::EcsOperationFailed("Service monitor stopped before polling".to_string())
+        })?
+    }
+}
+
+/// Wait one interval, then answer every waiter queued for the cluster
+async fn poll_cluster(aws: Arc<dyn AwsOperations>, pending: PendingPolls, cluster_name: String, interval: Duration) {
+    tokio::time::sleep(interval).await;
+    
+    let waiters = pending.lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&cluster_name)
+        .unwrap_or_default();
+    
+    let mut service_names: Vec<String> = waiters.iter().map(|(name, _)| name.clone()).collect();
+    service_names.sort();
+    service_names.dedup();
+    
+    // Services ECS didn't return are left out, and reported as not found
+    let mut results: HashMap<String, Result<ServiceHealth, AwsServiceError>> = HashMap::new();
+    for batch in service_names.chunks(MAX_DESCRIBE_SERVICES) {
+        match aws.describe_services(&cluster_name, batch).await {
+            Ok(mut health) => {
+                for name in batch {
+                    if let Some(service) = health.remove(name) {
+                        results.insert(name.clone(), Ok(service));
+                    }
+                }
+            }
+            Err(e) => {
+                for name in batch {
+                    results.insert(name.clone(), Err(e.clone()));
+                }
+            }
+        }
+    }
+    
+    for (name, reply) in waiters {
+        let result = results.get(&name)
+            .cloned()
+            .unwrap_or_else(|| Err(missing_service_error(&cluster_name, &name, None)));
+        // The waiter may have given up; nothing to do then
+        let _ = reply.send(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shadow::{MockAwsService, ShadowConfig, ShadowState};
+    
+    fn mock_monitor() -> (Arc<ServiceMonitor>, Arc<ShadowState>) {
+        let config = ShadowConfig {
+            enabled: true,
+            failure_rate: 0.0,
+            simulate_delays: false,
+        };
+        let state = Arc::new(ShadowState::new());
+        let aws = Arc::new(MockAwsService::new(None, config, state.clone()));
+        (Arc::new(ServiceMonitor::new(aws, Duration::from_millis(20))), state)
+    }
+    
+    #[tokio::test]
+    async fn test_same_cluster_shares_describe_call() {
+        let (monitor, state) = mock_monitor();
+        
+        let (web, worker) = tokio::join!(
+            monitor.next_health("cluster", "web"),
+            monitor.next_health("cluster", "worker"),
+        );
+        
+        assert!(web.unwrap().is_healthy);
+        assert!(worker.unwrap().is_healthy);
+        assert_eq!(state.describe_services_calls(), 1);
+        
+        // A second poll round makes exactly one more call
+        let (web, worker) = tokio::join!(
+            monitor.next_health("cluster", "web"),
+            monitor.next_health("cluster", "worker"),
+        );
+        assert!(web.is_ok() && worker.is_ok());
+        assert_eq!(state.describe_services_calls(), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_large_poll_is_split_into_batches() {
+        let (monitor, state) = mock_monitor();
+        
+        let mut polls = tokio::task::JoinSet::new();
+        for i in 0..MAX_DESCRIBE_SERVICES + 1 {
+            let monitor = monitor.clone();
+            polls.spawn(async move { monitor.next_health("cluster", &format!("svc-{}", i)).await });
+        }
+        
+        while let Some(result) = polls.join_next().await {
+            assert!(result.unwrap().is_ok());
+        }
+        assert_eq!(state.describe_services_calls(), 2);
+    }
+}
@@ -0,0 +1,323 @@
+//! Tearing down the AWS resources a project deployed
+//!
+//! A teardown plan lists what tearing a project down would delete — its ECS
+//! service, task definition revisions, log group and the ECR images its
+//! deployments pushed — using only reads. Tearing down carries out a plan the
+//! user reviewed, and is refused if a freshly computed plan no longer matches
+//! it; other images in a shared repository, including ones another project's
+//! deployments also used, are kept.
+
+use crate::application::orchestrator::{DeploymentOrchestrator, OrchestratorError};
+use crate::application::preview::PreviewTarget;
+use crate::infrastructure::Database;
+use crate::models::{Environment, Project};
+use crate::services::{resolve_ecr_repository, AwsOperations, AwsServiceError, EcrUri};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// AWS resources tearing a project down would delete
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeardownPlan {
+    pub project_id: String,
+    pub cluster: String,
+    /// ECS service; `None` when it no longer exists
+    pub service: Option<String>,
+    /// ARNs of the task family's active revisions
+    pub task_definitions: Vec<String>,
+    /// CloudWatch log group; `None` when it no longer exists
+    pub log_group: Option<String>,
+    /// ECR repository name the images live in
+    pub ecr_repository: String,
+    /// Digests of images pushed by this project's deployments that are still
+    /// in the repository and no other project's deployments use
+    pub images: Vec<String>,
+}
+
+impl TeardownPlan {
+    /// Whether there is nothing left to delete
+    pub fn is_empty(&self) -> bool {
+        self.service.is_none() && self.task_definitions.is_empty() && self.log_group.is_none() && self.images.is_empty()
+    }
+}
+
+/// List what tearing a project down would delete, without deleting anything
+pub async fn teardown_plan(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    project_id: &str,
+) -> Result<TeardownPlan, OrchestratorError> {
+    let (project, deployed_digests, shared_digests) = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        let project = db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        let deployments = db.get_all_deployments()
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?;
+        let (own, others): (Vec<_>, Vec<_>) = deployments.into_iter()
+            .partition(|deployment| deployment.project_id == project_id);
+        let digests: Vec<String> = own.into_iter().filter_map(|deployment| deployment.image_digest).collect();
+        let shared: HashSet<String> = others.into_iter().filter_map(|deployment| deployment.image_digest).collect();
+        (project, digests, shared)
+    };
+    
+    let service_name = service_name(&project);
+    let service = match aws.describe_service_definition(&project.aws_cluster, &service_name).await {
+        Ok(_) => Some(service_name),
+        Err(AwsServiceError::ServiceNotFound(_)) | Err(AwsServiceError::ClusterNotFound(_)) => None,
+        Err(e) => return Err(OrchestratorError::AwsError(e.to_string())),
+    };
+    
+    let config = DeploymentOrchestrator::build_ecs_config(&project, "");
+    let task_definitions = aws.list_task_definitions(&config.task_family)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    
+    let log_group_name = config.log_group_name();
+    let log_group = if aws.log_group_exists(&log_group_name)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?
+    {
+        Some(log_group_name)
+    } else {
+        None
+    };
+    
+    let repository = resolve_ecr_repository(&project.ecr_repository, &project.environment)
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?;
+    let ecr_repository = EcrUri::parse(&repository)
+        .map(|uri| uri.repository)
+        .unwrap_or(repository);
+    
+    // Deployment rows outlive their images, so only digests still in the
+    // repository are planned
+    let existing: HashSet<String> = aws.list_image_digests(&ecr_repository)
+        .await
+        .map_err(|e| OrchestratorError::AwsError(e.to_string()))?
+        .into_iter()
+        .collect();
+    let mut images: Vec<String> = Vec::new();
+    for digest in deployed_digests {
+        if existing.contains(&digest) && !shared_digests.contains(&digest) && !images.contains(&digest) {
+            images.push(digest);
+        }
+    }
+    
+    Ok(TeardownPlan {
+        project_id: project.id,
+        cluster: project.aws_cluster,
+        service,
+        task_definitions,
+        log_group,
+        ecr_repository,
+        images,
+    })
+}
+
+/// Delete everything a reviewed teardown plan lists
+///
+/// Refused with `StalePlan` when the project's resources changed since `plan`
+/// was computed. Production projects also need `confirmed_name` to match the
+/// project's name. If a deletion fails part way, the error names what was
+/// already deleted.
+///
+/// Returns the plan that was carried out.
+pub async fn teardown_service(
+    database: &Mutex<Database>,
+    aws: &dyn AwsOperations,
+    plan: &TeardownPlan,
+    confirmed_name: Option<&str>,
+) -> Result<TeardownPlan, OrchestratorError> {
+    let project = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_project(&plan.project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+    };
+    
+    if project.environment == Environment::Production && confirmed_name != Some(project.name.as_str()) {
+        return Err(OrchestratorError::ConfirmationRequired(project.name));
+    }
+    
+    let current = teardown_plan(database, aws, &plan.project_id).await?;
+    if &current != plan {
+        return Err(OrchestratorError::StalePlan(format!("{}'s resources changed since the teardown was planned", project.name)));
+    }
+    
+    let mut deleted = Vec::new();
+    if let Err(e) = delete_planned(aws, &current, &mut deleted).await {
+        return Err(if deleted.is_empty() {
+            OrchestratorError::AwsError(e.to_string())
+        } else {
+            OrchestratorError::TeardownIncomplete(format!("deleted {} before failing: {}", deleted.join(", "), e))
+        });
+    }
+    
+    Ok(current)
+}
+
+/// Delete a plan's resources in order, noting each one in `deleted` once it is gone
+async fn delete_planned(
+    aws: &dyn AwsOperations,
+    plan: &TeardownPlan,
+    deleted: &mut Vec<String>,
+) -> Result<(), AwsServiceError> {
+    if let Some(service) = &plan.service {
+        aws.delete_service(&plan.cluster, service).await?;
+        deleted.push(format!("service {}", service));
+    }
+    for task_definition in &plan.task_definitions {
+        aws.deregister_task_definition(task_definition).await?;
+        deleted.push(format!("task definition {}", task_definition));
+    }
+    if let Some(log_group) = &plan.log_group {
+        aws.delete_log_group(log_group).await?;
+        deleted.push(format!("log group {}", log_group));
+    }
+    if !plan.images.is_empty() {
+        aws.delete_images(&plan.ecr_repository, &plan.images).await?;
+        deleted.push(format!("{} images in {}", plan.images.len(), plan.ecr_repository));
+    }
+    Ok(())
+}
+
+/// The ECS service a project deploys to; Preview projects use one per branch
+fn service_name(project: &Project) -> String {
+    if project.environment == Environment::Preview {
+        PreviewTarget::for_project(project, None).service_name
+    } else {
+        project.aws_service.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Deployment, DeploymentTrigger};
+    use crate::shadow::test_utils::{test_project, TestOrchestrator};
+    use crate::shadow::ShadowState;
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_plan_deletes_nothing_and_teardown_deletes_what_it_lists() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { orchestrator, database, aws, project, .. } = TestOrchestrator::new(state.clone());
+        let deployment_id = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let digest = database.lock().unwrap().get_deployment(&deployment_id).unwrap().image_digest.unwrap();
+        let task_definition = state.get_task_definition("web-task").unwrap();
+        
+        let operations_before = state.called_operations().len();
+        let plan = teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap();
+        
+        assert_eq!(plan.service.as_deref(), Some("web-service"));
+        assert_eq!(plan.task_definitions, vec![task_definition.clone()]);
+        assert_eq!(plan.log_group.as_deref(), Some("/ecs/web-task"));
+        assert_eq!(plan.ecr_repository, "web");
+        assert_eq!(plan.images, vec![digest.clone()]);
+        
+        // The dry run only read
+        assert!(state.called_operations()[operations_before..].iter()
+            .all(|operation| !operation.starts_with("delete") && !operation.starts_with("deregister")));
+        assert!(state.get_service_status("cluster", "web-service").is_some());
+        assert_eq!(state.get_task_definition("web-task"), Some(task_definition));
+        assert!(state.has_log_group("/ecs/web-task"));
+        assert_eq!(state.get_ecr_images("web"), vec![digest]);
+        
+        let carried_out = teardown_service(&database, aws.as_ref(), &plan, None).await.unwrap();
+        assert_eq!(carried_out, plan);
+        assert!(state.get_service_status("cluster", "web-service").is_none());
+        assert!(state.get_task_definition("web-task").is_none());
+        assert!(!state.has_log_group("/ecs/web-task"));
+        assert!(state.get_ecr_images("web").is_empty());
+        
+        assert!(teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_plan_skips_deleted_and_shared_images() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { orchestrator, database, aws, project, .. } = TestOrchestrator::new(state.clone());
+        let deployment_id = orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let digest = database.lock().unwrap().get_deployment(&deployment_id).unwrap().image_digest.unwrap();
+        let shared_digest = format!("sha256:{}", "a".repeat(64));
+        let deleted_digest = format!("sha256:{}", "b".repeat(64));
+        state.add_ecr_image("web", &shared_digest);
+        
+        // Another project pushing to the same repository deployed the shared image
+        let other = test_project("api", Environment::Development);
+        {
+            let db = database.lock().unwrap();
+            db.create_project(&other).unwrap();
+            for (project_id, image_digest) in [(&project.id, &shared_digest), (&other.id, &shared_digest), (&project.id, &deleted_digest)] {
+                let mut deployment = Deployment::new(project_id.clone(), None, None, "web".to_string(), None);
+                deployment.image_digest = Some(image_digest.clone());
+                db.create_deployment(&deployment).unwrap();
+            }
+        }
+        
+        let plan = teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap();
+        assert_eq!(plan.images, vec![digest]);
+        
+        teardown_service(&database, aws.as_ref(), &plan, None).await.unwrap();
+        assert_eq!(state.get_ecr_images("web"), vec![shared_digest]);
+        assert!(teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_teardown_refused_when_plan_no_longer_matches() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { orchestrator, database, aws, project, .. } = TestOrchestrator::new(state.clone());
+        orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let plan = teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap();
+        
+        // The service is deleted outside Deployotron after the plan was reviewed
+        state.delete_service("cluster", "web-service");
+        assert!(matches!(
+            teardown_service(&database, aws.as_ref(), &plan, None).await,
+            Err(OrchestratorError::StalePlan(_))
+        ));
+        assert!(state.get_task_definition("web-task").is_some());
+        assert!(state.has_log_group("/ecs/web-task"));
+    }
+    
+    #[tokio::test]
+    async fn test_production_teardown_needs_the_project_name() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { orchestrator, database, aws, project, .. } = TestOrchestrator::new(state.clone());
+        let mut project = project;
+        project.environment = Environment::Production;
+        database.lock().unwrap().update_project(&project).unwrap();
+        orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let plan = teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap();
+        
+        for confirmed_name in [None, Some("wrong")] {
+            assert!(matches!(
+                teardown_service(&database, aws.as_ref(), &plan, confirmed_name).await,
+                Err(OrchestratorError::ConfirmationRequired(_))
+            ));
+        }
+        assert!(state.get_service_status("cluster", &plan.service.clone().unwrap()).is_some());
+        
+        teardown_service(&database, aws.as_ref(), &plan, Some("web")).await.unwrap();
+        assert!(teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_partial_teardown_reports_what_was_deleted() {
+        let state = Arc::new(ShadowState::new());
+        let TestOrchestrator { orchestrator, database, aws, project, .. } = TestOrchestrator::new(state.clone());
+        orchestrator.run_deployment(project.clone(), None, DeploymentTrigger::Manual, true).await.unwrap();
+        let plan = teardown_plan(&database, aws.as_ref(), &project.id).await.unwrap();
+        
+        state.fail_operation("delete_log_group");
+        let message = match teardown_service(&database, aws.as_ref(), &plan, None).await {
+            Err(OrchestratorError::TeardownIncomplete(message)) => message,
+            other => panic!("expected an incomplete teardown, got {:?}", other),
+        };
+        assert!(message.contains("service web-service"));
+        assert!(message.contains(&format!("task definition {}", plan.task_definitions[0])));
+        assert!(!message.contains("log group"));
+        assert!(state.get_service_status("cluster", "web-service").is_none());
+        assert!(state.has_log_group("/ecs/web-task"));
+    }
+}
@@ -0,0 +1,201 @@
+//! Building a project's image without deploying it
+//!
+//! Clones the repository and runs the same `docker build` a deployment would,
+//! so a Dockerfile can be checked without AWS credentials or touching ECR or ECS.
+
+use crate::application::orchestrator::OrchestratorError;
+use crate::infrastructure::Database;
+use crate::models::{Project, TestBuildReport};
+use crate::services::{build_docker_image, CommandRunner, GitOperations};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// One line of test build output, already redacted
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct TestBuildLogEvent {
+    pub project_id: String,
+    pub line: String,
+}
+
+/// Clone a project and build its image, reporting the build output
+///
+/// Each line of output is passed to `on_line` as it arrives and also kept in
+/// the report. A failed build is reported in the result rather than as an
+/// error; errors are reserved for not being able to attempt the build at all.
+/// Docker runs through `runner`. With `remove_image`, a successfully built
+/// image is deleted afterwards.
+pub async fn test_build(
+    database: &Mutex<Database>,
+    git: &dyn GitOperations,
+    runner: &dyn CommandRunner,
+    project_id: &str,
+    remove_image: bool,
+    on_line: &(dyn Fn(&str) + Send + Sync),
+) -> Result<TestBuildReport, OrchestratorError> {
+    let project = {
+        let db = database.lock()
+            .map_err(|e| OrchestratorError::DatabaseError(format!("Lock failed: {}", e)))?;
+        db.get_project(project_id)
+            .map_err(|e| OrchestratorError::DatabaseError(e.to_string()))?
+    };
+    
+    let repo_path = git.clone_repository(&project.repository_url, &project.branch)
+        .await
+        .map_err(|e| OrchestratorError::GitError(e.to_string()))?;
+    
+    let result = build_checkout(runner, &repo_path, &project, remove_image, on_line).await;
+    
+    // Best effort cleanup - the build result matters more
+    let _ = git.cleanup_repository(&repo_path).await;
+    
+    result
+}
+
+/// Build the image from a cloned checkout, then optionally remove it
+async fn build_checkout(
+    runner: &dyn CommandRunner,
+    repo_path: &Path,
+    project: &Project,
+    remove_image: bool,
+    on_line: &(dyn Fn(&str) + Send + Sync),
+) -> Result<TestBuildReport, OrchestratorError> {
+    let source_dir = repo_path.to_str()
+        .ok_or_else(|| OrchestratorError::AwsError("Invalid path".to_string()))?;
+    
+    let image_tag = format!("{}:test-build", image_name(&project.name));
+    let logs = Mutex::new(String::new());
+    let collect_line = |line: &str| {
+        if let Ok(mut logs) = logs.lock() {
+            logs.push_str(line);
+            logs.push('\n');
+        }
+        on_line(line);
+    };
+    
+    let build = build_docker_image(runner, source_dir, &image_tag, &project.framework, &collect_line).await;
+    let mut logs = logs.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+    
+    // A build that never started has no output to explain it
+    if let Err(e) = &build {
+        if logs.is_empty() {
+            logs = format!("{}\n", e);
+        }
+    }
+    
+    let image_removed = if build.is_ok() && remove_image {
+        runner.run("docker", &["rmi", &image_tag], None)
+            .await
+            .map(|removed| removed.success)
+            .unwrap_or(false)
+    } else {
+        false
+    };
+    
+    Ok(TestBuildReport {
+        project_id: project.id.clone(),
+        image_tag,
+        success: build.is_ok(),
+        logs,
+        image_removed,
+    })
+}
+
+/// Docker repository name for a project: lowercase, with runs of other characters as `-`
+fn image_name(project_name: &str) -> String {
+    let mut name = String::new();
+    for c in project_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    match name.trim_end_matches('-') {
+        "" => "app".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Environment;
+    use crate::services::CommandOutput;
+    use crate::shadow::test_utils::{mock_git, test_project};
+    use crate::shadow::{MockCommandRunner, MockGitService, ShadowState};
+    use std::sync::Arc;
+    
+    fn setup(project: Project) -> (Mutex<Database>, MockGitService) {
+        let git = mock_git(Arc::new(ShadowState::new()));
+        let db = Database::new_in_memory().unwrap();
+        db.create_project(&project).unwrap();
+        (Mutex::new(db), git)
+    }
+    
+    #[tokio::test]
+    async fn test_successful_build_streamed_and_removed() {
+        let project = test_project("web", Environment::Development);
+        let (database, git) = setup(project.clone());
+        let runner = MockCommandRunner::new();
+        runner.push_response(CommandOutput::success("#5 exporting layers\n#6 naming to web:test-build done"));
+        runner.push_response(CommandOutput::success(""));
+        let streamed = Mutex::new(Vec::new());
+        
+        let report = test_build(&database, &git, &runner, &project.id, true, &|line: &str| {
+            streamed.lock().unwrap().push(line.to_string());
+        }).await.unwrap();
+        
+        assert!(report.success);
+        let streamed = streamed.into_inner().unwrap();
+        assert_eq!(streamed, ["#5 exporting layers", "#6 naming to web:test-build done"]);
+        assert_eq!(report.logs, format!("{}\n", streamed.join("\n")));
+        assert!(report.image_removed);
+        
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].args[..3], ["build", "-t", "web:test-build"]);
+        assert_eq!(calls[1].args, ["rmi", "web:test-build"]);
+    }
+    
+    #[tokio::test]
+    async fn test_failed_build_reported_with_logs() {
+        let project = test_project("web", Environment::Development);
+        let (database, git) = setup(project.clone());
+        let runner = MockCommandRunner::new();
+        runner.push_response(CommandOutput::failure("COPY failed: file not found"));
+        
+        let report = test_build(&database, &git, &runner, &project.id, true, &|_: &str| {}).await.unwrap();
+        
+        assert!(!report.success);
+        assert!(report.logs.contains("COPY failed: file not found"));
+        assert!(!report.image_removed);
+        // Nothing to remove after a failed build
+        assert_eq!(runner.calls().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_docker_missing_reported_in_logs() {
+        let project = test_project("web", Environment::Development);
+        let (database, git) = setup(project.clone());
+        let runner = MockCommandRunner::new();
+        runner.push_spawn_failure(std::io::ErrorKind::NotFound);
+        
+        let report = test_build(&database, &git, &runner, &project.id, true, &|_: &str| {}).await.unwrap();
+        
+        assert!(!report.success);
+        assert!(report.logs.contains("Failed to build"), "{}", report.logs);
+    }
+    
+    #[tokio::test]
+    async fn test_image_tag_uses_slug_of_project_name() {
+        let project = test_project("My Web App!", Environment::Development);
+        let (database, git) = setup(project.clone());
+        let runner = MockCommandRunner::new();
+        
+        let report = test_build(&database, &git, &runner, &project.id, false, &|_: &str| {}).await.unwrap();
+        
+        assert_eq!(report.image_tag, "my-web-app:test-build");
+        assert_eq!(runner.calls()[0].args[2], "my-web-app:test-build");
+        assert_eq!(image_name("--"), "app");
+    }
+}
@@ -1,5 +1,9 @@
-use crate::models::{Deployment, DeploymentStatus, Environment, FrameworkType, Project};
-use rusqlite::{params, Connection, Result as SqliteResult};
+use crate::models::{ActiveDeployment, AppSettings, Application, ApplicationProjects, Deployment, DeploymentDetail, DeploymentError, DeploymentStatus, Environment, EnvironmentProjects, FrameworkType, PhaseTiming, ProgressRecord, Project, ProjectPatch, SloMetrics};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use rusqlite::types::{Type, Value, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult, Row};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -15,9 +19,15 @@ pub enum DatabaseError {
     #[error("Project not found: {0}")]
     ProjectNotFound(String),
     
+    #[error("Application not found: {0}")]
+    ApplicationNotFound(String),
+    
     #[error("Deployment not found: {0}")]
     DeploymentNotFound(String),
     
+    #[error("Deployment is still running: {0}")]
+    DeploymentInProgress(String),
+    
     #[error("Database query failed: {0}")]
     QueryFailed(String),
     
@@ -40,6 +50,186 @@ impl From<serde_json::Error> for DatabaseError {
 /// Database connection wrapper
 pub struct Database {
     conn: Connection,
+    compress_logs: bool,
+}
+
+/// Settings table key holding the `AppSettings` document
+const SETTINGS_KEY: &str = "app";
+
+/// Schema version recorded in `PRAGMA user_version` once migrations have run
+///
+/// Bump whenever `migrate()` gains a step.
+pub const SCHEMA_VERSION: i64 = 4;
+
+/// Logs at least this large (bytes) are gzip-compressed when compression is on
+const LOG_COMPRESSION_THRESHOLD: usize = 4096;
+
+/// Leading bytes of every gzip stream, used to recognise compressed logs
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Column list for project queries (order matches `row_to_project`)
+const PROJECT_COLUMNS: &str = "id, name, repository_url, branch, framework, environment,
+                    aws_cluster, aws_service, ecr_repository, created_at, updated_at,
+                    resource_size, pin_image_digest, stop_timeout, deploys_enabled,
+                    sidecars, ephemeral_storage_gib, container_port, environment_variables,
+                    inject_deploy_metadata, default_tags, desired_count, port_protocol,
+                    application_id, launch_type, network, extra_image_tags";
+
+/// Column list for deployment queries (order matches `row_to_deployment`)
+const DEPLOYMENT_COLUMNS: &str = "id, project_id, status, commit_sha, commit_message,
+                    image_tag, started_at, completed_at, error_message, logs,
+                    image_digest, notes, trigger, image_info, wait_for_healthy";
+
+/// Qualify each column in a column list with a table alias (for joins)
+fn qualified_columns(columns: &str, alias: &str) -> String {
+    columns
+        .split(',')
+        .map(|c| format!("{}.{}", alias, c.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Parse a JSON-encoded text column into a typed value
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row, idx: usize, name: &str) -> SqliteResult<T> {
+    serde_json::from_str(&row.get::<_, String>(idx)?)
+        .map_err(|_| rusqlite::Error::InvalidColumnType(
+            idx, name.to_string(), rusqlite::types::Type::Text
+        ))
+}
+
+/// Encode deployment logs for storage
+///
+/// Large logs are stored as a gzip BLOB when `compress` is set; everything else
+/// stays plain TEXT so existing rows and small logs are unchanged.
+fn encode_logs(logs: Option<&str>, compress: bool) -> Result<Option<Value>, DatabaseError> {
+    let text = match logs {
+        Some(text) => text,
+        None => return Ok(None),
+    };
+    
+    if !compress || text.len() < LOG_COMPRESSION_THRESHOLD {
+        return Ok(Some(Value::Text(text.to_string())));
+    }
+    
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes())
+        .and_then(|_| encoder.finish())
+        .map(|bytes| Some(Value::Blob(bytes)))
+        .map_err(|e| DatabaseError::SerializationFailed(format!("Failed to compress logs: {}", e)))
+}
+
+/// Read a logs column, transparently decompressing gzip BLOBs
+fn logs_column(row: &Row, idx: usize) -> SqliteResult<Option<String>> {
+    match row.get_ref(idx)? {
+        ValueRef::Null => Ok(None),
+        ValueRef::Text(bytes) => Ok(Some(String::from_utf8_lossy(bytes).to_string())),
+        ValueRef::Blob(bytes) if bytes.starts_with(&GZIP_MAGIC) => {
+            let mut text = String::new();
+            GzDecoder::new(bytes)
+                .read_to_string(&mut text)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Blob, Box::new(e)))?;
+            Ok(Some(text))
+        }
+        ValueRef::Blob(bytes) => Ok(Some(String::from_utf8_lossy(bytes).to_string())),
+        other => Err(rusqlite::Error::InvalidColumnType(idx, "logs".to_string(), other.data_type())),
+    }
+}
+
+/// Bring a deployment's `deployment_log_lines` in line with its new `logs`
+///
+/// When a migrated deployment's logs only had complete lines appended, just
+/// the new lines are inserted; otherwise every line is rewritten. The
+/// deployment is then flagged as migrated.
+fn write_log_lines(
+    conn: &Connection,
+    id: &str,
+    previous: Option<&str>,
+    migrated: bool,
+    logs: Option<&str>,
+) -> Result<(), DatabaseError> {
+    let (previous, logs) = (previous.unwrap_or_default(), logs.unwrap_or_default());
+    let appended = migrated
+        && logs.starts_with(previous)
+        && (previous.is_empty() || previous.ends_with('\n') || logs == previous);
+    
+    let (first_line, text) = if appended {
+        (previous.lines().count(), &logs[previous.len()..])
+    } else {
+        conn.execute("DELETE FROM deployment_log_lines WHERE deployment_id = ?1", params![id])?;
+        (0, logs)
+    };
+    for (offset, line) in text.lines().enumerate() {
+        conn.execute(
+            "INSERT INTO deployment_log_lines (deployment_id, line_number, line) VALUES (?1, ?2, ?3)",
+            params![id, (first_line + offset) as i64, line],
+        )?;
+    }
+    conn.execute("UPDATE deployments SET logs_migrated = 1 WHERE id = ?1", params![id])?;
+    
+    Ok(())
+}
+
+/// Map a row selected with `PROJECT_COLUMNS` to a Project
+fn row_to_project(row: &Row) -> SqliteResult<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        repository_url: row.get(2)?,
+        branch: row.get(3)?,
+        framework: json_column(row, 4, "framework")?,
+        environment: json_column(row, 5, "environment")?,
+        aws_cluster: row.get(6)?,
+        aws_service: row.get(7)?,
+        ecr_repository: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+        resource_size: json_column(row, 11, "resource_size")?,
+        pin_image_digest: row.get(12)?,
+        stop_timeout: row.get(13)?,
+        deploys_enabled: row.get(14)?,
+        sidecars: json_column(row, 15, "sidecars")?,
+        ephemeral_storage_gib: row.get(16)?,
+        container_port: row.get(17)?,
+        environment_variables: json_column(row, 18, "environment_variables")?,
+        inject_deploy_metadata: row.get(19)?,
+        default_tags: json_column(row, 20, "default_tags")?,
+        desired_count: row.get(21)?,
+        port_protocol: json_column(row, 22, "port_protocol")?,
+        application_id: row.get(23)?,
+        launch_type: json_column(row, 24, "launch_type")?,
+        network: json_column(row, 25, "network")?,
+        extra_image_tags: json_column(row, 26, "extra_image_tags")?,
+    })
+}
+
+/// Map an `id, name, created_at` row to an Application
+fn row_to_application(row: &Row) -> SqliteResult<Application> {
+    Ok(Application {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: row.get(2)?,
+    })
+}
+
+/// Map a row selected with `DEPLOYMENT_COLUMNS` to a Deployment
+fn row_to_deployment(row: &Row) -> SqliteResult<Deployment> {
+    Ok(Deployment {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        status: json_column(row, 2, "status")?,
+        commit_sha: row.get(3)?,
+        commit_message: row.get(4)?,
+        image_tag: row.get(5)?,
+        started_at: row.get(6)?,
+        completed_at: row.get(7)?,
+        error_message: row.get(8)?,
+        logs: logs_column(row, 9)?,
+        image_digest: row.get(10)?,
+        notes: row.get(11)?,
+        trigger: json_column(row, 12, "trigger")?,
+        image_info: json_column(row, 13, "image_info")?,
+        wait_for_healthy: row.get(14)?,
+    })
 }
 
 impl Database {
@@ -56,12 +246,30 @@ impl Database {
         let conn = Connection::open(&db_path)
             .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
         
-        let mut db = Database { conn };
+        let mut db = Database { conn, compress_logs: true };
+        db.init_database()?;
+        
+        Ok(db)
+    }
+    
+    /// Create an in-memory database (used by shadow mode and tests)
+    pub fn new_in_memory() -> Result<Self, DatabaseError> {
+        let conn = Connection::open_in_memory()
+            .map_err(|e| DatabaseError::ConnectionFailed(e.to_string()))?;
+        
+        let mut db = Database { conn, compress_logs: true };
         db.init_database()?;
         
         Ok(db)
     }
     
+    /// Enable or disable gzip compression of newly written deployment logs
+    ///
+    /// Reads always handle both compressed and plain logs.
+    pub fn set_log_compression(&mut self, enabled: bool) {
+        self.compress_logs = enabled;
+    }
+    
     /// Get the database file path
     fn get_database_path() -> Result<PathBuf, DatabaseError> {
         let data_dir = dirs::data_dir()
@@ -78,6 +286,16 @@ impl Database {
         self.conn.execute("PRAGMA foreign_keys = ON", [])
             .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
         
+        // Create applications table (groups a project per environment)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS applications (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
         // Create projects table
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS projects (
@@ -102,7 +320,7 @@ impl Database {
                 id TEXT PRIMARY KEY,
                 project_id TEXT NOT NULL,
                 status TEXT NOT NULL,
-                commit_sha TEXT NOT NULL,
+                commit_sha TEXT,
                 commit_message TEXT,
                 image_tag TEXT NOT NULL,
                 started_at INTEGER NOT NULL,
@@ -127,6 +345,210 @@ impl Database {
             [],
         ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
         
+        // Create settings table (key -> JSON document)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        // Create framework detection cache (one entry per project, latest commit only)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS framework_cache (
+                project_id TEXT PRIMARY KEY,
+                commit_sha TEXT NOT NULL,
+                framework TEXT NOT NULL,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+            )",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        // Create progress history (one row per progress update, in order)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS deployment_progress (
+                deployment_id TEXT NOT NULL,
+                sequence INTEGER NOT NULL,
+                progress INTEGER NOT NULL,
+                message TEXT NOT NULL,
+                recorded_at_ms INTEGER NOT NULL,
+                PRIMARY KEY (deployment_id, sequence),
+                FOREIGN KEY (deployment_id) REFERENCES deployments(id) ON DELETE CASCADE
+            )",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        // Create deployment artifacts (e.g. the Terraform matching each deployment)
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS deployment_artifacts (
+                deployment_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                content TEXT NOT NULL,
+                PRIMARY KEY (deployment_id, name),
+                FOREIGN KEY (deployment_id) REFERENCES deployments(id) ON DELETE CASCADE
+            )",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        self.migrate()?;
+        
+        self.conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))
+            .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    /// Apply additive schema changes to databases created by older versions
+    fn migrate(&mut self) -> Result<(), DatabaseError> {
+        self.ensure_column("projects", "resource_size", "TEXT NOT NULL DEFAULT '\"medium\"'")?;
+        self.ensure_column("projects", "pin_image_digest", "INTEGER NOT NULL DEFAULT 0")?;
+        self.ensure_column("projects", "stop_timeout", "INTEGER")?;
+        self.ensure_column("projects", "deploys_enabled", "INTEGER NOT NULL DEFAULT 1")?;
+        self.ensure_column("projects", "sidecars", "TEXT NOT NULL DEFAULT '[]'")?;
+        self.ensure_column("projects", "ephemeral_storage_gib", "INTEGER")?;
+        self.ensure_column("projects", "container_port", "INTEGER")?;
+        self.ensure_column("projects", "environment_variables", "TEXT NOT NULL DEFAULT '{}'")?;
+        self.ensure_column("projects", "inject_deploy_metadata", "INTEGER NOT NULL DEFAULT 1")?;
+        self.ensure_column("projects", "default_tags", "TEXT NOT NULL DEFAULT '[]'")?;
+        self.ensure_column("projects", "desired_count", "INTEGER")?;
+        self.ensure_column("projects", "port_protocol", "TEXT NOT NULL DEFAULT '\"tcp\"'")?;
+        self.ensure_column("projects", "application_id", "TEXT REFERENCES applications(id) ON DELETE SET NULL")?;
+        self.ensure_column("projects", "launch_type", "TEXT NOT NULL DEFAULT '\"fargate\"'")?;
+        self.ensure_column("projects", "network", "TEXT NOT NULL DEFAULT '{}'")?;
+        self.ensure_column("projects", "extra_image_tags", "TEXT NOT NULL DEFAULT '[]'")?;
+        self.ensure_column("deployments", "image_digest", "TEXT")?;
+        self.ensure_column("deployments", "notes", "TEXT")?;
+        self.ensure_column("deployments", "trigger", "TEXT NOT NULL DEFAULT '\"manual\"'")?;
+        self.ensure_column("deployments", "image_info", "TEXT NOT NULL DEFAULT 'null'")?;
+        self.ensure_column("deployments", "wait_for_healthy", "INTEGER NOT NULL DEFAULT 1")?;
+        self.make_commit_sha_nullable()?;
+        // After the rebuild above, which only keeps the columns it knows about
+        self.ensure_column("deployments", "logs_migrated", "INTEGER NOT NULL DEFAULT 0")?;
+        self.migrate_logs_to_lines()?;
+        
+        Ok(())
+    }
+    
+    /// Split finished deployments' single-blob logs into `deployment_log_lines`
+    ///
+    /// Migrated deployments are flagged so reads prefer the line table; the
+    /// original `logs` column is left in place. A deployment still running is
+    /// migrated by its next log write instead.
+    fn migrate_logs_to_lines(&mut self) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS deployment_log_lines (
+                deployment_id TEXT NOT NULL,
+                line_number INTEGER NOT NULL,
+                line TEXT NOT NULL,
+                PRIMARY KEY (deployment_id, line_number),
+                FOREIGN KEY (deployment_id) REFERENCES deployments(id) ON DELETE CASCADE
+            )",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        let tx = self.conn.transaction()
+            .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        let pending = {
+            let mut stmt = tx.prepare(
+                "SELECT id, logs FROM deployments
+                 WHERE logs IS NOT NULL AND logs_migrated = 0 AND completed_at IS NOT NULL"
+            ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+            let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, logs_column(row, 1)?)))
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?
+                .collect::<SqliteResult<Vec<_>>>()
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+            rows
+        };
+        
+        for (id, logs) in pending {
+            write_log_lines(&tx, &id, None, false, logs.as_deref())
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        }
+        
+        tx.commit()
+            .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    /// Drop the NOT NULL constraint older versions put on `deployments.commit_sha`
+    ///
+    /// SQLite can't relax a column constraint in place, so the table is rebuilt
+    /// (with foreign keys off, as SQLite's documented procedure requires).
+    /// Rows left with the old "pending" placeholder are cleared to NULL.
+    fn make_commit_sha_nullable(&mut self) -> Result<(), DatabaseError> {
+        let not_null: bool = self.conn.query_row(
+            "SELECT \"notnull\" FROM pragma_table_info('deployments') WHERE name = 'commit_sha'",
+            [],
+            |row| row.get(0),
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        if not_null {
+            self.conn.execute("PRAGMA foreign_keys = OFF", [])
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+            
+            let tx = self.conn.transaction()
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+            tx.execute_batch(&format!(
+                "CREATE TABLE deployments_new (
+                    id TEXT PRIMARY KEY,
+                    project_id TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    commit_sha TEXT,
+                    commit_message TEXT,
+                    image_tag TEXT NOT NULL,
+                    started_at INTEGER NOT NULL,
+                    completed_at INTEGER,
+                    error_message TEXT,
+                    logs TEXT,
+                    image_digest TEXT,
+                    notes TEXT,
+                    trigger TEXT NOT NULL DEFAULT '\"manual\"',
+                    image_info TEXT NOT NULL DEFAULT 'null',
+                    wait_for_healthy INTEGER NOT NULL DEFAULT 1,
+                    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+                );
+                INSERT INTO deployments_new ({columns}) SELECT {columns} FROM deployments;
+                DROP TABLE deployments;
+                ALTER TABLE deployments_new RENAME TO deployments;
+                CREATE INDEX IF NOT EXISTS idx_deployments_project_id ON deployments(project_id);
+                CREATE INDEX IF NOT EXISTS idx_deployments_status ON deployments(status);",
+                columns = DEPLOYMENT_COLUMNS,
+            )).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+            tx.commit()
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+            
+            self.conn.execute("PRAGMA foreign_keys = ON", [])
+                .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        }
+        
+        self.conn.execute(
+            "UPDATE deployments SET commit_sha = NULL WHERE commit_sha = 'pending'",
+            [],
+        ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    /// Add a column to a table if it does not already exist
+    fn ensure_column(&self, table: &str, column: &str, definition: &str) -> Result<(), DatabaseError> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({})", table))
+            .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        
+        let exists = stmt.query_map([], |row| row.get::<_, String>(1))
+            .map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+        
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            ).map_err(|e| DatabaseError::InitializationFailed(e.to_string()))?;
+        }
+        
         Ok(())
     }
     
@@ -137,8 +559,12 @@ impl Database {
         self.conn.execute(
             "INSERT INTO projects (
                 id, name, repository_url, branch, framework, environment,
-                aws_cluster, aws_service, ecr_repository, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                aws_cluster, aws_service, ecr_repository, created_at, updated_at,
+                resource_size, pin_image_digest, stop_timeout, deploys_enabled,
+                sidecars, ephemeral_storage_gib, container_port, environment_variables,
+                inject_deploy_metadata, default_tags, desired_count, port_protocol,
+                application_id, launch_type, network, extra_image_tags
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27)",
             params![
                 project.id,
                 project.name,
@@ -151,6 +577,22 @@ impl Database {
                 project.ecr_repository,
                 project.created_at,
                 project.updated_at,
+                serde_json::to_string(&project.resource_size)?,
+                project.pin_image_digest,
+                project.stop_timeout,
+                project.deploys_enabled,
+                serde_json::to_string(&project.sidecars)?,
+                project.ephemeral_storage_gib,
+                project.container_port,
+                serde_json::to_string(&project.environment_variables)?,
+                project.inject_deploy_metadata,
+                serde_json::to_string(&project.default_tags)?,
+                project.desired_count,
+                serde_json::to_string(&project.port_protocol)?,
+                project.application_id,
+                serde_json::to_string(&project.launch_type)?,
+                serde_json::to_string(&project.network)?,
+                serde_json::to_string(&project.extra_image_tags)?,
             ],
         )?;
         
@@ -159,71 +601,31 @@ impl Database {
     
     /// Get a project by ID
     pub fn get_project(&self, id: &str) -> Result<Project, DatabaseError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, repository_url, branch, framework, environment,
-                    aws_cluster, aws_service, ecr_repository, created_at, updated_at
-             FROM projects WHERE id = ?1"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM projects WHERE id = ?1",
+            PROJECT_COLUMNS
+        ))?;
         
-        let project = stmt.query_row(params![id], |row| {
-            Ok(Project {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                repository_url: row.get(2)?,
-                branch: row.get(3)?,
-                framework: serde_json::from_str(&row.get::<_, String>(4)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        4, "framework".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                environment: serde_json::from_str(&row.get::<_, String>(5)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        5, "environment".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                aws_cluster: row.get(6)?,
-                aws_service: row.get(7)?,
-                ecr_repository: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        }).map_err(|e| match e {
-            rusqlite::Error::QueryReturnedNoRows => {
-                DatabaseError::ProjectNotFound(id.to_string())
-            }
-            _ => DatabaseError::from(e),
-        })?;
+        let project = stmt.query_row(params![id], row_to_project)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DatabaseError::ProjectNotFound(id.to_string())
+                }
+                _ => DatabaseError::from(e),
+            })?;
         
         Ok(project)
     }
     
     /// Get all projects
     pub fn get_all_projects(&self) -> Result<Vec<Project>, DatabaseError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, name, repository_url, branch, framework, environment,
-                    aws_cluster, aws_service, ecr_repository, created_at, updated_at
-             FROM projects ORDER BY updated_at DESC"
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM projects ORDER BY updated_at DESC",
+            PROJECT_COLUMNS
+        ))?;
         
-        let projects = stmt.query_map([], |row| {
-            Ok(Project {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                repository_url: row.get(2)?,
-                branch: row.get(3)?,
-                framework: serde_json::from_str(&row.get::<_, String>(4)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        4, "framework".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                environment: serde_json::from_str(&row.get::<_, String>(5)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        5, "environment".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                aws_cluster: row.get(6)?,
-                aws_service: row.get(7)?,
-                ecr_repository: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        })?.collect::<SqliteResult<Vec<_>>>()?;
+        let projects = stmt.query_map([], row_to_project)?
+            .collect::<SqliteResult<Vec<_>>>()?;
         
         Ok(projects)
     }
@@ -234,8 +636,14 @@ impl Database {
             "UPDATE projects SET 
                 name = ?1, repository_url = ?2, branch = ?3, framework = ?4,
                 environment = ?5, aws_cluster = ?6, aws_service = ?7,
-                ecr_repository = ?8, updated_at = ?9
-             WHERE id = ?10",
+                ecr_repository = ?8, updated_at = ?9, resource_size = ?10,
+                pin_image_digest = ?11, stop_timeout = ?12, deploys_enabled = ?13,
+                sidecars = ?14, ephemeral_storage_gib = ?15, container_port = ?16,
+                environment_variables = ?17, inject_deploy_metadata = ?18,
+                default_tags = ?19, desired_count = ?20, port_protocol = ?21,
+                application_id = ?22, launch_type = ?23, network = ?24,
+                extra_image_tags = ?25
+             WHERE id = ?26",
             params![
                 project.name,
                 project.repository_url,
@@ -246,6 +654,22 @@ impl Database {
                 project.aws_service,
                 project.ecr_repository,
                 project.updated_at,
+                serde_json::to_string(&project.resource_size)?,
+                project.pin_image_digest,
+                project.stop_timeout,
+                project.deploys_enabled,
+                serde_json::to_string(&project.sidecars)?,
+                project.ephemeral_storage_gib,
+                project.container_port,
+                serde_json::to_string(&project.environment_variables)?,
+                project.inject_deploy_metadata,
+                serde_json::to_string(&project.default_tags)?,
+                project.desired_count,
+                serde_json::to_string(&project.port_protocol)?,
+                project.application_id,
+                serde_json::to_string(&project.launch_type)?,
+                serde_json::to_string(&project.network)?,
+                serde_json::to_string(&project.extra_image_tags)?,
                 project.id,
             ],
         )?;
@@ -257,6 +681,36 @@ impl Database {
         Ok(())
     }
     
+    /// Apply a sparse update to a project and return the result
+    ///
+    /// The read and write happen in one transaction, so fields the patch
+    /// leaves out keep whatever value is stored at that moment.
+    pub fn update_project_partial(&self, id: &str, patch: ProjectPatch) -> Result<Project, DatabaseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        let mut project = self.get_project(id)?;
+        patch.apply(&mut project);
+        project.touch();
+        self.update_project(&project)?;
+        
+        tx.commit()?;
+        Ok(project)
+    }
+    
+    /// Enable or disable new deployments for a project
+    pub fn set_project_deploys_enabled(&self, id: &str, enabled: bool) -> Result<(), DatabaseError> {
+        let rows_affected = self.conn.execute(
+            "UPDATE projects SET deploys_enabled = ?1, updated_at = ?2 WHERE id = ?3",
+            params![enabled, chrono::Utc::now().timestamp(), id],
+        )?;
+        
+        if rows_affected == 0 {
+            return Err(DatabaseError::ProjectNotFound(id.to_string()));
+        }
+        
+        Ok(())
+    }
+    
     /// Delete a project (and all associated deployments due to CASCADE)
     pub fn delete_project(&self, id: &str) -> Result<(), DatabaseError> {
         let rows_affected = self.conn.execute(
@@ -271,15 +725,146 @@ impl Database {
         Ok(())
     }
     
+    // ===== Applications =====
+    
+    /// Create a new application
+    pub fn create_application(&self, application: &Application) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO applications (id, name, created_at) VALUES (?1, ?2, ?3)",
+            params![application.id, application.name, application.created_at],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Get an application by ID
+    pub fn get_application(&self, id: &str) -> Result<Application, DatabaseError> {
+        self.conn.query_row(
+            "SELECT id, name, created_at FROM applications WHERE id = ?1",
+            params![id],
+            row_to_application,
+        ).optional()?
+            .ok_or_else(|| DatabaseError::ApplicationNotFound(id.to_string()))
+    }
+    
+    /// Get all applications, by name
+    pub fn get_all_applications(&self) -> Result<Vec<Application>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, created_at FROM applications ORDER BY name"
+        )?;
+        
+        let applications = stmt.query_map([], row_to_application)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        
+        Ok(applications)
+    }
+    
+    /// Get an application's projects grouped by environment
+    ///
+    /// Environments without a project are left out.
+    pub fn get_application_projects(&self, id: &str) -> Result<ApplicationProjects, DatabaseError> {
+        let application = self.get_application(id)?;
+        
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM projects WHERE application_id = ?1 ORDER BY name",
+            PROJECT_COLUMNS
+        ))?;
+        let projects = stmt.query_map(params![id], row_to_project)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        
+        let environments = Environment::ALL
+            .into_iter()
+            .map(|environment| EnvironmentProjects {
+                projects: projects.iter()
+                    .filter(|p| p.environment == environment)
+                    .cloned()
+                    .collect(),
+                environment,
+            })
+            .filter(|group| !group.projects.is_empty())
+            .collect();
+        
+        Ok(ApplicationProjects { application, environments })
+    }
+    
+    // ===== Diagnostics =====
+    
+    /// Schema version stored in the database file
+    ///
+    /// Also serves as a connectivity check, since it runs a query.
+    pub fn schema_version(&self) -> Result<i64, DatabaseError> {
+        Ok(self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+    
+    // ===== Settings =====
+    
+    /// Get application settings, falling back to defaults if none are stored
+    pub fn get_settings(&self) -> Result<AppSettings, DatabaseError> {
+        let value: Option<String> = self.conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            params![SETTINGS_KEY],
+            |row| row.get(0),
+        ).optional()?;
+        
+        match value {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(AppSettings::default()),
+        }
+    }
+    
+    /// Persist application settings
+    pub fn save_settings(&self, settings: &AppSettings) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![SETTINGS_KEY, serde_json::to_string(settings)?],
+        )?;
+        
+        Ok(())
+    }
+    
+    // ===== Framework Detection Cache =====
+    
+    /// Get the framework previously detected for a project at `commit_sha`
+    ///
+    /// Returns `None` if nothing was cached or the cache is for another commit.
+    pub fn get_cached_framework(&self, project_id: &str, commit_sha: &str) -> Result<Option<FrameworkType>, DatabaseError> {
+        let value: Option<String> = self.conn.query_row(
+            "SELECT framework FROM framework_cache WHERE project_id = ?1 AND commit_sha = ?2",
+            params![project_id, commit_sha],
+            |row| row.get(0),
+        ).optional()?;
+        
+        match value {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Record the framework detected for a project at `commit_sha`, replacing any older entry
+    pub fn set_cached_framework(&self, project_id: &str, commit_sha: &str, framework: &FrameworkType) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO framework_cache (project_id, commit_sha, framework) VALUES (?1, ?2, ?3)
+             ON CONFLICT(project_id) DO UPDATE SET
+                commit_sha = excluded.commit_sha, framework = excluded.framework",
+            params![project_id, commit_sha, serde_json::to_string(framework)?],
+        )?;
+        
+        Ok(())
+    }
+    
     // ===== Deployment CRUD Operations =====
     
     /// Create a new deployment
     pub fn create_deployment(&self, deployment: &Deployment) -> Result<(), DatabaseError> {
-        self.conn.execute(
+        let tx = self.conn.unchecked_transaction()?;
+        
+        tx.execute(
             "INSERT INTO deployments (
                 id, project_id, status, commit_sha, commit_message,
-                image_tag, started_at, completed_at, error_message, logs
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                image_tag, started_at, completed_at, error_message, logs,
+                image_digest, notes, trigger, image_info, wait_for_healthy
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             params![
                 deployment.id,
                 deployment.project_id,
@@ -290,162 +875,1234 @@ impl Database {
                 deployment.started_at,
                 deployment.completed_at,
                 deployment.error_message,
-                deployment.logs,
+                encode_logs(deployment.logs.as_deref(), self.compress_logs)?,
+                deployment.image_digest,
+                deployment.notes,
+                serde_json::to_string(&deployment.trigger)?,
+                serde_json::to_string(&deployment.image_info)?,
+                deployment.wait_for_healthy,
             ],
         )?;
+        write_log_lines(&tx, &deployment.id, None, false, deployment.logs.as_deref())?;
         
+        tx.commit()?;
         Ok(())
     }
     
     /// Get a deployment by ID
     pub fn get_deployment(&self, id: &str) -> Result<Deployment, DatabaseError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM deployments WHERE id = ?1",
+            DEPLOYMENT_COLUMNS
+        ))?;
+        
+        let deployment = stmt.query_row(params![id], row_to_deployment)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    DatabaseError::DeploymentNotFound(id.to_string())
+                }
+                _ => DatabaseError::from(e),
+            })?;
+        
+        Ok(deployment)
+    }
+    
+    /// Get the last `n` lines of a deployment's logs, oldest first
+    ///
+    /// Only the logs are read, so the rest of the record isn't loaded. For
+    /// migrated deployments only the last `n` lines are.
+    pub fn get_deployment_log_tail(&self, id: &str, n: usize) -> Result<Vec<String>, DatabaseError> {
+        let migrated = self.conn.query_row(
+            "SELECT logs_migrated FROM deployments WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, bool>(0),
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                DatabaseError::DeploymentNotFound(id.to_string())
+            }
+            _ => DatabaseError::from(e),
+        })?;
+        
+        if !migrated {
+            let lines = self.get_deployment_log_lines(id, 0)?;
+            return Ok(lines[lines.len().saturating_sub(n)..].to_vec());
+        }
+        
         let mut stmt = self.conn.prepare(
-            "SELECT id, project_id, status, commit_sha, commit_message,
-                    image_tag, started_at, completed_at, error_message, logs
-             FROM deployments WHERE id = ?1"
+            "SELECT line FROM deployment_log_lines
+             WHERE deployment_id = ?1
+             ORDER BY line_number DESC LIMIT ?2"
         )?;
-        
-        let deployment = stmt.query_row(params![id], |row| {
-            Ok(Deployment {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                status: serde_json::from_str(&row.get::<_, String>(2)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        2, "status".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                commit_sha: row.get(3)?,
-                commit_message: row.get(4)?,
-                image_tag: row.get(5)?,
-                started_at: row.get(6)?,
-                completed_at: row.get(7)?,
-                error_message: row.get(8)?,
-                logs: row.get(9)?,
-            })
-        }).map_err(|e| match e {
+        let mut lines = stmt.query_map(params![id, n as i64], |row| row.get(0))?
+            .collect::<SqliteResult<Vec<String>>>()?;
+        lines.reverse();
+        Ok(lines)
+    }
+    
+    /// Get a deployment's log lines starting at line `from` (0-based)
+    ///
+    /// Migrated deployments are read from `deployment_log_lines`; others are
+    /// split from the logs column.
+    pub fn get_deployment_log_lines(&self, id: &str, from: usize) -> Result<Vec<String>, DatabaseError> {
+        let (migrated, logs) = self.conn.query_row(
+            "SELECT logs_migrated, logs FROM deployments WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get::<_, bool>(0)?, logs_column(row, 1)?)),
+        ).map_err(|e| match e {
             rusqlite::Error::QueryReturnedNoRows => {
                 DatabaseError::DeploymentNotFound(id.to_string())
             }
             _ => DatabaseError::from(e),
         })?;
         
-        Ok(deployment)
+        if migrated {
+            let mut stmt = self.conn.prepare(
+                "SELECT line FROM deployment_log_lines
+                 WHERE deployment_id = ?1 AND line_number >= ?2
+                 ORDER BY line_number"
+            )?;
+            let lines = stmt.query_map(params![id, from as i64], |row| row.get(0))?
+                .collect::<SqliteResult<Vec<String>>>()?;
+            return Ok(lines);
+        }
+        
+        Ok(logs.unwrap_or_default()
+            .lines()
+            .skip(from)
+            .map(|line| line.to_string())
+            .collect())
     }
     
-    /// Get all deployments for a project
-    pub fn get_deployments_for_project(&self, project_id: &str) -> Result<Vec<Deployment>, DatabaseError> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, project_id, status, commit_sha, commit_message,
-                    image_tag, started_at, completed_at, error_message, logs
-             FROM deployments 
-             WHERE project_id = ?1 
-             ORDER BY started_at DESC"
+    /// Drop the logs of deployments that finished before `timestamp`
+    ///
+    /// The deployment rows and their other fields are kept; running
+    /// deployments are never touched. Returns how many deployments lost logs.
+    pub fn prune_deployment_logs_older_than(&self, timestamp: i64) -> Result<usize, DatabaseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        tx.execute(
+            "DELETE FROM deployment_log_lines WHERE deployment_id IN (
+                SELECT id FROM deployments WHERE completed_at IS NOT NULL AND completed_at < ?1
+             )",
+            params![timestamp],
+        )?;
+        let pruned = tx.execute(
+            "UPDATE deployments SET logs = NULL
+             WHERE completed_at IS NOT NULL AND completed_at < ?1 AND logs IS NOT NULL",
+            params![timestamp],
         )?;
         
-        let deployments = stmt.query_map(params![project_id], |row| {
-            Ok(Deployment {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                status: serde_json::from_str(&row.get::<_, String>(2)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        2, "status".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                commit_sha: row.get(3)?,
-                commit_message: row.get(4)?,
-                image_tag: row.get(5)?,
-                started_at: row.get(6)?,
-                completed_at: row.get(7)?,
-                error_message: row.get(8)?,
-                logs: row.get(9)?,
-            })
-        })?.collect::<SqliteResult<Vec<_>>>()?;
+        tx.commit()?;
+        Ok(pruned)
+    }
+    
+    /// Append a progress update to a deployment's history
+    pub fn record_progress(&self, deployment_id: &str, record: &ProgressRecord) -> Result<(), DatabaseError> {
+        self.conn.execute(
+            "INSERT INTO deployment_progress (deployment_id, sequence, progress, message, recorded_at_ms)
+             SELECT ?1, COALESCE(MAX(sequence) + 1, 0), ?2, ?3, ?4
+             FROM deployment_progress WHERE deployment_id = ?1",
+            params![deployment_id, record.progress, record.message, record.recorded_at_ms],
+        )?;
         
-        Ok(deployments)
+        Ok(())
     }
     
-    /// Get all deployments
-    pub fn get_all_deployments(&self) -> Result<Vec<Deployment>, DatabaseError> {
+    /// Get a deployment's progress history, oldest first
+    pub fn get_deployment_progress(&self, deployment_id: &str) -> Result<Vec<ProgressRecord>, DatabaseError> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, project_id, status, commit_sha, commit_message,
-                    image_tag, started_at, completed_at, error_message, logs
-             FROM deployments 
-             ORDER BY started_at DESC"
+            "SELECT progress, message, recorded_at_ms FROM deployment_progress
+             WHERE deployment_id = ?1
+             ORDER BY sequence"
         )?;
         
-        let deployments = stmt.query_map([], |row| {
-            Ok(Deployment {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                status: serde_json::from_str(&row.get::<_, String>(2)?)
-                    .map_err(|e| rusqlite::Error::InvalidColumnType(
-                        2, "status".to_string(), rusqlite::types::Type::Text
-                    ))?,
-                commit_sha: row.get(3)?,
-                commit_message: row.get(4)?,
-                image_tag: row.get(5)?,
-                started_at: row.get(6)?,
-                completed_at: row.get(7)?,
-                error_message: row.get(8)?,
-                logs: row.get(9)?,
+        let progress = stmt.query_map(params![deployment_id], |row| {
+            Ok(ProgressRecord {
+                progress: row.get(0)?,
+                message: row.get(1)?,
+                recorded_at_ms: row.get(2)?,
             })
         })?.collect::<SqliteResult<Vec<_>>>()?;
         
-        Ok(deployments)
+        Ok(progress)
     }
     
-    /// Update an existing deployment
-    pub fn update_deployment(&self, deployment: &Deployment) -> Result<(), DatabaseError> {
-        let rows_affected = self.conn.execute(
-            "UPDATE deployments SET 
-                status = ?1, commit_message = ?2, completed_at = ?3,
-                error_message = ?4, logs = ?5
-             WHERE id = ?6",
-            params![
-                serde_json::to_string(&deployment.status)?,
-                deployment.commit_message,
-                deployment.completed_at,
-                deployment.error_message,
-                deployment.logs,
-                deployment.id,
-            ],
-        )?;
+    /// Store files generated for a deployment, replacing any with the same name
+    pub fn save_deployment_artifacts(&self, deployment_id: &str, artifacts: &BTreeMap<String, String>) -> Result<(), DatabaseError> {
+        let tx = self.conn.unchecked_transaction()?;
         
-        if rows_affected == 0 {
-            return Err(DatabaseError::DeploymentNotFound(deployment.id.clone()));
+        for (name, content) in artifacts {
+            tx.execute(
+                "INSERT OR REPLACE INTO deployment_artifacts (deployment_id, name, content)
+                 VALUES (?1, ?2, ?3)",
+                params![deployment_id, name, content],
+            )?;
         }
         
+        tx.commit()?;
         Ok(())
     }
     
-    /// Delete a deployment
-    pub fn delete_deployment(&self, id: &str) -> Result<(), DatabaseError> {
-        let rows_affected = self.conn.execute(
-            "DELETE FROM deployments WHERE id = ?1",
-            params![id],
+    /// Get the files stored for a deployment, keyed by name
+    pub fn get_deployment_artifacts(&self, deployment_id: &str) -> Result<BTreeMap<String, String>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT name, content FROM deployment_artifacts WHERE deployment_id = ?1"
         )?;
         
-        if rows_affected == 0 {
+        let artifacts = stmt.query_map(params![deployment_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<BTreeMap<_, _>>>()?;
+        
+        Ok(artifacts)
+    }
+    
+    /// Get a deployment with its project, progress history and phase timings
+    ///
+    /// Callers hold the database lock, so nothing is written between the reads.
+    pub fn get_deployment_detail(&self, id: &str) -> Result<DeploymentDetail, DatabaseError> {
+        let deployment = self.get_deployment(id)?;
+        let project = self.get_project(&deployment.project_id)?;
+        let progress = self.get_deployment_progress(id)?;
+        
+        Ok(DeploymentDetail {
+            phase_timings: PhaseTiming::from_progress(&progress),
+            deployment,
+            project,
+            progress,
+        })
+    }
+    
+    /// Get all deployments for a project
+    pub fn get_deployments_for_project(&self, project_id: &str) -> Result<Vec<Deployment>, DatabaseError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM deployments 
+             WHERE project_id = ?1 
+             ORDER BY started_at DESC",
+            DEPLOYMENT_COLUMNS
+        ))?;
+        
+        let deployments = stmt.query_map(params![project_id], row_to_deployment)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        
+        Ok(deployments)
+    }
+    
+    /// Get all deployments
+    pub fn get_all_deployments(&self) -> Result<Vec<Deployment>, DatabaseError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM deployments 
+             ORDER BY started_at DESC",
+            DEPLOYMENT_COLUMNS
+        ))?;
+        
+        let deployments = stmt.query_map([], row_to_deployment)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        
+        Ok(deployments)
+    }
+    
+    /// Get all pending or in-progress deployments across projects, newest first
+    pub fn get_active_deployments(&self) -> Result<Vec<ActiveDeployment>, DatabaseError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {}, p.name FROM deployments d
+             JOIN projects p ON p.id = d.project_id
+             WHERE d.status IN (?1, ?2)
+             ORDER BY d.started_at DESC",
+            qualified_columns(DEPLOYMENT_COLUMNS, "d")
+        ))?;
+        
+        let name_idx = DEPLOYMENT_COLUMNS.split(',').count();
+        let active = stmt.query_map(
+            params![
+                serde_json::to_string(&DeploymentStatus::Pending)?,
+                serde_json::to_string(&DeploymentStatus::InProgress)?,
+            ],
+            |row| {
+                Ok(ActiveDeployment {
+                    deployment: row_to_deployment(row)?,
+                    project_name: row.get(name_idx)?,
+                })
+            },
+        )?.collect::<SqliteResult<Vec<_>>>()?;
+        
+        Ok(active)
+    }
+    
+    /// Get the most recent error message recorded for a project, if any
+    pub fn get_last_error(&self, project_id: &str) -> Result<Option<DeploymentError>, DatabaseError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, error_message, started_at FROM deployments
+             WHERE project_id = ?1 AND error_message IS NOT NULL
+             ORDER BY started_at DESC
+             LIMIT 1"
+        )?;
+        
+        let last_error = stmt.query_row(params![project_id], |row| {
+            Ok(DeploymentError {
+                deployment_id: row.get(0)?,
+                message: row.get(1)?,
+                occurred_at: row.get(2)?,
+            })
+        }).optional()?;
+        
+        Ok(last_error)
+    }
+    
+    /// Get the status of a project's most recent deployment, if it has any
+    pub fn get_latest_deployment_status(&self, project_id: &str) -> Result<Option<DeploymentStatus>, DatabaseError> {
+        let status: Option<String> = self.conn.query_row(
+            "SELECT status FROM deployments
+             WHERE project_id = ?1
+             ORDER BY started_at DESC
+             LIMIT 1",
+            params![project_id],
+            |row| row.get(0),
+        ).optional()?;
+        
+        match status {
+            Some(json) => Ok(Some(serde_json::from_str(&json)?)),
+            None => Ok(None),
+        }
+    }
+    
+    /// Success rate and mean time to recovery over the `window_days` before `now`
+    ///
+    /// Covers one project, or every project when `project_id` is `None`.
+    /// Failed and rolled-back deployments both count as failures; a recovery
+    /// runs from the first failure in a streak to the project's next success.
+    pub fn get_slo_metrics(&self, project_id: Option<&str>, window_days: u32, now: i64) -> Result<SloMetrics, DatabaseError> {
+        let since = now - i64::from(window_days) * 24 * 60 * 60;
+        let success = serde_json::to_string(&DeploymentStatus::Success)?;
+        let failed = serde_json::to_string(&DeploymentStatus::Failed)?;
+        let rolled_back = serde_json::to_string(&DeploymentStatus::RolledBack)?;
+        
+        let (successful, failures): (u32, u32) = self.conn.query_row(
+            "SELECT COALESCE(SUM(status = ?3), 0), COALESCE(SUM(status IN (?4, ?5)), 0)
+             FROM deployments
+             WHERE (?1 IS NULL OR project_id = ?1) AND started_at BETWEEN ?2 AND ?6",
+            params![project_id, since, success, failed, rolled_back, now],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        
+        let mut stmt = self.conn.prepare(
+            "SELECT project_id, status = ?3, COALESCE(completed_at, started_at)
+             FROM deployments
+             WHERE (?1 IS NULL OR project_id = ?1) AND started_at BETWEEN ?2 AND ?6 AND status IN (?3, ?4, ?5)
+             ORDER BY project_id, started_at"
+        )?;
+        let outcomes = stmt.query_map(params![project_id, since, success, failed, rolled_back, now], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?, row.get::<_, i64>(2)?))
+        })?.collect::<SqliteResult<Vec<_>>>()?;
+        
+        let mut recoveries = Vec::new();
+        let mut open_failure: Option<(&str, i64)> = None;
+        for (project, succeeded, finished_at) in &outcomes {
+            // A streak doesn't carry over to the next project
+            if matches!(open_failure, Some((failed_project, _)) if failed_project != project.as_str()) {
+                open_failure = None;
+            }
+            match (*succeeded, open_failure) {
+                (true, Some((_, failed_at))) => {
+                    recoveries.push(finished_at - failed_at);
+                    open_failure = None;
+                }
+                (false, None) => open_failure = Some((project.as_str(), *finished_at)),
+                _ => {}
+            }
+        }
+        
+        let finished = successful + failures;
+        Ok(SloMetrics {
+            project_id: project_id.map(|id| id.to_string()),
+            window_days,
+            successful,
+            failed: failures,
+            success_rate: (finished > 0).then(|| f64::from(successful) / f64::from(finished)),
+            mean_time_to_recovery_secs: (!recoveries.is_empty())
+                .then(|| recoveries.iter().sum::<i64>() as f64 / recoveries.len() as f64),
+        })
+    }
+    
+    /// Update an existing deployment
+    pub fn update_deployment(&self, deployment: &Deployment) -> Result<(), DatabaseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        let (previous, migrated) = tx.query_row(
+            "SELECT logs, logs_migrated FROM deployments WHERE id = ?1",
+            params![deployment.id],
+            |row| Ok((logs_column(row, 0)?, row.get::<_, bool>(1)?)),
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                DatabaseError::DeploymentNotFound(deployment.id.clone())
+            }
+            _ => DatabaseError::from(e),
+        })?;
+        
+        tx.execute(
+            "UPDATE deployments SET 
+                status = ?1, commit_sha = ?2, commit_message = ?3, completed_at = ?4,
+                error_message = ?5, logs = ?6, image_digest = ?7, image_info = ?8
+             WHERE id = ?9",
+            params![
+                serde_json::to_string(&deployment.status)?,
+                deployment.commit_sha,
+                deployment.commit_message,
+                deployment.completed_at,
+                deployment.error_message,
+                encode_logs(deployment.logs.as_deref(), self.compress_logs)?,
+                deployment.image_digest,
+                serde_json::to_string(&deployment.image_info)?,
+                deployment.id,
+            ],
+        )?;
+        write_log_lines(&tx, &deployment.id, previous.as_deref(), migrated, deployment.logs.as_deref())?;
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Append text to a deployment's logs, leaving its other fields alone
+    ///
+    /// The read and write happen in one transaction, so text appended by a
+    /// concurrent `update_deployment` isn't lost, and this doesn't overwrite
+    /// the status or other fields the orchestrator is writing.
+    pub fn append_deployment_logs(&self, id: &str, text: &str) -> Result<(), DatabaseError> {
+        let tx = self.conn.unchecked_transaction()?;
+        
+        let (previous, migrated) = tx.query_row(
+            "SELECT logs, logs_migrated FROM deployments WHERE id = ?1",
+            params![id],
+            |row| Ok((logs_column(row, 0)?, row.get::<_, bool>(1)?)),
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => {
+                DatabaseError::DeploymentNotFound(id.to_string())
+            }
+            _ => DatabaseError::from(e),
+        })?;
+        
+        let logs = previous.clone().unwrap_or_default() + text;
+        tx.execute(
+            "UPDATE deployments SET logs = ?1 WHERE id = ?2",
+            params![encode_logs(Some(&logs), self.compress_logs)?, id],
+        )?;
+        write_log_lines(&tx, id, previous.as_deref(), migrated, Some(&logs))?;
+        
+        tx.commit()?;
+        Ok(())
+    }
+    
+    /// Set or clear the operator note on a deployment
+    ///
+    /// Kept separate from `update_deployment` so editing a note while a
+    /// deployment is running doesn't race with the orchestrator's writes.
+    pub fn set_deployment_notes(&self, id: &str, notes: Option<&str>) -> Result<(), DatabaseError> {
+        let rows_affected = self.conn.execute(
+            "UPDATE deployments SET notes = ?1 WHERE id = ?2",
+            params![notes, id],
+        )?;
+        
+        if rows_affected == 0 {
             return Err(DatabaseError::DeploymentNotFound(id.to_string()));
         }
         
-        Ok(())
+        Ok(())
+    }
+    
+    /// Delete a finished deployment along with its log lines
+    ///
+    /// Pending and in-progress deployments are refused, since their task is
+    /// still writing to the record.
+    pub fn delete_deployment(&self, id: &str) -> Result<(), DatabaseError> {
+        let status = self.conn.query_row(
+            "SELECT status FROM deployments WHERE id = ?1",
+            params![id],
+            |row| row.get::<_, String>(0),
+        ).optional()?
+            .ok_or_else(|| DatabaseError::DeploymentNotFound(id.to_string()))?;
+        
+        let status: DeploymentStatus = serde_json::from_str(&status)?;
+        if matches!(status, DeploymentStatus::Pending | DeploymentStatus::InProgress) {
+            return Err(DatabaseError::DeploymentInProgress(id.to_string()));
+        }
+        
+        // Log lines go with it through ON DELETE CASCADE
+        self.conn.execute(
+            "DELETE FROM deployments WHERE id = ?1",
+            params![id],
+        )?;
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DeploymentTrigger, Environment, FrameworkType, LaunchType, ListenerRule, ProjectNetwork, ResourceSize, SidecarImage};
+
+    fn create_test_db() -> Database {
+        // Use in-memory database for tests
+        Database::new_in_memory().unwrap()
+    }
+
+    #[test]
+    fn test_create_and_get_project() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        let retrieved = db.get_project(&project.id).unwrap();
+        
+        assert_eq!(retrieved.id, project.id);
+        assert_eq!(retrieved.name, project.name);
+    }
+
+    #[test]
+    fn test_schema_upgraded_from_older_version() {
+        // A database as the first release left it: base columns only, version 1
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                repository_url TEXT NOT NULL,
+                branch TEXT NOT NULL,
+                framework TEXT NOT NULL,
+                environment TEXT NOT NULL,
+                aws_cluster TEXT NOT NULL,
+                aws_service TEXT NOT NULL,
+                ecr_repository TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            INSERT INTO projects VALUES (
+                'old-project', 'legacy', 'https://github.com/test/repo', 'main', '\"nextjs\"',
+                '\"staging\"', 'cluster', 'service', 'repo', 1, 1
+            );
+            PRAGMA user_version = 1;"
+        ).unwrap();
+        let mut db = Database { conn, compress_logs: true };
+        assert_eq!(db.schema_version().unwrap(), 1);
+        
+        db.init_database().unwrap();
+        
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        let project = db.get_project("old-project").unwrap();
+        assert_eq!((project.name.as_str(), &project.environment), ("legacy", &Environment::Staging));
+        assert_eq!(project.launch_type, LaunchType::Fargate);
+        assert!(project.deploys_enabled);
+        assert!(project.sidecars.is_empty());
+    }
+    
+    #[test]
+    fn test_update_project() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        
+        project.name = "Updated Project".to_string();
+        project.touch();
+        db.update_project(&project).unwrap();
+        
+        let retrieved = db.get_project(&project.id).unwrap();
+        assert_eq!(retrieved.name, "Updated Project");
+    }
+    
+    #[test]
+    fn test_update_project_partial_changes_only_patched_fields() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        project.container_port = Some(8080);
+        project.environment_variables.insert("LOG_LEVEL".to_string(), "debug".to_string());
+        db.create_project(&project).unwrap();
+        
+        let patch = ProjectPatch {
+            branch: Some("release".to_string()),
+            ..ProjectPatch::default()
+        };
+        let patched = db.update_project_partial(&project.id, patch).unwrap();
+        assert_eq!(patched.branch, "release");
+        
+        let retrieved = db.get_project(&project.id).unwrap();
+        assert_eq!(retrieved.branch, "release");
+        assert_eq!(retrieved.name, project.name);
+        assert_eq!(retrieved.repository_url, project.repository_url);
+        assert_eq!(retrieved.aws_cluster, project.aws_cluster);
+        assert_eq!(retrieved.aws_service, project.aws_service);
+        assert_eq!(retrieved.ecr_repository, project.ecr_repository);
+        assert_eq!(retrieved.container_port, Some(8080));
+        assert_eq!(retrieved.environment_variables, project.environment_variables);
+        
+        assert!(matches!(
+            db.update_project_partial("missing", ProjectPatch::default()),
+            Err(DatabaseError::ProjectNotFound(_))
+        ));
+    }
+    
+    #[test]
+    fn test_application_projects_grouped_by_environment() {
+        let db = create_test_db();
+        let application = Application::new("storefront".to_string());
+        db.create_application(&application).unwrap();
+        
+        let mut ids = Vec::new();
+        for environment in [Environment::Production, Environment::Staging] {
+            let project = Project::new(
+                format!("storefront-{}", environment.as_str()),
+                "https://github.com/test/storefront".to_string(),
+                "main".to_string(),
+                FrameworkType::NextJs,
+                environment,
+                "cluster".to_string(),
+                "storefront".to_string(),
+                "storefront".to_string(),
+            );
+            db.create_project(&project).unwrap();
+            ids.push(project.id);
+        }
+        
+        // A project outside the application stays out of the listing
+        let other = Project::new(
+            "other".to_string(),
+            "https://github.com/test/other".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Staging,
+            "cluster".to_string(),
+            "other".to_string(),
+            "other".to_string(),
+        );
+        db.create_project(&other).unwrap();
+        assert_eq!(db.get_project(&other.id).unwrap().application_id, None);
+        
+        for id in &ids {
+            let patch = ProjectPatch {
+                application_id: Some(Some(application.id.clone())),
+                ..ProjectPatch::default()
+            };
+            db.update_project_partial(id, patch).unwrap();
+        }
+        
+        let grouped = db.get_application_projects(&application.id).unwrap();
+        assert_eq!(grouped.application, application);
+        let environments: Vec<_> = grouped.environments.iter()
+            .map(|group| (group.environment.clone(), group.projects.iter().map(|p| p.id.clone()).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(environments, vec![
+            (Environment::Staging, vec![ids[1].clone()]),
+            (Environment::Production, vec![ids[0].clone()]),
+        ]);
+        
+        assert!(matches!(
+            db.get_application_projects("missing"),
+            Err(DatabaseError::ApplicationNotFound(_))
+        ));
+    }
+    
+    #[test]
+    fn test_resource_size_roundtrip() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        project.resource_size = ResourceSize::Custom(1024, 4096);
+        
+        db.create_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().resource_size, ResourceSize::Custom(1024, 4096));
+        
+        project.resource_size = ResourceSize::Large;
+        db.update_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().resource_size, ResourceSize::Large);
+    }
+    
+    #[test]
+    fn test_sidecars_roundtrip() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        project.sidecars = vec![SidecarImage {
+            name: "proxy".to_string(),
+            dockerfile_path: "proxy/Dockerfile".to_string(),
+            context: "proxy".to_string(),
+        }];
+        
+        db.create_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().sidecars, project.sidecars);
+        
+        project.sidecars.clear();
+        db.update_project(&project).unwrap();
+        assert!(db.get_project(&project.id).unwrap().sidecars.is_empty());
+    }
+    
+    #[test]
+    fn test_launch_type_roundtrip() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().launch_type, LaunchType::Fargate);
+        
+        project.launch_type = LaunchType::Ec2;
+        db.update_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().launch_type, LaunchType::Ec2);
+    }
+    
+    #[test]
+    fn test_network_roundtrip() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().network, ProjectNetwork::default());
+        
+        project.network = ProjectNetwork {
+            vpc_id: Some("vpc-0abc".to_string()),
+            subnet_ids: vec!["subnet-a".to_string()],
+            listener_rules: vec![ListenerRule { path_pattern: "/api/*".to_string(), priority: 10 }],
+            shared_alb_arn: None,
+            health_check_path: Some("/ready".to_string()),
+        };
+        db.update_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().network, project.network);
+    }
+
+    #[test]
+    fn test_extra_image_tags_roundtrip() {
+        let db = create_test_db();
+        let mut project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        assert!(db.get_project(&project.id).unwrap().extra_image_tags.is_empty());
+        
+        project.extra_image_tags = vec!["production".to_string(), "v1.4.0".to_string()];
+        db.update_project(&project).unwrap();
+        assert_eq!(db.get_project(&project.id).unwrap().extra_image_tags, project.extra_image_tags);
+    }
+
+    #[test]
+    fn test_delete_project() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        db.delete_project(&project.id).unwrap();
+        
+        let result = db.get_project(&project.id);
+        assert!(matches!(result, Err(DatabaseError::ProjectNotFound(_))));
+    }
+
+    #[test]
+    fn test_create_and_get_deployment() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        
+        db.create_project(&project).unwrap();
+        
+        let deployment = Deployment::new(
+            project.id.clone(),
+            Some("abc123".to_string()),
+            Some("Test commit".to_string()),
+            "v1.0.0".to_string(),
+            None,
+        );
+        
+        db.create_deployment(&deployment).unwrap();
+        let retrieved = db.get_deployment(&deployment.id).unwrap();
+        
+        assert_eq!(retrieved.id, deployment.id);
+        assert_eq!(retrieved.project_id, project.id);
+    }
+    
+    #[test]
+    fn test_image_digest_stored_on_deployment() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project).unwrap();
+        
+        let mut deployment = Deployment::new(
+            project.id.clone(),
+            Some("abc123".to_string()),
+            None,
+            "v1.0.0".to_string(),
+            None,
+        );
+        db.create_deployment(&deployment).unwrap();
+        assert!(db.get_deployment(&deployment.id).unwrap().image_digest.is_none());
+        
+        let digest = "sha256:4f2b7c0e9a1d3b5c7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9b1c3d";
+        deployment.image_digest = Some(digest.to_string());
+        db.update_deployment(&deployment).unwrap();
+        
+        let retrieved = db.get_deployment(&deployment.id).unwrap();
+        assert_eq!(retrieved.image_digest.as_deref(), Some(digest));
+    }
+    
+    #[test]
+    fn test_delete_deployment_refuses_running_and_removes_log_lines() {
+        let mut db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project).unwrap();
+        
+        let mut running = Deployment::new(project.id.clone(), None, None, "v1".to_string(), None);
+        running.status = DeploymentStatus::InProgress;
+        db.create_deployment(&running).unwrap();
+        assert!(matches!(db.delete_deployment(&running.id), Err(DatabaseError::DeploymentInProgress(_))));
+        assert!(db.get_deployment(&running.id).is_ok());
+        
+        let mut finished = Deployment::new(project.id.clone(), None, None, "v2".to_string(), None);
+        finished.logs = Some("Cloning repository\nDeployed".to_string());
+        finished.complete(DeploymentStatus::Failed, Some("Health check failed".to_string()));
+        db.create_deployment(&finished).unwrap();
+        db.migrate_logs_to_lines().unwrap();
+        assert_eq!(db.get_deployment_log_lines(&finished.id, 0).unwrap().len(), 2);
+        
+        db.delete_deployment(&finished.id).unwrap();
+        
+        assert!(matches!(db.get_deployment(&finished.id), Err(DatabaseError::DeploymentNotFound(_))));
+        let orphaned: i64 = db.conn
+            .query_row("SELECT COUNT(*) FROM deployment_log_lines WHERE deployment_id = ?1", params![finished.id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(orphaned, 0);
+        assert!(matches!(db.delete_deployment(&finished.id), Err(DatabaseError::DeploymentNotFound(_))));
+    }
+    
+    #[test]
+    fn test_prune_logs_keeps_deployment_metadata() {
+        let mut db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project).unwrap();
+        
+        let mut old = Deployment::new(project.id.clone(), Some("abc123".to_string()), Some("Fix login".to_string()), "v1".to_string(), None);
+        old.logs = Some("Cloning repository\nDeployed".to_string());
+        old.complete(DeploymentStatus::Success, None);
+        old.completed_at = Some(1_000);
+        db.create_deployment(&old).unwrap();
+        db.migrate_logs_to_lines().unwrap();
+        
+        let mut recent = Deployment::new(project.id.clone(), None, None, "v2".to_string(), None);
+        recent.logs = Some("Deployed".to_string());
+        recent.complete(DeploymentStatus::Success, None);
+        recent.completed_at = Some(5_000);
+        db.create_deployment(&recent).unwrap();
+        
+        let mut running = Deployment::new(project.id.clone(), None, None, "v3".to_string(), None);
+        running.logs = Some("Cloning repository".to_string());
+        running.status = DeploymentStatus::InProgress;
+        db.create_deployment(&running).unwrap();
+        
+        assert_eq!(db.prune_deployment_logs_older_than(2_000).unwrap(), 1);
+        
+        let pruned = db.get_deployment(&old.id).unwrap();
+        assert_eq!(pruned.logs, None);
+        assert!(db.get_deployment_log_lines(&old.id, 0).unwrap().is_empty());
+        assert_eq!(pruned.commit_sha.as_deref(), Some("abc123"));
+        assert_eq!(pruned.commit_message.as_deref(), Some("Fix login"));
+        assert_eq!(pruned.image_tag, "v1");
+        assert_eq!(pruned.status, DeploymentStatus::Success);
+        assert_eq!(db.get_deployments_for_project(&project.id).unwrap().len(), 3);
+        
+        assert_eq!(db.get_deployment_log_lines(&recent.id, 0).unwrap(), vec!["Deployed"]);
+        assert_eq!(db.get_deployment_log_lines(&running.id, 0).unwrap(), vec!["Cloning repository"]);
+    }
+    
+    #[test]
+    fn test_deployment_detail_joins_progress_and_timings() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project).unwrap();
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        deployment.complete(DeploymentStatus::Success, None);
+        db.create_deployment(&deployment).unwrap();
+        
+        let updates = [(10, "Initializing deployment", 1_000), (20, "Repository cloned", 3_500), (100, "Deployment successful", 9_000)];
+        for (progress, message, recorded_at_ms) in updates {
+            let record = ProgressRecord { progress, message: message.to_string(), recorded_at_ms };
+            db.record_progress(&deployment.id, &record).unwrap();
+        }
+        
+        let detail = db.get_deployment_detail(&deployment.id).unwrap();
+        assert_eq!(detail.deployment.id, deployment.id);
+        assert_eq!(detail.deployment.commit_sha.as_deref(), Some("abc123"));
+        assert_eq!(detail.project.id, project.id);
+        assert_eq!(
+            detail.progress.iter().map(|record| (record.progress, record.recorded_at_ms)).collect::<Vec<_>>(),
+            vec![(10, 1_000), (20, 3_500), (100, 9_000)],
+        );
+        assert_eq!(detail.phase_timings, vec![
+            PhaseTiming { phase: "Repository cloned".to_string(), duration_ms: 2_500 },
+            PhaseTiming { phase: "Deployment successful".to_string(), duration_ms: 5_500 },
+        ]);
+        
+        assert!(matches!(db.get_deployment_detail("missing"), Err(DatabaseError::DeploymentNotFound(_))));
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::models::{Environment, FrameworkType};
-
-    fn create_test_db() -> Database {
-        // Use in-memory database for tests
+    
+    #[test]
+    fn test_slo_metrics_success_rate_and_mttr() {
+        let db = create_test_db();
+        let create_project = |name: &str| {
+            let project = Project::new(
+                name.to_string(),
+                "https://github.com/test/repo".to_string(),
+                "main".to_string(),
+                FrameworkType::NextJs,
+                Environment::Production,
+                "test-cluster".to_string(),
+                "test-service".to_string(),
+                "test.ecr.repo".to_string(),
+            );
+            db.create_project(&project).unwrap();
+            project
+        };
+        let web = create_project("web");
+        let api = create_project("api");
+        
+        const DAY: i64 = 24 * 60 * 60;
+        let now = 100 * DAY;
+        let record = |project: &Project, status: DeploymentStatus, started_at: i64| {
+            let mut deployment = Deployment::new(project.id.clone(), None, None, "v1".to_string(), None);
+            deployment.started_at = started_at;
+            if status != DeploymentStatus::InProgress {
+                deployment.completed_at = Some(started_at + 100);
+            }
+            deployment.status = status;
+            db.create_deployment(&deployment).unwrap();
+        };
+        record(&web, DeploymentStatus::Success, now - 10 * DAY);
+        record(&web, DeploymentStatus::Failed, now - 5 * DAY);
+        record(&web, DeploymentStatus::Failed, now - 4 * DAY);
+        record(&web, DeploymentStatus::Success, now - 3 * DAY);
+        record(&web, DeploymentStatus::RolledBack, now - 2 * DAY);
+        record(&web, DeploymentStatus::Success, now - DAY);
+        record(&web, DeploymentStatus::InProgress, now - 60);
+        record(&api, DeploymentStatus::Failed, now - 3600);
+        
+        // Recoveries take two days (from the first failure of the streak) and one day
+        let week = db.get_slo_metrics(Some(&web.id), 7, now).unwrap();
+        assert_eq!((week.successful, week.failed), (2, 3));
+        assert_eq!(week.success_rate, Some(0.4));
+        assert_eq!(week.mean_time_to_recovery_secs, Some(1.5 * DAY as f64));
+        
+        let month = db.get_slo_metrics(Some(&web.id), 30, now).unwrap();
+        assert_eq!((month.successful, month.failed), (3, 3));
+        assert_eq!(month.success_rate, Some(0.5));
+        
+        // The api failure hasn't recovered, so it only lowers the success rate
+        let overall = db.get_slo_metrics(None, 7, now).unwrap();
+        assert_eq!(overall.project_id, None);
+        assert_eq!((overall.successful, overall.failed), (2, 4));
+        assert_eq!(overall.mean_time_to_recovery_secs, Some(1.5 * DAY as f64));
+        
+        let empty = db.get_slo_metrics(Some(&api.id), 7, now - 7 * DAY).unwrap();
+        assert_eq!((empty.successful, empty.failed), (0, 0));
+        assert_eq!(empty.success_rate, None);
+        assert_eq!(empty.mean_time_to_recovery_secs, None);
+    }
+    
+    #[test]
+    fn test_legacy_pending_commit_sha_migrated() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE deployments (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                commit_message TEXT,
+                image_tag TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                completed_at INTEGER,
+                error_message TEXT,
+                logs TEXT
+            );
+            INSERT INTO deployments (id, project_id, status, commit_sha, image_tag, started_at)
+                VALUES ('stuck', 'p1', '\"failed\"', 'pending', 'v1', 1);
+            INSERT INTO deployments (id, project_id, status, commit_sha, image_tag, started_at)
+                VALUES ('done', 'p1', '\"success\"', 'abc123', 'v2', 2);",
+        ).unwrap();
+        
+        let mut db = Database { conn, compress_logs: false };
+        db.init_database().unwrap();
+        
+        assert!(db.get_deployment("stuck").unwrap().commit_sha.is_none());
+        assert_eq!(db.get_deployment("done").unwrap().commit_sha.as_deref(), Some("abc123"));
+        
+        // The rebuilt table accepts deployments without a SHA
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project).unwrap();
+        let deployment = Deployment::new(project.id.clone(), None, None, "v3".to_string(), None);
+        db.create_deployment(&deployment).unwrap();
+        assert!(db.get_deployment(&deployment.id).unwrap().commit_sha.is_none());
+    }
+    
+    #[test]
+    fn test_legacy_logs_migrated_to_lines() {
         let conn = Connection::open_in_memory().unwrap();
-        let mut db = Database { conn };
+        conn.execute_batch(
+            "CREATE TABLE deployments (
+                id TEXT PRIMARY KEY,
+                project_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                commit_sha TEXT,
+                commit_message TEXT,
+                image_tag TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                completed_at INTEGER,
+                error_message TEXT,
+                logs TEXT
+            );
+            INSERT INTO deployments (id, project_id, status, commit_sha, image_tag, started_at, completed_at, logs)
+                VALUES ('legacy', 'p1', '\"success\"', 'abc123', 'v1', 1, 2,
+                        'Cloning repository\nBuilding image\nDeployed');
+            INSERT INTO deployments (id, project_id, status, commit_sha, image_tag, started_at, logs)
+                VALUES ('running', 'p1', '\"inprogress\"', 'def456', 'v2', 3, 'Cloning repository');",
+        ).unwrap();
+        
+        let mut db = Database { conn, compress_logs: false };
+        db.init_database().unwrap();
+        
+        let lines: Vec<(i64, String)> = db.conn
+            .prepare("SELECT line_number, line FROM deployment_log_lines WHERE deployment_id = 'legacy' ORDER BY line_number")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap();
+        assert_eq!(lines, vec![
+            (0, "Cloning repository".to_string()),
+            (1, "Building image".to_string()),
+            (2, "Deployed".to_string()),
+        ]);
+        assert_eq!(db.get_deployment_log_lines("legacy", 1).unwrap(), vec!["Building image", "Deployed"]);
+        assert_eq!(db.get_deployment_log_tail("legacy", 1).unwrap(), vec!["Deployed"]);
+        
+        // A deployment still running keeps its blob until it finishes
+        let running_lines: i64 = db.conn.query_row(
+            "SELECT COUNT(*) FROM deployment_log_lines WHERE deployment_id = 'running'",
+            [],
+            |row| row.get(0),
+        ).unwrap();
+        assert_eq!(running_lines, 0);
+        assert_eq!(db.get_deployment_log_lines("running", 0).unwrap(), vec!["Cloning repository"]);
+        
+        // Running the migration again doesn't duplicate lines
         db.init_database().unwrap();
-        db
+        assert_eq!(db.get_deployment_log_lines("legacy", 0).unwrap().len(), 3);
     }
-
+    
     #[test]
-    fn test_create_and_get_project() {
+    fn test_new_environment_roundtrip() {
+        let db = create_test_db();
+        
+        for environment in [Environment::Qa, Environment::Preview, Environment::Sandbox] {
+            let project = Project::new(
+                "Test Project".to_string(),
+                "https://github.com/test/repo".to_string(),
+                "main".to_string(),
+                FrameworkType::NextJs,
+                environment.clone(),
+                "test-cluster".to_string(),
+                "test-service".to_string(),
+                "test.ecr.repo".to_string(),
+            );
+            db.create_project(&project).unwrap();
+            
+            let retrieved = db.get_project(&project.id).unwrap();
+            assert_eq!(retrieved.environment, environment);
+            assert_eq!(retrieved.resource_size, environment.default_resource_size());
+        }
+    }
+    
+    #[test]
+    fn test_project_deploys_enabled_toggle() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Production,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project).unwrap();
+        assert!(db.get_project(&project.id).unwrap().deploys_enabled);
+        
+        db.set_project_deploys_enabled(&project.id, false).unwrap();
+        let frozen = db.get_project(&project.id).unwrap();
+        assert!(!frozen.deploys_enabled);
+        
+        // Other edits keep the project frozen
+        let mut renamed = frozen.clone();
+        renamed.name = "Renamed".to_string();
+        db.update_project(&renamed).unwrap();
+        assert!(!db.get_project(&project.id).unwrap().deploys_enabled);
+        
+        db.set_project_deploys_enabled(&project.id, true).unwrap();
+        assert!(db.get_project(&project.id).unwrap().deploys_enabled);
+        
+        assert!(matches!(
+            db.set_project_deploys_enabled("missing", false),
+            Err(DatabaseError::ProjectNotFound(_))
+        ));
+    }
+    
+    #[test]
+    fn test_settings_roundtrip() {
+        let db = create_test_db();
+        assert_eq!(db.get_settings().unwrap(), AppSettings::default());
+        
+        let settings = AppSettings {
+            aws_keepalive_enabled: true,
+            aws_keepalive_interval_secs: 60,
+            ..AppSettings::default()
+        };
+        db.save_settings(&settings).unwrap();
+        assert_eq!(db.get_settings().unwrap(), settings);
+        
+        // Saving again overwrites rather than duplicating
+        let settings = AppSettings {
+            aws_keepalive_enabled: false,
+            ..settings
+        };
+        db.save_settings(&settings).unwrap();
+        assert_eq!(db.get_settings().unwrap(), settings);
+    }
+    
+    #[test]
+    fn test_deployment_trigger_roundtrip() {
         let db = create_test_db();
         let project = Project::new(
             "Test Project".to_string(),
@@ -457,18 +2114,71 @@ mod tests {
             "test-service".to_string(),
             "test.ecr.repo".to_string(),
         );
+        db.create_project(&project).unwrap();
+        
+        let manual = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        let rollback = Deployment::new(
+            project.id.clone(),
+            Some("def456".to_string()),
+            None,
+            "v0".to_string(),
+            Some(DeploymentTrigger::Rollback),
+        );
+        db.create_deployment(&manual).unwrap();
+        db.create_deployment(&rollback).unwrap();
         
+        assert_eq!(db.get_deployment(&manual.id).unwrap().trigger, DeploymentTrigger::Manual);
+        assert_eq!(db.get_deployment(&rollback.id).unwrap().trigger, DeploymentTrigger::Rollback);
+    }
+    
+    #[test]
+    fn test_get_last_error() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
         db.create_project(&project).unwrap();
-        let retrieved = db.get_project(&project.id).unwrap();
+        assert!(db.get_last_error(&project.id).unwrap().is_none());
         
-        assert_eq!(retrieved.id, project.id);
-        assert_eq!(retrieved.name, project.name);
+        let mut failed = Deployment::new(
+            project.id.clone(),
+            Some("abc123".to_string()),
+            None,
+            "v1.0.0".to_string(),
+            None,
+        );
+        failed.started_at -= 60;
+        failed.complete(DeploymentStatus::Failed, Some("Health check timed out".to_string()));
+        db.create_deployment(&failed).unwrap();
+        
+        let mut succeeded = Deployment::new(
+            project.id.clone(),
+            Some("def456".to_string()),
+            None,
+            "v1.0.1".to_string(),
+            None,
+        );
+        succeeded.complete(DeploymentStatus::Success, None);
+        db.create_deployment(&succeeded).unwrap();
+        
+        // The newer successful deployment has no error, so the failure is reported
+        let last_error = db.get_last_error(&project.id).unwrap().unwrap();
+        assert_eq!(last_error.deployment_id, failed.id);
+        assert_eq!(last_error.message, "Health check timed out");
+        assert_eq!(last_error.occurred_at, failed.started_at);
     }
-
+    
     #[test]
-    fn test_update_project() {
+    fn test_large_logs_stored_compressed() {
         let db = create_test_db();
-        let mut project = Project::new(
+        let project = Project::new(
             "Test Project".to_string(),
             "https://github.com/test/repo".to_string(),
             "main".to_string(),
@@ -478,19 +2188,79 @@ mod tests {
             "test-service".to_string(),
             "test.ecr.repo".to_string(),
         );
+        db.create_project(&project).unwrap();
+        
+        let mut deployment = Deployment::new(
+            project.id.clone(),
+            Some("abc123".to_string()),
+            None,
+            "v1.0.0".to_string(),
+            None,
+        );
+        db.create_deployment(&deployment).unwrap();
         
+        for i in 0..2000 {
+            deployment.append_logs(&format!("Step {}/2000: npm install finished\n", i));
+        }
+        let raw_len = deployment.logs.as_ref().unwrap().len();
+        db.update_deployment(&deployment).unwrap();
+        
+        let (kind, stored_len): (String, usize) = db.conn.query_row(
+            "SELECT typeof(logs), length(logs) FROM deployments WHERE id = ?1",
+            params![deployment.id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).unwrap();
+        assert_eq!(kind, "blob");
+        assert!(stored_len < raw_len);
+        
+        let retrieved = db.get_deployment(&deployment.id).unwrap();
+        assert_eq!(retrieved.logs, deployment.logs);
+    }
+    
+    #[test]
+    fn test_deployment_log_tail() {
+        let db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
         db.create_project(&project).unwrap();
         
-        project.name = "Updated Project".to_string();
-        project.touch();
-        db.update_project(&project).unwrap();
+        // Large enough to be stored compressed
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        for i in 0..5000 {
+            deployment.append_logs(&format!("line {}\n", i));
+        }
+        db.create_deployment(&deployment).unwrap();
         
-        let retrieved = db.get_project(&project.id).unwrap();
-        assert_eq!(retrieved.name, "Updated Project");
+        let tail = db.get_deployment_log_tail(&deployment.id, 20).unwrap();
+        let expected: Vec<String> = (4980..5000).map(|i| format!("line {}", i)).collect();
+        assert_eq!(tail, expected);
+        
+        // Asking for more lines than exist returns them all
+        let mut short = Deployment::new(project.id.clone(), Some("def456".to_string()), None, "v2".to_string(), None);
+        short.append_logs("Build started\nBuild finished\n");
+        db.create_deployment(&short).unwrap();
+        assert_eq!(db.get_deployment_log_tail(&short.id, 20).unwrap(), vec!["Build started", "Build finished"]);
+        
+        let empty = Deployment::new(project.id.clone(), Some("fed789".to_string()), None, "v3".to_string(), None);
+        db.create_deployment(&empty).unwrap();
+        assert!(db.get_deployment_log_tail(&empty.id, 20).unwrap().is_empty());
+        
+        assert!(matches!(
+            db.get_deployment_log_tail("missing", 20),
+            Err(DatabaseError::DeploymentNotFound(_))
+        ));
     }
-
+    
     #[test]
-    fn test_delete_project() {
+    fn test_new_deployment_logs_written_as_lines() {
         let db = create_test_db();
         let project = Project::new(
             "Test Project".to_string(),
@@ -502,16 +2272,75 @@ mod tests {
             "test-service".to_string(),
             "test.ecr.repo".to_string(),
         );
+        db.create_project(&project).unwrap();
         
+        let mut deployment = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        db.create_deployment(&deployment).unwrap();
+        db.append_deployment_logs(&deployment.id, "Cloning repository\n").unwrap();
+        deployment.logs = Some("Cloning repository\nBuilding image\n".to_string());
+        db.update_deployment(&deployment).unwrap();
+        db.append_deployment_logs(&deployment.id, "Deployed\n").unwrap();
+        
+        let stored: Vec<(i64, String)> = db.conn
+            .prepare("SELECT line_number, line FROM deployment_log_lines WHERE deployment_id = ?1 ORDER BY line_number")
+            .unwrap()
+            .query_map(params![deployment.id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap();
+        assert_eq!(stored, vec![
+            (0, "Cloning repository".to_string()),
+            (1, "Building image".to_string()),
+            (2, "Deployed".to_string()),
+        ]);
+        assert_eq!(db.get_deployment_log_tail(&deployment.id, 2).unwrap(), vec!["Building image", "Deployed"]);
+        
+        // Rewritten logs replace the stored lines
+        deployment.logs = Some("Redeployed\n".to_string());
+        db.update_deployment(&deployment).unwrap();
+        assert_eq!(db.get_deployment_log_lines(&deployment.id, 0).unwrap(), vec!["Redeployed"]);
+        assert_eq!(db.get_deployment_log_tail(&deployment.id, 5).unwrap(), vec!["Redeployed"]);
+    }
+    
+    #[test]
+    fn test_plain_logs_still_readable() {
+        let mut db = create_test_db();
+        let project = Project::new(
+            "Test Project".to_string(),
+            "https://github.com/test/repo".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "test-service".to_string(),
+            "test.ecr.repo".to_string(),
+        );
         db.create_project(&project).unwrap();
-        db.delete_project(&project.id).unwrap();
         
-        let result = db.get_project(&project.id);
-        assert!(matches!(result, Err(DatabaseError::ProjectNotFound(_))));
+        // Small logs are left as text even with compression on
+        let mut small = Deployment::new(project.id.clone(), Some("abc123".to_string()), None, "v1".to_string(), None);
+        small.append_logs("Build started\n");
+        db.create_deployment(&small).unwrap();
+        
+        // With compression off, large logs are written as text too
+        db.set_log_compression(false);
+        let mut large = Deployment::new(project.id.clone(), Some("def456".to_string()), None, "v2".to_string(), None);
+        large.append_logs(&"x".repeat(LOG_COMPRESSION_THRESHOLD * 2));
+        db.create_deployment(&large).unwrap();
+        
+        for deployment in [&small, &large] {
+            let kind: String = db.conn.query_row(
+                "SELECT typeof(logs) FROM deployments WHERE id = ?1",
+                params![deployment.id],
+                |row| row.get(0),
+            ).unwrap();
+            assert_eq!(kind, "text");
+            assert_eq!(db.get_deployment(&deployment.id).unwrap().logs, deployment.logs);
+        }
     }
-
+    
     #[test]
-    fn test_create_and_get_deployment() {
+    fn test_deployment_notes() {
         let db = create_test_db();
         let project = Project::new(
             "Test Project".to_string(),
@@ -523,21 +2352,35 @@ mod tests {
             "test-service".to_string(),
             "test.ecr.repo".to_string(),
         );
-        
         db.create_project(&project).unwrap();
         
-        let deployment = Deployment::new(
+        let mut deployment = Deployment::new(
             project.id.clone(),
-            "abc123".to_string(),
-            Some("Test commit".to_string()),
+            Some("abc123".to_string()),
+            None,
             "v1.0.0".to_string(),
+            None,
         );
-        
+        deployment.notes = Some("first prod rollout".to_string());
         db.create_deployment(&deployment).unwrap();
+        
         let retrieved = db.get_deployment(&deployment.id).unwrap();
+        assert_eq!(retrieved.notes.as_deref(), Some("first prod rollout"));
         
-        assert_eq!(retrieved.id, deployment.id);
-        assert_eq!(retrieved.project_id, project.id);
+        db.set_deployment_notes(&deployment.id, Some("hotfix for incident #123")).unwrap();
+        let listed = db.get_deployments_for_project(&project.id).unwrap();
+        assert_eq!(listed[0].notes.as_deref(), Some("hotfix for incident #123"));
+        
+        // Orchestrator updates leave the note alone
+        deployment.complete(DeploymentStatus::Success, None);
+        db.update_deployment(&deployment).unwrap();
+        let retrieved = db.get_deployment(&deployment.id).unwrap();
+        assert_eq!(retrieved.notes.as_deref(), Some("hotfix for incident #123"));
+        
+        db.set_deployment_notes(&deployment.id, None).unwrap();
+        assert!(db.get_deployment(&deployment.id).unwrap().notes.is_none());
+        
+        assert!(db.set_deployment_notes("missing", Some("note")).is_err());
     }
 
     #[test]
@@ -558,16 +2401,18 @@ mod tests {
         
         let deployment1 = Deployment::new(
             project.id.clone(),
-            "abc123".to_string(),
+            Some("abc123".to_string()),
             None,
             "v1.0.0".to_string(),
+            None,
         );
         
         let deployment2 = Deployment::new(
             project.id.clone(),
-            "def456".to_string(),
+            Some("def456".to_string()),
             None,
             "v1.0.1".to_string(),
+            None,
         );
         
         db.create_deployment(&deployment1).unwrap();
@@ -576,6 +2421,54 @@ mod tests {
         let deployments = db.get_deployments_for_project(&project.id).unwrap();
         assert_eq!(deployments.len(), 2);
     }
+    
+    #[test]
+    fn test_get_active_deployments() {
+        let db = create_test_db();
+        let project_a = Project::new(
+            "Project A".to_string(),
+            "https://github.com/test/a".to_string(),
+            "main".to_string(),
+            FrameworkType::NextJs,
+            Environment::Development,
+            "test-cluster".to_string(),
+            "service-a".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        let project_b = Project::new(
+            "Project B".to_string(),
+            "https://github.com/test/b".to_string(),
+            "main".to_string(),
+            FrameworkType::Python,
+            Environment::Production,
+            "test-cluster".to_string(),
+            "service-b".to_string(),
+            "test.ecr.repo".to_string(),
+        );
+        db.create_project(&project_a).unwrap();
+        db.create_project(&project_b).unwrap();
+        
+        let pending = Deployment::new(project_a.id.clone(), Some("aaa111".to_string()), None, "v1".to_string(), None);
+        let mut in_progress = Deployment::new(project_b.id.clone(), Some("bbb222".to_string()), None, "v2".to_string(), None);
+        in_progress.status = DeploymentStatus::InProgress;
+        let mut succeeded = Deployment::new(project_a.id.clone(), Some("ccc333".to_string()), None, "v3".to_string(), None);
+        succeeded.complete(DeploymentStatus::Success, None);
+        let mut failed = Deployment::new(project_b.id.clone(), Some("ddd444".to_string()), None, "v4".to_string(), None);
+        failed.complete(DeploymentStatus::Failed, Some("boom".to_string()));
+        
+        for deployment in [&pending, &in_progress, &succeeded, &failed] {
+            db.create_deployment(deployment).unwrap();
+        }
+        
+        let active = db.get_active_deployments().unwrap();
+        assert_eq!(active.len(), 2);
+        
+        let a = active.iter().find(|a| a.deployment.id == pending.id).unwrap();
+        assert_eq!(a.project_name, "Project A");
+        let b = active.iter().find(|a| a.deployment.id == in_progress.id).unwrap();
+        assert_eq!(b.project_name, "Project B");
+        assert_eq!(b.deployment.status, DeploymentStatus::InProgress);
+    }
 
     #[test]
     fn test_cascade_delete() {
@@ -595,9 +2488,10 @@ mod tests {
         
         let deployment = Deployment::new(
             project.id.clone(),
-            "abc123".to_string(),
+            Some("abc123".to_string()),
             None,
             "v1.0.0".to_string(),
+            None,
         );
         
         db.create_deployment(&deployment).unwrap();
@@ -3,9 +3,12 @@
 //! This module provides the foundational infrastructure services:
 //! - Database: SQLite-based persistent storage for projects and deployments
 //! - KeychainService: Secure credential storage using OS keychain with encrypted fallback
+//! - CaBundle: Custom CA certificates for self-hosted Git and HTTPS endpoints
 
 pub mod database;
 pub mod keychain;
+pub mod tls;
 
 pub use database::{Database, DatabaseError};
-pub use keychain::{KeychainService, KeychainError};
+pub use keychain::{FileRetry, KeychainService, KeychainError, KeyringStore, OsKeyring};
+pub use tls::{CaBundle, TlsError};
@@ -0,0 +1,226 @@
+//! Custom CA certificate support
+//!
+//! Lets self-hosted Git servers and internal HTTPS endpoints signed by a
+//! private CA pass TLS verification. The bundle is either a single PEM file
+//! (which may hold several certificates) or a directory of `.pem`/`.crt` files.
+
+use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
+use aws_smithy_runtime_api::client::http::SharedHttpClient;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use reqwest::{Certificate, ClientBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// CA bundle errors
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read CA bundle {0}: {1}")]
+    ReadFailed(String, String),
+    
+    #[error("Invalid CA certificate in {0}: {1}")]
+    InvalidCertificate(String, String),
+    
+    #[error("No certificates found in {0}")]
+    NoCertificates(String),
+    
+    #[error("Failed to configure git TLS: {0}")]
+    GitConfigFailed(String),
+}
+
+/// Custom CA certificates loaded from disk
+#[derive(Clone)]
+pub struct CaBundle {
+    path: PathBuf,
+    certificates: Vec<Certificate>,
+    /// DER encoding of each certificate, for TLS stacks other than reqwest's
+    der: Vec<Vec<u8>>,
+}
+
+impl PartialEq for CaBundle {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.der == other.der
+    }
+}
+
+impl std::fmt::Debug for CaBundle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaBundle")
+            .field("path", &self.path)
+            .field("certificates", &self.der.len())
+            .finish()
+    }
+}
+
+impl CaBundle {
+    /// Load and parse every certificate at `path`
+    ///
+    /// Fails if the path cannot be read, a certificate is malformed, or no
+    /// certificates are found at all.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, TlsError> {
+        let path = path.as_ref().to_path_buf();
+        let display = path.display().to_string();
+        
+        let files = if path.is_dir() {
+            let mut files: Vec<PathBuf> = fs::read_dir(&path)
+                .map_err(|e| TlsError::ReadFailed(display.clone(), e.to_string()))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("pem") | Some("crt")))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.clone()]
+        };
+        
+        let mut certificates = Vec::new();
+        let mut der = Vec::new();
+        for file in &files {
+            let file_display = file.display().to_string();
+            let pem = fs::read_to_string(file)
+                .map_err(|e| TlsError::ReadFailed(file_display.clone(), e.to_string()))?;
+            
+            for block in pem_blocks(&pem) {
+                let certificate = Certificate::from_pem(block.as_bytes())
+                    .map_err(|e| TlsError::InvalidCertificate(file_display.clone(), e.to_string()))?;
+                certificates.push(certificate);
+                der.push(pem_block_der(&block)
+                    .map_err(|e| TlsError::InvalidCertificate(file_display.clone(), e))?);
+            }
+        }
+        
+        if certificates.is_empty() {
+            return Err(TlsError::NoCertificates(display));
+        }
+        
+        Ok(Self { path, certificates, der })
+    }
+    
+    /// Path the bundle was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    /// Number of certificates in the bundle
+    pub fn certificate_count(&self) -> usize {
+        self.certificates.len()
+    }
+    
+    /// Add every certificate as an extra trusted root on a reqwest client
+    pub fn apply_to_client(&self, builder: ClientBuilder) -> ClientBuilder {
+        self.certificates
+            .iter()
+            .cloned()
+            .fold(builder, |builder, certificate| builder.add_root_certificate(certificate))
+    }
+    
+    /// HTTPS client for AWS SDK calls that trusts the bundle alongside the system roots
+    pub fn aws_http_client(&self) -> SharedHttpClient {
+        let mut roots = rustls::RootCertStore::empty();
+        let system_roots: Vec<Vec<u8>> = rustls_native_certs::load_native_certs()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|certificate| certificate.0)
+            .collect();
+        roots.add_parsable_certificates(&system_roots);
+        roots.add_parsable_certificates(&self.der);
+        
+        let tls_config = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_tls_config(tls_config)
+            .https_or_http()
+            .enable_http1()
+            .enable_http2()
+            .build();
+        
+        HyperClientBuilder::new().build(connector)
+    }
+    
+    /// Point libgit2 at the bundle for HTTPS clones
+    ///
+    /// This is a process-wide libgit2 setting, so call it once at startup
+    /// before any git operation runs.
+    pub fn apply_to_git(&self) -> Result<(), TlsError> {
+        // SAFETY: libgit2 global options must not change while other threads
+        // use libgit2; this runs during startup before any clone starts.
+        let result = unsafe {
+            if self.path.is_dir() {
+                git2::opts::set_ssl_cert_dir(&self.path)
+            } else {
+                git2::opts::set_ssl_cert_file(&self.path)
+            }
+        };
+        
+        result.map_err(|e| TlsError::GitConfigFailed(e.to_string()))
+    }
+}
+
+/// Decode the base64 body of one PEM certificate block
+fn pem_block_der(block: &str) -> Result<Vec<u8>, String> {
+    let body: String = block
+        .trim()
+        .trim_start_matches(PEM_BEGIN)
+        .trim_end_matches(PEM_END)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    BASE64.decode(body).map_err(|e| e.to_string())
+}
+
+/// Split a PEM document into individual certificate blocks
+fn pem_blocks(pem: &str) -> Vec<String> {
+    pem.split(PEM_BEGIN)
+        .skip(1)
+        .filter_map(|rest| {
+            rest.find(PEM_END)
+                .map(|end| format!("{}{}{}\n", PEM_BEGIN, &rest[..end], PEM_END))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const TEST_CA: &str = include_str!("../../tests/fixtures/test_ca.pem");
+    
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("tls_test_{}_{}", uuid::Uuid::new_v4(), name))
+    }
+    
+    #[test]
+    fn test_client_built_with_custom_root() {
+        let path = temp_path("ca.pem");
+        fs::write(&path, format!("{}{}", TEST_CA, TEST_CA)).unwrap();
+        
+        let bundle = CaBundle::load(&path).unwrap();
+        assert_eq!(bundle.certificate_count(), 2);
+        
+        let client = bundle.apply_to_client(reqwest::Client::builder()).build();
+        assert!(client.is_ok());
+        
+        // Both copies decoded to the same DER certificate
+        assert_eq!(bundle.der.len(), 2);
+        assert_eq!(bundle.der[0], bundle.der[1]);
+        assert_eq!(bundle.der[0][0], 0x30);
+        
+        fs::remove_file(&path).ok();
+    }
+    
+    #[test]
+    fn test_load_rejects_file_without_certificates() {
+        let path = temp_path("empty.pem");
+        fs::write(&path, "not a certificate").unwrap();
+        
+        assert!(matches!(CaBundle::load(&path), Err(TlsError::NoCertificates(_))));
+        assert!(matches!(CaBundle::load(temp_path("missing.pem")), Err(TlsError::ReadFailed(_, _))));
+        
+        fs::remove_file(&path).ok();
+    }
+}
@@ -8,6 +8,9 @@ mod application;
 mod shadow;
 
 use application::commands::*;
+use application::{forward_build_logs, forward_events, rest_api, TauriProgressReporter};
+use std::sync::Arc;
+use tauri::Manager;
 
 fn main() {
     // Initialize application state
@@ -15,31 +18,145 @@ fn main() {
 
     tauri::Builder::default()
         .manage(app_state)
+        .setup(|app| {
+            // Start the AWS keepalive if it is enabled in settings
+            let handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                let _ = handle.state::<AppState>().refresh_keepalive().await;
+            });
+            
+            // Prune expired deployment logs periodically; startup already pruned once
+            let handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                handle.state::<AppState>().start_log_maintenance();
+            });
+            
+            // Start the background health refresh if it is enabled in settings
+            let handle = app.handle();
+            tauri::async_runtime::spawn(async move {
+                let listener = health_event_emitter(handle.clone());
+                let _ = handle.state::<AppState>().refresh_health_watch(listener).await;
+            });
+            
+            // Relay deployment progress and build output from the event bus to the frontend
+            let events = app.state::<AppState>().event_bus.subscribe();
+            let build_logs = app.state::<AppState>().event_bus.subscribe_build_logs();
+            let frontend = Arc::new(TauriProgressReporter::new(app.handle()));
+            tauri::async_runtime::spawn(forward_events(events, frontend.clone()));
+            tauri::async_runtime::spawn(forward_build_logs(build_logs, frontend));
+            
+            // Serve the local REST API if it is enabled in settings
+            let settings = app.state::<AppState>().database.lock()
+                .map_err(|e| e.to_string())?
+                .get_settings()?;
+            if settings.rest_api_enabled {
+                let token = app.state::<AppState>().keychain.lock()
+                    .map_err(|e| e.to_string())?
+                    .get_rest_api_token()
+                    .ok();
+                match token {
+                    Some(token) => {
+                        let handle = app.handle();
+                        tauri::async_runtime::spawn(async move {
+                            let address = ("127.0.0.1", settings.rest_api_port);
+                            let result = match tokio::net::TcpListener::bind(address).await {
+                                Ok(listener) => rest_api::serve(listener, handle.clone(), &token).await,
+                                Err(e) => Err(e),
+                            };
+                            if let Err(e) = result {
+                                handle.state::<AppState>().warn(format!("REST API stopped: {}", e));
+                            }
+                        });
+                    }
+                    None => app.state::<AppState>().warn(
+                        "REST API enabled without an auth token; not starting it".to_string(),
+                    ),
+                }
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Project commands
             create_project,
             get_projects,
             get_project,
             update_project,
+            update_project_partial,
+            set_project_deploys_enabled,
             delete_project,
+            import_ecs_service,
+            teardown_preview,
+            teardown_plan,
+            teardown_service,
+            reconcile_project,
+            refresh_all_service_health,
+            suggest_resources,
+            check_iam_permissions,
+            test_build,
+            get_image_info,
+            build_project_ecr_uri,
+            
+            // Application commands
+            create_application,
+            get_applications,
+            get_application_projects,
             
             // Deployment commands
             start_deployment,
+            prepare_deployment,
+            confirm_deployment,
+            emergency_stop,
+            resume_deployments,
             get_deployment_status,
+            get_deployment_detail,
+            get_deployment_artifacts,
             get_project_deployments,
+            delete_deployment,
+            get_active_deployments,
+            set_deployment_notes,
             get_deployment_logs,
+            get_deployment_log_tail,
+            snapshot_service_logs,
+            get_last_error,
+            get_slo_metrics,
+            get_operation_metrics,
+            get_warnings,
             
             // Credential commands
             store_aws_credentials,
             store_git_credentials,
             get_credentials_status,
+            diagnose,
+            get_aws_credential_info,
+            get_git_credential_info,
+            list_remote_refs,
             delete_aws_credentials,
             delete_git_credentials,
+            store_claude_api_keys,
+            rotate_claude_api_key,
+            delete_claude_api_keys,
+            store_rest_api_token,
+            delete_rest_api_token,
+            
+            // Terraform commands
+            apply_terraform,
+            
+            // Settings commands
+            get_settings,
+            update_settings,
             
             // AI chat commands
             ask_claude,
+            ask_claude_streaming,
+            cancel_claude_stream,
             analyze_deployment_logs,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                app_handle.state::<AppState>().stop_keepalive();
+                app_handle.state::<AppState>().stop_health_watch();
+            }
+        });
 }
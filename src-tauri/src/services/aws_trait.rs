@@ -4,8 +4,10 @@
 //! real AWS service and mock service for testing.
 
 use async_trait::async_trait;
-use crate::services::{AwsServiceError, EcsDeploymentConfig, ServiceHealth};
-use crate::models::FrameworkType;
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::services::{AwsServiceError, BuildSpec, EcsDeploymentConfig, ServiceDefinition, ServiceHealth, ServiceUtilization, StoppedTask};
+use crate::models::{FrameworkType, ImageInfo, PermissionCheck};
 
 /// Trait for AWS operations (ECS, ECR, CloudWatch)
 ///
@@ -17,10 +19,11 @@ pub trait AwsOperations: Send + Sync {
     ///
     /// # Arguments
     /// * `repository_name` - Name of the ECR repository
+    /// * `tags` - `(key, value)` tags applied to the repository, new or existing
     ///
     /// # Returns
     /// Repository URI on success
-    async fn ensure_ecr_repository(&self, repository_name: &str) -> Result<String, AwsServiceError>;
+    async fn ensure_ecr_repository(&self, repository_name: &str, tags: &[(String, String)]) -> Result<String, AwsServiceError>;
     
     /// Get ECR login credentials and authenticate Docker
     ///
@@ -33,19 +36,36 @@ pub trait AwsOperations: Send + Sync {
     /// * `source_dir` - Path to source code directory
     /// * `image_tag` - Tag for the Docker image
     /// * `framework` - Framework type for Dockerfile generation
+    /// * `on_line` - Receives each line of build output as it arrives
     async fn build_docker_image(
         &self,
         source_dir: &str,
         image_tag: &str,
-        framework: &FrameworkType
+        framework: &FrameworkType,
+        on_line: &(dyn Fn(&str) + Send + Sync),
     ) -> Result<(), AwsServiceError>;
     
+    /// Build Docker image from an explicit Dockerfile and context
+    ///
+    /// # Arguments
+    /// * `spec` - Dockerfile path, image tag and build context
+    async fn build_image(&self, spec: &BuildSpec) -> Result<(), AwsServiceError>;
+    
     /// Push Docker image to ECR
     ///
     /// # Arguments
     /// * `local_tag` - Local Docker image tag
     /// * `ecr_uri` - Full ECR URI with tag
-    async fn push_docker_image(&self, local_tag: &str, ecr_uri: &str) -> Result<(), AwsServiceError>;
+    ///
+    /// # Returns
+    /// Digest of the pushed image (e.g. `sha256:...`)
+    async fn push_docker_image(&self, local_tag: &str, ecr_uri: &str) -> Result<String, AwsServiceError>;
+    
+    /// Get the size and layer breakdown of a locally built image
+    ///
+    /// # Arguments
+    /// * `image_tag` - Local Docker image tag
+    async fn inspect_image(&self, image_tag: &str) -> Result<ImageInfo, AwsServiceError>;
     
     /// Register ECS task definition
     ///
@@ -81,6 +101,85 @@ pub trait AwsOperations: Send + Sync {
         service_name: &str
     ) -> Result<ServiceHealth, AwsServiceError>;
     
+    /// Get health for up to 10 services in one cluster with a single call
+    ///
+    /// # Arguments
+    /// * `cluster_name` - ECS cluster name
+    /// * `service_names` - ECS service names (at most `MAX_DESCRIBE_SERVICES`)
+    ///
+    /// # Returns
+    /// Health keyed by service name; services ECS does not know are absent
+    async fn describe_services(
+        &self,
+        cluster_name: &str,
+        service_names: &[String],
+    ) -> Result<HashMap<String, ServiceHealth>, AwsServiceError>;
+    
+    /// List recently stopped tasks of a service with their stop reasons
+    ///
+    /// # Arguments
+    /// * `cluster_name` - ECS cluster name
+    /// * `service_name` - ECS service name
+    async fn list_stopped_tasks(&self, cluster_name: &str, service_name: &str) -> Result<Vec<StoppedTask>, AwsServiceError>;
+    
+    /// Describe what an existing service runs, from its current task definition
+    ///
+    /// # Arguments
+    /// * `cluster_name` - ECS cluster name
+    /// * `service_name` - ECS service name
+    async fn describe_service_definition(&self, cluster_name: &str, service_name: &str) -> Result<ServiceDefinition, AwsServiceError>;
+    
+    /// Force the service to replace its tasks using the current task definition
+    ///
+    /// # Arguments
+    /// * `cluster_name` - ECS cluster name
+    /// * `service_name` - ECS service name
+    async fn force_new_deployment(&self, cluster_name: &str, service_name: &str) -> Result<(), AwsServiceError>;
+    
+    /// Scale the service to zero and delete it
+    ///
+    /// # Arguments
+    /// * `cluster_name` - ECS cluster name
+    /// * `service_name` - ECS service name
+    async fn delete_service(&self, cluster_name: &str, service_name: &str) -> Result<(), AwsServiceError>;
+    
+    /// ARNs of a task definition family's active revisions
+    ///
+    /// # Arguments
+    /// * `family` - Task definition family name
+    async fn list_task_definitions(&self, family: &str) -> Result<Vec<String>, AwsServiceError>;
+    
+    /// Deregister one task definition revision
+    ///
+    /// # Arguments
+    /// * `task_definition_arn` - ARN of the revision
+    async fn deregister_task_definition(&self, task_definition_arn: &str) -> Result<(), AwsServiceError>;
+    
+    /// Whether a CloudWatch log group exists
+    ///
+    /// # Arguments
+    /// * `log_group` - CloudWatch log group name
+    async fn log_group_exists(&self, log_group: &str) -> Result<bool, AwsServiceError>;
+    
+    /// Delete a CloudWatch log group and every stream in it
+    ///
+    /// # Arguments
+    /// * `log_group` - CloudWatch log group name
+    async fn delete_log_group(&self, log_group: &str) -> Result<(), AwsServiceError>;
+    
+    /// Digests of the images in an ECR repository; empty when the repository doesn't exist
+    ///
+    /// # Arguments
+    /// * `repository_name` - ECR repository name
+    async fn list_image_digests(&self, repository_name: &str) -> Result<Vec<String>, AwsServiceError>;
+    
+    /// Delete images from an ECR repository; digests already gone are ignored
+    ///
+    /// # Arguments
+    /// * `repository_name` - ECR repository name
+    /// * `digests` - Image digests (`sha256:...`)
+    async fn delete_images(&self, repository_name: &str, digests: &[String]) -> Result<(), AwsServiceError>;
+    
     /// Fetch recent logs from CloudWatch
     ///
     /// # Arguments
@@ -96,4 +195,48 @@ pub trait AwsOperations: Send + Sync {
         log_stream: &str,
         limit: i32
     ) -> Result<Vec<String>, AwsServiceError>;
+    
+    /// List log streams in a CloudWatch log group
+    ///
+    /// # Arguments
+    /// * `log_group` - CloudWatch log group name
+    /// * `limit` - Maximum number of stream names to return
+    ///
+    /// # Returns
+    /// Stream names, most recently written first
+    async fn list_log_streams(&self, log_group: &str, limit: i32) -> Result<Vec<String>, AwsServiceError>;
+    
+    /// Read a service's CPU and memory utilization from CloudWatch metrics
+    ///
+    /// # Arguments
+    /// * `cluster_name` - ECS cluster name
+    /// * `service_name` - ECS service name
+    /// * `window` - How far back to read
+    ///
+    /// # Returns
+    /// Average utilization percentages per period, oldest first
+    async fn get_service_utilization(
+        &self,
+        cluster_name: &str,
+        service_name: &str,
+        window: Duration,
+    ) -> Result<ServiceUtilization, AwsServiceError>;
+    
+    /// Issue a cheap authenticated call to keep SDK connections warm
+    async fn ping(&self) -> Result<(), AwsServiceError>;
+    
+    /// Look up the AWS account ID the configured credentials belong to
+    async fn get_account_id(&self) -> Result<String, AwsServiceError>;
+    
+    /// Check which IAM actions the configured identity is allowed to perform
+    ///
+    /// # Arguments
+    /// * `actions` - IAM action names, e.g. `ecs:UpdateService`
+    ///
+    /// # Returns
+    /// One check per action, in the order IAM evaluated them
+    async fn simulate_permissions(&self, actions: &[String]) -> Result<Vec<PermissionCheck>, AwsServiceError>;
+    
+    /// Region operations are performed in
+    fn region(&self) -> &str;
 }
@@ -0,0 +1,41 @@
+//! Background tasks that run until stopped
+//!
+//! The AWS keepalive and the health watch each loop on an interval; both hand
+//! back the same handle type to stop them.
+
+use std::future::Future;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Handle to a running background task
+///
+/// Dropping the handle also stops the task, without waiting for it to exit.
+pub struct BackgroundTaskHandle {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl BackgroundTaskHandle {
+    /// Spawn the task `run` returns, passing it a receiver that resolves when
+    /// the handle is stopped, cancelled or dropped
+    pub fn spawn<F, Fut>(run: F) -> Self
+    where
+        F: FnOnce(oneshot::Receiver<()>) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let task = tokio::spawn(run(shutdown_rx));
+        Self { shutdown, task }
+    }
+    
+    /// Signal the task to stop and wait for it to exit
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.task.await;
+    }
+    
+    /// Signal the task to stop without waiting (for synchronous shutdown paths)
+    pub fn cancel(self) {
+        let _ = self.shutdown.send(());
+    }
+}
@@ -0,0 +1,102 @@
+//! Build prerequisite checks
+//!
+//! The generated Dockerfile templates assume certain files exist (a `build`
+//! script for Next.js/React, `index.js` for Node, `main.py` for Python). A repo
+//! missing them fails deep inside `docker build`; checking first turns that into
+//! a readable warning.
+
+use crate::models::FrameworkType;
+use std::path::Path;
+
+/// Describe anything the generated Dockerfile for `framework` needs but `repo_path` lacks
+///
+/// A repository with its own Dockerfile isn't checked, since the template
+/// isn't used. An empty result means the build can go ahead.
+pub fn check_build_prerequisites(repo_path: &Path, framework: &FrameworkType) -> Vec<String> {
+    if repo_path.join("Dockerfile").exists() {
+        return Vec::new();
+    }
+    
+    let mut missing = Vec::new();
+    let require_file = |missing: &mut Vec<String>, file: &str, reason: &str| {
+        if !repo_path.join(file).exists() {
+            missing.push(format!("{} not found ({})", file, reason));
+        }
+    };
+    
+    match framework {
+        FrameworkType::NextJs | FrameworkType::React => {
+            match std::fs::read_to_string(repo_path.join("package.json")) {
+                Ok(content) => {
+                    let has_build_script = serde_json::from_str::<serde_json::Value>(&content)
+                        .ok()
+                        .and_then(|parsed| parsed.get("scripts")?.get("build").cloned())
+                        .is_some();
+                    if !has_build_script {
+                        missing.push("package.json has no \"build\" script (the image runs `npm run build`)".to_string());
+                    }
+                }
+                Err(_) => missing.push("package.json not found (dependencies are installed from it)".to_string()),
+            }
+        }
+        FrameworkType::Node => {
+            require_file(&mut missing, "package.json", "dependencies are installed from it");
+            require_file(&mut missing, "index.js", "the container runs `node index.js`");
+        }
+        FrameworkType::Python => {
+            require_file(&mut missing, "requirements.txt", "dependencies are installed from it");
+            require_file(&mut missing, "main.py", "the container runs `python main.py`");
+        }
+        other => missing.push(format!(
+            "No Dockerfile found, and there is no generated template for {:?}",
+            other
+        )),
+    }
+    
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    
+    fn create_repo(files: &[(&str, &str)]) -> PathBuf {
+        let repo = std::env::temp_dir().join(format!("prerequisites_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&repo).unwrap();
+        for (name, content) in files {
+            std::fs::write(repo.join(name), content).unwrap();
+        }
+        repo
+    }
+    
+    #[test]
+    fn test_react_without_build_script_flagged() {
+        let repo = create_repo(&[("package.json", r#"{"scripts": {"start": "react-scripts start"}}"#)]);
+        
+        let missing = check_build_prerequisites(&repo, &FrameworkType::React);
+        
+        assert_eq!(missing.len(), 1);
+        assert!(missing[0].contains("\"build\" script"));
+        std::fs::remove_dir_all(&repo).ok();
+    }
+    
+    #[test]
+    fn test_react_with_build_script_passes() {
+        let repo = create_repo(&[("package.json", r#"{"scripts": {"build": "react-scripts build"}}"#)]);
+        
+        assert!(check_build_prerequisites(&repo, &FrameworkType::React).is_empty());
+        std::fs::remove_dir_all(&repo).ok();
+    }
+    
+    #[test]
+    fn test_own_dockerfile_skips_checks() {
+        let repo = create_repo(&[("Dockerfile", "FROM python:3.11-slim\n")]);
+        let empty = create_repo(&[]);
+        
+        assert!(check_build_prerequisites(&repo, &FrameworkType::Python).is_empty());
+        assert_eq!(check_build_prerequisites(&empty, &FrameworkType::Python).len(), 2);
+        std::fs::remove_dir_all(&repo).ok();
+        std::fs::remove_dir_all(&empty).ok();
+    }
+}
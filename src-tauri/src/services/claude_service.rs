@@ -4,11 +4,16 @@
 //! - Initializing Anthropic API client
 //! - Sending questions with deployment context
 //! - Analyzing logs and suggesting fixes
+//! - Streaming answers, with cancellation part way through
 //! - Using Claude 3.5 Sonnet model
 
+use crate::infrastructure::CaBundle;
+use crate::services::Redactor;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::oneshot;
 
 /// Claude service specific errors
 #[derive(Error, Debug)]
@@ -39,8 +44,10 @@ impl From<reqwest::Error> for ClaudeServiceError {
 pub struct ClaudeService {
     client: Client,
     api_key: String,
+    secondary_api_key: Option<String>,
     base_url: String,
     model: String,
+    redactor: Option<Arc<Redactor>>,
 }
 
 /// Request to Claude API
@@ -50,6 +57,8 @@ struct ClaudeRequest {
     max_tokens: u32,
     messages: Vec<Message>,
     system: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 /// Message in Claude conversation
@@ -69,6 +78,34 @@ struct ClaudeApiResponse {
     content: Vec<ContentBlock>,
     model: String,
     stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Usage,
+}
+
+/// Token counts reported by the API for a single request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// List prices in USD per million `(input, output)` tokens, matched by model prefix
+const MODEL_PRICES: &[(&str, f64, f64)] = &[
+    ("claude-3-5-sonnet", 3.0, 15.0),
+    ("claude-3-5-haiku", 0.80, 4.0),
+    ("claude-3-opus", 15.0, 75.0),
+    ("claude-3-sonnet", 3.0, 15.0),
+    ("claude-3-haiku", 0.25, 1.25),
+];
+
+impl Usage {
+    /// Estimated cost in USD at `model`'s list price, or `None` for a model without a price
+    pub fn estimated_cost(&self, model: &str) -> Option<f64> {
+        let (_, input_price, output_price) = MODEL_PRICES.iter()
+            .find(|(prefix, _, _)| model.starts_with(prefix))?;
+        
+        Some((self.input_tokens as f64 * input_price + self.output_tokens as f64 * output_price) / 1_000_000.0)
+    }
 }
 
 /// Content block in Claude response
@@ -79,6 +116,48 @@ struct ContentBlock {
     text: String,
 }
 
+/// Server-sent event from a streaming Messages API request
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart { message: StreamMessage },
+    ContentBlockDelta { delta: StreamDelta },
+    MessageDelta {
+        #[serde(default)]
+        usage: StreamUsage,
+    },
+    Error { error: StreamError },
+    #[serde(other)]
+    Other,
+}
+
+/// Message envelope sent at the start of a stream
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    #[serde(default)]
+    usage: Usage,
+}
+
+/// Incremental content; only text deltas carry `text`
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    text: String,
+}
+
+/// Output token count reported as a stream progresses
+#[derive(Debug, Default, Deserialize)]
+struct StreamUsage {
+    #[serde(default)]
+    output_tokens: u64,
+}
+
+/// Error reported inside an otherwise successful stream
+#[derive(Debug, Deserialize)]
+struct StreamError {
+    message: String,
+}
+
 /// Deployment context for Claude
 #[derive(Debug, Clone)]
 pub struct DeploymentContext {
@@ -90,46 +169,103 @@ pub struct DeploymentContext {
     pub commit_sha: String,
     pub error_message: Option<String>,
     pub logs: Option<Vec<String>>,
+    pub notes: Option<String>,
 }
 
 /// Claude response with suggestion
 #[derive(Debug, Clone)]
-pub struct ClaudeApiResponse {
+pub struct ClaudeResponse {
     pub answer: String,
     pub suggestions: Vec<String>,
+    pub usage: Usage,
+    /// Estimated cost in USD, if the model has a known price
+    pub estimated_cost_usd: Option<f64>,
+    /// Generation was cancelled; `answer` holds only the text received before then
+    pub partial: bool,
 }
 
 impl ClaudeService {
     /// Create a new ClaudeService instance
     pub fn new(api_key: String) -> Result<Self, ClaudeServiceError> {
+        Self::with_ca_bundle(api_key, None)
+    }
+    
+    /// Create a ClaudeService that also trusts the given custom CA certificates
+    pub fn with_ca_bundle(api_key: String, ca_bundle: Option<&CaBundle>) -> Result<Self, ClaudeServiceError> {
         if api_key.is_empty() {
             return Err(ClaudeServiceError::ApiKeyMissing);
         }
         
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(60))
+        let mut builder = Client::builder()
+            .timeout(std::time::Duration::from_secs(60));
+        if let Some(bundle) = ca_bundle {
+            builder = bundle.apply_to_client(builder);
+        }
+        
+        let client = builder
             .build()
             .map_err(|e| ClaudeServiceError::InitializationFailed(e.to_string()))?;
         
         Ok(Self {
             client,
             api_key,
+            secondary_api_key: None,
             base_url: "https://api.anthropic.com/v1".to_string(),
             model: "claude-3-5-sonnet-20241022".to_string(),
+            redactor: None,
         })
     }
     
+    /// Redact secrets from every prompt before it is sent
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+    
+    /// Retry with `api_key` when the primary key is rejected (401) or rate limited (429)
+    pub fn with_secondary_key(mut self, api_key: Option<String>) -> Self {
+        self.secondary_api_key = api_key.filter(|key| !key.is_empty());
+        self
+    }
+    
     /// Ask Claude a question about deployment
     pub async fn ask_question(&self, question: &str, context: Option<&DeploymentContext>) -> Result<ClaudeResponse, ClaudeServiceError> {
         let system_prompt = self.build_system_prompt();
         let user_message = self.build_user_message(question, context);
         
-        let response_text = self.send_request(&system_prompt, &user_message).await?;
+        let (response_text, usage) = self.send_request(&system_prompt, &user_message).await?;
+        let suggestions = self.extract_suggestions(&response_text);
         
-        Ok(ClaudeResponse {
-            answer: response_text.clone(),
-            suggestions: self.extract_suggestions(&response_text),
-        })
+        Ok(self.build_response(response_text, suggestions, usage))
+    }
+    
+    /// Ask Claude a question, passing each piece of the answer to `on_chunk` as it arrives
+    ///
+    /// Sending on the `cancel` channel's sender aborts the HTTP stream; no
+    /// further chunks are forwarded and the text so far comes back with
+    /// `partial` set. Dropping the sender without sending has no effect.
+    pub async fn ask_question_streaming(
+        &self,
+        question: &str,
+        context: Option<&DeploymentContext>,
+        cancel: oneshot::Receiver<()>,
+        on_chunk: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<ClaudeResponse, ClaudeServiceError> {
+        let system_prompt = self.build_system_prompt();
+        let user_message = self.build_user_message(question, context);
+        
+        let (response_text, usage, partial) = self
+            .send_streaming_request(&system_prompt, &user_message, cancel, on_chunk)
+            .await?;
+        let suggestions = if partial {
+            Vec::new()
+        } else {
+            self.extract_suggestions(&response_text)
+        };
+        
+        let mut response = self.build_response(response_text, suggestions, usage);
+        response.partial = partial;
+        Ok(response)
     }
     
     /// Analyze deployment logs and suggest fixes
@@ -161,12 +297,10 @@ impl ClaudeService {
         user_message.push_str("2. Suggest specific fixes or configuration changes\n");
         user_message.push_str("3. Provide step-by-step remediation instructions\n");
         
-        let response_text = self.send_request(system_prompt, &user_message).await?;
+        let (response_text, usage) = self.send_request(system_prompt, &user_message).await?;
+        let suggestions = self.extract_suggestions(&response_text);
         
-        Ok(ClaudeResponse {
-            answer: response_text.clone(),
-            suggestions: self.extract_suggestions(&response_text),
-        })
+        Ok(self.build_response(response_text, suggestions, usage))
     }
     
     /// Get deployment recommendations for a framework
@@ -185,12 +319,10 @@ impl ClaudeService {
             framework, environment
         );
         
-        let response_text = self.send_request(system_prompt, &user_message).await?;
+        let (response_text, usage) = self.send_request(system_prompt, &user_message).await?;
+        let suggestions = self.extract_suggestions(&response_text);
         
-        Ok(ClaudeResponse {
-            answer: response_text.clone(),
-            suggestions: self.extract_suggestions(&response_text),
-        })
+        Ok(self.build_response(response_text, suggestions, usage))
     }
     
     /// Explain a deployment error
@@ -214,36 +346,118 @@ impl ClaudeService {
             context.service_name
         );
         
-        let response_text = self.send_request(system_prompt, &user_message).await?;
+        let (response_text, usage) = self.send_request(system_prompt, &user_message).await?;
         
-        Ok(ClaudeResponse {
-            answer: response_text,
-            suggestions: Vec::new(),
-        })
+        Ok(self.build_response(response_text, Vec::new(), usage))
     }
     
     // ===== Helper Methods =====
     
-    /// Send request to Claude API
-    async fn send_request(&self, system_prompt: &str, user_message: &str) -> Result<String, ClaudeServiceError> {
-        let request = ClaudeRequest {
+    /// Send request to Claude API, returning the answer text and token usage
+    async fn send_request(&self, system_prompt: &str, user_message: &str) -> Result<(String, Usage), ClaudeServiceError> {
+        let request = self.build_request(system_prompt, user_message, false);
+        let response = self.post_with_failover(&request).await?;
+        
+        let body = response.text().await?;
+        Self::parse_response(&body)
+    }
+    
+    /// Send a streaming request, returning the answer text, token usage and
+    /// whether it was cancelled before completing
+    async fn send_streaming_request(
+        &self,
+        system_prompt: &str,
+        user_message: &str,
+        mut cancel: oneshot::Receiver<()>,
+        on_chunk: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<(String, Usage, bool), ClaudeServiceError> {
+        let request = self.build_request(system_prompt, user_message, true);
+        let mut response = self.post_with_failover(&request).await?;
+        
+        let mut text = String::new();
+        let mut usage = Usage::default();
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut cancel_open = true;
+        
+        loop {
+            // Returning drops `response`, which closes the connection
+            let chunk = tokio::select! {
+                result = &mut cancel, if cancel_open => match result {
+                    Ok(()) => return Ok((text, usage, true)),
+                    Err(_) => {
+                        cancel_open = false;
+                        continue;
+                    }
+                },
+                chunk = response.chunk() => chunk?,
+            };
+            
+            let bytes = match chunk {
+                Some(bytes) => bytes,
+                None => break,
+            };
+            buffer.extend_from_slice(&bytes);
+            
+            // A network chunk may hold several events; stop between them too
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                if cancel_open && cancel.try_recv().is_ok() {
+                    return Ok((text, usage, true));
+                }
+                
+                let line = String::from_utf8_lossy(&line);
+                let data = match line.trim_end().strip_prefix("data:") {
+                    Some(data) => data.trim_start(),
+                    None => continue,
+                };
+                
+                let event: StreamEvent = serde_json::from_str(data)
+                    .map_err(|e| ClaudeServiceError::InvalidResponse(e.to_string()))?;
+                match event {
+                    StreamEvent::MessageStart { message } => usage = message.usage,
+                    StreamEvent::ContentBlockDelta { delta } if !delta.text.is_empty() => {
+                        text.push_str(&delta.text);
+                        on_chunk(&delta.text);
+                    }
+                    StreamEvent::MessageDelta { usage: delta } => usage.output_tokens = delta.output_tokens,
+                    StreamEvent::Error { error } => return Err(ClaudeServiceError::RequestFailed(error.message)),
+                    _ => {}
+                }
+            }
+        }
+        
+        Ok((text, usage, false))
+    }
+    
+    /// Build a Messages API request with secrets redacted from both prompts
+    fn build_request(&self, system_prompt: &str, user_message: &str, stream: bool) -> ClaudeRequest {
+        let redact = |text: &str| match &self.redactor {
+            Some(redactor) => redactor.redact(text),
+            None => text.to_string(),
+        };
+        
+        ClaudeRequest {
             model: self.model.clone(),
             max_tokens: 4096,
             messages: vec![Message {
                 role: "user".to_string(),
-                content: user_message.to_string(),
+                content: redact(user_message),
             }],
-            system: Some(system_prompt.to_string()),
-        };
+            system: Some(redact(system_prompt)),
+            stream,
+        }
+    }
+    
+    /// POST a request, failing over to the secondary key, and reject error statuses
+    async fn post_with_failover(&self, request: &ClaudeRequest) -> Result<reqwest::Response, ClaudeServiceError> {
+        let mut response = self.post_messages(&self.api_key, request).await?;
         
-        let response = self.client
-            .post(&format!("{}/messages", self.base_url))
-            .header("x-api-key", &self.api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+        // Fail over to the secondary key if the primary is revoked or throttled
+        if let Some(secondary) = &self.secondary_api_key {
+            if response.status() == 401 || response.status() == 429 {
+                response = self.post_messages(secondary, request).await?;
+            }
+        }
         
         // Check for rate limiting
         if response.status() == 429 {
@@ -251,16 +465,35 @@ impl ClaudeService {
         }
         
         // Check for success
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             return Err(ClaudeServiceError::RequestFailed(format!(
                 "Status {}: {}",
-                response.status(),
+                status,
                 error_text
             )));
         }
         
-        let claude_response: ClaudeApiResponse = response.json().await
+        Ok(response)
+    }
+    
+    /// POST a request to the Messages API authenticated with `api_key`
+    async fn post_messages(&self, api_key: &str, request: &ClaudeRequest) -> Result<reqwest::Response, ClaudeServiceError> {
+        let response = self.client
+            .post(&format!("{}/messages", self.base_url))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .header("content-type", "application/json")
+            .json(request)
+            .send()
+            .await?;
+        Ok(response)
+    }
+    
+    /// Parse a Messages API response body into its first text block and token usage
+    fn parse_response(body: &str) -> Result<(String, Usage), ClaudeServiceError> {
+        let claude_response: ClaudeApiResponse = serde_json::from_str(body)
             .map_err(|e| ClaudeServiceError::InvalidResponse(e.to_string()))?;
         
         // Extract text from first content block
@@ -269,7 +502,18 @@ impl ClaudeService {
             .map(|block| block.text.clone())
             .ok_or_else(|| ClaudeServiceError::InvalidResponse("No content in response".to_string()))?;
         
-        Ok(text)
+        Ok((text, claude_response.usage))
+    }
+    
+    /// Combine an answer with its token usage and estimated cost
+    fn build_response(&self, answer: String, suggestions: Vec<String>, usage: Usage) -> ClaudeResponse {
+        ClaudeResponse {
+            answer,
+            suggestions,
+            usage,
+            estimated_cost_usd: usage.estimated_cost(&self.model),
+            partial: false,
+        }
     }
     
     /// Build system prompt for general questions
@@ -301,6 +545,10 @@ impl ClaudeService {
                 ctx.commit_sha
             ));
             
+            if let Some(notes) = &ctx.notes {
+                message.push_str(&format!("Operator Notes: {}\n\n", notes));
+            }
+            
             if let Some(error) = &ctx.error_message {
                 message.push_str(&format!("Current Error: {}\n\n", error));
             }
@@ -354,6 +602,32 @@ mod tests {
         assert_eq!(suggestions[1], "Check environment variables");
     }
     
+    #[test]
+    fn test_parse_response_usage_and_cost() {
+        let body = r#"{
+            "id": "msg_01XFDUDYJgAACzvnptvVoYEL",
+            "type": "message",
+            "role": "assistant",
+            "content": [{"type": "text", "text": "Increase the task memory."}],
+            "model": "claude-3-5-sonnet-20241022",
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 2000, "output_tokens": 500}
+        }"#;
+        
+        let (text, usage) = ClaudeService::parse_response(body).unwrap();
+        assert_eq!(text, "Increase the task memory.");
+        assert_eq!(usage, Usage { input_tokens: 2000, output_tokens: 500 });
+        
+        // 2000 * $3/M + 500 * $15/M
+        let cost = usage.estimated_cost("claude-3-5-sonnet-20241022").unwrap();
+        assert!((cost - 0.0135).abs() < 1e-9);
+        assert_eq!(usage.estimated_cost("unknown-model"), None);
+        
+        let service = ClaudeService::new("test_key".to_string()).unwrap();
+        let response = service.build_response(text, Vec::new(), usage);
+        assert_eq!(response.estimated_cost_usd, Some(cost));
+    }
+    
     #[test]
     fn test_new_service_without_api_key() {
         let result = ClaudeService::new("".to_string());
@@ -372,11 +646,133 @@ mod tests {
             commit_sha: "abc123".to_string(),
             error_message: Some("Connection timeout".to_string()),
             logs: None,
+            notes: Some("hotfix for incident #123".to_string()),
         };
         
         let message = service.build_user_message("How do I fix this?", Some(&context));
         assert!(message.contains("test-project"));
         assert!(message.contains("nextjs"));
         assert!(message.contains("Connection timeout"));
+        assert!(message.contains("hotfix for incident #123"));
+    }
+    
+    #[tokio::test]
+    async fn test_rate_limited_primary_fails_over_to_secondary() {
+        use axum::http::{HeaderMap, StatusCode};
+        use std::sync::Mutex;
+        
+        // Mock Messages API that throttles the primary key
+        let seen_keys = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen_keys.clone();
+        let app = axum::Router::new().route("/messages", axum::routing::post(move |headers: HeaderMap| {
+            let recorded = recorded.clone();
+            async move {
+                let key = headers.get("x-api-key").and_then(|v| v.to_str().ok()).unwrap_or_default().to_string();
+                recorded.lock().unwrap().push(key.clone());
+                if key == "primary-key" {
+                    return (StatusCode::TOO_MANY_REQUESTS, String::new());
+                }
+                (StatusCode::OK, r#"{
+                    "id": "msg_1",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": "Answered with the secondary key."}],
+                    "model": "claude-3-5-sonnet-20241022",
+                    "stop_reason": "end_turn",
+                    "usage": {"input_tokens": 10, "output_tokens": 5}
+                }"#.to_string())
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        
+        let mut service = ClaudeService::new("primary-key".to_string()).unwrap()
+            .with_secondary_key(Some("secondary-key".to_string()));
+        service.base_url = format!("http://{}", address);
+        
+        let response = service.ask_question("Why did the deploy fail?", None).await.unwrap();
+        assert_eq!(response.answer, "Answered with the secondary key.");
+        assert_eq!(*seen_keys.lock().unwrap(), vec!["primary-key", "secondary-key"]);
+        
+        // Without a secondary key the rate limit surfaces
+        let mut service = ClaudeService::new("primary-key".to_string()).unwrap();
+        service.base_url = format!("http://{}", address);
+        let result = service.ask_question("Why did the deploy fail?", None).await;
+        assert!(matches!(result, Err(ClaudeServiceError::RateLimitExceeded)));
+    }
+    
+    #[tokio::test]
+    async fn test_cancelled_stream_stops_forwarding_chunks() {
+        use std::sync::Mutex;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        
+        /// Frame an SSE event as one HTTP chunk
+        fn sse_chunk(event: &str) -> Vec<u8> {
+            let data = format!("event: message\ndata: {}\n\n", event);
+            format!("{:x}\r\n{}\r\n", data.len(), data).into_bytes()
+        }
+        
+        // Mock Messages API streaming one text delta, then more after a pause,
+        // and never finishing on its own
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length = text.lines()
+                        .find_map(|line| line.strip_prefix("content-length:"))
+                        .and_then(|value| value.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if n == 0 || request.len() >= header_end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            
+            let _ = socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n").await;
+            let _ = socket.write_all(&sse_chunk(r#"{"type":"message_start","message":{"usage":{"input_tokens":12,"output_tokens":1}}}"#)).await;
+            let _ = socket.write_all(&sse_chunk(r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Check the "}}"#)).await;
+            for _ in 0..100 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let delta = sse_chunk(r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"task role."}}"#);
+                if socket.write_all(&delta).await.is_err() {
+                    break;
+                }
+            }
+        });
+        
+        let mut service = ClaudeService::new("test_key".to_string()).unwrap();
+        service.base_url = format!("http://{}", address);
+        
+        // Cancel as soon as the first chunk arrives
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let cancel_tx = Mutex::new(Some(cancel_tx));
+        let chunks = Mutex::new(Vec::new());
+        let on_chunk = |chunk: &str| {
+            chunks.lock().unwrap().push(chunk.to_string());
+            if let Some(sender) = cancel_tx.lock().unwrap().take() {
+                let _ = sender.send(());
+            }
+        };
+        
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            service.ask_question_streaming("Why did the deploy fail?", None, cancel_rx, &on_chunk),
+        ).await.expect("cancelled stream should end promptly").unwrap();
+        
+        assert!(response.partial);
+        assert_eq!(response.answer, "Check the ");
+        assert_eq!(response.usage.input_tokens, 12);
+        
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(*chunks.lock().unwrap(), vec!["Check the "]);
     }
 }
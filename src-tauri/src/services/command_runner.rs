@@ -0,0 +1,147 @@
+//! External command execution
+//!
+//! Defines the trait used for every shell-out (currently `docker`) so those
+//! code paths can be driven by a mock runner in tests instead of real processes.
+
+use async_trait::async_trait;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+/// Captured result of running an external command
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    /// Whether the process exited with a zero status
+    pub success: bool,
+    /// Captured standard output (lossy UTF-8)
+    pub stdout: String,
+    /// Captured standard error (lossy UTF-8)
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    /// Successful output with the given stdout
+    pub fn success(stdout: &str) -> Self {
+        Self {
+            success: true,
+            stdout: stdout.to_string(),
+            stderr: String::new(),
+        }
+    }
+    
+    /// Failed output with the given stderr
+    pub fn failure(stderr: &str) -> Self {
+        Self {
+            success: false,
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+        }
+    }
+}
+
+/// Trait for running external programs
+///
+/// An `Err` means the program could not be started at all; a non-zero exit is
+/// reported through `CommandOutput::success`.
+#[async_trait]
+pub trait CommandRunner: Send + Sync {
+    /// Run a program to completion
+    ///
+    /// # Arguments
+    /// * `program` - Executable name or path
+    /// * `args` - Command-line arguments
+    /// * `stdin` - Optional bytes written to the process's standard input
+    async fn run(&self, program: &str, args: &[&str], stdin: Option<&[u8]>) -> std::io::Result<CommandOutput>;
+    
+    /// Run a program to completion, passing each output line to `on_line` as it arrives
+    ///
+    /// Lines from stdout and stderr are both passed on (docker's BuildKit
+    /// writes its build output to stderr) but are captured separately. The
+    /// default implementation runs the program and replays its stdout then its
+    /// stderr afterwards, which is sufficient for runners that don't spawn processes.
+    async fn run_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        on_line: &(dyn Fn(&str) + Send + Sync),
+    ) -> std::io::Result<CommandOutput> {
+        let output = self.run(program, args, None).await?;
+        output.stdout.lines().chain(output.stderr.lines()).for_each(|line| on_line(line));
+        Ok(output)
+    }
+}
+
+/// Command runner backed by `tokio::process`
+#[derive(Debug, Clone, Default)]
+pub struct TokioCommandRunner;
+
+#[async_trait]
+impl CommandRunner for TokioCommandRunner {
+    async fn run(&self, program: &str, args: &[&str], stdin: Option<&[u8]>) -> std::io::Result<CommandOutput> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        
+        if let Some(input) = stdin {
+            if let Some(mut child_stdin) = child.stdin.take() {
+                child_stdin.write_all(input).await?;
+                // Dropping stdin closes the pipe so the process sees EOF
+            }
+        }
+        
+        let output = child.wait_with_output().await?;
+        
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+    
+    async fn run_streaming(
+        &self,
+        program: &str,
+        args: &[&str],
+        on_line: &(dyn Fn(&str) + Send + Sync),
+    ) -> std::io::Result<CommandOutput> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        
+        let missing_pipe = || std::io::Error::new(std::io::ErrorKind::BrokenPipe, "child pipe not captured");
+        let mut stdout_lines = BufReader::new(child.stdout.take().ok_or_else(missing_pipe)?).lines();
+        let mut stderr_lines = BufReader::new(child.stderr.take().ok_or_else(missing_pipe)?).lines();
+        let (mut stdout, mut stderr) = (String::new(), String::new());
+        let (mut stdout_open, mut stderr_open) = (true, true);
+        
+        // Read both pipes as lines arrive so a chatty process can't block on a full pipe
+        while stdout_open || stderr_open {
+            let (line, captured, open) = tokio::select! {
+                line = stdout_lines.next_line(), if stdout_open => (line?, &mut stdout, &mut stdout_open),
+                line = stderr_lines.next_line(), if stderr_open => (line?, &mut stderr, &mut stderr_open),
+            };
+            match line {
+                Some(line) => {
+                    on_line(&line);
+                    captured.push_str(&line);
+                    captured.push('\n');
+                }
+                None => *open = false,
+            }
+        }
+        
+        let status = child.wait().await?;
+        
+        Ok(CommandOutput {
+            success: status.success(),
+            stdout,
+            stderr,
+        })
+    }
+}
@@ -0,0 +1,52 @@
+//! Resolving a project's container settings
+//!
+//! The orchestrator's ECS task definition and the generated Terraform must
+//! agree on port and task size, so both read them from `resolve_deployment_spec`
+//! rather than applying the fallbacks themselves.
+
+use crate::models::{Environment, LaunchType, PortProtocol, Project};
+use crate::services::{AwsService, PlacementStrategy};
+
+/// Container settings for a project, with every default applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedSpec {
+    pub port: i32,
+    pub port_protocol: PortProtocol,
+    /// CPU units
+    pub cpu: u32,
+    /// Memory in MiB
+    pub memory: u32,
+    pub desired_count: i32,
+    pub ephemeral_storage_gib: Option<i32>,
+    pub launch_type: LaunchType,
+    /// Placement strategies for the service (only applied on EC2)
+    pub placement_strategies: Vec<PlacementStrategy>,
+}
+
+/// Resolve a project's container settings
+///
+/// The port is the project's explicit `container_port`, falling back to the
+/// framework default (which is also what the generated Dockerfiles expose).
+/// Task size comes from the project's resource preset, which starts out as its
+/// environment's default, and the task count from the project or else one.
+pub fn resolve_deployment_spec(project: &Project) -> ResolvedSpec {
+    let port = project.container_port
+        .unwrap_or_else(|| AwsService::get_framework_port(&project.framework));
+    let (cpu, memory) = project.resource_size.cpu_memory();
+    // Production survives an AZ outage
+    let placement_strategies = match project.environment {
+        Environment::Production => vec![PlacementStrategy::spread_across_azs()],
+        _ => Vec::new(),
+    };
+    
+    ResolvedSpec {
+        port,
+        port_protocol: project.port_protocol,
+        cpu,
+        memory,
+        desired_count: project.desired_count.unwrap_or(1),
+        ephemeral_storage_gib: project.ephemeral_storage_gib,
+        launch_type: project.launch_type,
+        placement_strategies,
+    }
+}
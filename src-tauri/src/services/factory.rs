@@ -4,7 +4,7 @@
 //! Enables easy switching between real and mock implementations.
 
 use crate::services::{AwsOperations, GitOperations};
-use crate::services::{AwsService, GitService};
+use crate::services::{AwsClientConfig, AwsService, GitService};
 use crate::shadow::{ShadowConfig, ShadowState, MockAwsService, MockGitService};
 use std::sync::Arc;
 
@@ -41,7 +41,7 @@ pub async fn create_aws_operations(
     if config.is_enabled() {
         Ok(Arc::new(MockAwsService::new(region, config.clone(), state)))
     } else {
-        Ok(Arc::new(AwsService::new(region).await?))
+        Ok(Arc::new(AwsService::new(region, &AwsClientConfig::default()).await?))
     }
 }
 
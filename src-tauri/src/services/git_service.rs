@@ -4,15 +4,20 @@
 //! - Cloning repositories to temporary directories
 //! - Detecting framework types from project files
 //! - Retrieving commit information
+//! - Listing a remote's branches and tags
+//! - Downloading a commit's tree as an archive (GitHub and GitLab)
 
-use crate::models::FrameworkType;
-use crate::services::GitOperations;
-use crate::services::git_trait::CommitInfo;
-use git2::{Repository, Oid, Commit};
+use crate::infrastructure::CaBundle;
+use crate::models::{FrameworkType, GitCredentials};
+use crate::services::{GitOperations, OperationMetrics};
+use crate::services::git_trait::{CommitInfo, RemoteRefs};
+use git2::{Repository, Oid, Commit, ErrorCode};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 use async_trait::async_trait;
 use std::fs;
+use std::io::{self, Read};
 
 /// Git service specific errors
 #[derive(Error, Debug)]
@@ -26,6 +31,9 @@ pub enum GitServiceError {
     #[error("Commit not found: {0}")]
     CommitNotFound(String),
     
+    #[error("Repository has no commits")]
+    EmptyRepository,
+    
     #[error("Failed to read file: {0}")]
     FileReadFailed(String),
     
@@ -34,6 +42,12 @@ pub enum GitServiceError {
     
     #[error("Failed to create temporary directory: {0}")]
     TempDirFailed(String),
+    
+    #[error("Failed to list remote refs: {0}")]
+    RemoteListFailed(String),
+    
+    #[error("Failed to fetch archive: {0}")]
+    ArchiveFailed(String),
 }
 
 impl From<git2::Error> for GitServiceError {
@@ -48,13 +62,36 @@ impl From<std::io::Error> for GitServiceError {
     }
 }
 
+/// Archive chunks buffered between the download and the extraction
+const ARCHIVE_CHUNK_BUFFER: usize = 16;
+
 /// Git service for repository operations
-pub struct GitService;
+pub struct GitService {
+    /// Outcome counts of calls made through `GitOperations`
+    metrics: Arc<OperationMetrics>,
+    /// Extra trusted roots for archive downloads
+    ca_bundle: Option<CaBundle>,
+}
 
 impl GitService {
     /// Create a new GitService instance
     pub fn new() -> Self {
-        GitService
+        GitService {
+            metrics: Arc::new(OperationMetrics::new()),
+            ca_bundle: None,
+        }
+    }
+    
+    /// Count `GitOperations` calls in a shared registry instead of the service's own
+    pub fn with_operation_metrics(mut self, metrics: Arc<OperationMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+    
+    /// Trust `ca_bundle` alongside the system roots when downloading archives
+    pub fn with_ca_bundle(mut self, ca_bundle: Option<CaBundle>) -> Self {
+        self.ca_bundle = ca_bundle;
+        self
     }
     
     /// Clone a repository to a temporary directory
@@ -88,6 +125,210 @@ impl GitService {
         .map_err(|e| GitServiceError::CloneFailed(e.to_string()))?
     }
     
+    /// List a remote's branches and tags (the equivalent of `git ls-remote`)
+    pub async fn list_remote_refs(
+        &self,
+        repo_url: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<RemoteRefs, GitServiceError> {
+        let repo_url = repo_url.to_string();
+        let credentials = credentials.map(|c| (c.username.clone(), c.token.clone()));
+        
+        tokio::task::spawn_blocking(move || {
+            let mut remote = git2::Remote::create_detached(repo_url.as_str())
+                .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?;
+            
+            let callbacks = Self::remote_callbacks(&credentials);
+            let mut connection = remote.connect_auth(git2::Direction::Fetch, Some(callbacks), None)
+                .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?;
+            let heads = connection.remote().list()
+                .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?;
+            
+            Ok(RemoteRefs::from_ref_names(heads.iter().map(|head| head.name())))
+        })
+        .await
+        .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?
+    }
+    
+    /// Callbacks answering a remote's authentication request with `credentials`, if any
+    fn remote_callbacks(credentials: &Option<(String, String)>) -> git2::RemoteCallbacks<'_> {
+        let mut callbacks = git2::RemoteCallbacks::new();
+        if let Some((username, token)) = credentials {
+            callbacks.credentials(move |_url, _username_from_url, _allowed| {
+                git2::Cred::userpass_plaintext(username, token)
+            });
+        }
+        callbacks
+    }
+    
+    /// Resolve a branch to its head commit SHA on the remote
+    pub async fn resolve_remote_commit(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<String, GitServiceError> {
+        let repo_url = repo_url.to_string();
+        let branch_ref = format!("refs/heads/{}", branch);
+        let credentials = credentials.map(|c| (c.username.clone(), c.token.clone()));
+        
+        tokio::task::spawn_blocking(move || {
+            let mut remote = git2::Remote::create_detached(repo_url.as_str())
+                .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?;
+            
+            let callbacks = Self::remote_callbacks(&credentials);
+            let mut connection = remote.connect_auth(git2::Direction::Fetch, Some(callbacks), None)
+                .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?;
+            let heads = connection.remote().list()
+                .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?;
+            
+            heads.iter()
+                .find(|head| head.name() == branch_ref)
+                .map(|head| head.oid().to_string())
+                .ok_or_else(|| GitServiceError::CommitNotFound(branch_ref.clone()))
+        })
+        .await
+        .map_err(|e| GitServiceError::RemoteListFailed(e.to_string()))?
+    }
+    
+    /// Download and extract the tree at `commit_sha` through the host's archive API
+    ///
+    /// The archive is extracted as it downloads rather than held in memory.
+    /// Nothing is left behind when the download or extraction fails.
+    pub async fn fetch_archive(
+        &self,
+        repo_url: &str,
+        commit_sha: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<PathBuf, GitServiceError> {
+        let url = Self::archive_url(repo_url, commit_sha)
+            .ok_or_else(|| GitServiceError::ArchiveFailed(format!("No archive API known for {}", repo_url)))?;
+        
+        let temp_dir = std::env::temp_dir()
+            .join("deployotron")
+            .join(format!("archive_{}", uuid::Uuid::new_v4()));
+        
+        fs::create_dir_all(&temp_dir)
+            .map_err(|e| GitServiceError::TempDirFailed(e.to_string()))?;
+        
+        match self.download_archive(&url, credentials, &temp_dir).await {
+            Ok(()) => Ok(temp_dir),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&temp_dir);
+                Err(e)
+            }
+        }
+    }
+    
+    /// Stream the archive at `url` into `extract_archive` on a blocking thread
+    async fn download_archive(
+        &self,
+        url: &str,
+        credentials: Option<&GitCredentials>,
+        dest: &Path,
+    ) -> Result<(), GitServiceError> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(bundle) = &self.ca_bundle {
+            builder = bundle.apply_to_client(builder);
+        }
+        let client = builder.build()
+            .map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?;
+        
+        let mut request = client.get(url);
+        if let Some(credentials) = credentials {
+            request = request.basic_auth(&credentials.username, Some(&credentials.token));
+        }
+        let mut response = request.send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?;
+        
+        let (sender, receiver) = tokio::sync::mpsc::channel(ARCHIVE_CHUNK_BUFFER);
+        let extract_path = dest.to_path_buf();
+        let extraction = tokio::task::spawn_blocking(move || {
+            Self::extract_archive(ChunkReader::new(receiver), &extract_path)
+        });
+        
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => Ok(chunk.to_vec()),
+                Ok(None) => break,
+                Err(e) => Err(io::Error::new(io::ErrorKind::Other, e)),
+            };
+            let failed = chunk.is_err();
+            // The extraction stopped early; its error says why
+            if sender.send(chunk).await.is_err() || failed {
+                break;
+            }
+        }
+        drop(sender);
+        
+        extraction.await
+            .map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?
+    }
+    
+    /// Tarball URL for a commit on GitHub or GitLab
+    ///
+    /// `https://github.com/owner/repo.git` becomes
+    /// `https://codeload.github.com/owner/repo/tar.gz/<sha>`. Other hosts have
+    /// no known archive API and return `None`.
+    pub fn archive_url(repo_url: &str, commit_sha: &str) -> Option<String> {
+        let rest = repo_url.strip_prefix("https://")?;
+        let (host, path) = rest.split_once('/')?;
+        let path = path.trim_end_matches('/').trim_end_matches(".git");
+        if path.is_empty() {
+            return None;
+        }
+        
+        match host {
+            "github.com" => Some(format!("https://codeload.github.com/{}/tar.gz/{}", path, commit_sha)),
+            "gitlab.com" => Some(format!("https://gitlab.com/{}/-/archive/{}/archive.tar.gz", path, commit_sha)),
+            _ => None,
+        }
+    }
+    
+    /// Unpack a gzipped tarball into `dest`
+    ///
+    /// The single top-level directory hosts wrap archives in (`repo-<sha>/`)
+    /// is stripped, and `.git` entries and paths escaping `dest` are skipped.
+    /// Symlinks and hard links are skipped too, since a link could point a
+    /// later entry outside `dest`.
+    pub fn extract_archive(archive: impl Read, dest: &Path) -> Result<(), GitServiceError> {
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+        let entries = archive.entries()
+            .map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?;
+        
+        for entry in entries {
+            let mut entry = entry.map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?;
+            let kind = entry.header().entry_type();
+            if kind.is_symlink() || kind.is_hard_link() {
+                continue;
+            }
+            
+            let path = entry.path()
+                .map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?
+                .into_owned();
+            
+            let mut components = path.components();
+            components.next();
+            let relative = components.as_path();
+            
+            let safe = relative.components().all(|c| matches!(c, std::path::Component::Normal(name) if name != ".git"));
+            if relative.as_os_str().is_empty() || !safe {
+                continue;
+            }
+            
+            let target = dest.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            entry.unpack(&target)
+                .map_err(|e| GitServiceError::ArchiveFailed(e.to_string()))?;
+        }
+        
+        Ok(())
+    }
+    
     /// Detect the framework type from project files
     pub async fn detect_framework(&self, repo_path: &Path) -> Result<FrameworkType, GitServiceError> {
         let repo_path = repo_path.to_path_buf();
@@ -185,12 +426,10 @@ impl GitService {
                 repo.find_commit(oid)
                     .map_err(|e| GitServiceError::CommitNotFound(e.to_string()))?
             } else {
-                // Get HEAD commit
-                let head = repo.head()?;
-                head.peel_to_commit()?
+                Self::head_commit(&repo)?
             };
             
-            Ok(Self::commit_to_info(&commit))
+            Ok(Self::commit_to_info(&repo, &commit))
         })
         .await
         .map_err(|e| GitServiceError::CommitNotFound(e.to_string()))?
@@ -202,24 +441,157 @@ impl GitService {
         
         tokio::task::spawn_blocking(move || {
             let repo = Repository::open(&repo_path)?;
-            let head = repo.head()?;
-            let commit = head.peel_to_commit()?;
+            let commit = Self::head_commit(&repo)?;
             Ok(commit.id().to_string())
         })
         .await
         .map_err(|e| GitServiceError::CommitNotFound(e.to_string()))?
     }
     
+    /// List commits reachable from HEAD but not from `since_sha`, newest first
+    ///
+    /// A `since_sha` the clone doesn't contain (e.g. after a force push) is
+    /// ignored, so up to `limit` commits from HEAD are listed instead.
+    pub async fn commits_since(&self, repo_path: &Path, since_sha: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, GitServiceError> {
+        let repo_path = repo_path.to_path_buf();
+        let since_sha = since_sha.map(|s| s.to_string());
+        
+        tokio::task::spawn_blocking(move || {
+            let repo = Repository::open(&repo_path)?;
+            let head = Self::head_commit(&repo)?;
+            Self::walk_commits(&repo, head.id(), since_sha.as_deref(), limit)
+        })
+        .await
+        .map_err(|e| GitServiceError::CommitNotFound(e.to_string()))?
+    }
+    
+    /// List commits on a remote branch since `since_sha`, newest first, without a working tree
+    ///
+    /// At most `limit` commits of history are fetched into a throwaway bare
+    /// repository, which is removed afterwards. As with `commits_since`, a
+    /// `since_sha` outside the fetched history is ignored.
+    pub async fn list_remote_commits(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        since_sha: Option<&str>,
+        limit: usize,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<Vec<CommitInfo>, GitServiceError> {
+        let temp_dir = std::env::temp_dir()
+            .join("deployotron")
+            .join(format!("commits_{}", uuid::Uuid::new_v4()));
+        
+        let repo_url = repo_url.to_string();
+        let branch_ref = format!("refs/heads/{}", branch);
+        let since_sha = since_sha.map(|s| s.to_string());
+        let credentials = credentials.map(|c| (c.username.clone(), c.token.clone()));
+        let repo_path = temp_dir.clone();
+        
+        let commits = tokio::task::spawn_blocking(move || {
+            let repo = Repository::init_bare(&repo_path)?;
+            let mut remote = repo.remote_anonymous(&repo_url)?;
+            
+            let mut options = git2::FetchOptions::new();
+            options.remote_callbacks(Self::remote_callbacks(&credentials));
+            options.depth(i32::try_from(limit).unwrap_or(i32::MAX));
+            remote.fetch(&[format!("+{}:{}", branch_ref, branch_ref)], Some(&mut options), None)
+                .map_err(|e| GitServiceError::CloneFailed(e.to_string()))?;
+            
+            let head = repo.find_reference(&branch_ref)
+                .and_then(|reference| reference.peel_to_commit())
+                .map_err(|_| GitServiceError::CommitNotFound(branch_ref.clone()))?;
+            Self::walk_commits(&repo, head.id(), since_sha.as_deref(), limit)
+        })
+        .await
+        .map_err(|e| GitServiceError::CloneFailed(e.to_string()))?;
+        
+        // Best effort cleanup; the result doesn't depend on it
+        let _ = fs::remove_dir_all(&temp_dir);
+        commits
+    }
+    
+    /// Check out `commit_sha` in a clone, detaching HEAD
+    ///
+    /// Fails with `CommitNotFound` when the clone doesn't contain the commit
+    /// (e.g. the branch was force pushed since it was chosen).
+    pub async fn checkout_commit(&self, repo_path: &Path, commit_sha: &str) -> Result<(), GitServiceError> {
+        let repo_path = repo_path.to_path_buf();
+        let commit_sha = commit_sha.to_string();
+        
+        tokio::task::spawn_blocking(move || {
+            let repo = Repository::open(&repo_path)?;
+            let commit = Oid::from_str(&commit_sha)
+                .and_then(|oid| repo.find_commit(oid))
+                .map_err(|_| GitServiceError::CommitNotFound(commit_sha.clone()))?;
+            
+            repo.checkout_tree(commit.as_object(), Some(git2::build::CheckoutBuilder::new().force()))?;
+            repo.set_head_detached(commit.id())?;
+            Ok(())
+        })
+        .await
+        .map_err(|e| GitServiceError::CommitNotFound(e.to_string()))?
+    }
+    
+    /// Walk history from `head`, newest first, stopping at `since_sha` if the repository has it
+    fn walk_commits(repo: &Repository, head: Oid, since_sha: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, GitServiceError> {
+        let mut walk = repo.revwalk()?;
+        walk.push(head)?;
+        if let Some(since) = since_sha.and_then(|sha| Oid::from_str(sha).ok()) {
+            if repo.find_commit(since).is_ok() {
+                walk.hide(since)?;
+            }
+        }
+        
+        let mut commits = Vec::new();
+        for oid in walk.take(limit) {
+            let commit = repo.find_commit(oid?)?;
+            commits.push(Self::commit_to_info(repo, &commit));
+        }
+        Ok(commits)
+    }
+    
+    /// Resolve the commit HEAD points at (attached or detached)
+    ///
+    /// An unborn HEAD (fresh or empty repository) is reported as
+    /// `EmptyRepository` rather than git2's lower-level reference error.
+    fn head_commit(repo: &Repository) -> Result<Commit<'_>, GitServiceError> {
+        let head = match repo.head() {
+            Ok(head) => head,
+            Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => {
+                return Err(GitServiceError::EmptyRepository);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        
+        Ok(head.peel_to_commit()?)
+    }
+    
     /// Convert git2::Commit to CommitInfo
-    fn commit_to_info(commit: &Commit) -> CommitInfo {
+    fn commit_to_info(repo: &Repository, commit: &Commit) -> CommitInfo {
         CommitInfo {
             sha: commit.id().to_string(),
             message: commit.message().unwrap_or("").to_string(),
             author: commit.author().name().unwrap_or("Unknown").to_string(),
             timestamp: commit.time().seconds(),
+            parent_sha: Self::parent_sha(repo, commit),
         }
     }
     
+    /// Resolve the first parent SHA without failing on shallow clones
+    ///
+    /// Only the parent ID from the commit header is read. In a shallow repo the
+    /// parent object may be missing, so it is reported only if it can be found.
+    fn parent_sha(repo: &Repository, commit: &Commit) -> Option<String> {
+        let parent_id = commit.parent_id(0).ok()?;
+        
+        if repo.is_shallow() && repo.find_commit(parent_id).is_err() {
+            return None;
+        }
+        
+        Some(parent_id.to_string())
+    }
+    
     /// Clean up cloned repository directory
     pub async fn cleanup_repository(&self, repo_path: &Path) -> Result<(), GitServiceError> {
         let repo_path = repo_path.to_path_buf();
@@ -242,15 +614,47 @@ impl Default for GitService {
     }
 }
 
+/// Blocking reader over archive chunks sent from the downloading task
+///
+/// Reads end once the sender is dropped; an error chunk fails the read.
+struct ChunkReader {
+    chunks: tokio::sync::mpsc::Receiver<io::Result<Vec<u8>>>,
+    current: io::Cursor<Vec<u8>>,
+}
+
+impl ChunkReader {
+    fn new(chunks: tokio::sync::mpsc::Receiver<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            chunks,
+            current: io::Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.current = io::Cursor::new(chunk?),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
 // Implement GitOperations trait for GitService
 #[async_trait]
 impl GitOperations for GitService {
     async fn clone_repository(&self, repo_url: &str, branch: &str) -> Result<PathBuf, GitServiceError> {
-        self.clone_repository(repo_url, branch).await
+        self.metrics.track("clone_repository", self.clone_repository(repo_url, branch)).await
     }
     
     async fn detect_framework(&self, repo_path: &Path) -> Result<FrameworkType, GitServiceError> {
-        self.detect_framework(repo_path).await
+        self.metrics.track("detect_framework", self.detect_framework(repo_path)).await
     }
     
     async fn get_commit_info(
@@ -258,15 +662,60 @@ impl GitOperations for GitService {
         repo_path: &Path,
         commit_sha: Option<&str>
     ) -> Result<CommitInfo, GitServiceError> {
-        self.get_commit_info(repo_path, commit_sha).await
+        self.metrics.track("get_commit_info", self.get_commit_info(repo_path, commit_sha)).await
     }
     
     async fn get_latest_commit_sha(&self, repo_path: &Path) -> Result<String, GitServiceError> {
-        self.get_latest_commit_sha(repo_path).await
+        self.metrics.track("get_latest_commit_sha", self.get_latest_commit_sha(repo_path)).await
+    }
+    
+    async fn commits_since(&self, repo_path: &Path, since_sha: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, GitServiceError> {
+        self.metrics.track("commits_since", self.commits_since(repo_path, since_sha, limit)).await
+    }
+    
+    async fn checkout_commit(&self, repo_path: &Path, commit_sha: &str) -> Result<(), GitServiceError> {
+        self.metrics.track("checkout_commit", self.checkout_commit(repo_path, commit_sha)).await
     }
     
     async fn cleanup_repository(&self, repo_path: &Path) -> Result<(), GitServiceError> {
-        self.cleanup_repository(repo_path).await
+        self.metrics.track("cleanup_repository", self.cleanup_repository(repo_path)).await
+    }
+    
+    async fn resolve_remote_commit(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<String, GitServiceError> {
+        self.metrics.track("resolve_remote_commit", self.resolve_remote_commit(repo_url, branch, credentials)).await
+    }
+    
+    async fn fetch_archive(
+        &self,
+        repo_url: &str,
+        commit_sha: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<PathBuf, GitServiceError> {
+        self.metrics.track("fetch_archive", self.fetch_archive(repo_url, commit_sha, credentials)).await
+    }
+    
+    async fn list_remote_refs(
+        &self,
+        repo_url: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<RemoteRefs, GitServiceError> {
+        self.metrics.track("list_remote_refs", self.list_remote_refs(repo_url, credentials)).await
+    }
+    
+    async fn list_remote_commits(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        since_sha: Option<&str>,
+        limit: usize,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<Vec<CommitInfo>, GitServiceError> {
+        self.metrics.track("list_remote_commits", self.list_remote_commits(repo_url, branch, since_sha, limit, credentials)).await
     }
 }
 
@@ -274,6 +723,117 @@ impl GitOperations for GitService {
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_archive_url_for_known_hosts() {
+        assert_eq!(
+            GitService::archive_url("https://github.com/acme/web.git", "abc123").as_deref(),
+            Some("https://codeload.github.com/acme/web/tar.gz/abc123"),
+        );
+        assert_eq!(
+            GitService::archive_url("https://gitlab.com/acme/platform/web/", "abc123").as_deref(),
+            Some("https://gitlab.com/acme/platform/web/-/archive/abc123/archive.tar.gz"),
+        );
+        assert_eq!(GitService::archive_url("https://git.example.com/acme/web.git", "abc123"), None);
+        assert_eq!(GitService::archive_url("git@github.com:acme/web.git", "abc123"), None);
+    }
+    
+    #[test]
+    fn test_extract_archive_builds_context_without_git_dir() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        for (path, contents) in [
+            ("web-abc123/package.json", "{}"),
+            ("web-abc123/src/index.js", "console.log('hi');"),
+            ("web-abc123/.git/config", "[core]"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+        
+        let dest = std::env::temp_dir().join(format!("archive_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dest).unwrap();
+        GitService::extract_archive(archive.as_slice(), &dest).unwrap();
+        
+        assert_eq!(fs::read_to_string(dest.join("package.json")).unwrap(), "{}");
+        assert!(dest.join("src/index.js").exists());
+        assert!(!dest.join(".git").exists());
+        assert!(!dest.join("web-abc123").exists());
+        
+        fs::remove_dir_all(&dest).ok();
+    }
+    
+    #[test]
+    fn test_extract_archive_skips_links() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+        for (path, kind, target) in [
+            ("web-abc123/escape", tar::EntryType::Symlink, "/etc"),
+            ("web-abc123/passwd", tar::EntryType::Link, "/etc/passwd"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(kind);
+            header.set_size(0);
+            header.set_link_name(target).unwrap();
+            header.set_cksum();
+            builder.append_data(&mut header, path, io::empty()).unwrap();
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "web-abc123/package.json", "{}".as_bytes()).unwrap();
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+        
+        let dest = std::env::temp_dir().join(format!("archive_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dest).unwrap();
+        GitService::extract_archive(archive.as_slice(), &dest).unwrap();
+        
+        assert!(fs::symlink_metadata(dest.join("escape")).is_err());
+        assert!(fs::symlink_metadata(dest.join("passwd")).is_err());
+        assert!(dest.join("package.json").exists());
+        
+        fs::remove_dir_all(&dest).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_chunk_reader_joins_chunks_and_surfaces_errors() {
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+        sender.send(Ok(b"hello ".to_vec())).await.unwrap();
+        sender.send(Ok(b"world".to_vec())).await.unwrap();
+        drop(sender);
+        
+        let read = tokio::task::spawn_blocking(move || {
+            let mut text = String::new();
+            ChunkReader::new(receiver).read_to_string(&mut text).map(|_| text)
+        }).await.unwrap();
+        assert_eq!(read.unwrap(), "hello world");
+        
+        let (sender, receiver) = tokio::sync::mpsc::channel(4);
+        sender.send(Err(io::Error::new(io::ErrorKind::Other, "connection reset"))).await.unwrap();
+        let read = tokio::task::spawn_blocking(move || {
+            ChunkReader::new(receiver).read_to_end(&mut Vec::new())
+        }).await.unwrap();
+        assert!(read.is_err());
+    }
+    
+    #[test]
+    fn test_remote_refs_split_and_sorted() {
+        let refs = RemoteRefs::from_ref_names([
+            "HEAD",
+            "refs/heads/release/2.0",
+            "refs/tags/v2.0.0",
+            "refs/heads/main",
+            "refs/tags/v1.0.0",
+            "refs/tags/v1.0.0^{}",
+            "refs/merge-requests/4/head",
+        ]);
+        
+        assert_eq!(refs.branches, ["main", "release/2.0"]);
+        assert_eq!(refs.tags, ["v1.0.0", "v2.0.0"]);
+    }
+    
     #[test]
     fn test_detect_nextjs_framework() {
         let package_json = r#"{
@@ -335,4 +895,117 @@ mod tests {
         let framework = GitService::detect_js_framework(package_json).unwrap();
         assert_eq!(framework, FrameworkType::Node);
     }
+    
+    /// Create a repository with commits on HEAD, returning its path
+    fn init_repo_with_commits(count: usize) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("git_service_test_{}", uuid::Uuid::new_v4()));
+        let repo = Repository::init(&path).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        
+        for i in 0..count {
+            let tree_id = repo.index().unwrap().write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<&Commit> = parent.iter().collect();
+            repo.commit(Some("HEAD"), &signature, &signature, &format!("Commit {}", i), &tree, &parents)
+                .unwrap();
+        }
+        
+        path
+    }
+    
+    #[tokio::test]
+    async fn test_empty_repository_reports_no_commits() {
+        let path = init_repo_with_commits(0);
+        let service = GitService::new();
+        
+        let info = service.get_commit_info(&path, None).await;
+        assert!(matches!(info, Err(GitServiceError::EmptyRepository)));
+        
+        let sha = service.get_latest_commit_sha(&path).await;
+        assert!(matches!(sha, Err(GitServiceError::EmptyRepository)));
+        
+        fs::remove_dir_all(&path).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_commit_info_on_detached_head() {
+        let path = init_repo_with_commits(2);
+        let repo = Repository::open(&path).unwrap();
+        let first = repo.head().unwrap().peel_to_commit().unwrap().parent_id(0).unwrap();
+        repo.set_head_detached(first).unwrap();
+        let service = GitService::new();
+        
+        let info = service.get_commit_info(&path, None).await.unwrap();
+        
+        assert_eq!(info.sha, first.to_string());
+        assert_eq!(info.message, "Commit 0");
+        
+        fs::remove_dir_all(&path).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_commit_info_without_parent() {
+        // A single-commit repo looks like the tip of a depth-1 shallow clone
+        let path = init_repo_with_commits(1);
+        let service = GitService::new();
+        
+        let info = service.get_commit_info(&path, None).await.unwrap();
+        
+        assert_eq!(info.message, "Commit 0");
+        assert!(info.parent_sha.is_none());
+        
+        fs::remove_dir_all(&path).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_commits_since_stops_at_deployed_commit() {
+        let path = init_repo_with_commits(4);
+        let repo = Repository::open(&path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let deployed = head.parent(0).unwrap().parent_id(0).unwrap().to_string();
+        let service = GitService::new();
+        
+        let commits = service.commits_since(&path, Some(&deployed), 10).await.unwrap();
+        let messages: Vec<&str> = commits.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(messages, vec!["Commit 3", "Commit 2"]);
+        
+        // Unknown or absent starting points list from HEAD, up to the limit
+        let commits = service.commits_since(&path, Some("0123456789abcdef0123456789abcdef01234567"), 3).await.unwrap();
+        assert_eq!(commits.len(), 3);
+        assert_eq!(service.commits_since(&path, None, 10).await.unwrap().len(), 4);
+        
+        fs::remove_dir_all(&path).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_commit_info_with_parent() {
+        let path = init_repo_with_commits(2);
+        let service = GitService::new();
+        
+        let info = service.get_commit_info(&path, None).await.unwrap();
+        let repo = Repository::open(&path).unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        
+        assert_eq!(info.parent_sha, Some(head.parent_id(0).unwrap().to_string()));
+        
+        fs::remove_dir_all(&path).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_operation_metrics_shared_across_services() {
+        let path = init_repo_with_commits(1);
+        let metrics = Arc::new(OperationMetrics::new());
+        let first = GitService::new().with_operation_metrics(metrics.clone());
+        let second = GitService::new().with_operation_metrics(metrics.clone());
+        
+        assert!(GitOperations::get_latest_commit_sha(&first, &path).await.is_ok());
+        assert!(GitOperations::get_latest_commit_sha(&second, &path.join("missing")).await.is_err());
+        
+        let metric = &metrics.snapshot()[0];
+        assert_eq!(metric.operation, "get_latest_commit_sha");
+        assert_eq!((metric.successes, metric.failures, metric.retries), (1, 1, 0));
+        
+        fs::remove_dir_all(&path).ok();
+    }
 }
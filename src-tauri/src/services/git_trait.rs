@@ -5,16 +5,50 @@
 
 use async_trait::async_trait;
 use crate::services::GitServiceError;
-use crate::models::FrameworkType;
+use crate::models::{FrameworkType, GitCredentials};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 
 /// Git commit information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CommitInfo {
     pub sha: String,
     pub message: String,
     pub author: String,
     pub timestamp: i64,
+    /// First parent SHA; `None` for root commits or when the parent is
+    /// beyond a shallow clone's boundary
+    pub parent_sha: Option<String>,
+}
+
+/// Branches and tags advertised by a remote repository
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RemoteRefs {
+    pub branches: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl RemoteRefs {
+    /// Split full ref names (`refs/heads/main`, `refs/tags/v1.0`) into sorted branch and tag names
+    ///
+    /// Other refs (`HEAD`, `refs/pull/...`) are ignored, and peeled tag
+    /// entries (`refs/tags/v1.0^{}`) are folded into their tag.
+    pub fn from_ref_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut refs = Self::default();
+        for name in names {
+            if let Some(branch) = name.strip_prefix("refs/heads/") {
+                refs.branches.push(branch.to_string());
+            } else if let Some(tag) = name.strip_prefix("refs/tags/") {
+                refs.tags.push(tag.trim_end_matches("^{}").to_string());
+            }
+        }
+        
+        refs.branches.sort();
+        refs.branches.dedup();
+        refs.tags.sort();
+        refs.tags.dedup();
+        refs
+    }
 }
 
 /// Trait for Git operations
@@ -65,9 +99,93 @@ pub trait GitOperations: Send + Sync {
     /// Latest commit SHA string
     async fn get_latest_commit_sha(&self, repo_path: &Path) -> Result<String, GitServiceError>;
     
+    /// List commits on HEAD since a previously deployed commit
+    ///
+    /// # Arguments
+    /// * `repo_path` - Path to repository directory
+    /// * `since_sha` - Commit to stop at (excluded); `None` lists from HEAD
+    /// * `limit` - Most commits to return
+    ///
+    /// # Returns
+    /// Commits newest first
+    async fn commits_since(&self, repo_path: &Path, since_sha: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, GitServiceError>;
+    
+    /// Check out a commit in a cloned repository, detaching HEAD
+    ///
+    /// # Arguments
+    /// * `repo_path` - Path to repository directory
+    /// * `commit_sha` - Commit to check out; must be in the clone's history
+    async fn checkout_commit(&self, repo_path: &Path, commit_sha: &str) -> Result<(), GitServiceError>;
+    
     /// Clean up cloned repository directory
     ///
     /// # Arguments
     /// * `repo_path` - Path to repository directory to remove
     async fn cleanup_repository(&self, repo_path: &Path) -> Result<(), GitServiceError>;
+    
+    /// Resolve a branch to the commit SHA at its head without cloning (`git ls-remote`)
+    ///
+    /// # Arguments
+    /// * `repo_url` - Git repository URL
+    /// * `branch` - Branch name
+    /// * `credentials` - Username and token for private repositories
+    async fn resolve_remote_commit(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<String, GitServiceError>;
+    
+    /// Download a snapshot of the tree at a commit into a temporary directory
+    ///
+    /// The directory has no `.git` folder, and so no history; it is only
+    /// meant as a build context.
+    ///
+    /// # Arguments
+    /// * `repo_url` - Git repository URL
+    /// * `commit_sha` - Commit whose tree to download
+    /// * `credentials` - Username and token for private repositories
+    ///
+    /// # Returns
+    /// Path to the extracted tree
+    async fn fetch_archive(
+        &self,
+        repo_url: &str,
+        commit_sha: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<PathBuf, GitServiceError>;
+    
+    /// List a remote repository's branches and tags without cloning it
+    ///
+    /// # Arguments
+    /// * `repo_url` - Git repository URL
+    /// * `credentials` - Username and token for private repositories
+    async fn list_remote_refs(
+        &self,
+        repo_url: &str,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<RemoteRefs, GitServiceError>;
+    
+    /// List commits on a remote branch since a previously deployed commit, without cloning it
+    ///
+    /// Only the history needed for `limit` commits is fetched, and no
+    /// working tree is written.
+    ///
+    /// # Arguments
+    /// * `repo_url` - Git repository URL
+    /// * `branch` - Branch name
+    /// * `since_sha` - Commit to stop at (excluded); `None` lists from the branch head
+    /// * `limit` - Most commits to return
+    /// * `credentials` - Username and token for private repositories
+    ///
+    /// # Returns
+    /// Commits newest first
+    async fn list_remote_commits(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        since_sha: Option<&str>,
+        limit: usize,
+        credentials: Option<&GitCredentials>,
+    ) -> Result<Vec<CommitInfo>, GitServiceError>;
 }
@@ -0,0 +1,371 @@
+//! Concurrent image builds and pushes
+//!
+//! Builds or pushes several images at once (e.g. an app and its sidecars, or
+//! one image under several tags) with a cap on how many docker operations
+//! run in parallel.
+
+use crate::models::FrameworkType;
+use crate::services::{AwsOperations, AwsServiceError, BuildSpec};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// An image pushed to ECR
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushedImage {
+    pub local_tag: String,
+    pub ecr_uri: String,
+    pub digest: String,
+}
+
+/// Outcome of pushing an image under one ECR tag
+#[derive(Debug)]
+pub struct TagPush {
+    pub ecr_uri: String,
+    /// Digest of the pushed image, or why the push failed
+    pub result: Result<String, AwsServiceError>,
+}
+
+/// Build every spec, running at most `max_concurrent` builds at a time
+///
+/// Stops at the first failure; builds already running are aborted.
+pub async fn build_images(
+    aws: Arc<dyn AwsOperations>,
+    specs: Vec<BuildSpec>,
+    max_concurrent: usize,
+) -> Result<(), AwsServiceError> {
+    build_images_with_permits(aws, specs, Arc::new(Semaphore::new(max_concurrent.max(1)))).await
+}
+
+/// Build every spec, each holding one of `permits` while it runs
+async fn build_images_with_permits(
+    aws: Arc<dyn AwsOperations>,
+    specs: Vec<BuildSpec>,
+    permits: Arc<Semaphore>,
+) -> Result<(), AwsServiceError> {
+    let mut builds = JoinSet::new();
+    
+    for spec in specs {
+        let aws = aws.clone();
+        let permits = permits.clone();
+        builds.spawn(async move {
+            let _permit = permits.acquire_owned().await
+                .map_err(|e| AwsServiceError::DockerOperationFailed(e.to_string()))?;
+            aws.build_image(&spec).await
+        });
+    }
+    
+    while let Some(result) = builds.join_next().await {
+        result.map_err(|e| AwsServiceError::DockerOperationFailed(format!("Build task failed: {}", e)))??;
+    }
+    
+    Ok(())
+}
+
+/// Build the app and sidecar images while ensuring the ECR repository exists
+///
+/// The repository isn't needed until the push, so creating it concurrently
+/// keeps its latency off the critical path. All operations run to completion;
+/// if several fail, the repository error is reported first. `tags` are applied
+/// to the repository, whether it is created or already exists. Each line of
+/// the app image's build output is passed to `on_build_line` as it arrives.
+/// The app build shares the `max_concurrent` limit with the sidecar builds.
+///
+/// # Returns
+/// The ECR repository URI
+pub async fn build_with_repository(
+    aws: Arc<dyn AwsOperations>,
+    repository_name: &str,
+    tags: &[(String, String)],
+    source_dir: &str,
+    image_tag: &str,
+    framework: &FrameworkType,
+    sidecars: Vec<BuildSpec>,
+    max_concurrent: usize,
+    on_build_line: &(dyn Fn(&str) + Send + Sync),
+) -> Result<String, AwsServiceError> {
+    let permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let app_build = async {
+        let _permit = permits.acquire().await
+            .map_err(|e| AwsServiceError::DockerOperationFailed(e.to_string()))?;
+        aws.build_docker_image(source_dir, image_tag, framework, on_build_line).await
+    };
+    
+    let (repository, app, sidecars) = tokio::join!(
+        aws.ensure_ecr_repository(repository_name, tags),
+        app_build,
+        build_images_with_permits(aws.clone(), sidecars, permits.clone()),
+    );
+    
+    let repository_uri = repository?;
+    app?;
+    sidecars?;
+    
+    Ok(repository_uri)
+}
+
+/// Push each `(local_tag, ecr_uri)` pair, running at most `max_concurrent` pushes at a time
+///
+/// Results are returned in the same order as `images`.
+pub async fn push_images(
+    aws: Arc<dyn AwsOperations>,
+    images: Vec<(String, String)>,
+    max_concurrent: usize,
+) -> Result<Vec<PushedImage>, AwsServiceError> {
+    let permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let mut pushes = JoinSet::new();
+    
+    for (index, (local_tag, ecr_uri)) in images.into_iter().enumerate() {
+        let aws = aws.clone();
+        let permits = permits.clone();
+        pushes.spawn(async move {
+            let _permit = permits.acquire_owned().await
+                .map_err(|e| AwsServiceError::DockerOperationFailed(e.to_string()))?;
+            let digest = aws.push_docker_image(&local_tag, &ecr_uri).await?;
+            Ok::<_, AwsServiceError>((index, PushedImage { local_tag, ecr_uri, digest }))
+        });
+    }
+    
+    let mut pushed = Vec::new();
+    while let Some(result) = pushes.join_next().await {
+        pushed.push(result.map_err(|e| AwsServiceError::DockerOperationFailed(format!("Push task failed: {}", e)))??);
+    }
+    
+    pushed.sort_by_key(|(index, _)| *index);
+    Ok(pushed.into_iter().map(|(_, image)| image).collect())
+}
+
+/// Push one local image under several ECR tags, running at most `max_concurrent` pushes at a time
+///
+/// The tags share the image's layers, so only the first push uploads them.
+/// Every push runs to completion whatever happens to the others, and each
+/// tag's digest or error is returned in the same order as `ecr_uris`.
+pub async fn push_image_tags(
+    aws: Arc<dyn AwsOperations>,
+    local_tag: &str,
+    ecr_uris: Vec<String>,
+    max_concurrent: usize,
+) -> Vec<TagPush> {
+    let permits = Arc::new(Semaphore::new(max_concurrent.max(1)));
+    let mut pushes = Vec::new();
+    
+    for ecr_uri in ecr_uris {
+        let aws = aws.clone();
+        let permits = permits.clone();
+        let local_tag = local_tag.to_string();
+        let uri = ecr_uri.clone();
+        let push = tokio::spawn(async move {
+            let _permit = permits.acquire_owned().await
+                .map_err(|e| AwsServiceError::DockerOperationFailed(e.to_string()))?;
+            aws.push_docker_image(&local_tag, &uri).await
+        });
+        pushes.push((ecr_uri, push));
+    }
+    
+    let mut results = Vec::new();
+    for (ecr_uri, push) in pushes {
+        let result = push.await
+            .unwrap_or_else(|e| Err(AwsServiceError::DockerOperationFailed(format!("Push task failed: {}", e))));
+        results.push(TagPush { ecr_uri, result });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shadow::{MockAwsService, ShadowConfig, ShadowState};
+    
+    fn create_mock(state: Arc<ShadowState>) -> Arc<dyn AwsOperations> {
+        let config = ShadowConfig {
+            enabled: true,
+            failure_rate: 0.0,
+            simulate_delays: false,
+        };
+        Arc::new(MockAwsService::new(None, config, state))
+    }
+    
+    #[tokio::test]
+    async fn test_two_specs_built_and_pushed() {
+        let state = Arc::new(ShadowState::new());
+        let aws = create_mock(state.clone());
+        
+        let specs = vec![
+            BuildSpec {
+                dockerfile_path: None,
+                image_tag: "web:abc12345".to_string(),
+                context: "/repo".to_string(),
+            },
+            BuildSpec {
+                dockerfile_path: Some("/repo/proxy/Dockerfile".to_string()),
+                image_tag: "web-proxy:abc12345".to_string(),
+                context: "/repo/proxy".to_string(),
+            },
+        ];
+        build_images(aws.clone(), specs, 2).await.unwrap();
+        assert!(state.has_docker_image("web:abc12345"));
+        assert!(state.has_docker_image("web-proxy:abc12345"));
+        
+        let pushed = push_images(
+            aws,
+            vec![
+                ("web:abc12345".to_string(), "repo.example/web:abc12345".to_string()),
+                ("web-proxy:abc12345".to_string(), "repo.example/web:proxy-abc12345".to_string()),
+            ],
+            2,
+        ).await.unwrap();
+        
+        assert_eq!(pushed.len(), 2);
+        assert_eq!(pushed[0].ecr_uri, "repo.example/web:abc12345");
+        assert_eq!(pushed[1].ecr_uri, "repo.example/web:proxy-abc12345");
+        assert_ne!(pushed[0].digest, pushed[1].digest);
+        assert!(state.has_docker_image("repo.example/web:abc12345"));
+        assert!(state.has_docker_image("repo.example/web:proxy-abc12345"));
+    }
+    
+    #[tokio::test]
+    async fn test_push_image_tags_reports_each_tag() {
+        let state = Arc::new(ShadowState::new());
+        state.add_docker_image("web:abc12345".to_string());
+        let aws = create_mock(state.clone());
+        let tags = vec![
+            "repo.example/web:abc12345".to_string(),
+            "repo.example/web:production".to_string(),
+            "repo.example/web:v1.4.0".to_string(),
+        ];
+        
+        let pushed = push_image_tags(aws.clone(), "web:abc12345", tags.clone(), 3).await;
+        let uris: Vec<&str> = pushed.iter().map(|push| push.ecr_uri.as_str()).collect();
+        assert_eq!(uris, tags.iter().map(String::as_str).collect::<Vec<_>>());
+        assert!(pushed.iter().all(|push| matches!(&push.result, Ok(digest) if digest.starts_with("sha256:"))));
+        assert!(tags.iter().all(|tag| state.has_docker_image(tag)));
+        
+        let state = Arc::new(ShadowState::new());
+        state.add_docker_image("web:abc12345".to_string());
+        state.fail_push_to("repo.example/web:production");
+        let aws = create_mock(state.clone());
+        
+        let pushed = push_image_tags(aws, "web:abc12345", tags, 2).await;
+        assert!(pushed[0].result.is_ok());
+        assert!(pushed[2].result.is_ok());
+        let error = pushed[1].result.as_ref().unwrap_err().to_string();
+        assert!(error.contains("Simulated push failure: repo.example/web:production"), "{}", error);
+        // The failing tag didn't stop the others
+        assert!(state.has_docker_image("repo.example/web:abc12345"));
+        assert!(state.has_docker_image("repo.example/web:v1.4.0"));
+        assert!(!state.has_docker_image("repo.example/web:production"));
+    }
+    
+    #[tokio::test]
+    async fn test_build_with_repository_completes_both() {
+        let state = Arc::new(ShadowState::new());
+        let aws = create_mock(state.clone());
+        let source_dir = std::env::temp_dir().join(format!("image_build_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&source_dir).unwrap();
+        
+        let uri = build_with_repository(
+            aws,
+            "web",
+            &[],
+            source_dir.to_str().unwrap(),
+            "web:abc12345",
+            &FrameworkType::Node,
+            Vec::new(),
+            2,
+            &|_| {},
+        ).await.unwrap();
+        
+        assert!(uri.ends_with("/web"));
+        assert_eq!(state.get_ecr_repository("web"), Some(uri));
+        assert!(state.has_docker_image("web:abc12345"));
+        
+        std::fs::remove_dir_all(&source_dir).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_build_with_repository_limits_app_and_sidecar_builds() {
+        let state = Arc::new(ShadowState::new());
+        state.set_build_duration(std::time::Duration::from_millis(20));
+        let aws = create_mock(state.clone());
+        let source_dir = std::env::temp_dir().join(format!("image_build_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&source_dir).unwrap();
+        let sidecars = vec![
+            BuildSpec {
+                dockerfile_path: Some("proxy/Dockerfile".to_string()),
+                image_tag: "web-proxy:abc12345".to_string(),
+                context: source_dir.to_str().unwrap().to_string(),
+            },
+            BuildSpec {
+                dockerfile_path: Some("worker/Dockerfile".to_string()),
+                image_tag: "web-worker:abc12345".to_string(),
+                context: source_dir.to_str().unwrap().to_string(),
+            },
+        ];
+        
+        build_with_repository(
+            aws,
+            "web",
+            &[],
+            source_dir.to_str().unwrap(),
+            "web:abc12345",
+            &FrameworkType::Node,
+            sidecars,
+            1,
+            &|_| {},
+        ).await.unwrap();
+        
+        assert_eq!(state.max_concurrent_builds(), 1);
+        assert!(state.has_docker_image("web:abc12345"));
+        assert!(state.has_docker_image("web-worker:abc12345"));
+        
+        std::fs::remove_dir_all(&source_dir).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_build_with_repository_fails_if_either_fails() {
+        let source_dir = std::env::temp_dir().join(format!("image_build_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&source_dir).unwrap();
+        
+        for failing in ["ensure_ecr_repository", "build_docker_image"] {
+            let state = Arc::new(ShadowState::new());
+            state.fail_operation(failing);
+            let aws = create_mock(state.clone());
+            
+            let result = build_with_repository(
+                aws,
+                "web",
+                &[],
+                source_dir.to_str().unwrap(),
+                "web:abc12345",
+                &FrameworkType::Node,
+                Vec::new(),
+                2,
+                &|_| {},
+            ).await;
+            
+            assert!(result.is_err(), "{} failure should fail the build stage", failing);
+            // The other operation still ran to completion
+            if failing == "ensure_ecr_repository" {
+                assert!(state.has_docker_image("web:abc12345"));
+            } else {
+                assert!(state.get_ecr_repository("web").is_some());
+            }
+        }
+        
+        std::fs::remove_dir_all(&source_dir).ok();
+    }
+    
+    #[tokio::test]
+    async fn test_push_fails_for_unbuilt_image() {
+        let state = Arc::new(ShadowState::new());
+        let aws = create_mock(state);
+        
+        let result = push_images(
+            aws,
+            vec![("missing:v1".to_string(), "repo.example/missing:v1".to_string())],
+            1,
+        ).await;
+        
+        assert!(matches!(result, Err(AwsServiceError::DockerOperationFailed(_))));
+    }
+}
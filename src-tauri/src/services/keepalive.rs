@@ -0,0 +1,71 @@
+//! AWS connection keepalive
+//!
+//! Periodically issues a cheap AWS call so cached SDK clients keep their
+//! DNS/TLS connections warm between deployments.
+
+use crate::services::{AwsOperations, BackgroundTaskHandle};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Spawn a background task that pings AWS every `interval`
+///
+/// The first ping is sent immediately. Ping failures are ignored; the next
+/// tick simply tries again.
+pub fn spawn_keepalive(aws: Arc<dyn AwsOperations>, interval: Duration) -> BackgroundTaskHandle {
+    BackgroundTaskHandle::spawn(|mut shutdown_rx| async move {
+        let mut ticker = tokio::time::interval(interval);
+        
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    let _ = aws.ping().await;
+                }
+                _ = &mut shutdown_rx => break,
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shadow::test_utils::mock_aws;
+    use crate::shadow::{MockAwsService, ShadowConfig, ShadowState};
+    
+    #[tokio::test]
+    async fn test_keepalive_pings_until_stopped() {
+        let config = ShadowConfig {
+            enabled: true,
+            failure_rate: 0.0,
+            simulate_delays: false,
+        };
+        let state = Arc::new(ShadowState::new());
+        let aws = Arc::new(MockAwsService::new(None, config, state.clone()));
+        
+        let handle = spawn_keepalive(aws, Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        handle.stop().await;
+        
+        let pings = state.keepalive_pings();
+        assert!(pings >= 1);
+        
+        // No further pings once stopped
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(state.keepalive_pings(), pings);
+    }
+    
+    #[tokio::test]
+    async fn test_dropping_handle_stops_keepalive() {
+        let state = Arc::new(ShadowState::new());
+        let aws = Arc::new(mock_aws(state.clone()));
+        
+        let handle = spawn_keepalive(aws, Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        drop(handle);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        
+        let pings = state.keepalive_pings();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(state.keepalive_pings(), pings);
+    }
+}
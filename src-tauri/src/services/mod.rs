@@ -5,11 +5,27 @@
 //! - AwsService: AWS ECS/ECR deployments, CloudWatch logs, health monitoring
 //! - TerraformService: Infrastructure-as-Code generation for ECS deployments
 //! - ClaudeService: AI-powered deployment assistance and troubleshooting
+//! - CommandRunner: Injectable execution of external programs (docker)
+//! - Keepalive: Background pings that keep AWS SDK connections warm
+//! - Background: The handle shared by tasks that run until stopped
+//! - ImageBuild: Bounded concurrent builds and pushes of multiple images
+//! - Redactor: Masking secrets in text that leaves the machine
+//! - BuildPrerequisites: Checking a repo has what the generated Dockerfile expects
+//! - DeploymentSpec: The one place a project's port and task size are resolved
+//! - OperationMetrics: Success, failure and retry counts per AWS and Git operation
 
 pub mod git_service;
 pub mod aws_service;
 pub mod terraform_service;
 pub mod claude_service;
+pub mod command_runner;
+pub mod keepalive;
+pub mod background;
+pub mod image_build;
+pub mod redaction;
+pub mod build_prerequisites;
+pub mod deployment_spec;
+pub mod operation_metrics;
 
 // Trait definitions for testability
 pub mod aws_trait;
@@ -17,9 +33,20 @@ pub mod git_trait;
 pub mod factory;
 
 pub use git_service::{GitService, GitServiceError};
-pub use git_trait::{GitOperations, CommitInfo};
-pub use aws_service::{AwsService, AwsServiceError, EcsDeploymentConfig, ServiceHealth};
+pub use git_trait::{GitOperations, CommitInfo, RemoteRefs};
+pub use aws_service::{
+    build_docker_image, build_ecr_uri, check_ecr_region, inspect_image, parse_image_info, resolve_ecr_repository, resolve_ecr_uri, AwsClientConfig, AwsService, AwsServiceError, BuildSpec, EcrUri, EcsDeploymentConfig,
+    PlacementConstraint, PlacementStrategy, ServiceDefinition, ServiceHealth, ServiceUtilization, SidecarContainer, StoppedTask, EPHEMERAL_STORAGE_GIB_RANGE, MAX_DESCRIBE_SERVICES,
+};
 pub use aws_trait::AwsOperations;
-pub use terraform_service::{TerraformService, TerraformServiceError, TerraformConfig};
-pub use claude_service::{ClaudeService, ClaudeServiceError, DeploymentContext, ClaudeResponse};
+pub use terraform_service::{TerraformService, TerraformServiceError, TerraformConfig, ApplyResult};
+pub use command_runner::{CommandRunner, CommandOutput, TokioCommandRunner};
+pub use keepalive::spawn_keepalive;
+pub use background::BackgroundTaskHandle;
+pub use image_build::{build_images, build_with_repository, push_image_tags, push_images, PushedImage, TagPush};
+pub use redaction::Redactor;
+pub use build_prerequisites::check_build_prerequisites;
+pub use deployment_spec::{resolve_deployment_spec, ResolvedSpec};
+pub use operation_metrics::OperationMetrics;
+pub use claude_service::{ClaudeService, ClaudeServiceError, DeploymentContext, ClaudeResponse, Usage};
 pub use factory::{create_aws_operations, create_git_operations};
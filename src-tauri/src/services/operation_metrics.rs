@@ -0,0 +1,83 @@
+//! Success, failure and retry counts per AWS and Git operation
+//!
+//! Operations are keyed by their `AwsOperations`/`GitOperations` method name.
+//! Counters are atomics behind a read-mostly map, so recording an outcome only
+//! takes a write lock (and allocates) the first time an operation is seen.
+
+use crate::models::OperationMetric;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{PoisonError, RwLock};
+
+#[derive(Debug, Default)]
+struct Counters {
+    successes: AtomicU64,
+    failures: AtomicU64,
+    retries: AtomicU64,
+    /// Whether the most recent call failed
+    last_failed: AtomicBool,
+}
+
+/// Outcome counters for every operation called so far
+#[derive(Debug, Default)]
+pub struct OperationMetrics {
+    counters: RwLock<HashMap<&'static str, Counters>>,
+}
+
+impl OperationMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Count one call of `operation`
+    ///
+    /// A call made after the previous call of the same operation failed is
+    /// also counted as a retry.
+    pub fn record(&self, operation: &'static str, succeeded: bool) {
+        let count = |counters: &Counters| {
+            if counters.last_failed.swap(!succeeded, Ordering::Relaxed) {
+                counters.retries.fetch_add(1, Ordering::Relaxed);
+            }
+            let outcome = if succeeded { &counters.successes } else { &counters.failures };
+            outcome.fetch_add(1, Ordering::Relaxed);
+        };
+        
+        {
+            let known = self.counters.read().unwrap_or_else(PoisonError::into_inner);
+            if let Some(counters) = known.get(operation) {
+                count(counters);
+                return;
+            }
+        }
+        
+        let mut known = self.counters.write().unwrap_or_else(PoisonError::into_inner);
+        count(known.entry(operation).or_default());
+    }
+    
+    /// Await an operation and count its outcome
+    pub async fn track<T, E>(
+        &self,
+        operation: &'static str,
+        call: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let result = call.await;
+        self.record(operation, result.is_ok());
+        result
+    }
+    
+    /// Current counts, sorted by operation name
+    pub fn snapshot(&self) -> Vec<OperationMetric> {
+        let known = self.counters.read().unwrap_or_else(PoisonError::into_inner);
+        let mut metrics: Vec<OperationMetric> = known.iter()
+            .map(|(operation, counters)| OperationMetric {
+                operation: operation.to_string(),
+                successes: counters.successes.load(Ordering::Relaxed),
+                failures: counters.failures.load(Ordering::Relaxed),
+                retries: counters.retries.load(Ordering::Relaxed),
+            })
+            .collect();
+        metrics.sort_by(|a, b| a.operation.cmp(&b.operation));
+        metrics
+    }
+}
@@ -4,13 +4,27 @@
 //! - Generating main.tf, variables.tf, outputs.tf
 //! - Creating ECS cluster, service, and task definition templates
 //! - Framework-specific port mappings
+//! - Path-based ALB routing, on a new or an existing shared load balancer
 //! - Writing configurations to output directory
+//! - Applying configurations and parsing the structured apply output
 
-use crate::models::FrameworkType;
+use crate::models::{FrameworkType, LaunchType, ListenerRule, PortProtocol, Project};
+use crate::services::aws_service::{EcrUri, PlacementStrategy, EPHEMERAL_STORAGE_GIB_RANGE};
+use crate::services::command_runner::{CommandOutput, CommandRunner, TokioCommandRunner};
+use crate::services::deployment_spec::resolve_deployment_spec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 use std::fs;
+use std::sync::Arc;
 use thiserror::Error;
 
+/// Longest name AWS accepts for a load balancer or target group
+const LB_NAME_MAX_LEN: usize = 32;
+
+/// Target group health check path unless the config sets another
+pub const DEFAULT_HEALTH_CHECK_PATH: &str = "/health";
+
 /// Terraform service specific errors
 #[derive(Error, Debug)]
 pub enum TerraformServiceError {
@@ -22,6 +36,15 @@ pub enum TerraformServiceError {
     
     #[error("Invalid configuration: {0}")]
     InvalidConfiguration(String),
+    
+    #[error("Terraform binary not found: {0}")]
+    BinaryNotFound(String),
+    
+    #[error("Failed to run terraform: {0}")]
+    CommandFailed(String),
+    
+    #[error("Terraform apply failed: {0}")]
+    ApplyFailed(String),
 }
 
 impl From<std::io::Error> for TerraformServiceError {
@@ -31,7 +54,59 @@ impl From<std::io::Error> for TerraformServiceError {
 }
 
 /// Terraform service for IaC generation
-pub struct TerraformService;
+pub struct TerraformService {
+    runner: Arc<dyn CommandRunner>,
+}
+
+/// Resources and outputs reported by `terraform apply`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ApplyResult {
+    /// Addresses of resources created
+    pub created: Vec<String>,
+    /// Addresses of resources updated in place
+    pub updated: Vec<String>,
+    /// Addresses of resources destroyed
+    pub destroyed: Vec<String>,
+    /// Root module outputs (sensitive values are null)
+    pub outputs: HashMap<String, serde_json::Value>,
+}
+
+/// A single line of Terraform's machine-readable (`-json`) UI output
+#[derive(Debug, Deserialize)]
+struct TerraformMessage {
+    #[serde(rename = "@message", default)]
+    message: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    hook: Option<ApplyHook>,
+    outputs: Option<HashMap<String, OutputValue>>,
+    diagnostic: Option<Diagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyHook {
+    resource: HookResource,
+    action: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HookResource {
+    addr: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OutputValue {
+    #[serde(default)]
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct Diagnostic {
+    severity: String,
+    summary: String,
+    #[serde(default)]
+    detail: String,
+}
 
 /// Terraform configuration parameters
 #[derive(Debug, Clone)]
@@ -43,43 +118,294 @@ pub struct TerraformConfig {
     pub subnet_ids: Vec<String>,
     pub ecr_repository_name: String,
     pub container_port: i32,
+    /// Transport protocol of `container_port`
+    pub port_protocol: PortProtocol,
     pub cpu: String,
     pub memory: String,
     pub desired_count: i32,
     pub framework: FrameworkType,
+    /// Path-based routing rules; when empty no load balancer resources are generated
+    pub listener_rules: Vec<ListenerRule>,
+    /// ARN of an existing ALB to attach the rules to instead of creating one
+    pub shared_alb_arn: Option<String>,
+    /// Path the rules' target group health check requests
+    pub health_check_path: String,
+    /// Task ephemeral storage in GiB (21-200); Fargate's 20 GiB default when unset
+    pub ephemeral_storage_gib: Option<i32>,
+    /// Infrastructure the service's tasks run on
+    pub launch_type: LaunchType,
+    /// Placement strategies for the service (EC2 only)
+    pub placement_strategies: Vec<PlacementStrategy>,
+    /// Tags applied to every resource through the provider's `default_tags`
+    pub default_tags: Vec<(String, String)>,
+    /// Image tag, or `sha256:` digest, the task definition runs
+    pub image_tag: String,
+}
+
+impl TerraformConfig {
+    /// Configuration for a project, sized, ported and networked exactly as its deployments are
+    ///
+    /// The image is `latest` until the caller sets the tag or digest it deployed.
+    pub fn for_project(project: &Project, region: &str) -> Self {
+        let spec = resolve_deployment_spec(project);
+        let ecr_repository_name = EcrUri::parse(&project.ecr_repository)
+            .map(|uri| uri.repository)
+            .unwrap_or_else(|| project.ecr_repository.clone());
+        
+        Self {
+            project_name: project.name.clone(),
+            environment: project.environment.as_str().to_string(),
+            region: region.to_string(),
+            vpc_id: project.network.vpc_id.clone(),
+            subnet_ids: project.network.subnet_ids.clone(),
+            ecr_repository_name,
+            container_port: spec.port,
+            port_protocol: spec.port_protocol,
+            cpu: spec.cpu.to_string(),
+            memory: spec.memory.to_string(),
+            desired_count: spec.desired_count,
+            framework: project.framework.clone(),
+            listener_rules: project.network.listener_rules.clone(),
+            shared_alb_arn: project.network.shared_alb_arn.clone(),
+            health_check_path: project.network.health_check_path.clone()
+                .unwrap_or_else(|| DEFAULT_HEALTH_CHECK_PATH.to_string()),
+            ephemeral_storage_gib: spec.ephemeral_storage_gib,
+            launch_type: spec.launch_type,
+            placement_strategies: spec.placement_strategies,
+            default_tags: project.default_tags.clone(),
+            image_tag: "latest".to_string(),
+        }
+    }
 }
 
 impl TerraformService {
     /// Create a new TerraformService instance
     pub fn new() -> Self {
-        TerraformService
+        Self {
+            runner: Arc::new(TokioCommandRunner),
+        }
+    }
+    
+    /// Replace the runner used for `terraform` shell-outs
+    pub fn with_command_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+    
+    /// Initialize and apply the configuration in `output_dir`
+    ///
+    /// Runs `terraform apply -auto-approve -json`, passing each human-readable
+    /// message to `on_progress` as it streams in, and returns the resources
+    /// changed and outputs produced.
+    pub async fn apply_terraform(
+        &self,
+        output_dir: &Path,
+        on_progress: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<ApplyResult, TerraformServiceError> {
+        let chdir = format!("-chdir={}", output_dir.display());
+        
+        let init = self.run_terraform(&[&chdir, "init", "-input=false", "-no-color"], &|_| {}).await?;
+        if !init.success {
+            return Err(TerraformServiceError::ApplyFailed(format!("terraform init failed: {}", init.stderr.trim())));
+        }
+        
+        let apply = self.run_terraform(
+            &[&chdir, "apply", "-auto-approve", "-input=false", "-json"],
+            &|line| {
+                if let Ok(message) = serde_json::from_str::<TerraformMessage>(line) {
+                    on_progress(&message.message);
+                }
+            },
+        ).await?;
+        
+        if !apply.success {
+            let errors = Self::parse_apply_errors(&apply.stdout);
+            let reason = if errors.is_empty() { apply.stderr.trim().to_string() } else { errors.join("; ") };
+            return Err(TerraformServiceError::ApplyFailed(reason));
+        }
+        
+        Ok(Self::parse_apply_output(&apply.stdout))
+    }
+    
+    /// Run the terraform binary, mapping a missing binary to `BinaryNotFound`
+    async fn run_terraform(
+        &self,
+        args: &[&str],
+        on_line: &(dyn Fn(&str) + Send + Sync),
+    ) -> Result<CommandOutput, TerraformServiceError> {
+        self.runner
+            .run_streaming("terraform", args, on_line)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => TerraformServiceError::BinaryNotFound(
+                    "install Terraform and ensure `terraform` is on PATH".to_string()
+                ),
+                _ => TerraformServiceError::CommandFailed(e.to_string()),
+            })
+    }
+    
+    /// Parse streamed `terraform apply -json` output into an `ApplyResult`
+    ///
+    /// Lines that aren't JSON messages are ignored.
+    pub fn parse_apply_output(output: &str) -> ApplyResult {
+        let mut result = ApplyResult::default();
+        
+        for message in output.lines().filter_map(|line| serde_json::from_str::<TerraformMessage>(line).ok()) {
+            match message.kind.as_str() {
+                "apply_complete" => {
+                    if let Some(hook) = message.hook {
+                        match hook.action.as_str() {
+                            "create" => result.created.push(hook.resource.addr),
+                            "update" => result.updated.push(hook.resource.addr),
+                            "delete" => result.destroyed.push(hook.resource.addr),
+                            _ => {}
+                        }
+                    }
+                }
+                "outputs" => {
+                    if let Some(outputs) = message.outputs {
+                        result.outputs = outputs.into_iter()
+                            .map(|(name, output)| (name, output.value))
+                            .collect();
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        result
+    }
+    
+    /// Collect error diagnostics from `terraform -json` output
+    fn parse_apply_errors(output: &str) -> Vec<String> {
+        output.lines()
+            .filter_map(|line| serde_json::from_str::<TerraformMessage>(line).ok())
+            .filter_map(|message| message.diagnostic)
+            .filter(|diagnostic| diagnostic.severity == "error")
+            .map(|diagnostic| {
+                if diagnostic.detail.is_empty() {
+                    diagnostic.summary
+                } else {
+                    format!("{}: {}", diagnostic.summary, diagnostic.detail)
+                }
+            })
+            .collect()
     }
     
     /// Generate all Terraform configuration files
     pub async fn generate_terraform(&self, config: &TerraformConfig, output_dir: &Path) -> Result<(), TerraformServiceError> {
+        let files = self.render_terraform(config)?;
+        
         // Create output directory if it doesn't exist
         fs::create_dir_all(output_dir)
             .map_err(|e| TerraformServiceError::DirectoryCreationFailed(e.to_string()))?;
         
-        // Generate main.tf
-        let main_tf = self.generate_main_tf(config);
-        fs::write(output_dir.join("main.tf"), main_tf)?;
+        for (name, contents) in files {
+            fs::write(output_dir.join(name), contents)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Render all Terraform configuration files in memory, keyed by file name
+    pub fn render_terraform(&self, config: &TerraformConfig) -> Result<BTreeMap<String, String>, TerraformServiceError> {
+        Self::validate_listener_rules(&config.listener_rules, config.port_protocol)?;
+        Self::validate_health_check_path(&config.health_check_path)?;
+        Self::validate_shared_alb_arn(config.shared_alb_arn.as_deref())?;
+        Self::validate_ephemeral_storage(config.ephemeral_storage_gib)?;
+        
+        let mut files = BTreeMap::new();
+        files.insert("main.tf".to_string(), self.generate_main_tf(config));
+        files.insert("variables.tf".to_string(), self.generate_variables_tf(config));
+        files.insert("outputs.tf".to_string(), self.generate_outputs_tf());
+        // terraform.tfvars holds the default values
+        files.insert("terraform.tfvars".to_string(), self.generate_tfvars(config));
+        
+        Ok(files)
+    }
+    
+    /// Check listener rule priorities are in ALB's accepted range and unique
+    ///
+    /// Listener rules put the service behind an ALB, which only forwards HTTP,
+    /// so they can't be combined with a UDP port.
+    fn validate_listener_rules(rules: &[ListenerRule], protocol: PortProtocol) -> Result<(), TerraformServiceError> {
+        if protocol == PortProtocol::Udp && !rules.is_empty() {
+            return Err(TerraformServiceError::InvalidConfiguration(
+                "listener rules need an HTTP port; an ALB can't forward UDP".to_string()
+            ));
+        }
         
-        // Generate variables.tf
-        let variables_tf = self.generate_variables_tf(config);
-        fs::write(output_dir.join("variables.tf"), variables_tf)?;
+        let mut seen = std::collections::HashSet::new();
         
-        // Generate outputs.tf
-        let outputs_tf = self.generate_outputs_tf();
-        fs::write(output_dir.join("outputs.tf"), outputs_tf)?;
+        for rule in rules {
+            if !(1..=50000).contains(&rule.priority) {
+                return Err(TerraformServiceError::InvalidConfiguration(format!(
+                    "listener rule priority {} must be between 1 and 50000", rule.priority
+                )));
+            }
+            if !seen.insert(rule.priority) {
+                return Err(TerraformServiceError::InvalidConfiguration(format!(
+                    "listener rule priority {} is used more than once", rule.priority
+                )));
+            }
+        }
         
-        // Generate terraform.tfvars with default values
-        let tfvars = self.generate_tfvars(config);
-        fs::write(output_dir.join("terraform.tfvars"), tfvars)?;
+        Ok(())
+    }
+    
+    /// Check a target group health check path is absolute
+    fn validate_health_check_path(path: &str) -> Result<(), TerraformServiceError> {
+        if !path.starts_with('/') {
+            return Err(TerraformServiceError::InvalidConfiguration(format!(
+                "health check path '{}' must start with /", path
+            )));
+        }
         
         Ok(())
     }
     
+    /// Check a shared load balancer ARN names an Application Load Balancer
+    ///
+    /// The expected form is `arn:<partition>:elasticloadbalancing:<region>:<account>:loadbalancer/app/<name>/<id>`.
+    pub fn validate_shared_alb_arn(arn: Option<&str>) -> Result<(), TerraformServiceError> {
+        let Some(arn) = arn else {
+            return Ok(());
+        };
+        
+        let parts: Vec<&str> = arn.splitn(6, ':').collect();
+        let valid = parts.len() == 6
+            && parts[0] == "arn"
+            && parts[1].starts_with("aws")
+            && parts[2] == "elasticloadbalancing"
+            && !parts[3].is_empty()
+            && parts[4].len() == 12 && parts[4].chars().all(|c| c.is_ascii_digit())
+            && parts[5].strip_prefix("loadbalancer/app/")
+                .and_then(|rest| rest.split_once('/'))
+                .is_some_and(|(name, id)| !name.is_empty() && !id.is_empty())
+            && arn.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ':' | '/' | '-'));
+        
+        if !valid {
+            return Err(TerraformServiceError::InvalidConfiguration(format!(
+                "'{}' is not an Application Load Balancer ARN", arn
+            )));
+        }
+        
+        Ok(())
+    }
+    
+    /// Check an ephemeral storage override is within Fargate's accepted range
+    fn validate_ephemeral_storage(size_in_gib: Option<i32>) -> Result<(), TerraformServiceError> {
+        match size_in_gib {
+            Some(size) if !EPHEMERAL_STORAGE_GIB_RANGE.contains(&size) => {
+                Err(TerraformServiceError::InvalidConfiguration(format!(
+                    "ephemeral storage must be between {} and {} GiB, got {}",
+                    EPHEMERAL_STORAGE_GIB_RANGE.start(), EPHEMERAL_STORAGE_GIB_RANGE.end(), size
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+    
     /// Generate main.tf with ECS resources
     fn generate_main_tf(&self, config: &TerraformConfig) -> String {
         format!(r#"terraform {{
@@ -95,7 +421,7 @@ impl TerraformService {
 
 provider "aws" {{
   region = var.aws_region
-}}
+{default_tags}}}
 
 # ECS Cluster
 resource "aws_ecs_cluster" "{project_name}_cluster" {{
@@ -184,7 +510,7 @@ resource "aws_security_group" "{project_name}_sg" {{
   ingress {{
     from_port   = {port}
     to_port     = {port}
-    protocol    = "tcp"
+    protocol    = "{protocol}"
     cidr_blocks = ["0.0.0.0/0"]
     description = "Allow inbound traffic on application port"
   }}
@@ -207,23 +533,23 @@ resource "aws_security_group" "{project_name}_sg" {{
 resource "aws_ecs_task_definition" "{project_name}_task" {{
   family                   = "${{var.project_name}}-${{var.environment}}"
   network_mode             = "awsvpc"
-  requires_compatibilities = ["FARGATE"]
+  requires_compatibilities = ["{launch_type}"]
   cpu                      = var.task_cpu
   memory                   = var.task_memory
   execution_role_arn       = aws_iam_role.{project_name}_execution_role.arn
   task_role_arn            = aws_iam_role.{project_name}_task_role.arn
-  
+{ephemeral_storage}  
   container_definitions = jsonencode([
     {{
       name      = "${{var.project_name}}-container"
-      image     = "${{var.ecr_repository_url}}:${{var.image_tag}}"
+      image     = "${{var.ecr_repository_url}}{image_separator}${{var.image_tag}}"
       essential = true
       
       portMappings = [
         {{
           containerPort = {port}
           hostPort      = {port}
-          protocol      = "tcp"
+          protocol      = "{protocol}"
         }}
       ]
       
@@ -246,15 +572,7 @@ resource "aws_ecs_task_definition" "{project_name}_task" {{
           "awslogs-stream-prefix" = "ecs"
         }}
       }}
-      
-      healthCheck = {{
-        command     = ["CMD-SHELL", "curl -f http://localhost:{port}/health || exit 1"]
-        interval    = 30
-        timeout     = 5
-        retries     = 3
-        startPeriod = 60
-      }}
-    }}
+{health_check}    }}
   ])
   
   tags = {{
@@ -269,30 +587,283 @@ resource "aws_ecs_service" "{project_name}_service" {{
   cluster         = aws_ecs_cluster.{project_name}_cluster.id
   task_definition = aws_ecs_task_definition.{project_name}_task.arn
   desired_count   = var.desired_count
-  launch_type     = "FARGATE"
+  launch_type     = "{launch_type}"
   
   network_configuration {{
     subnets          = var.subnet_ids
     security_groups  = [aws_security_group.{project_name}_sg.id]
-    assign_public_ip = true
+    assign_public_ip = {assign_public_ip}
   }}
-  
+{placement}  
   deployment_configuration {{
     maximum_percent         = 200
     minimum_healthy_percent = 100
   }}
-  
+{service_load_balancer}  
   tags = {{
     Name        = "${{var.project_name}}-${{var.environment}}-service"
     Environment = var.environment
   }}
 }}
+{load_balancer}"#,
+            project_name = self.sanitize_name(&config.project_name),
+            default_tags = self.generate_default_tags(config),
+            port = config.container_port,
+            protocol = config.port_protocol.as_str(),
+            image_separator = if config.image_tag.starts_with("sha256:") { "@" } else { ":" },
+            launch_type = config.launch_type.as_str(),
+            // awsvpc tasks on EC2 can't be given public IPs
+            assign_public_ip = config.launch_type == LaunchType::Fargate,
+            ephemeral_storage = self.generate_ephemeral_storage(config),
+            health_check = self.generate_container_health_check(config),
+            placement = self.generate_placement(config),
+            service_load_balancer = self.generate_service_load_balancer(config),
+            load_balancer = self.generate_load_balancer_tf(config),
+        )
+    }
+    
+    /// Generate the provider's `default_tags` block, if any tags are configured
+    fn generate_default_tags(&self, config: &TerraformConfig) -> String {
+        if config.default_tags.is_empty() {
+            return String::new();
+        }
+        
+        let tags: String = config.default_tags.iter()
+            .map(|(key, value)| format!("      \"{}\" = \"{}\"\n", hcl_escape(key), hcl_escape(value)))
+            .collect();
+        format!("  \n  default_tags {{\n    tags = {{\n{}    }}\n  }}\n", tags)
+    }
+    
+    /// Generate the task definition's `ephemeral_storage` block, if overridden
+    fn generate_ephemeral_storage(&self, config: &TerraformConfig) -> String {
+        match config.ephemeral_storage_gib {
+            Some(size_in_gib) => format!(r#"  
+  ephemeral_storage {{
+    size_in_gib = {size_in_gib}
+  }}
+"#,
+                size_in_gib = size_in_gib,
+            ),
+            None => String::new(),
+        }
+    }
+    
+    /// Generate the container's `healthCheck`, if its port speaks HTTP
+    ///
+    /// A UDP-only service can't answer an HTTP probe, so ECS would mark every
+    /// task unhealthy and keep replacing it; it gets no container health check.
+    fn generate_container_health_check(&self, config: &TerraformConfig) -> String {
+        if config.port_protocol == PortProtocol::Udp {
+            return String::new();
+        }
+        
+        format!(r#"      
+      healthCheck = {{
+        command     = ["CMD-SHELL", "curl -f http://localhost:{port}{health_check_path} || exit 1"]
+        interval    = 30
+        timeout     = 5
+        retries     = 3
+        startPeriod = 60
+      }}
+"#,
+            port = config.container_port,
+            health_check_path = hcl_escape(&config.health_check_path),
+        )
+    }
+    
+    /// Generate the service's placement strategy blocks
+    ///
+    /// Fargate doesn't support placement, so nothing is rendered unless the
+    /// service runs on EC2.
+    fn generate_placement(&self, config: &TerraformConfig) -> String {
+        if config.launch_type != LaunchType::Ec2 {
+            return String::new();
+        }
+        
+        let mut blocks = String::new();
+        for strategy in &config.placement_strategies {
+            let (kind, field) = match strategy {
+                PlacementStrategy::Spread(field) => ("spread", Some(field)),
+                PlacementStrategy::Binpack(field) => ("binpack", Some(field)),
+                PlacementStrategy::Random => ("random", None),
+            };
+            blocks.push_str(&format!("  \n  ordered_placement_strategy {{\n    type  = \"{}\"\n", kind));
+            if let Some(field) = field {
+                blocks.push_str(&format!("    field = \"{}\"\n", hcl_escape(field)));
+            }
+            blocks.push_str("  }\n");
+        }
+        blocks
+    }
+    
+    /// Generate the `load_balancer` block attaching the ECS service to its target group
+    fn generate_service_load_balancer(&self, config: &TerraformConfig) -> String {
+        if config.listener_rules.is_empty() {
+            return String::new();
+        }
+        
+        format!(r#"  
+  load_balancer {{
+    target_group_arn = aws_lb_target_group.{project_name}_tg.arn
+    container_name   = "${{var.project_name}}-container"
+    container_port   = {port}
+  }}
 "#,
             project_name = self.sanitize_name(&config.project_name),
             port = config.container_port,
         )
     }
     
+    /// Generate the target group, listener rules, and (unless shared) the ALB itself
+    ///
+    /// With `shared_alb_arn` set, the ALB and its HTTP listener are looked up as
+    /// data sources so several services can route through one load balancer.
+    fn generate_load_balancer_tf(&self, config: &TerraformConfig) -> String {
+        if config.listener_rules.is_empty() {
+            return String::new();
+        }
+        
+        let project_name = self.sanitize_name(&config.project_name);
+        
+        let (load_balancer, listener_arn) = match &config.shared_alb_arn {
+            Some(arn) => (
+                format!(r#"
+# Existing shared Application Load Balancer
+data "aws_lb" "shared" {{
+  arn = "{arn}"
+}}
+
+data "aws_lb_listener" "shared_http" {{
+  load_balancer_arn = data.aws_lb.shared.arn
+  port              = 80
+}}
+"#,
+                    arn = hcl_escape(arn),
+                ),
+                "data.aws_lb_listener.shared_http.arn".to_string(),
+            ),
+            None => (
+                format!(r#"
+# Security Group for the Application Load Balancer
+resource "aws_security_group" "{project_name}_alb_sg" {{
+  name        = "${{var.project_name}}-${{var.environment}}-alb-sg"
+  description = "Security group for ${{var.project_name}} load balancer"
+  vpc_id      = var.vpc_id
+  
+  ingress {{
+    from_port   = 80
+    to_port     = 80
+    protocol    = "tcp"
+    cidr_blocks = ["0.0.0.0/0"]
+    description = "Allow inbound HTTP"
+  }}
+  
+  egress {{
+    from_port   = 0
+    to_port     = 0
+    protocol    = "-1"
+    cidr_blocks = ["0.0.0.0/0"]
+    description = "Allow all outbound traffic"
+  }}
+  
+  tags = {{
+    Name        = "${{var.project_name}}-${{var.environment}}-alb-sg"
+    Environment = var.environment
+  }}
+}}
+
+# Application Load Balancer
+resource "aws_lb" "{project_name}_alb" {{
+  name               = "{alb_name}"
+  load_balancer_type = "application"
+  subnets            = var.subnet_ids
+  security_groups    = [aws_security_group.{project_name}_alb_sg.id]
+  
+  tags = {{
+    Name        = "${{var.project_name}}-${{var.environment}}-alb"
+    Environment = var.environment
+  }}
+}}
+
+resource "aws_lb_listener" "{project_name}_http" {{
+  load_balancer_arn = aws_lb.{project_name}_alb.arn
+  port              = 80
+  protocol          = "HTTP"
+  
+  default_action {{
+    type = "fixed-response"
+    
+    fixed_response {{
+      content_type = "text/plain"
+      status_code  = "404"
+    }}
+  }}
+}}
+"#,
+                    project_name = project_name,
+                    alb_name = self.load_balancer_name(config, "alb"),
+                ),
+                format!("aws_lb_listener.{}_http.arn", project_name),
+            ),
+        };
+        
+        let mut tf = load_balancer;
+        
+        tf.push_str(&format!(r#"
+# Target Group for ECS Tasks
+resource "aws_lb_target_group" "{project_name}_tg" {{
+  name        = "{target_group_name}"
+  port        = {port}
+  protocol    = "HTTP"
+  target_type = "ip"
+  vpc_id      = var.vpc_id
+  
+  health_check {{
+    path    = "{health_check_path}"
+    matcher = "200"
+  }}
+  
+  tags = {{
+    Name        = "${{var.project_name}}-${{var.environment}}-tg"
+    Environment = var.environment
+  }}
+}}
+"#,
+            project_name = project_name,
+            target_group_name = self.load_balancer_name(config, "tg"),
+            port = config.container_port,
+            health_check_path = hcl_escape(&config.health_check_path),
+        ));
+        
+        for (index, rule) in config.listener_rules.iter().enumerate() {
+            tf.push_str(&format!(r#"
+resource "aws_lb_listener_rule" "{project_name}_rule_{index}" {{
+  listener_arn = {listener_arn}
+  priority     = {priority}
+  
+  action {{
+    type             = "forward"
+    target_group_arn = aws_lb_target_group.{project_name}_tg.arn
+  }}
+  
+  condition {{
+    path_pattern {{
+      values = ["{path_pattern}"]
+    }}
+  }}
+}}
+"#,
+                project_name = project_name,
+                index = index,
+                listener_arn = listener_arn,
+                priority = rule.priority,
+                path_pattern = hcl_escape(&rule.path_pattern),
+            ));
+        }
+        
+        tf
+    }
+    
     /// Generate variables.tf
     fn generate_variables_tf(&self, config: &TerraformConfig) -> String {
         format!(r#"variable "project_name" {{
@@ -329,7 +900,7 @@ variable "ecr_repository_url" {{
 }}
 
 variable "image_tag" {{
-  description = "Docker image tag to deploy"
+  description = "Docker image tag, or sha256 digest, to deploy"
   type        = string
   default     = "latest"
 }}
@@ -425,7 +996,7 @@ subnet_ids = {subnet_ids}
 
 # ECR Configuration
 ecr_repository_url = "{ecr_repository}"
-image_tag          = "latest"
+image_tag          = "{image_tag}"
 
 # ECS Task Configuration
 task_cpu       = "{cpu}"
@@ -439,6 +1010,7 @@ container_port = {port}
             vpc_id = vpc_id,
             subnet_ids = subnet_ids,
             ecr_repository = config.ecr_repository_name,
+            image_tag = hcl_escape(&config.image_tag),
             cpu = config.cpu,
             memory = config.memory,
             desired_count = config.desired_count,
@@ -446,6 +1018,25 @@ container_port = {port}
         )
     }
     
+    /// `{project}-{environment}-{suffix}` as a load balancer or target group name
+    ///
+    /// AWS allows 32 alphanumeric or hyphen characters; longer names are cut
+    /// short and end in a hash of the full name so they stay distinct.
+    fn load_balancer_name(&self, config: &TerraformConfig, suffix: &str) -> String {
+        let full: String = format!("{}-{}-{}", config.project_name, config.environment, suffix)
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        if full.len() <= LB_NAME_MAX_LEN {
+            return full;
+        }
+        
+        let hash = name_hash(&full);
+        let prefix = full[..LB_NAME_MAX_LEN - hash.len() - 1].trim_end_matches('-');
+        format!("{}-{}", prefix, hash)
+    }
+    
     /// Sanitize project name for Terraform resource names
     fn sanitize_name(&self, name: &str) -> String {
         name.to_lowercase()
@@ -453,27 +1044,6 @@ container_port = {port}
             .map(|c| if c.is_alphanumeric() { c } else { '_' })
             .collect()
     }
-    
-    /// Get framework-specific defaults
-    pub fn get_framework_defaults(framework: &FrameworkType) -> (i32, String, String) {
-        match framework {
-            FrameworkType::NextJs | FrameworkType::React | FrameworkType::Node => {
-                (3000, "512".to_string(), "1024".to_string())
-            }
-            FrameworkType::Python => {
-                (8000, "256".to_string(), "512".to_string())
-            }
-            FrameworkType::Ruby => {
-                (3000, "512".to_string(), "1024".to_string())
-            }
-            FrameworkType::Go | FrameworkType::Rust => {
-                (8080, "256".to_string(), "512".to_string())
-            }
-            _ => {
-                (8080, "256".to_string(), "512".to_string())
-            }
-        }
-    }
 }
 
 impl Default for TerraformService {
@@ -482,6 +1052,25 @@ impl Default for TerraformService {
     }
 }
 
+/// Escape a value for use inside a quoted HCL string
+///
+/// Besides quotes and backslashes, `${` and `%{` are doubled so Terraform reads
+/// them literally instead of as interpolation or a template directive.
+pub(crate) fn hcl_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace("${", "$${")
+        .replace("%{", "%%{")
+}
+
+/// First 8 hex digits of the SHA-256 of `value`
+pub(crate) fn name_hash(value: &str) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, value.as_bytes());
+    digest.as_ref()[..4].iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -494,16 +1083,427 @@ mod tests {
         assert_eq!(service.sanitize_name("Project@Name"), "project_name");
     }
     
+    fn create_config(listener_rules: Vec<ListenerRule>, shared_alb_arn: Option<String>) -> TerraformConfig {
+        TerraformConfig {
+            project_name: "web".to_string(),
+            environment: "production".to_string(),
+            region: "us-east-1".to_string(),
+            vpc_id: None,
+            subnet_ids: Vec::new(),
+            ecr_repository_name: "web".to_string(),
+            container_port: 3000,
+            port_protocol: PortProtocol::Tcp,
+            cpu: "512".to_string(),
+            memory: "1024".to_string(),
+            desired_count: 2,
+            framework: FrameworkType::NextJs,
+            listener_rules,
+            shared_alb_arn,
+            health_check_path: DEFAULT_HEALTH_CHECK_PATH.to_string(),
+            ephemeral_storage_gib: None,
+            launch_type: LaunchType::Fargate,
+            placement_strategies: Vec::new(),
+            default_tags: Vec::new(),
+            image_tag: "latest".to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_listener_rules_rendered() {
+        let service = TerraformService::new();
+        let config = create_config(
+            vec![
+                ListenerRule { path_pattern: "/api/*".to_string(), priority: 10 },
+                ListenerRule { path_pattern: "/admin/*".to_string(), priority: 20 },
+            ],
+            None,
+        );
+        
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(main_tf.contains(r#"resource "aws_lb_listener_rule" "web_rule_0""#));
+        assert!(main_tf.contains(r#"values = ["/api/*"]"#));
+        assert!(main_tf.contains("priority     = 10"));
+        assert!(main_tf.contains(r#"resource "aws_lb_listener_rule" "web_rule_1""#));
+        assert!(main_tf.contains(r#"values = ["/admin/*"]"#));
+        assert!(main_tf.contains("priority     = 20"));
+        assert!(main_tf.contains("target_group_arn = aws_lb_target_group.web_tg.arn"));
+        
+        // Without a shared ALB one is created for the service
+        assert!(main_tf.contains(r#"resource "aws_lb" "web_alb""#));
+        assert!(main_tf.contains("listener_arn = aws_lb_listener.web_http.arn"));
+    }
+    
+    #[test]
+    fn test_shared_alb_referenced_not_created() {
+        let service = TerraformService::new();
+        let arn = "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/app/shared/50dc6c495c0c9188";
+        let config = create_config(
+            vec![ListenerRule { path_pattern: "/".to_string(), priority: 100 }],
+            Some(arn.to_string()),
+        );
+        
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(main_tf.contains(&format!(r#"arn = "{}""#, arn)));
+        assert!(main_tf.contains("listener_arn = data.aws_lb_listener.shared_http.arn"));
+        assert!(!main_tf.contains(r#"resource "aws_lb" "#));
+        assert!(!main_tf.contains(r#"resource "aws_lb_listener" "#));
+    }
+    
+    #[test]
+    fn test_shared_alb_arn_validated() {
+        let service = TerraformService::new();
+        let rules = vec![ListenerRule { path_pattern: "/".to_string(), priority: 100 }];
+        
+        let config = create_config(rules.clone(), Some("arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/app/shared/50dc6c495c0c9188".to_string()));
+        assert!(service.render_terraform(&config).is_ok());
+        
+        for arn in [
+            "shared-alb",
+            "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/net/shared/50dc6c495c0c9188",
+            "arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/app/shared\"}/1",
+        ] {
+            let config = create_config(rules.clone(), Some(arn.to_string()));
+            assert!(matches!(service.render_terraform(&config), Err(TerraformServiceError::InvalidConfiguration(_))), "{}", arn);
+        }
+        
+        // Rendered escaped even when the check is bypassed
+        let config = create_config(rules, Some("arn:aws:x/\"${y}".to_string()));
+        assert!(service.generate_main_tf(&config).contains(r#"arn = "arn:aws:x/\"$${y}""#));
+    }
+    
+    #[test]
+    fn test_listener_rule_values_escaped() {
+        let service = TerraformService::new();
+        let mut config = create_config(
+            vec![ListenerRule { path_pattern: r#"/a"b/${var.x}/%{if}\*"#.to_string(), priority: 10 }],
+            None,
+        );
+        config.health_check_path = "/status?q=\"1\"".to_string();
+    
+        let main_tf = service.generate_main_tf(&config);
+    
+        assert!(main_tf.contains(r#"values = ["/a\"b/$${var.x}/%%{if}\\*"]"#), "{}", main_tf);
+        assert!(main_tf.contains(r#"path    = "/status?q=\"1\"""#), "{}", main_tf);
+    }
+    
+    #[test]
+    fn test_load_balancer_names_fit_aws_limit() {
+        let service = TerraformService::new();
+        let mut config = create_config(vec![ListenerRule { path_pattern: "/".to_string(), priority: 1 }], None);
+        assert_eq!(service.load_balancer_name(&config, "alb"), "web-production-alb");
+    
+        config.project_name = "customer-analytics-dashboard".to_string();
+        let alb = service.load_balancer_name(&config, "alb");
+        let tg = service.load_balancer_name(&config, "tg");
+        assert!(alb.len() <= 32 && tg.len() <= 32, "{} {}", alb, tg);
+        assert!(alb.starts_with("customer-analytics-dash"));
+        assert_ne!(alb, tg);
+    
+        // Names differing only past the cut still get distinct hashes
+        config.environment = "staging".to_string();
+        assert_ne!(service.load_balancer_name(&config, "alb"), alb);
+    
+        let main_tf = service.generate_main_tf(&config);
+        assert!(main_tf.contains(&format!(r#"name               = "{}""#, service.load_balancer_name(&config, "alb"))));
+        assert!(main_tf.contains(&format!(r#"name        = "{}""#, service.load_balancer_name(&config, "tg"))));
+    }
+    
+    #[test]
+    fn test_health_check_path_configurable() {
+        let service = TerraformService::new();
+        let mut config = create_config(vec![ListenerRule { path_pattern: "/api/*".to_string(), priority: 10 }], None);
+        config.health_check_path = "/api/healthz".to_string();
+    
+        let main_tf = service.generate_main_tf(&config);
+        assert!(main_tf.contains(r#"path    = "/api/healthz""#));
+        assert!(main_tf.contains("curl -f http://localhost:3000/api/healthz || exit 1"), "{}", main_tf);
+        assert!(!main_tf.contains("/health ||"));
+    
+        config.health_check_path = "healthz".to_string();
+        assert!(matches!(service.render_terraform(&config), Err(TerraformServiceError::InvalidConfiguration(_))));
+    }
+    
+    #[test]
+    fn test_no_load_balancer_without_rules() {
+        let service = TerraformService::new();
+        let main_tf = service.generate_main_tf(&create_config(Vec::new(), None));
+        
+        assert!(!main_tf.contains("aws_lb"));
+        assert!(!main_tf.contains("load_balancer {"));
+    }
+    
+    #[test]
+    fn test_udp_port_rendered_in_ingress_and_port_mapping() {
+        let service = TerraformService::new();
+        
+        let mut config = create_config(Vec::new(), None);
+        config.container_port = 27015;
+        config.port_protocol = PortProtocol::Udp;
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(main_tf.contains("from_port   = 27015\n    to_port     = 27015\n    protocol    = \"udp\""));
+        assert!(main_tf.contains("containerPort = 27015\n          hostPort      = 27015\n          protocol      = \"udp\""));
+        assert!(!main_tf.contains("protocol    = \"tcp\""));
+        assert!(!main_tf.contains("healthCheck"));
+        assert!(!main_tf.contains("curl"));
+    }
+    
+    #[test]
+    fn test_udp_port_rejected_with_listener_rules() {
+        let service = TerraformService::new();
+        
+        let mut config = create_config(vec![ListenerRule { path_pattern: "/*".to_string(), priority: 1 }], None);
+        config.port_protocol = PortProtocol::Udp;
+        assert!(matches!(service.render_terraform(&config), Err(TerraformServiceError::InvalidConfiguration(_))));
+        
+        config.listener_rules.clear();
+        assert!(service.render_terraform(&config).is_ok());
+    }
+    
+    #[test]
+    fn test_ephemeral_storage_rendered() {
+        let service = TerraformService::new();
+        
+        let mut config = create_config(Vec::new(), None);
+        assert!(!service.generate_main_tf(&config).contains("ephemeral_storage"));
+        
+        config.ephemeral_storage_gib = Some(50);
+        let main_tf = service.generate_main_tf(&config);
+        assert!(main_tf.contains("ephemeral_storage {\n    size_in_gib = 50\n  }"));
+        
+        assert!(TerraformService::validate_ephemeral_storage(Some(50)).is_ok());
+        assert!(matches!(
+            TerraformService::validate_ephemeral_storage(Some(20)),
+            Err(TerraformServiceError::InvalidConfiguration(_))
+        ));
+        assert!(TerraformService::validate_ephemeral_storage(Some(201)).is_err());
+    }
+    
+    #[test]
+    fn test_placement_rendered_for_ec2() {
+        let service = TerraformService::new();
+        let mut config = create_config(Vec::new(), None);
+        config.launch_type = LaunchType::Ec2;
+        config.placement_strategies = vec![
+            PlacementStrategy::spread_across_azs(),
+            PlacementStrategy::Binpack("memory".to_string()),
+            PlacementStrategy::Spread("attribute:\"${x}".to_string()),
+        ];
+        
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(main_tf.contains("ordered_placement_strategy {\n    type  = \"spread\"\n    field = \"attribute:ecs.availability-zone\"\n  }"));
+        assert!(main_tf.contains("ordered_placement_strategy {\n    type  = \"binpack\"\n    field = \"memory\"\n  }"));
+        assert!(main_tf.contains(r#"field = "attribute:\"$${x}""#));
+        assert!(main_tf.contains(r#"launch_type     = "EC2""#));
+        assert!(main_tf.contains(r#"requires_compatibilities = ["EC2"]"#));
+        assert!(main_tf.contains("assign_public_ip = false"));
+    }
+    
+    #[test]
+    fn test_for_project_spreads_ec2_production_across_azs() {
+        use crate::models::Environment;
+        use crate::shadow::test_utils::test_project;
+        
+        let service = TerraformService::new();
+        let mut project = test_project("web", Environment::Production);
+        project.launch_type = LaunchType::Ec2;
+        
+        let main_tf = service.generate_main_tf(&TerraformConfig::for_project(&project, "us-east-1"));
+        
+        assert!(main_tf.contains("ordered_placement_strategy {\n    type  = \"spread\"\n    field = \"attribute:ecs.availability-zone\"\n  }"));
+        assert!(main_tf.contains(r#"launch_type     = "EC2""#));
+    }
+    
+    #[test]
+    fn test_for_project_carries_network_settings() {
+        use crate::models::{Environment, ProjectNetwork};
+        use crate::shadow::test_utils::test_project;
+        
+        let mut project = test_project("web", Environment::Production);
+        project.network = ProjectNetwork {
+            vpc_id: Some("vpc-0abc".to_string()),
+            subnet_ids: vec!["subnet-a".to_string(), "subnet-b".to_string()],
+            listener_rules: vec![ListenerRule { path_pattern: "/api/*".to_string(), priority: 10 }],
+            shared_alb_arn: Some("arn:aws:elasticloadbalancing:us-east-1:123456789012:loadbalancer/app/shared/1".to_string()),
+            health_check_path: Some("/ready".to_string()),
+        };
+        
+        let config = TerraformConfig::for_project(&project, "us-east-1");
+        assert_eq!(config.vpc_id.as_deref(), Some("vpc-0abc"));
+        assert_eq!(config.subnet_ids, ["subnet-a", "subnet-b"]);
+        assert_eq!(config.listener_rules, project.network.listener_rules);
+        assert_eq!(config.shared_alb_arn, project.network.shared_alb_arn);
+        assert_eq!(config.health_check_path, "/ready");
+        
+        let files = TerraformService::new().render_terraform(&config).unwrap();
+        assert!(files["terraform.tfvars"].contains(r#"vpc_id     = "vpc-0abc""#));
+        assert!(files["main.tf"].contains(r#"resource "aws_lb_listener_rule" "web_rule_0""#));
+        
+        project.network = ProjectNetwork::default();
+        assert_eq!(TerraformConfig::for_project(&project, "us-east-1").health_check_path, DEFAULT_HEALTH_CHECK_PATH);
+    }
+    
+    #[test]
+    fn test_deployed_image_in_tfvars() {
+        let service = TerraformService::new();
+        let mut config = create_config(Vec::new(), None);
+        
+        config.image_tag = "abc1234".to_string();
+        let files = service.render_terraform(&config).unwrap();
+        assert!(files["terraform.tfvars"].contains(r#"image_tag          = "abc1234""#));
+        assert!(files["main.tf"].contains(r#"image     = "${var.ecr_repository_url}:${var.image_tag}""#));
+        
+        // A pinned digest is referenced with `@`
+        config.image_tag = "sha256:0123abcd".to_string();
+        let files = service.render_terraform(&config).unwrap();
+        assert!(files["terraform.tfvars"].contains(r#"image_tag          = "sha256:0123abcd""#));
+        assert!(files["main.tf"].contains(r#"image     = "${var.ecr_repository_url}@${var.image_tag}""#));
+    }
+    
+    #[test]
+    fn test_placement_omitted_for_fargate() {
+        let service = TerraformService::new();
+        let mut config = create_config(Vec::new(), None);
+        config.placement_strategies = vec![PlacementStrategy::spread_across_azs()];
+        
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(!main_tf.contains("ordered_placement_strategy"));
+        assert!(main_tf.contains(r#"launch_type     = "FARGATE""#));
+        assert!(main_tf.contains("assign_public_ip = true"));
+    }
+    
+    #[test]
+    fn test_default_tags_rendered_on_provider() {
+        let service = TerraformService::new();
+        let mut config = create_config(Vec::new(), None);
+        assert!(!service.generate_main_tf(&config).contains("default_tags"));
+        
+        config.default_tags = vec![
+            ("team".to_string(), "platform".to_string()),
+            ("cost-center".to_string(), "1234".to_string()),
+        ];
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(main_tf.contains(
+            "provider \"aws\" {\n  region = var.aws_region\n  \n  default_tags {\n    tags = {\n      \"team\" = \"platform\"\n      \"cost-center\" = \"1234\"\n    }\n  }\n}"
+        ));
+    }
+    
+    #[test]
+    fn test_default_tags_escaped() {
+        let service = TerraformService::new();
+        let mut config = create_config(Vec::new(), None);
+        config.default_tags = vec![("owner \"ops\"".to_string(), "${var.secret}".to_string())];
+        
+        let main_tf = service.generate_main_tf(&config);
+        
+        assert!(main_tf.contains(r#"      "owner \"ops\"" = "$${var.secret}""#));
+    }
+    
     #[test]
-    fn test_framework_defaults() {
-        let (port, cpu, mem) = TerraformService::get_framework_defaults(&FrameworkType::NextJs);
-        assert_eq!(port, 3000);
-        assert_eq!(cpu, "512");
-        assert_eq!(mem, "1024");
-        
-        let (port, cpu, mem) = TerraformService::get_framework_defaults(&FrameworkType::Python);
-        assert_eq!(port, 8000);
-        assert_eq!(cpu, "256");
-        assert_eq!(mem, "512");
+    fn test_listener_rule_priorities_validated() {
+        let duplicate = vec![
+            ListenerRule { path_pattern: "/api/*".to_string(), priority: 10 },
+            ListenerRule { path_pattern: "/".to_string(), priority: 10 },
+        ];
+        assert!(matches!(
+            TerraformService::validate_listener_rules(&duplicate, PortProtocol::Tcp),
+            Err(TerraformServiceError::InvalidConfiguration(_))
+        ));
+        
+        let out_of_range = vec![ListenerRule { path_pattern: "/".to_string(), priority: 0 }];
+        assert!(TerraformService::validate_listener_rules(&out_of_range, PortProtocol::Tcp).is_err());
+    }
+    
+    #[test]
+    fn test_listener_rules_rejected_for_udp() {
+        let rules = vec![ListenerRule { path_pattern: "/api/*".to_string(), priority: 10 }];
+        assert!(TerraformService::validate_listener_rules(&rules, PortProtocol::Tcp).is_ok());
+        assert!(matches!(
+            TerraformService::validate_listener_rules(&rules, PortProtocol::Udp),
+            Err(TerraformServiceError::InvalidConfiguration(_))
+        ));
+        
+        assert!(TerraformService::validate_listener_rules(&[], PortProtocol::Udp).is_ok());
+    }
+    
+    const APPLY_FIXTURE: &str = include_str!("../../tests/fixtures/terraform_apply.jsonl");
+    
+    #[test]
+    fn test_parse_apply_output_fixture() {
+        let result = TerraformService::parse_apply_output(APPLY_FIXTURE);
+        
+        let mut outputs = HashMap::new();
+        outputs.insert("cluster_name".to_string(), serde_json::json!("my_app-production-cluster"));
+        outputs.insert("desired_count".to_string(), serde_json::json!(2));
+        
+        assert_eq!(result, ApplyResult {
+            created: vec!["aws_ecs_cluster.main".to_string(), "aws_cloudwatch_log_group.app".to_string()],
+            updated: vec!["aws_ecs_service.app".to_string()],
+            destroyed: vec!["aws_security_group.legacy".to_string()],
+            outputs,
+        });
+    }
+    
+    #[tokio::test]
+    async fn test_apply_terraform_streams_progress() {
+        use crate::shadow::MockCommandRunner;
+        use std::sync::Mutex;
+        
+        let runner = Arc::new(MockCommandRunner::new());
+        runner.push_response(CommandOutput::success("Terraform has been successfully initialized!"));
+        runner.push_response(CommandOutput::success(APPLY_FIXTURE));
+        let service = TerraformService::new().with_command_runner(runner.clone());
+        
+        let progress = Mutex::new(Vec::new());
+        let result = service
+            .apply_terraform(Path::new("/tmp/infra"), &|message| progress.lock().unwrap().push(message.to_string()))
+            .await
+            .unwrap();
+        
+        assert_eq!(result.created.len(), 2);
+        let progress = progress.into_inner().unwrap();
+        assert!(progress.contains(&"Apply complete! Resources: 2 added, 1 changed, 1 destroyed.".to_string()));
+        
+        let calls = runner.calls();
+        assert_eq!(calls[0].args[..2], ["-chdir=/tmp/infra".to_string(), "init".to_string()]);
+        assert_eq!(calls[1].args[1..3], ["apply".to_string(), "-auto-approve".to_string()]);
+    }
+    
+    #[tokio::test]
+    async fn test_apply_terraform_reports_errors() {
+        use crate::shadow::MockCommandRunner;
+        
+        let runner = Arc::new(MockCommandRunner::new());
+        runner.push_response(CommandOutput::success(""));
+        runner.push_response(CommandOutput {
+            success: false,
+            stdout: r#"{"@level":"error","@message":"Error: creating ECS Cluster","type":"diagnostic","diagnostic":{"severity":"error","summary":"creating ECS Cluster","detail":"AccessDeniedException"}}"#.to_string(),
+            stderr: String::new(),
+        });
+        let service = TerraformService::new().with_command_runner(runner);
+        
+        match service.apply_terraform(Path::new("/tmp/infra"), &|_| {}).await {
+            Err(TerraformServiceError::ApplyFailed(msg)) => {
+                assert_eq!(msg, "creating ECS Cluster: AccessDeniedException");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_apply_terraform_missing_binary() {
+        use crate::shadow::MockCommandRunner;
+        
+        let runner = Arc::new(MockCommandRunner::new());
+        runner.push_spawn_failure(std::io::ErrorKind::NotFound);
+        let service = TerraformService::new().with_command_runner(runner);
+        
+        let result = service.apply_terraform(Path::new("/tmp/infra"), &|_| {}).await;
+        assert!(matches!(result, Err(TerraformServiceError::BinaryNotFound(_))));
     }
 }
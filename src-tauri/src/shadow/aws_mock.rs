@@ -4,12 +4,28 @@
 //! real AWS credentials, Docker, or infrastructure.
 
 use async_trait::async_trait;
-use crate::services::{AwsOperations, AwsServiceError, EcsDeploymentConfig, ServiceHealth};
-use crate::models::FrameworkType;
+use crate::services::{
+    AwsOperations, AwsServiceError, BuildSpec, EcrUri, EcsDeploymentConfig, ServiceDefinition, ServiceHealth, ServiceUtilization, StoppedTask, MAX_DESCRIBE_SERVICES,
+};
+use crate::services::aws_service::{collect_log_pages, missing_service_error, task_definition_family};
+use crate::models::{FrameworkType, ImageInfo, LayerInfo, PermissionCheck};
 use crate::shadow::{ShadowConfig, ShadowState};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::time::Duration;
 
+/// Events returned per simulated `GetLogEvents` call, kept small so paging is exercised
+const MOCK_LOG_PAGE_SIZE: usize = 100;
+
+/// Synthetic `docker build` output reported by every mock build, before the tagging line
+const MOCK_BUILD_OUTPUT: &[&str] = &[
+    "#1 [internal] load build definition from Dockerfile",
+    "#2 [internal] load metadata for docker.io/library/node:18-alpine",
+    "#3 [1/3] WORKDIR /app",
+    "#4 [2/3] COPY . .",
+    "#5 [3/3] RUN npm ci --omit=dev",
+];
+
 /// Mock AWS service for testing
 pub struct MockAwsService {
     config: ShadowConfig,
@@ -42,7 +58,8 @@ impl MockAwsService {
     
     /// Check if operation should fail based on config
     fn check_failure(&self, operation: &str) -> Result<(), AwsServiceError> {
-        if self.config.should_fail() {
+        self.state.record_operation(operation);
+        if self.state.is_operation_failing(operation) || self.config.should_fail() {
             Err(AwsServiceError::EcsOperationFailed(
                 format!("Simulated failure: {}", operation)
             ))
@@ -54,10 +71,14 @@ impl MockAwsService {
 
 #[async_trait]
 impl AwsOperations for MockAwsService {
-    async fn ensure_ecr_repository(&self, repository_name: &str) -> Result<String, AwsServiceError> {
+    async fn ensure_ecr_repository(&self, repository_name: &str, tags: &[(String, String)]) -> Result<String, AwsServiceError> {
         self.simulate_delay(100).await;
         self.check_failure("ensure_ecr_repository")?;
         
+        if !tags.is_empty() {
+            self.state.set_ecr_repository_tags(repository_name, tags.to_vec());
+        }
+        
         // Check if repository already exists
         if let Some(uri) = self.state.get_ecr_repository(repository_name) {
             return Ok(uri);
@@ -87,11 +108,23 @@ impl AwsOperations for MockAwsService {
         &self,
         source_dir: &str,
         image_tag: &str,
-        framework: &FrameworkType
+        framework: &FrameworkType,
+        on_line: &(dyn Fn(&str) + Send + Sync),
     ) -> Result<(), AwsServiceError> {
         self.simulate_delay(2000).await; // Building takes longer
         self.check_failure("build_docker_image")?;
         
+        for line in MOCK_BUILD_OUTPUT {
+            on_line(line);
+        }
+        on_line(&format!("#6 naming to {} done", image_tag));
+        
+        let build_duration = self.state.start_build();
+        if !build_duration.is_zero() {
+            tokio::time::sleep(build_duration).await;
+        }
+        self.state.finish_build();
+        
         // Generate mock Dockerfile if it doesn't exist
         let dockerfile_path = format!("{}/Dockerfile", source_dir);
         if !std::path::Path::new(&dockerfile_path).exists() {
@@ -104,9 +137,30 @@ impl AwsOperations for MockAwsService {
         Ok(())
     }
     
-    async fn push_docker_image(&self, local_tag: &str, ecr_uri: &str) -> Result<(), AwsServiceError> {
+    async fn build_image(&self, spec: &BuildSpec) -> Result<(), AwsServiceError> {
+        self.simulate_delay(2000).await;
+        self.check_failure("build_image")?;
+        
+        let build_duration = self.state.start_build();
+        if !build_duration.is_zero() {
+            tokio::time::sleep(build_duration).await;
+        }
+        self.state.finish_build();
+        
+        // Track built image; the Dockerfile itself is not inspected
+        self.state.add_docker_image(spec.image_tag.clone());
+        
+        Ok(())
+    }
+    
+    async fn push_docker_image(&self, local_tag: &str, ecr_uri: &str) -> Result<String, AwsServiceError> {
         self.simulate_delay(3000).await; // Pushing takes longer
         self.check_failure("push_docker_image")?;
+        if self.state.is_push_failing(ecr_uri) {
+            return Err(AwsServiceError::DockerOperationFailed(
+                format!("Simulated push failure: {}", ecr_uri)
+            ));
+        }
         
         // Verify image was built
         if !self.state.has_docker_image(local_tag) {
@@ -117,13 +171,41 @@ impl AwsOperations for MockAwsService {
         
         // Track pushed image with ECR URI
         self.state.add_docker_image(ecr_uri.to_string());
+        let digest = self.generate_image_digest(ecr_uri);
+        if let Some(uri) = EcrUri::parse(ecr_uri) {
+            self.state.add_ecr_image(&uri.repository, &digest);
+        }
         
-        Ok(())
+        Ok(digest)
+    }
+    
+    async fn inspect_image(&self, image_tag: &str) -> Result<ImageInfo, AwsServiceError> {
+        self.simulate_delay(100).await;
+        self.check_failure("inspect_image")?;
+        
+        if !self.state.has_docker_image(image_tag) {
+            return Err(AwsServiceError::DockerOperationFailed(
+                format!("Image not found: {}", image_tag)
+            ));
+        }
+        
+        // Synthetic breakdown of a typical small Node image
+        let layers = vec![
+            LayerInfo { created_by: "CMD [\"node\" \"index.js\"]".to_string(), size_bytes: 0 },
+            LayerInfo { created_by: "COPY . .".to_string(), size_bytes: 2_400_000 },
+            LayerInfo { created_by: "RUN npm ci --only=production".to_string(), size_bytes: 48_000_000 },
+            LayerInfo { created_by: "FROM node:18-alpine".to_string(), size_bytes: 127_000_000 },
+        ];
+        Ok(ImageInfo {
+            size_bytes: layers.iter().map(|layer| layer.size_bytes).sum(),
+            layers,
+        })
     }
     
     async fn register_task_definition(&self, config: &EcsDeploymentConfig) -> Result<String, AwsServiceError> {
         self.simulate_delay(500).await;
         self.check_failure("register_task_definition")?;
+        config.validate()?;
         
         // Generate mock task definition ARN
         let task_arn = format!(
@@ -133,6 +215,8 @@ impl AwsOperations for MockAwsService {
         );
         
         self.state.add_task_definition(config.task_family.clone(), task_arn.clone());
+        self.state.record_task_definition_config(config.clone());
+        self.state.add_log_group(&config.log_group_name());
         
         Ok(task_arn)
     }
@@ -145,6 +229,8 @@ impl AwsOperations for MockAwsService {
         self.simulate_delay(800).await;
         self.check_failure("deploy_service")?;
         
+        self.state.set_service_task_family(&config.cluster_name, &config.service_name, &config.task_family);
+        
         // Set service to deploying state initially
         self.state.set_service_status(
             &config.cluster_name,
@@ -170,38 +256,168 @@ impl AwsOperations for MockAwsService {
         self.simulate_delay(300).await;
         self.check_failure("get_service_health")?;
         
-        // Get current status or create initial status
-        let status = self.state.get_service_status(cluster_name, service_name)
-            .unwrap_or(crate::shadow::ServiceStatus {
-                running_count: 0,
-                desired_count: 1,
-                pending_count: 1,
-            });
+        if self.state.is_cluster_deleted(cluster_name) {
+            return Err(AwsServiceError::ClusterNotFound(cluster_name.to_string()));
+        }
+        if self.state.is_service_deleted(cluster_name, service_name) {
+            return Err(missing_service_error(cluster_name, service_name, Some("MISSING")));
+        }
         
-        // Simulate progression towards healthy state
-        // If not yet running, gradually move pending to running
-        let mut new_status = status.clone();
+        Ok(self.advance_service(cluster_name, service_name))
+    }
+    
+    async fn describe_services(
+        &self,
+        cluster_name: &str,
+        service_names: &[String],
+    ) -> Result<HashMap<String, ServiceHealth>, AwsServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("describe_services")?;
+        
+        if service_names.len() > MAX_DESCRIBE_SERVICES {
+            return Err(AwsServiceError::InvalidConfiguration(format!(
+                "describe_services accepts at most {} services, got {}",
+                MAX_DESCRIBE_SERVICES,
+                service_names.len()
+            )));
+        }
         
-        if new_status.pending_count > 0 && new_status.running_count < new_status.desired_count {
-            new_status.pending_count -= 1;
-            new_status.running_count += 1;
-            
-            self.state.set_service_status(
-                cluster_name,
-                service_name,
-                new_status.clone()
-            );
+        self.state.record_describe_services_call();
+        
+        if self.state.is_cluster_deleted(cluster_name) {
+            return Err(AwsServiceError::ClusterNotFound(cluster_name.to_string()));
         }
         
-        Ok(ServiceHealth {
-            running_count: new_status.running_count,
-            desired_count: new_status.desired_count,
-            pending_count: new_status.pending_count,
-            is_healthy: new_status.running_count == new_status.desired_count 
-                        && new_status.pending_count == 0,
+        // Deleted services are omitted, as ECS reports them as failures
+        Ok(service_names.iter()
+            .filter(|name| !self.state.is_service_deleted(cluster_name, name))
+            .map(|name| (name.clone(), self.advance_service(cluster_name, name)))
+            .collect())
+    }
+    
+    async fn list_stopped_tasks(&self, cluster_name: &str, service_name: &str) -> Result<Vec<StoppedTask>, AwsServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("list_stopped_tasks")?;
+        
+        Ok(self.state.get_stopped_tasks(cluster_name, service_name))
+    }
+    
+    async fn describe_service_definition(&self, cluster_name: &str, service_name: &str) -> Result<ServiceDefinition, AwsServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("describe_service_definition")?;
+        
+        if self.state.is_cluster_deleted(cluster_name) {
+            return Err(AwsServiceError::ClusterNotFound(cluster_name.to_string()));
+        }
+        let family = match self.state.get_service_task_family(cluster_name, service_name) {
+            Some(family) if !self.state.is_service_deleted(cluster_name, service_name) => family,
+            _ => return Err(missing_service_error(cluster_name, service_name, Some("MISSING"))),
+        };
+        let config = self.state.get_task_definition_config(&family)
+            .ok_or_else(|| AwsServiceError::EcsOperationFailed(format!("Task definition {} not found", family)))?;
+        let desired_count = self.state.get_service_status(cluster_name, service_name)
+            .map(|status| status.desired_count)
+            .unwrap_or(config.desired_count);
+        
+        Ok(ServiceDefinition {
+            task_definition_arn: self.state.get_task_definition(&family).unwrap_or_default(),
+            image_uri: config.image_uri,
+            container_port: Some(config.port),
+            cpu: config.cpu.parse().ok(),
+            memory: config.memory.parse().ok(),
+            desired_count,
+            environment: config.environment,
         })
     }
     
+    async fn force_new_deployment(&self, cluster_name: &str, service_name: &str) -> Result<(), AwsServiceError> {
+        self.simulate_delay(500).await;
+        self.check_failure("force_new_deployment")?;
+        
+        // Replacement tasks start pending, like a fresh deploy
+        let desired_count = self.state.get_service_status(cluster_name, service_name)
+            .map(|status| status.desired_count)
+            .unwrap_or(1);
+        self.state.set_service_status(
+            cluster_name,
+            service_name,
+            crate::shadow::ServiceStatus {
+                running_count: 0,
+                desired_count,
+                pending_count: desired_count,
+            }
+        );
+        self.state.record_forced_deployment(cluster_name, service_name);
+        
+        Ok(())
+    }
+    
+    async fn delete_service(&self, cluster_name: &str, service_name: &str) -> Result<(), AwsServiceError> {
+        self.simulate_delay(500).await;
+        self.check_failure("delete_service")?;
+        
+        self.state.delete_service(cluster_name, service_name);
+        
+        Ok(())
+    }
+    
+    async fn list_task_definitions(&self, family: &str) -> Result<Vec<String>, AwsServiceError> {
+        self.simulate_delay(200).await;
+        self.check_failure("list_task_definitions")?;
+        
+        Ok(self.state.get_task_definition(family).into_iter().collect())
+    }
+    
+    async fn deregister_task_definition(&self, task_definition_arn: &str) -> Result<(), AwsServiceError> {
+        self.simulate_delay(200).await;
+        self.check_failure("deregister_task_definition")?;
+        
+        if let Some(family) = task_definition_family(task_definition_arn) {
+            if self.state.get_task_definition(family).as_deref() == Some(task_definition_arn) {
+                self.state.remove_task_definition(family);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    async fn log_group_exists(&self, log_group: &str) -> Result<bool, AwsServiceError> {
+        self.simulate_delay(100).await;
+        self.check_failure("log_group_exists")?;
+        
+        Ok(self.state.has_log_group(log_group))
+    }
+    
+    async fn delete_log_group(&self, log_group: &str) -> Result<(), AwsServiceError> {
+        self.simulate_delay(200).await;
+        self.check_failure("delete_log_group")?;
+        
+        if !self.state.has_log_group(log_group) {
+            return Err(AwsServiceError::CloudWatchOperationFailed(format!("Log group not found: {}", log_group)));
+        }
+        self.state.delete_log_group(log_group);
+        
+        Ok(())
+    }
+    
+    async fn list_image_digests(&self, repository_name: &str) -> Result<Vec<String>, AwsServiceError> {
+        self.simulate_delay(100).await;
+        self.check_failure("list_image_digests")?;
+        
+        Ok(self.state.get_ecr_images(repository_name))
+    }
+    
+    async fn delete_images(&self, repository_name: &str, digests: &[String]) -> Result<(), AwsServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("delete_images")?;
+        
+        for digest in digests {
+            self.state.remove_ecr_image(repository_name, digest);
+        }
+        
+        Ok(())
+    }
+    
     async fn fetch_logs(
         &self,
         log_group: &str,
@@ -211,8 +427,12 @@ impl AwsOperations for MockAwsService {
         self.simulate_delay(400).await;
         self.check_failure("fetch_logs")?;
         
-        // Return mock logs or empty if none exist
-        let logs = self.state.get_logs(log_group, log_stream, limit as usize);
+        // Page backward through stored logs the same way CloudWatch does
+        let logs = collect_log_pages(limit.max(0) as usize, MOCK_LOG_PAGE_SIZE, |token, page_limit| {
+            let end = token.and_then(|t| t.parse::<usize>().ok());
+            let (events, start) = self.state.get_logs_page(log_group, log_stream, end, page_limit);
+            async move { Ok((events, Some(start.to_string()))) }
+        }).await?;
         
         // If no logs exist, add some mock logs
         if logs.is_empty() {
@@ -231,9 +451,113 @@ impl AwsOperations for MockAwsService {
         
         Ok(logs)
     }
+    
+    async fn list_log_streams(&self, log_group: &str, limit: i32) -> Result<Vec<String>, AwsServiceError> {
+        self.simulate_delay(200).await;
+        self.check_failure("list_log_streams")?;
+        
+        let mut streams = self.state.list_log_streams(log_group);
+        streams.truncate(limit.max(0) as usize);
+        Ok(streams)
+    }
+    
+    async fn get_service_utilization(
+        &self,
+        cluster_name: &str,
+        service_name: &str,
+        window: Duration,
+    ) -> Result<ServiceUtilization, AwsServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("get_service_utilization")?;
+        
+        // One hourly point per hour of the window, steady at 50% unless a series is set
+        Ok(self.state.get_service_utilization(cluster_name, service_name).unwrap_or_else(|| {
+            let points = (window.as_secs() / 3600).max(1) as usize;
+            ServiceUtilization {
+                cpu_percent: vec![50.0; points],
+                memory_percent: vec![50.0; points],
+            }
+        }))
+    }
+    
+    async fn ping(&self) -> Result<(), AwsServiceError> {
+        self.check_failure("ping")?;
+        
+        self.state.record_keepalive_ping();
+        Ok(())
+    }
+    
+    async fn get_account_id(&self) -> Result<String, AwsServiceError> {
+        self.check_failure("get_account_id")?;
+        
+        Ok("123456789012".to_string())
+    }
+    
+    async fn simulate_permissions(&self, actions: &[String]) -> Result<Vec<PermissionCheck>, AwsServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("simulate_permissions")?;
+        
+        // Everything is allowed unless the state denies it
+        Ok(actions.iter().map(|action| {
+            let allowed = !self.state.is_action_denied(action);
+            PermissionCheck {
+                action: action.clone(),
+                allowed,
+                decision: if allowed { "allowed" } else { "implicitDeny" }.to_string(),
+            }
+        }).collect())
+    }
+    
+    fn region(&self) -> &str {
+        &self.region
+    }
 }
 
 impl MockAwsService {
+    /// Advance a service one step towards healthy and return its new health
+    fn advance_service(&self, cluster_name: &str, service_name: &str) -> ServiceHealth {
+        // Get current status or create initial status
+        let status = self.state.get_service_status(cluster_name, service_name)
+            .unwrap_or(crate::shadow::ServiceStatus {
+                running_count: 0,
+                desired_count: 1,
+                pending_count: 1,
+            });
+        
+        // Simulate progression towards healthy state
+        // If not yet running, gradually move pending to running
+        let mut new_status = status.clone();
+        
+        if new_status.pending_count > 0 && new_status.running_count < new_status.desired_count {
+            new_status.pending_count -= 1;
+            new_status.running_count += 1;
+            
+            self.state.set_service_status(
+                cluster_name,
+                service_name,
+                new_status.clone()
+            );
+        }
+        
+        ServiceHealth {
+            running_count: new_status.running_count,
+            desired_count: new_status.desired_count,
+            pending_count: new_status.pending_count,
+            is_healthy: new_status.running_count == new_status.desired_count 
+                        && new_status.pending_count == 0,
+        }
+    }
+    
+    /// Generate a deterministic synthetic image digest for a pushed URI
+    fn generate_image_digest(&self, ecr_uri: &str) -> String {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        
+        let mut hasher = DefaultHasher::new();
+        ecr_uri.hash(&mut hasher);
+        format!("sha256:{:064x}", hasher.finish())
+    }
+    
     /// Generate mock Dockerfile for testing
     fn generate_mock_dockerfile(&self, source_dir: &str, framework: &FrameworkType) -> Result<(), AwsServiceError> {
         let dockerfile_content = match framework {
@@ -325,13 +649,13 @@ mod tests {
     async fn test_ensure_ecr_repository() {
         let service = create_test_service();
         
-        let uri = service.ensure_ecr_repository("test-repo").await.unwrap();
+        let uri = service.ensure_ecr_repository("test-repo", &[]).await.unwrap();
         
         assert!(uri.contains("test-repo"));
         assert!(uri.contains("us-east-1"));
         
         // Second call should return same URI
-        let uri2 = service.ensure_ecr_repository("test-repo").await.unwrap();
+        let uri2 = service.ensure_ecr_repository("test-repo", &[]).await.unwrap();
         assert_eq!(uri, uri2);
     }
     
@@ -354,7 +678,8 @@ mod tests {
         let result = service.build_docker_image(
             temp_dir.to_str().unwrap(),
             "test-app:latest",
-            &FrameworkType::NextJs
+            &FrameworkType::NextJs,
+            &|_| {},
         ).await;
         
         assert!(result.is_ok());
@@ -378,6 +703,7 @@ mod tests {
             memory: "512".to_string(),
             port: 3000,
             desired_count: 1,
+            ..Default::default()
         };
         
         let arn = service.register_task_definition(&config).await.unwrap();
@@ -386,6 +712,61 @@ mod tests {
         assert!(arn.starts_with("arn:aws:ecs:"));
     }
     
+    #[tokio::test]
+    async fn test_register_task_definition_stop_timeout() {
+        let service = create_test_service();
+        
+        let mut config = EcsDeploymentConfig {
+            cluster_name: "test-cluster".to_string(),
+            service_name: "test-service".to_string(),
+            task_family: "graceful-task".to_string(),
+            container_name: "test-container".to_string(),
+            image_uri: "test-image".to_string(),
+            cpu: "256".to_string(),
+            memory: "512".to_string(),
+            port: 3000,
+            desired_count: 1,
+            stop_timeout: Some(90),
+            ..Default::default()
+        };
+        
+        service.register_task_definition(&config).await.unwrap();
+        let registered = service.state.get_task_definition_config("graceful-task").unwrap();
+        assert_eq!(registered.stop_timeout, Some(90));
+        
+        // Out-of-range values are rejected before registration
+        config.stop_timeout = Some(300);
+        let result = service.register_task_definition(&config).await;
+        assert!(matches!(result, Err(AwsServiceError::InvalidConfiguration(_))));
+    }
+    
+    #[tokio::test]
+    async fn test_register_task_definition_ephemeral_storage() {
+        let service = create_test_service();
+        
+        let mut config = EcsDeploymentConfig {
+            cluster_name: "test-cluster".to_string(),
+            service_name: "test-service".to_string(),
+            task_family: "build-heavy-task".to_string(),
+            container_name: "test-container".to_string(),
+            image_uri: "test-image".to_string(),
+            cpu: "256".to_string(),
+            memory: "512".to_string(),
+            port: 3000,
+            desired_count: 1,
+            ephemeral_storage_gib: Some(100),
+            ..Default::default()
+        };
+        
+        service.register_task_definition(&config).await.unwrap();
+        let registered = service.state.get_task_definition_config("build-heavy-task").unwrap();
+        assert_eq!(registered.ephemeral_storage_gib, Some(100));
+        
+        config.ephemeral_storage_gib = Some(500);
+        let result = service.register_task_definition(&config).await;
+        assert!(matches!(result, Err(AwsServiceError::InvalidConfiguration(_))));
+    }
+    
     #[tokio::test]
     async fn test_service_health_progression() {
         let service = create_test_service();
@@ -400,6 +781,7 @@ mod tests {
             memory: "512".to_string(),
             port: 3000,
             desired_count: 1,
+            ..Default::default()
         };
         
         // Deploy service
@@ -437,14 +819,19 @@ mod tests {
         service.build_docker_image(
             temp_dir.to_str().unwrap(),
             "test-app:v1",
-            &FrameworkType::React
+            &FrameworkType::React,
+            &|_| {},
         ).await.unwrap();
         
-        // Now push should succeed
+        // Now push should succeed and report a digest
         let result = service.push_docker_image("test-app:v1", "ecr-uri:v1").await;
         assert!(result.is_ok());
         assert!(service.state.has_docker_image("ecr-uri:v1"));
         
+        let digest = result.unwrap();
+        assert!(digest.starts_with("sha256:"));
+        assert_eq!(digest.len(), "sha256:".len() + 64);
+        
         // Cleanup
         std::fs::remove_dir_all(&temp_dir).ok();
     }
@@ -459,7 +846,7 @@ mod tests {
         let state = Arc::new(ShadowState::new());
         let service = MockAwsService::new(Some("us-east-1".into()), config, state);
         
-        let result = service.ensure_ecr_repository("test-repo").await;
+        let result = service.ensure_ecr_repository("test-repo", &[]).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Simulated failure"));
     }
@@ -492,6 +879,7 @@ mod tests {
             memory: "512".to_string(),
             port: 3000,
             desired_count: 3, // Multiple tasks
+            ..Default::default()
         };
         
         service.deploy_service(&config, "arn:test").await.unwrap();
@@ -538,6 +926,26 @@ mod tests {
         assert_eq!(logs[1], "Custom log 2");
     }
     
+    #[tokio::test]
+    async fn test_fetch_logs_pages_past_single_call() {
+        let service = create_test_service();
+        
+        for i in 0..250 {
+            service.state.add_log("/ecs/my-task", "my-stream", format!("Log {}", i));
+        }
+        
+        // More than two simulated pages' worth
+        let logs = service.fetch_logs("/ecs/my-task", "my-stream", 220).await.unwrap();
+        assert_eq!(logs.len(), 220);
+        assert_eq!(logs[0], "Log 30");
+        assert_eq!(logs[219], "Log 249");
+        
+        // Asking for more than exists returns everything in order
+        let logs = service.fetch_logs("/ecs/my-task", "my-stream", 500).await.unwrap();
+        assert_eq!(logs.len(), 250);
+        assert_eq!(logs[0], "Log 0");
+    }
+    
     #[tokio::test]
     async fn test_ecr_repository_different_regions() {
         let config = ShadowConfig {
@@ -551,8 +959,8 @@ mod tests {
         let service_us = MockAwsService::new(Some("us-west-2".into()), config.clone(), state.clone());
         let service_eu = MockAwsService::new(Some("eu-west-1".into()), config, state);
         
-        let uri_us = service_us.ensure_ecr_repository("test-repo").await.unwrap();
-        let uri_eu = service_eu.ensure_ecr_repository("test-repo-eu").await.unwrap();
+        let uri_us = service_us.ensure_ecr_repository("test-repo", &[]).await.unwrap();
+        let uri_eu = service_eu.ensure_ecr_repository("test-repo-eu", &[]).await.unwrap();
         
         assert!(uri_us.contains("us-west-2"));
         assert!(uri_eu.contains("eu-west-1"));
@@ -567,7 +975,8 @@ mod tests {
         service.build_docker_image(
             temp_dir.to_str().unwrap(),
             "test:v1",
-            &FrameworkType::NextJs
+            &FrameworkType::NextJs,
+            &|_| {},
         ).await.unwrap();
         
         let dockerfile = std::fs::read_to_string(temp_dir.join("Dockerfile")).unwrap();
@@ -586,7 +995,8 @@ mod tests {
         service.build_docker_image(
             temp_dir.to_str().unwrap(),
             "test:v1",
-            &FrameworkType::Python
+            &FrameworkType::Python,
+            &|_| {},
         ).await.unwrap();
         
         let dockerfile = std::fs::read_to_string(temp_dir.join("Dockerfile")).unwrap();
@@ -610,6 +1020,7 @@ mod tests {
             memory: "1024".to_string(),
             port: 8080,
             desired_count: 2,
+            ..Default::default()
         };
         
         let arn = service.register_task_definition(&config).await.unwrap();
@@ -620,14 +1031,34 @@ mod tests {
         assert!(service.state.get_task_definition("my-app-task").is_some());
     }
     
+    #[tokio::test]
+    async fn test_missing_cluster_and_service_reported_distinctly() {
+        let service = create_test_service();
+        service.state.delete_service("prod", "web-service");
+        service.state.delete_cluster("retired");
+        
+        assert!(matches!(
+            service.get_service_health("prod", "web-service").await,
+            Err(AwsServiceError::ServiceNotFound(_))
+        ));
+        assert!(matches!(
+            service.get_service_health("retired", "web-service").await,
+            Err(AwsServiceError::ClusterNotFound(cluster)) if cluster == "retired"
+        ));
+        assert!(matches!(
+            service.describe_services("retired", &["web-service".to_string()]).await,
+            Err(AwsServiceError::ClusterNotFound(_))
+        ));
+    }
+    
     #[tokio::test]
     async fn test_ecr_repository_idempotent() {
         let service = create_test_service();
         
         // Call multiple times
-        let uri1 = service.ensure_ecr_repository("my-repo").await.unwrap();
-        let uri2 = service.ensure_ecr_repository("my-repo").await.unwrap();
-        let uri3 = service.ensure_ecr_repository("my-repo").await.unwrap();
+        let uri1 = service.ensure_ecr_repository("my-repo", &[]).await.unwrap();
+        let uri2 = service.ensure_ecr_repository("my-repo", &[]).await.unwrap();
+        let uri3 = service.ensure_ecr_repository("my-repo", &[]).await.unwrap();
         
         // Should return same URI
         assert_eq!(uri1, uri2);
@@ -0,0 +1,100 @@
+//! Mock command runner for shadow world testing
+//!
+//! Records every invocation and replays scripted outputs so code that shells
+//! out (e.g. to `docker`) can be tested without the real program installed.
+
+use async_trait::async_trait;
+use crate::services::{CommandOutput, CommandRunner};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::sync::Mutex;
+
+/// A single recorded command invocation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandInvocation {
+    pub program: String,
+    pub args: Vec<String>,
+    pub stdin: Option<Vec<u8>>,
+}
+
+/// Mock command runner for testing
+///
+/// Scripted responses are returned in order; once exhausted, every call
+/// succeeds with empty output.
+#[derive(Debug, Default)]
+pub struct MockCommandRunner {
+    responses: Mutex<VecDeque<Result<CommandOutput, ErrorKind>>>,
+    calls: Mutex<Vec<CommandInvocation>>,
+}
+
+impl MockCommandRunner {
+    /// Create a new mock runner with no scripted responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Queue the output returned by the next unanswered call
+    pub fn push_response(&self, output: CommandOutput) {
+        self.responses.lock().unwrap().push_back(Ok(output));
+    }
+    
+    /// Make the next unanswered call fail to start (e.g. `ErrorKind::NotFound`
+    /// for a missing binary)
+    pub fn push_spawn_failure(&self, kind: ErrorKind) {
+        self.responses.lock().unwrap().push_back(Err(kind));
+    }
+    
+    /// All invocations recorded so far, in call order
+    pub fn calls(&self) -> Vec<CommandInvocation> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl CommandRunner for MockCommandRunner {
+    async fn run(&self, program: &str, args: &[&str], stdin: Option<&[u8]>) -> std::io::Result<CommandOutput> {
+        self.calls.lock().unwrap().push(CommandInvocation {
+            program: program.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            stdin: stdin.map(|s| s.to_vec()),
+        });
+        
+        self.responses.lock().unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Ok(CommandOutput::success("")))
+            .map_err(|kind| std::io::Error::new(kind, format!("failed to spawn {}", program)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_records_calls_and_replays_responses() {
+        let runner = MockCommandRunner::new();
+        runner.push_response(CommandOutput::failure("no such image"));
+        
+        let first = runner.run("docker", &["push", "app:v1"], None).await.unwrap();
+        let second = runner.run("docker", &["login"], Some(b"secret")).await.unwrap();
+        
+        assert!(!first.success);
+        assert_eq!(first.stderr, "no such image");
+        assert!(second.success);
+        
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].args, vec!["push", "app:v1"]);
+        assert_eq!(calls[1].stdin.as_deref(), Some(&b"secret"[..]));
+    }
+    
+    #[tokio::test]
+    async fn test_spawn_failure() {
+        let runner = MockCommandRunner::new();
+        runner.push_spawn_failure(ErrorKind::NotFound);
+        
+        let err = runner.run("terraform", &["version"], None).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(runner.calls().len(), 1);
+    }
+}
@@ -5,8 +5,8 @@
 
 use async_trait::async_trait;
 use crate::services::{GitOperations, GitServiceError};
-use crate::services::git_trait::CommitInfo;
-use crate::models::FrameworkType;
+use crate::services::git_trait::{CommitInfo, RemoteRefs};
+use crate::models::{FrameworkType, GitCredentials};
 use crate::shadow::{ShadowConfig, ShadowState};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -193,6 +193,15 @@ edition = "2021"
         }
     }
     
+    /// History set on the state, newest first, cut at `since_sha`
+    fn history_since(&self, since_sha: Option<&str>, limit: usize) -> Vec<CommitInfo> {
+        self.state.get_commit_history()
+            .into_iter()
+            .take_while(|commit| Some(commit.sha.as_str()) != since_sha)
+            .take(limit)
+            .collect()
+    }
+    
     /// Generate mock commit SHA from repo URL
     fn generate_commit_sha(&self, repo_url: &str) -> String {
         use std::collections::hash_map::DefaultHasher;
@@ -200,7 +209,7 @@ edition = "2021"
         
         let mut hasher = DefaultHasher::new();
         repo_url.hash(&mut hasher);
-        format!("{:x}", hasher.finish())
+        format!("{:016x}", hasher.finish())
     }
 }
 
@@ -233,6 +242,7 @@ impl GitOperations for MockGitService {
     async fn detect_framework(&self, repo_path: &Path) -> Result<FrameworkType, GitServiceError> {
         self.simulate_delay(100).await;
         self.check_failure("detect_framework")?;
+        self.state.record_framework_detection();
         
         // Check for package.json (Node.js ecosystem)
         if let Ok(content) = std::fs::read_to_string(repo_path.join("package.json")) {
@@ -285,6 +295,7 @@ impl GitOperations for MockGitService {
             message: "Mock commit: Initial implementation".to_string(),
             author: "Mock Developer".to_string(),
             timestamp: chrono::Utc::now().timestamp(),
+            parent_sha: None,
         })
     }
     
@@ -296,6 +307,21 @@ impl GitOperations for MockGitService {
         Ok(sha[..16].to_string())
     }
     
+    async fn commits_since(&self, _repo_path: &Path, since_sha: Option<&str>, limit: usize) -> Result<Vec<CommitInfo>, GitServiceError> {
+        self.simulate_delay(100).await;
+        self.check_failure("commits_since")?;
+        
+        Ok(self.history_since(since_sha, limit))
+    }
+    
+    async fn checkout_commit(&self, _repo_path: &Path, commit_sha: &str) -> Result<(), GitServiceError> {
+        self.simulate_delay(100).await;
+        self.check_failure("checkout_commit")?;
+        
+        self.state.record_checkout(commit_sha.to_string());
+        Ok(())
+    }
+    
     async fn cleanup_repository(&self, repo_path: &Path) -> Result<(), GitServiceError> {
         self.simulate_delay(100).await;
         
@@ -307,6 +333,81 @@ impl GitOperations for MockGitService {
         
         Ok(())
     }
+    
+    async fn resolve_remote_commit(
+        &self,
+        repo_url: &str,
+        branch: &str,
+        _credentials: Option<&GitCredentials>,
+    ) -> Result<String, GitServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("resolve_remote_commit")?;
+        
+        let sha = self.generate_commit_sha(&format!("{}#{}", repo_url, branch));
+        Ok(sha[..16].to_string())
+    }
+    
+    async fn fetch_archive(
+        &self,
+        repo_url: &str,
+        _commit_sha: &str,
+        _credentials: Option<&GitCredentials>,
+    ) -> Result<PathBuf, GitServiceError> {
+        self.simulate_delay(500).await;
+        self.check_failure("fetch_archive")?;
+        
+        let temp_dir = std::env::temp_dir()
+            .join("deployotron_shadow")
+            .join(format!("archive_{}", uuid::Uuid::new_v4()));
+        
+        std::fs::create_dir_all(&temp_dir)
+            .map_err(|e| GitServiceError::TempDirFailed(e.to_string()))?;
+        
+        // Same files as a clone, without any `.git` directory
+        self.create_mock_project(&temp_dir, repo_url)?;
+        
+        self.state.add_fetched_archive(
+            repo_url.to_string(),
+            temp_dir.to_string_lossy().to_string()
+        );
+        
+        Ok(temp_dir)
+    }
+    
+    async fn list_remote_refs(
+        &self,
+        _repo_url: &str,
+        _credentials: Option<&GitCredentials>,
+    ) -> Result<RemoteRefs, GitServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("list_remote_refs")?;
+        
+        // Synthetic refs in advertisement order, as a real remote would list them
+        Ok(RemoteRefs::from_ref_names([
+            "HEAD",
+            "refs/heads/main",
+            "refs/heads/develop",
+            "refs/heads/feature/login",
+            "refs/pull/12/head",
+            "refs/tags/v1.1.0",
+            "refs/tags/v1.0.0",
+            "refs/tags/v1.0.0^{}",
+        ]))
+    }
+    
+    async fn list_remote_commits(
+        &self,
+        _repo_url: &str,
+        _branch: &str,
+        since_sha: Option<&str>,
+        limit: usize,
+        _credentials: Option<&GitCredentials>,
+    ) -> Result<Vec<CommitInfo>, GitServiceError> {
+        self.simulate_delay(300).await;
+        self.check_failure("list_remote_commits")?;
+        
+        Ok(self.history_since(since_sha, limit))
+    }
 }
 
 impl MockGitService {
@@ -744,6 +845,16 @@ mod tests {
         service.cleanup_repository(&repo_path).await.unwrap();
     }
     
+    #[tokio::test]
+    async fn test_list_remote_refs_separated_and_sorted() {
+        let service = create_test_service();
+        
+        let refs = service.list_remote_refs("https://github.com/test/app", None).await.unwrap();
+        
+        assert_eq!(refs.branches, ["develop", "feature/login", "main"]);
+        assert_eq!(refs.tags, ["v1.0.0", "v1.1.0"]);
+    }
+    
     #[tokio::test]
     async fn test_sha_generation_deterministic() {
         let service = create_test_service();
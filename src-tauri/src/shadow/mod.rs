@@ -7,6 +7,7 @@ pub mod config;
 pub mod state;
 pub mod aws_mock;
 pub mod git_mock;
+pub mod command_mock;
 
 #[cfg(test)]
 pub mod test_utils;
@@ -15,3 +16,4 @@ pub use config::ShadowConfig;
 pub use state::{ShadowState, ServiceStatus};
 pub use aws_mock::MockAwsService;
 pub use git_mock::MockGitService;
+pub use command_mock::{MockCommandRunner, CommandInvocation};
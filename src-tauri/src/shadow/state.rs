@@ -3,8 +3,10 @@
 //! Tracks mock state for AWS resources, Docker images, and Git repositories.
 //! All state is stored in-memory and can be reset for testing.
 
-use std::collections::HashMap;
+use crate::services::{CommitInfo, EcsDeploymentConfig, ServiceUtilization, StoppedTask};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Thread-safe shadow state for mock operations
 #[derive(Debug, Clone)]
@@ -18,20 +20,92 @@ struct StateInner {
     /// ECR repositories: name -> URI
     ecr_repositories: HashMap<String, String>,
     
+    /// Tags applied to ECR repositories: name -> (key, value) pairs
+    ecr_repository_tags: HashMap<String, Vec<(String, String)>>,
+    
     /// Docker images: tag -> built status
     docker_images: HashMap<String, bool>,
     
+    /// Images pushed to ECR: repository name -> digests
+    ecr_images: HashMap<String, Vec<String>>,
+    
     /// ECS task definitions: family -> ARN
     task_definitions: HashMap<String, String>,
     
+    /// Most recently registered task definition config: family -> config
+    task_definition_configs: HashMap<String, EcsDeploymentConfig>,
+    
     /// ECS services: "cluster:service" -> status
     services: HashMap<String, ServiceStatus>,
     
+    /// Task definition family each ECS service runs: "cluster:service" -> family
+    service_task_families: HashMap<String, String>,
+    
+    /// ECS services deleted out-of-band: "cluster:service"
+    deleted_services: HashSet<String>,
+    
+    /// ECS clusters deleted out-of-band
+    deleted_clusters: HashSet<String>,
+    
+    /// Stopped ECS tasks: "cluster:service" -> tasks
+    stopped_tasks: HashMap<String, Vec<StoppedTask>>,
+    
+    /// CloudWatch utilization series: "cluster:service" -> utilization
+    service_utilization: HashMap<String, ServiceUtilization>,
+    
+    /// Number of forced service redeployments
+    forced_deployments: usize,
+    
+    /// Mock operations forced to fail regardless of the failure rate
+    failing_operations: HashSet<String>,
+    
+    /// ECR URIs whose pushes are forced to fail
+    failing_push_targets: HashSet<String>,
+    
+    /// Mock AWS operations called so far, in call order
+    called_operations: Vec<String>,
+    
+    /// IAM actions the simulated identity is denied
+    denied_actions: HashSet<String>,
+    
     /// Git repositories: URL -> cloned path
     cloned_repos: HashMap<String, String>,
     
+    /// Git repositories downloaded as archives: URL -> extracted path
+    fetched_archives: HashMap<String, String>,
+    
+    /// Commit history clones and remote listings report, newest first
+    commit_history: Vec<CommitInfo>,
+    
+    /// Commits checked out in clones, in checkout order
+    checked_out_commits: Vec<String>,
+    
     /// CloudWatch logs: "log_group:stream" -> messages
     logs: HashMap<String, Vec<String>>,
+    
+    /// CloudWatch log streams as (log_group, stream), in creation order
+    log_streams: Vec<(String, String)>,
+    
+    /// CloudWatch log groups, including ones without streams yet
+    log_groups: HashSet<String>,
+    
+    /// Number of keepalive pings issued
+    keepalive_pings: usize,
+    
+    /// Number of batched describe_services calls made
+    describe_services_calls: usize,
+    
+    /// Number of times framework detection read a checkout
+    framework_detections: usize,
+    
+    /// How long each mock app image build takes
+    build_duration: Duration,
+    
+    /// App image builds currently in progress
+    active_builds: usize,
+    
+    /// Most app image builds ever in progress at once
+    max_concurrent_builds: usize,
 }
 
 /// ECS service health status
@@ -64,6 +138,18 @@ impl ShadowState {
         inner.ecr_repositories.get(name).cloned()
     }
     
+    /// Record the tags applied to an ECR repository
+    pub fn set_ecr_repository_tags(&self, name: &str, tags: Vec<(String, String)>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.ecr_repository_tags.insert(name.to_string(), tags);
+    }
+    
+    /// Tags last applied to an ECR repository
+    pub fn get_ecr_repository_tags(&self, name: &str) -> Vec<(String, String)> {
+        let inner = self.inner.lock().unwrap();
+        inner.ecr_repository_tags.get(name).cloned().unwrap_or_default()
+    }
+    
     // ===== Docker Operations =====
     
     /// Mark Docker image as built
@@ -78,6 +164,29 @@ impl ShadowState {
         inner.docker_images.get(tag).copied().unwrap_or(false)
     }
     
+    /// Record an image pushed to an ECR repository
+    pub fn add_ecr_image(&self, repository: &str, digest: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let digests = inner.ecr_images.entry(repository.to_string()).or_default();
+        if !digests.iter().any(|known| known == digest) {
+            digests.push(digest.to_string());
+        }
+    }
+    
+    /// Digests of the images in an ECR repository, in push order
+    pub fn get_ecr_images(&self, repository: &str) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.ecr_images.get(repository).cloned().unwrap_or_default()
+    }
+    
+    /// Remove an image from an ECR repository
+    pub fn remove_ecr_image(&self, repository: &str, digest: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(digests) = inner.ecr_images.get_mut(repository) {
+            digests.retain(|known| known != digest);
+        }
+    }
+    
     // ===== ECS Operations =====
     
     /// Add ECS task definition
@@ -92,13 +201,114 @@ impl ShadowState {
         inner.task_definitions.get(family).cloned()
     }
     
+    /// Remove a family's task definition, as if deregistered
+    pub fn remove_task_definition(&self, family: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.task_definitions.remove(family);
+    }
+    
+    /// Record the configuration a task definition was registered with
+    pub fn record_task_definition_config(&self, config: EcsDeploymentConfig) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.task_definition_configs.insert(config.task_family.clone(), config);
+    }
+    
+    /// Get the configuration of the latest registered task definition
+    pub fn get_task_definition_config(&self, family: &str) -> Option<EcsDeploymentConfig> {
+        let inner = self.inner.lock().unwrap();
+        inner.task_definition_configs.get(family).cloned()
+    }
+    
     /// Set ECS service status
     pub fn set_service_status(&self, cluster: &str, service: &str, status: ServiceStatus) {
         let mut inner = self.inner.lock().unwrap();
         let key = format!("{}:{}", cluster, service);
+        inner.deleted_services.remove(&key);
         inner.services.insert(key, status);
     }
     
+    /// Record the task definition family a service was deployed with
+    pub fn set_service_task_family(&self, cluster: &str, service: &str, family: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.service_task_families.insert(format!("{}:{}", cluster, service), family.to_string());
+    }
+    
+    /// Get the task definition family a service runs
+    pub fn get_service_task_family(&self, cluster: &str, service: &str) -> Option<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.service_task_families.get(&format!("{}:{}", cluster, service)).cloned()
+    }
+    
+    /// Simulate an ECS service being deleted outside the app
+    pub fn delete_service(&self, cluster: &str, service: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let key = format!("{}:{}", cluster, service);
+        inner.services.remove(&key);
+        inner.deleted_services.insert(key);
+    }
+    
+    /// Simulate an ECS cluster being deleted outside the app
+    pub fn delete_cluster(&self, cluster: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.deleted_clusters.insert(cluster.to_string());
+    }
+    
+    /// Check whether an ECS cluster has been deleted
+    pub fn is_cluster_deleted(&self, cluster: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.deleted_clusters.contains(cluster)
+    }
+    
+    /// Record a stopped task for a service
+    pub fn add_stopped_task(&self, cluster: &str, service: &str, reason: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let key = format!("{}:{}", cluster, service);
+        let tasks = inner.stopped_tasks.entry(key).or_default();
+        let task_arn = format!("arn:aws:ecs:us-east-1:123456789012:task/{}/{}", cluster, tasks.len() + 1);
+        tasks.push(StoppedTask {
+            task_arn,
+            reason: reason.to_string(),
+            stopped_at: Some(chrono::Utc::now().timestamp()),
+        });
+    }
+    
+    /// Get stopped tasks for a service
+    pub fn get_stopped_tasks(&self, cluster: &str, service: &str) -> Vec<StoppedTask> {
+        let inner = self.inner.lock().unwrap();
+        inner.stopped_tasks.get(&format!("{}:{}", cluster, service)).cloned().unwrap_or_default()
+    }
+    
+    /// Set the utilization series CloudWatch reports for a service
+    pub fn set_service_utilization(&self, cluster: &str, service: &str, utilization: ServiceUtilization) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.service_utilization.insert(format!("{}:{}", cluster, service), utilization);
+    }
+    
+    /// Get the utilization series set for a service
+    pub fn get_service_utilization(&self, cluster: &str, service: &str) -> Option<ServiceUtilization> {
+        let inner = self.inner.lock().unwrap();
+        inner.service_utilization.get(&format!("{}:{}", cluster, service)).cloned()
+    }
+    
+    /// Record a forced redeployment, clearing the service's stopped tasks
+    pub fn record_forced_deployment(&self, cluster: &str, service: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.stopped_tasks.remove(&format!("{}:{}", cluster, service));
+        inner.forced_deployments += 1;
+    }
+    
+    /// Number of forced redeployments so far
+    pub fn forced_deployments(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.forced_deployments
+    }
+    
+    /// Check whether an ECS service has been deleted
+    pub fn is_service_deleted(&self, cluster: &str, service: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.deleted_services.contains(&format!("{}:{}", cluster, service))
+    }
+    
     /// Get ECS service status
     pub fn get_service_status(&self, cluster: &str, service: &str) -> Option<ServiceStatus> {
         let inner = self.inner.lock().unwrap();
@@ -120,15 +330,88 @@ impl ShadowState {
         inner.cloned_repos.get(url).cloned()
     }
     
+    /// Record a repository downloaded as an archive
+    pub fn add_fetched_archive(&self, url: String, path: String) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.fetched_archives.insert(url, path);
+    }
+    
+    /// Get the extracted path of a repository downloaded as an archive
+    pub fn get_fetched_archive(&self, url: &str) -> Option<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.fetched_archives.get(url).cloned()
+    }
+    
+    /// Set the commit history clones report, newest first
+    pub fn set_commit_history(&self, commits: Vec<CommitInfo>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.commit_history = commits;
+    }
+    
+    /// Get the commit history clones report, newest first
+    pub fn get_commit_history(&self) -> Vec<CommitInfo> {
+        let inner = self.inner.lock().unwrap();
+        inner.commit_history.clone()
+    }
+    
+    /// Record a commit checked out in a clone
+    pub fn record_checkout(&self, commit_sha: String) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.checked_out_commits.push(commit_sha);
+    }
+    
+    /// Commits checked out in clones so far, in checkout order
+    pub fn checked_out_commits(&self) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.checked_out_commits.clone()
+    }
+    
     // ===== CloudWatch Operations =====
     
     /// Add log message
     pub fn add_log(&self, log_group: &str, stream: &str, message: String) {
         let mut inner = self.inner.lock().unwrap();
         let key = format!("{}:{}", log_group, stream);
+        if !inner.logs.contains_key(&key) {
+            inner.log_streams.push((log_group.to_string(), stream.to_string()));
+        }
+        inner.log_groups.insert(log_group.to_string());
         inner.logs.entry(key).or_insert_with(Vec::new).push(message);
     }
     
+    /// Create a log group with no streams
+    pub fn add_log_group(&self, log_group: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.log_groups.insert(log_group.to_string());
+    }
+    
+    /// Check if a log group exists
+    pub fn has_log_group(&self, log_group: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.log_groups.contains(log_group)
+    }
+    
+    /// Delete a log group along with its streams and messages
+    pub fn delete_log_group(&self, log_group: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.log_groups.remove(log_group);
+        inner.log_streams.retain(|(group, _)| group != log_group);
+        let prefix = format!("{}:", log_group);
+        inner.logs.retain(|key, _| !key.starts_with(&prefix));
+    }
+    
+    /// List log streams in a group, most recently created first
+    pub fn list_log_streams(&self, log_group: &str) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        
+        inner.log_streams
+            .iter()
+            .rev()
+            .filter(|(group, _)| group == log_group)
+            .map(|(_, stream)| stream.clone())
+            .collect()
+    }
+    
     /// Get log messages
     pub fn get_logs(&self, log_group: &str, stream: &str, limit: usize) -> Vec<String> {
         let inner = self.inner.lock().unwrap();
@@ -147,17 +430,176 @@ impl ShadowState {
             .unwrap_or_default()
     }
     
+    /// Get one page of log messages ending before index `end` (or the newest)
+    ///
+    /// Returns the page in chronological order and the index of its first
+    /// message, which callers pass back as `end` to read the preceding page.
+    pub fn get_logs_page(&self, log_group: &str, stream: &str, end: Option<usize>, limit: usize) -> (Vec<String>, usize) {
+        let inner = self.inner.lock().unwrap();
+        let key = format!("{}:{}", log_group, stream);
+        
+        let logs = match inner.logs.get(&key) {
+            Some(logs) => logs,
+            None => return (Vec::new(), 0),
+        };
+        
+        let end = end.unwrap_or(logs.len()).min(logs.len());
+        let start = end.saturating_sub(limit);
+        
+        (logs[start..end].to_vec(), start)
+    }
+    
+    // ===== Keepalive =====
+    
+    /// Record a keepalive ping
+    pub fn record_keepalive_ping(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.keepalive_pings += 1;
+    }
+    
+    /// Number of keepalive pings issued so far
+    pub fn keepalive_pings(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.keepalive_pings
+    }
+    
+    // ===== Call Counters =====
+    
+    /// Record a batched describe_services call
+    pub fn record_describe_services_call(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.describe_services_calls += 1;
+    }
+    
+    /// Number of batched describe_services calls made so far
+    pub fn describe_services_calls(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.describe_services_calls
+    }
+    
+    /// Record a framework detection that read a checkout
+    pub fn record_framework_detection(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.framework_detections += 1;
+    }
+    
+    /// Number of framework detections performed so far
+    pub fn framework_detections(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.framework_detections
+    }
+    
+    /// Record an image build starting, returning how long it should take
+    pub fn start_build(&self) -> Duration {
+        let mut inner = self.inner.lock().unwrap();
+        inner.active_builds += 1;
+        inner.max_concurrent_builds = inner.max_concurrent_builds.max(inner.active_builds);
+        inner.build_duration
+    }
+    
+    /// Record an image build finishing
+    pub fn finish_build(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.active_builds -= 1;
+    }
+    
+    /// Most image builds that have overlapped so far
+    pub fn max_concurrent_builds(&self) -> usize {
+        let inner = self.inner.lock().unwrap();
+        inner.max_concurrent_builds
+    }
+    
+    // ===== IAM Operations =====
+    
+    /// Deny an IAM action in permission simulations
+    pub fn deny_action(&self, action: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.denied_actions.insert(action.to_string());
+    }
+    
+    /// Check whether an IAM action is denied in permission simulations
+    pub fn is_action_denied(&self, action: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.denied_actions.contains(action)
+    }
+    
     // ===== Testing Utilities =====
     
+    /// Make each mock app image build take `duration`, so overlapping builds can be observed
+    pub fn set_build_duration(&self, duration: Duration) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.build_duration = duration;
+    }
+    
+    /// Make every call to the named mock operation fail
+    pub fn fail_operation(&self, operation: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failing_operations.insert(operation.to_string());
+    }
+    
+    /// Check whether the named mock operation is forced to fail
+    pub fn is_operation_failing(&self, operation: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.failing_operations.contains(operation)
+    }
+    
+    /// Make every push to one ECR URI fail, leaving pushes of other tags working
+    pub fn fail_push_to(&self, ecr_uri: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failing_push_targets.insert(ecr_uri.to_string());
+    }
+    
+    /// Check whether pushes to an ECR URI are forced to fail
+    pub fn is_push_failing(&self, ecr_uri: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.failing_push_targets.contains(ecr_uri)
+    }
+    
+    /// Record a call to the named mock AWS operation
+    pub fn record_operation(&self, operation: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.called_operations.push(operation.to_string());
+    }
+    
+    /// Mock AWS operations called so far, in call order (with repeats)
+    pub fn called_operations(&self) -> Vec<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.called_operations.clone()
+    }
+    
     /// Reset all state (useful for tests)
     pub fn reset(&self) {
         let mut inner = self.inner.lock().unwrap();
         inner.ecr_repositories.clear();
+        inner.ecr_repository_tags.clear();
         inner.docker_images.clear();
+        inner.ecr_images.clear();
         inner.task_definitions.clear();
+        inner.task_definition_configs.clear();
         inner.services.clear();
+        inner.service_task_families.clear();
+        inner.deleted_services.clear();
+        inner.deleted_clusters.clear();
+        inner.stopped_tasks.clear();
+        inner.service_utilization.clear();
+        inner.forced_deployments = 0;
+        inner.failing_operations.clear();
+        inner.failing_push_targets.clear();
+        inner.called_operations.clear();
+        inner.denied_actions.clear();
         inner.cloned_repos.clear();
+        inner.fetched_archives.clear();
+        inner.commit_history.clear();
+        inner.checked_out_commits.clear();
         inner.logs.clear();
+        inner.log_streams.clear();
+        inner.log_groups.clear();
+        inner.keepalive_pings = 0;
+        inner.describe_services_calls = 0;
+        inner.framework_detections = 0;
+        inner.build_duration = Duration::ZERO;
+        inner.active_builds = 0;
+        inner.max_concurrent_builds = 0;
     }
 }
 
@@ -2,10 +2,16 @@
 //!
 //! Provides helper functions and test environments for writing tests with shadow mode.
 
+use crate::application::progress::RecordingProgressReporter;
+use crate::application::rollout::PollBackoff;
+use crate::application::{DeploymentOrchestrator, ServiceMonitor};
+use crate::infrastructure::Database;
+use crate::models::{Environment, FrameworkType, Project};
 use crate::shadow::{ShadowConfig, ShadowState};
-use crate::services::{AwsOperations, GitOperations};
+use crate::services::{AwsOperations, GitOperations, TerraformService};
 use crate::shadow::{MockAwsService, MockGitService};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Create test configuration with shadow mode enabled
 pub fn test_config() -> ShadowConfig {
@@ -30,6 +36,67 @@ pub fn test_state() -> Arc<ShadowState> {
     Arc::new(ShadowState::new())
 }
 
+/// Mock AWS service over `state`, with the test configuration
+pub fn mock_aws(state: Arc<ShadowState>) -> MockAwsService {
+    MockAwsService::new(None, test_config(), state)
+}
+
+/// Mock Git service over `state`, with the test configuration
+pub fn mock_git(state: Arc<ShadowState>) -> MockGitService {
+    MockGitService::new(test_config(), state)
+}
+
+/// A Node project on cluster `cluster` deploying `name` to `{name}-service`
+///
+/// Its ECR repository is `123456789012.dkr.ecr.us-east-1.amazonaws.com/{name}`.
+pub fn test_project(name: &str, environment: Environment) -> Project {
+    Project::new(
+        name.to_string(),
+        format!("https://github.com/test/{}", name),
+        "main".to_string(),
+        FrameworkType::Node,
+        environment,
+        "cluster".to_string(),
+        format!("{}-service", name),
+        format!("123456789012.dkr.ecr.us-east-1.amazonaws.com/{}", name),
+    )
+}
+
+/// An orchestrator over mock services, deploying a stored `web` project
+pub struct TestOrchestrator {
+    pub orchestrator: DeploymentOrchestrator,
+    pub database: Arc<Mutex<Database>>,
+    pub aws: Arc<dyn AwsOperations>,
+    pub git: Arc<dyn GitOperations>,
+    pub reporter: Arc<RecordingProgressReporter>,
+    /// `test_project("web", Environment::Development)`, already in the database
+    pub project: Project,
+}
+
+impl TestOrchestrator {
+    /// Orchestrator polling every 5ms, with an in-memory database
+    pub fn new(state: Arc<ShadowState>) -> Self {
+        let aws: Arc<dyn AwsOperations> = Arc::new(mock_aws(state.clone()));
+        let git: Arc<dyn GitOperations> = Arc::new(mock_git(state));
+        let database = Arc::new(Mutex::new(Database::new_in_memory().unwrap()));
+        let reporter = Arc::new(RecordingProgressReporter::new());
+        
+        let project = test_project("web", Environment::Development);
+        database.lock().unwrap().create_project(&project).unwrap();
+        
+        let orchestrator = DeploymentOrchestrator::new(
+            database.clone(),
+            git.clone(),
+            aws.clone(),
+            Arc::new(TerraformService::new()),
+            Arc::new(ServiceMonitor::new(aws.clone(), Duration::from_millis(5))),
+            reporter.clone(),
+        ).with_poll_backoff(PollBackoff::Constant(Duration::from_millis(5)));
+        
+        Self { orchestrator, database, aws, git, reporter, project }
+    }
+}
+
 /// Complete test environment with all mock services
 pub struct TestEnvironment {
     pub config: ShadowConfig,
@@ -3,7 +3,7 @@
 //! Tests the complete deployment workflow using mock services without
 //! requiring real AWS, Docker, or Git infrastructure.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use deployotron::shadow::{ShadowConfig, ShadowState};
 use deployotron::services::{factory, AwsOperations, GitOperations, EcsDeploymentConfig};
 use deployotron::models::FrameworkType;
@@ -23,9 +23,11 @@ async fn test_ecr_docker_workflow() {
         .unwrap();
     
     // Step 1: Ensure ECR repository
-    let repo_uri = aws.ensure_ecr_repository("test-app").await.unwrap();
+    let tags = vec![("team".to_string(), "platform".to_string())];
+    let repo_uri = aws.ensure_ecr_repository("test-app", &tags).await.unwrap();
     assert!(repo_uri.contains("test-app"));
     assert!(repo_uri.contains("us-east-1"));
+    assert_eq!(state.get_ecr_repository_tags("test-app"), tags);
     
     // Step 2: Docker login
     aws.docker_login_ecr().await.unwrap();
@@ -34,14 +36,18 @@ async fn test_ecr_docker_workflow() {
     let temp_dir = std::env::temp_dir().join("test_build");
     std::fs::create_dir_all(&temp_dir).unwrap();
     
+    let lines = Mutex::new(Vec::new());
     aws.build_docker_image(
         temp_dir.to_str().unwrap(),
         "test-app:v1",
-        &FrameworkType::NextJs
+        &FrameworkType::NextJs,
+        &|line| lines.lock().unwrap().push(line.to_string())
     ).await.unwrap();
     
-    // Verify image was tracked
+    // Verify image was tracked and build output was streamed
     assert!(state.has_docker_image("test-app:v1"));
+    let lines = lines.into_inner().unwrap();
+    assert!(lines.last().unwrap().contains("test-app:v1"));
     
     // Step 4: Push to ECR
     let ecr_uri = format!("{}:v1", repo_uri);
@@ -122,6 +128,7 @@ async fn test_ecs_deployment_workflow() {
         memory: "512".to_string(),
         port: 3000,
         desired_count: 1,
+        ..Default::default()
     };
     
     let task_arn = aws.register_task_definition(&ecs_config).await.unwrap();
@@ -194,7 +201,7 @@ async fn test_failure_injection() {
         .unwrap();
     
     // Should fail due to failure injection
-    let result = aws.ensure_ecr_repository("test-repo").await;
+    let result = aws.ensure_ecr_repository("test-repo", &[]).await;
     assert!(result.is_err());
     
     let git = factory::create_git_operations(&config, state.clone());
@@ -233,11 +240,12 @@ async fn test_full_deployment_simulation() {
     aws.build_docker_image(
         repo_path.to_str().unwrap(),
         &image_tag,
-        &framework
+        &framework,
+        &|_| {}
     ).await.unwrap();
     
     // 5. Ensure ECR repository
-    let repo_uri = aws.ensure_ecr_repository("test-app").await.unwrap();
+    let repo_uri = aws.ensure_ecr_repository("test-app", &[]).await.unwrap();
     
     // 6. Login to ECR
     aws.docker_login_ecr().await.unwrap();
@@ -257,6 +265,7 @@ async fn test_full_deployment_simulation() {
         memory: "1024".to_string(),
         port: 3000,
         desired_count: 2,
+        ..Default::default()
     };
     
     let task_arn = aws.register_task_definition(&ecs_config).await.unwrap();
@@ -305,7 +314,7 @@ async fn test_state_reset() {
         .unwrap();
     
     // Add some state
-    aws.ensure_ecr_repository("test-repo").await.unwrap();
+    aws.ensure_ecr_repository("test-repo", &[]).await.unwrap();
     assert!(state.get_ecr_repository("test-repo").is_some());
     
     // Reset state